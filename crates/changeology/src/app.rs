@@ -1,13 +1,17 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::time::Duration;
 
 use log::{debug, info, warn};
 
+use gpui::prelude::FluentBuilder;
 use gpui::*;
 
 use gpui_component::{
     button::{Button, ButtonVariants},
     h_flex,
+    input::{Input, InputEvent, InputState},
     list::ListItem,
     menu::{DropdownMenu, PopupMenu},
     resizable::{h_resizable, resizable_panel},
@@ -16,13 +20,111 @@ use gpui_component::{
     v_flex, ActiveTheme, Icon, IconName, Root, Sizable, TitleBar,
 };
 
-use crate::diff_canvas::{DiffCanvasView, FileDiff};
+use crate::commit_graph::{self, GraphRow};
+use crate::diff_canvas::{
+    self, CommitDiffStats, DiffCanvasEvent, DiffCanvasView, DiffViewMode, DiffViewModes, FileDiff,
+};
+use crate::export;
+use crate::image_preview::{self, ImagePreview};
 use crate::menu::*;
-use crate::panels::file_tree;
-use crate::sidebar;
+use crate::panels::{commit_tree, file_tree, tree_diff};
+use crate::sidebar::{self, DateGroup};
 use crate::watcher::{DataSourceKind, RepoWatcher};
-use buffer_diff::DiffConfig;
-use git::{Commit, Repository};
+use buffer_diff::{BufferDiff, DiffConfig, DiffGranularity};
+use git::{Commit, Repository, StatusKind};
+use tree_viewer::tree::{GitTreeDiff, RepositoryTreeExt, TreeTraversal};
+
+/// Files with more lines than this are shown as a summary instead of a full diff
+const MAX_DIFF_LINES: usize = 50_000;
+
+/// Context lines for the View menu's wider context option
+const WIDE_CONTEXT: usize = 5;
+
+/// Context lines that show every unchanged line, so each card holds the whole file
+const FULL_CONTEXT: usize = usize::MAX;
+
+/// Diff settings used for every diff shown in the app, with `context_lines`
+/// unchanged lines around each change
+///
+/// Word granularity lets cards highlight just the words changed within a line.
+fn diff_config(context_lines: usize) -> DiffConfig {
+    DiffConfig::default()
+        .granularity(DiffGranularity::Word)
+        .context_lines(context_lines)
+        .max_lines(MAX_DIFF_LINES)
+}
+
+/// Which panels the main area shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MainLayout {
+    /// Resizable sidebar next to the content area
+    SidebarAndContent,
+    /// Content area only, taking the full width
+    ContentOnly,
+}
+
+impl MainLayout {
+    fn for_sidebar(sidebar_collapsed: bool) -> Self {
+        if sidebar_collapsed {
+            MainLayout::ContentOnly
+        } else {
+            MainLayout::SidebarAndContent
+        }
+    }
+}
+
+/// Delay between frames of the commit selection fade
+const COMMIT_TRANSITION_FRAME: Duration = Duration::from_millis(16);
+
+/// Progress added per frame, so the fade takes about 200ms
+const COMMIT_TRANSITION_STEP: f32 = 0.08;
+
+/// Fade-in of the diff cards after a commit is selected
+///
+/// Each selection starts a new run. Frames from an earlier run are ignored,
+/// so selecting another commit mid-animation cancels the previous fade.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CommitTransition {
+    /// Identifies the current run
+    generation: u64,
+    /// Progress of the current run, from 0 (just selected) to 1 (done)
+    progress: f32,
+}
+
+impl Default for CommitTransition {
+    fn default() -> Self {
+        Self {
+            generation: 0,
+            progress: 1.0,
+        }
+    }
+}
+
+impl CommitTransition {
+    /// Start a new run, cancelling any run in flight, and return its generation
+    fn start(&mut self) -> u64 {
+        self.generation += 1;
+        self.progress = 0.0;
+        self.generation
+    }
+
+    /// Advance run `generation` by `step`
+    ///
+    /// Returns whether the run should keep ticking: false once it has
+    /// finished or was superseded by a newer run.
+    fn advance(&mut self, generation: u64, step: f32) -> bool {
+        if generation != self.generation || self.progress >= 1.0 {
+            return false;
+        }
+        self.progress = (self.progress + step).min(1.0);
+        self.progress < 1.0
+    }
+
+    /// Opacity of the diff cards for the current progress
+    fn opacity(&self) -> f32 {
+        self.progress
+    }
+}
 
 pub struct ChangeologyApp {
     /// The git repository (if opened)
@@ -35,7 +137,6 @@ pub struct ChangeologyApp {
     watcher: Option<RepoWatcher>,
 
     /// Whether the sidebar is collapsed
-    #[allow(dead_code)]
     sidebar_collapsed: bool,
 
     /// Dirty files (unstaged changes)
@@ -53,6 +154,18 @@ pub struct ChangeologyApp {
     /// File tree state
     file_tree_state: Entity<TreeState>,
 
+    /// Every file present at the selected commit, not just the changed ones
+    commit_tree_state: Entity<TreeState>,
+
+    /// Number of files in `commit_tree_state`
+    commit_tree_files: usize,
+
+    /// Whether the selected commit's file tree is shown beside its diffs
+    show_commit_tree: bool,
+
+    /// Status of each file tree node, keyed by path
+    file_statuses: Rc<HashMap<String, StatusKind>>,
+
     /// Selected file path
     #[allow(dead_code)]
     selected_file: Option<String>,
@@ -60,17 +173,59 @@ pub struct ChangeologyApp {
     /// Commit history
     commits: Vec<Commit>,
 
+    /// Branch HEAD points to, shown in the title bar; `None` when detached
+    current_branch: Option<String>,
+
+    /// Commit graph rows, parallel to `commits`
+    commit_graph: Vec<GraphRow>,
+
+    /// Whether the commit graph is drawn beside the history list
+    show_commit_graph: bool,
+
     /// Selected commit index
     selected_commit: Option<usize>,
 
+    /// Commit the selected one is compared with, picked by shift-clicking in
+    /// the history
+    compare_commit: Option<usize>,
+
+    /// Merged file trees of the compared commits
+    commit_tree_diff: Option<GitTreeDiff>,
+
+    /// Folders of `commit_tree_diff` that are expanded, by path
+    expanded_tree_dirs: HashSet<String>,
+
+    /// File of `commit_tree_diff` whose diff is shown
+    selected_tree_file: Option<String>,
+
     /// Diffs for the selected commit
     commit_diffs: Vec<FileDiff>,
 
     /// The diff canvas view for displaying diffs
     diff_canvas: Entity<DiffCanvasView>,
 
+    /// Global diff view mode and per-file overrides, keyed by path
+    view_modes: DiffViewModes,
+
+    /// Unchanged lines shown around each change (`FULL_CONTEXT` for all of them)
+    context_lines: usize,
+
+    /// Find box for searching the displayed diff lines
+    search_input: Entity<InputState>,
+
+    /// Whether selecting a commit fades the diff cards in
+    animate_commit_selection: bool,
+
+    /// Fade-in state for the last commit selection
+    commit_transition: CommitTransition,
+
     /// Scroll handle for history list
     history_scroll_handle: ScrollHandle,
+
+    /// Focus handle so menu actions are dispatched to this view
+    focus_handle: FocusHandle,
+
+    _subscriptions: Vec<Subscription>,
 }
 
 impl ChangeologyApp {
@@ -90,10 +245,21 @@ impl ChangeologyApp {
 
         // Create tree state
         let file_tree_state = cx.new(|cx| TreeState::new(cx));
+        let commit_tree_state = cx.new(|cx| TreeState::new(cx));
 
         // Create the diff canvas view
         let diff_canvas = cx.new(|cx| DiffCanvasView::new(window, cx));
 
+        // Create the find box
+        let search_input = cx.new(|cx| InputState::new(window, cx).placeholder("Find in diffs..."));
+        let subscriptions = vec![
+            cx.subscribe_in(&search_input, window, Self::on_search_input_event),
+            cx.subscribe(&diff_canvas, Self::on_diff_canvas_event),
+        ];
+
+        let focus_handle = cx.focus_handle();
+        window.focus(&focus_handle);
+
         let mut app = Self {
             repository,
             cwd,
@@ -104,12 +270,30 @@ impl ChangeologyApp {
             selected_dirty_file: None,
             selected_staged_file: None,
             file_tree_state,
+            commit_tree_state,
+            commit_tree_files: 0,
+            show_commit_tree: true,
+            file_statuses: Rc::default(),
             selected_file: None,
             commits: Vec::new(),
+            current_branch: None,
+            commit_graph: Vec::new(),
+            show_commit_graph: true,
             selected_commit: None,
+            compare_commit: None,
+            commit_tree_diff: None,
+            expanded_tree_dirs: HashSet::new(),
+            selected_tree_file: None,
             commit_diffs: Vec::new(),
             diff_canvas,
+            view_modes: DiffViewModes::default(),
+            context_lines: BufferDiff::DEFAULT_CONTEXT_LINES,
+            search_input,
+            animate_commit_selection: true,
+            commit_transition: CommitTransition::default(),
             history_scroll_handle: ScrollHandle::new(),
+            focus_handle,
+            _subscriptions: subscriptions,
         };
 
         // Load initial data
@@ -184,17 +368,24 @@ impl ChangeologyApp {
     fn refresh_dirty_files(&mut self, cx: &mut Context<Self>) {
         let Some(repo) = &self.repository else { return };
 
-        if let Ok(dirty) = repo.unstaged_changes() {
-            debug!("Refreshed dirty files: {} files", dirty.len());
-            self.dirty_files = dirty;
+        match repo.unstaged_changes() {
+            Ok(dirty) => {
+                debug!("Refreshed dirty files: {} files", dirty.len());
+                self.dirty_files = dirty;
+            }
+            Err(e) => warn!("Failed to read dirty files: {}", e),
         }
 
         // Also update file tree since it shows all status
-        if let Ok(status) = repo.status() {
-            let items = file_tree::build_nested_tree(&status);
-            self.file_tree_state.update(cx, |state, cx| {
-                state.set_items(items, cx);
-            });
+        match repo.status() {
+            Ok(status) => {
+                let tree = file_tree::build_status_tree(&status, true);
+                self.file_statuses = Rc::new(tree.statuses);
+                self.file_tree_state.update(cx, |state, cx| {
+                    state.set_items(tree.items, cx);
+                });
+            }
+            Err(e) => warn!("Failed to read repository status: {}", e),
         }
 
         // Load all dirty file diffs onto the canvas
@@ -204,19 +395,35 @@ impl ChangeologyApp {
     fn refresh_staged_files(&mut self) {
         let Some(repo) = &self.repository else { return };
 
-        if let Ok(staged) = repo.staged_changes() {
-            debug!("Refreshed staged files: {} files", staged.len());
-            self.staged_files = staged;
+        match repo.staged_changes() {
+            Ok(staged) => {
+                debug!("Refreshed staged files: {} files", staged.len());
+                self.staged_files = staged;
+            }
+            Err(e) => warn!("Failed to read staged files: {}", e),
         }
     }
 
     fn refresh_history(&mut self) {
         let Some(repo) = &self.repository else { return };
 
-        if let Ok(commits) = repo.log(Some(100)) {
-            debug!("Refreshed history: {} commits", commits.len());
-            self.commits = commits;
+        // An unborn HEAD yields an empty history rather than an error
+        match repo.log(Some(100)) {
+            Ok(commits) => {
+                debug!("Refreshed history: {} commits", commits.len());
+                self.commits = commits;
+            }
+            Err(e) => {
+                warn!("Failed to read history: {}", e);
+                self.commits.clear();
+            }
         }
+        self.commit_graph = commit_graph::assign_lanes(&self.commits);
+
+        self.current_branch = repo.current_branch().unwrap_or_else(|e| {
+            warn!("Failed to read current branch: {}", e);
+            None
+        });
     }
 
     /// Load diffs for all dirty (unstaged) files and display on canvas
@@ -237,7 +444,7 @@ impl ChangeologyApp {
         info!("Loading diffs for {} dirty files", self.dirty_files.len());
 
         let mut diffs = Vec::new();
-        let config = DiffConfig::default();
+        let config = diff_config(self.context_lines);
 
         for entry in &self.dirty_files {
             let file_path = &entry.path;
@@ -264,6 +471,7 @@ impl ChangeologyApp {
                         old_content,
                         new_content,
                         buffer_diff,
+                        image: None,
                     });
                 }
                 Err(e) => {
@@ -314,7 +522,7 @@ impl ChangeologyApp {
         );
 
         // Compute diff
-        let config = DiffConfig::default();
+        let config = diff_config(self.context_lines);
         match config.diff(&old_content, &new_content) {
             Ok(buffer_diff) => {
                 let diffs = vec![FileDiff {
@@ -322,6 +530,7 @@ impl ChangeologyApp {
                     old_content,
                     new_content,
                     buffer_diff,
+                    image: None,
                 }];
 
                 self.diff_canvas.update(cx, |canvas, cx| {
@@ -335,6 +544,54 @@ impl ChangeologyApp {
         }
     }
 
+    /// Enable or disable the fade-in when a commit is selected
+    pub fn set_animate_commit_selection(&mut self, animate: bool) {
+        self.animate_commit_selection = animate;
+    }
+
+    /// Show a commit's diffs, fading them in if enabled
+    fn select_commit(&mut self, commit_index: usize, cx: &mut Context<Self>) {
+        self.selected_commit = Some(commit_index);
+        self.clear_comparison();
+        self.load_commit_diffs(commit_index, cx);
+        self.load_commit_tree(commit_index, cx);
+        if self.animate_commit_selection {
+            self.start_commit_transition(cx);
+        }
+        cx.notify();
+    }
+
+    /// Drive a new fade-in run from a timer until it finishes or is superseded
+    fn start_commit_transition(&mut self, cx: &mut Context<Self>) {
+        let generation = self.commit_transition.start();
+
+        cx.spawn(
+            async move |this: WeakEntity<Self>, cx: &mut AsyncApp| loop {
+                cx.background_executor()
+                    .timer(COMMIT_TRANSITION_FRAME)
+                    .await;
+
+                let keep_going = this
+                    .update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                        let before = this.commit_transition;
+                        let keep_going = this
+                            .commit_transition
+                            .advance(generation, COMMIT_TRANSITION_STEP);
+                        if this.commit_transition != before {
+                            cx.notify();
+                        }
+                        keep_going
+                    })
+                    .unwrap_or(false);
+
+                if !keep_going {
+                    break;
+                }
+            },
+        )
+        .detach();
+    }
+
     fn load_commit_diffs(&mut self, commit_index: usize, cx: &mut Context<Self>) {
         self.commit_diffs.clear();
 
@@ -344,33 +601,48 @@ impl ChangeologyApp {
             if let Some(commit) = self.commits.get(commit_index) {
                 commit_info = Some((commit.short_id.clone(), commit.message.clone()));
 
-                // Get list of files changed in this commit
-                if let Ok(files) = repo.get_commit_files(&commit.id) {
-                    for file_path in files {
-                        // Get the old content (parent commit) and new content (this commit)
-                        let old_content = if !commit.parent_ids.is_empty() {
-                            repo.get_content_at_revision(&commit.parent_ids[0], &file_path)
+                // Get the files changed in this commit, with their content
+                if let Ok(files) = repo.diff_commit(&commit.id) {
+                    for file in files {
+                        if image_preview::is_image_path(&file.path) {
+                            let old_path = file.old_path.as_deref().unwrap_or(&file.path);
+                            let old_blob = commit.parent_ids.first().and_then(|parent| {
+                                repo.get_blob_at_revision(parent, old_path).ok().flatten()
+                            });
+                            let new_blob = repo
+                                .get_blob_at_revision(&commit.id, &file.path)
                                 .ok()
-                                .flatten()
-                                .unwrap_or_default()
-                        } else {
-                            String::new() // First commit, no parent
-                        };
+                                .flatten();
+                            let preview =
+                                ImagePreview::from_blobs(old_blob.as_deref(), new_blob.as_deref());
+                            // Images that don't decode fall through to the text diff
+                            if let Some(preview) = preview {
+                                if let Ok(buffer_diff) = BufferDiff::new("", "") {
+                                    self.commit_diffs.push(FileDiff {
+                                        path: file.path,
+                                        old_content: String::new(),
+                                        new_content: String::new(),
+                                        buffer_diff,
+                                        image: Some(preview),
+                                    });
+                                }
+                                continue;
+                            }
+                        }
 
-                        let new_content = repo
-                            .get_content_at_revision(&commit.id, &file_path)
-                            .ok()
-                            .flatten()
-                            .unwrap_or_default();
+                        // Added files have no old content, deleted ones no new content
+                        let old_content = file.old_content.unwrap_or_default();
+                        let new_content = file.new_content.unwrap_or_default();
 
                         // Compute the BufferDiff
-                        let config = DiffConfig::default();
+                        let config = diff_config(self.context_lines);
                         if let Ok(buffer_diff) = config.diff(&old_content, &new_content) {
                             self.commit_diffs.push(FileDiff {
-                                path: file_path,
+                                path: file.path,
                                 old_content,
                                 new_content,
                                 buffer_diff,
+                                image: None,
                             });
                         }
                     }
@@ -378,6 +650,16 @@ impl ChangeologyApp {
             }
         }
 
+        // Show the largest changes first
+        diff_canvas::sort_by_change_size(&mut self.commit_diffs);
+        let stats = CommitDiffStats::from_diffs(&self.commit_diffs);
+        info!(
+            "Loaded {} commit diffs (+{} -{})",
+            stats.files.len(),
+            stats.additions,
+            stats.deletions
+        );
+
         // Update the canvas view with the new diffs
         let diffs = self.commit_diffs.clone();
         self.diff_canvas.update(cx, |canvas, cx| {
@@ -385,7 +667,420 @@ impl ChangeologyApp {
         });
     }
 
+    /// Fill the commit tree panel with every file present at a commit
+    fn load_commit_tree(&mut self, commit_index: usize, cx: &mut Context<Self>) {
+        let tree = match (&self.repository, self.commits.get(commit_index)) {
+            (Some(repo), Some(commit)) => repo
+                .tree_at_commit(&commit.id)
+                .inspect_err(|e| warn!("Failed to read the tree of {}: {}", commit.short_id, e))
+                .ok(),
+            _ => None,
+        };
+        let items = tree
+            .as_ref()
+            .map(commit_tree::tree_items)
+            .unwrap_or_default();
+        self.commit_tree_files = tree.map_or(0, |tree| tree.leaves().len());
+
+        self.commit_tree_state.update(cx, |state, cx| {
+            state.set_items(items, cx);
+        });
+    }
+
+    /// Compare the selected commit's tree with another commit's
+    ///
+    /// With no other commit selected, this just selects the commit.
+    fn compare_with_commit(&mut self, commit_index: usize, cx: &mut Context<Self>) {
+        if self
+            .selected_commit
+            .filter(|&selected| selected != commit_index)
+            .is_none()
+        {
+            self.select_commit(commit_index, cx);
+            return;
+        }
+
+        self.compare_commit = Some(commit_index);
+        self.selected_tree_file = None;
+        self.commit_tree_diff = match (&self.repository, self.compared_commits()) {
+            (Some(repo), Some((old, new))) => repo
+                .tree_diff(&old.id, &new.id)
+                .inspect_err(|e| warn!("Failed to compare {} with {}: {}", old.id, new.id, e))
+                .ok(),
+            _ => None,
+        };
+        self.expanded_tree_dirs = self
+            .commit_tree_diff
+            .as_ref()
+            .map(tree_diff::changed_folders)
+            .unwrap_or_default();
+        cx.notify();
+    }
+
+    /// Stop comparing the selected commit with another
+    fn clear_comparison(&mut self) {
+        self.compare_commit = None;
+        self.commit_tree_diff = None;
+        self.expanded_tree_dirs.clear();
+        self.selected_tree_file = None;
+    }
+
+    /// The older and newer of the two commits being compared
+    fn compared_commits(&self) -> Option<(&Commit, &Commit)> {
+        let (selected, compared) = (self.selected_commit?, self.compare_commit?);
+        // The history lists newer commits first
+        Some((
+            self.commits.get(selected.max(compared))?,
+            self.commits.get(selected.min(compared))?,
+        ))
+    }
+
+    /// Expand or collapse a folder of the tree comparison, or show a file's diff
+    fn on_tree_diff_row_clicked(&mut self, path: String, is_folder: bool, cx: &mut Context<Self>) {
+        if is_folder {
+            if !self.expanded_tree_dirs.remove(&path) {
+                self.expanded_tree_dirs.insert(path);
+            }
+        } else {
+            self.load_compared_file_diff(path, cx);
+        }
+        cx.notify();
+    }
+
+    /// Show one file's diff between the two compared commits
+    fn load_compared_file_diff(&mut self, path: String, cx: &mut Context<Self>) {
+        let (Some(repo), Some((old, new))) = (&self.repository, self.compared_commits()) else {
+            return;
+        };
+
+        // A file missing from either commit diffs against empty content
+        let content = |commit: &Commit| {
+            repo.get_content_at_revision(&commit.id, &path)
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        };
+        let old_content = content(old);
+        let new_content = content(new);
+        let commit_info = Some((
+            format!("{}..{}", old.short_id, new.short_id),
+            new.message.clone(),
+        ));
+
+        match diff_config(self.context_lines).diff(&old_content, &new_content) {
+            Ok(buffer_diff) => {
+                let diffs = vec![FileDiff {
+                    path: path.clone(),
+                    old_content,
+                    new_content,
+                    buffer_diff,
+                    image: None,
+                }];
+                self.selected_tree_file = Some(path);
+                self.diff_canvas.update(cx, |canvas, cx| {
+                    canvas.set_diffs(diffs, commit_info, cx);
+                });
+            }
+            Err(e) => warn!("Failed to compute diff for {}: {}", path, e),
+        }
+    }
+
+    /// Open the repository containing `path` and reload all state from it
+    ///
+    /// On failure the currently open repository (if any) is left untouched.
+    pub fn open_repository(&mut self, path: &Path, cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let (repository, work_dir) = resolve_repository(path)?;
+        info!("Opened repository at {:?}", work_dir);
+
+        self.watcher = RepoWatcher::new(&work_dir).ok();
+        self.repository = Some(repository);
+        self.cwd = Some(work_dir);
+        self.reset_repository_state(cx);
+        self.refresh_source(DataSourceKind::All, cx);
+
+        Ok(())
+    }
+
+    /// Drop everything loaded from the previous repository
+    fn reset_repository_state(&mut self, cx: &mut Context<Self>) {
+        self.dirty_files.clear();
+        self.staged_files.clear();
+        self.selected_dirty_file = None;
+        self.selected_staged_file = None;
+        self.selected_file = None;
+        self.commits.clear();
+        self.current_branch = None;
+        self.commit_graph.clear();
+        self.selected_commit = None;
+        self.clear_comparison();
+        self.commit_diffs.clear();
+
+        self.file_statuses = Rc::default();
+        self.file_tree_state.update(cx, |state, cx| {
+            state.set_items(Vec::new(), cx);
+        });
+        self.commit_tree_files = 0;
+        self.commit_tree_state.update(cx, |state, cx| {
+            state.set_items(Vec::new(), cx);
+        });
+        self.diff_canvas.update(cx, |canvas, cx| {
+            canvas.set_diffs(Vec::new(), None, cx);
+        });
+        self.history_scroll_handle = ScrollHandle::new();
+    }
+
+    fn on_open_repository(
+        &mut self,
+        _: &OpenRepository,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: false,
+            directories: true,
+            multiple: false,
+            prompt: Some("Open Repository".into()),
+        });
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx: &mut AsyncApp| {
+            let path = match paths.await {
+                Ok(Ok(Some(paths))) => paths.into_iter().next(),
+                Ok(Ok(None)) | Err(_) => None, // Cancelled
+                Ok(Err(e)) => {
+                    warn!("Failed to show directory picker: {}", e);
+                    None
+                }
+            };
+            let Some(path) = path else {
+                return;
+            };
+
+            let _ = this.update(cx, |this: &mut Self, cx: &mut Context<Self>| {
+                if let Err(e) = this.open_repository(&path, cx) {
+                    warn!("Failed to open repository at {:?}: {:#}", path, e);
+                }
+            });
+        })
+        .detach();
+    }
+
+    /// Export the selected commit's diffs as a self-contained HTML file
+    pub fn export_commit_html(&self, path: &Path) -> std::io::Result<()> {
+        let commit = self
+            .selected_commit
+            .and_then(|i| self.commits.get(i))
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::NotFound, "No commit selected")
+            })?;
+
+        let title = format!("{}: {}", commit.short_id, commit.message);
+        std::fs::write(path, export::commit_html(&title, &self.commit_diffs))
+    }
+
+    fn on_search_input_event(
+        &mut self,
+        input: &Entity<InputState>,
+        event: &InputEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            InputEvent::Change => {
+                let query = input.read(cx).value();
+                self.diff_canvas.update(cx, |canvas, cx| {
+                    canvas.set_search_query(&query, cx);
+                    canvas.select_next_match(cx);
+                });
+            }
+            InputEvent::PressEnter { secondary } => {
+                let secondary = *secondary;
+                self.diff_canvas.update(cx, |canvas, cx| {
+                    if secondary {
+                        canvas.select_previous_match(cx);
+                    } else {
+                        canvas.select_next_match(cx);
+                    }
+                });
+                // Hand focus back so n/N keep stepping through matches
+                window.focus(&self.focus_handle);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_diff_canvas_event(
+        &mut self,
+        _canvas: Entity<DiffCanvasView>,
+        event: &DiffCanvasEvent,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            DiffCanvasEvent::ToggleViewMode { path } => {
+                self.view_modes.toggle(path);
+                self.push_view_modes(cx);
+            }
+        }
+    }
+
+    /// Set the view mode used by files without an override
+    pub fn set_diff_view_mode(&mut self, mode: DiffViewMode, cx: &mut Context<Self>) {
+        self.view_modes.set_global(mode);
+        self.push_view_modes(cx);
+    }
+
+    /// Send the current view modes to the diff canvas
+    fn push_view_modes(&mut self, cx: &mut Context<Self>) {
+        let view_modes = self.view_modes.clone();
+        self.diff_canvas
+            .update(cx, |canvas, cx| canvas.set_view_modes(view_modes, cx));
+    }
+
+    /// Set the unchanged lines shown around each change, reloading the diffs
+    /// on screen with the new context
+    pub fn set_context_lines(&mut self, context_lines: usize, cx: &mut Context<Self>) {
+        if self.context_lines == context_lines {
+            return;
+        }
+        self.context_lines = context_lines;
+
+        match (self.selected_tree_file.clone(), self.selected_commit) {
+            (Some(path), _) => self.load_compared_file_diff(path, cx),
+            (None, Some(commit_index)) => self.load_commit_diffs(commit_index, cx),
+            (None, None) => self.load_all_dirty_diffs(cx),
+        }
+        cx.notify();
+    }
+
+    fn on_context_lines_none(
+        &mut self,
+        _: &ContextLinesNone,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_context_lines(0, cx);
+    }
+
+    fn on_context_lines_default(
+        &mut self,
+        _: &ContextLinesDefault,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_context_lines(BufferDiff::DEFAULT_CONTEXT_LINES, cx);
+    }
+
+    fn on_context_lines_more(
+        &mut self,
+        _: &ContextLinesMore,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_context_lines(WIDE_CONTEXT, cx);
+    }
+
+    fn on_context_lines_full(
+        &mut self,
+        _: &ContextLinesFull,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_context_lines(FULL_CONTEXT, cx);
+    }
+
+    fn on_toggle_side_by_side(
+        &mut self,
+        _: &ToggleSideBySide,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let mode = self.view_modes.global().toggled();
+        self.set_diff_view_mode(mode, cx);
+    }
+
+    fn on_toggle_commit_tree(
+        &mut self,
+        _: &ToggleCommitTree,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.show_commit_tree = !self.show_commit_tree;
+        cx.notify();
+    }
+
+    fn on_toggle_commit_graph(
+        &mut self,
+        _: &ToggleCommitGraph,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.show_commit_graph = !self.show_commit_graph;
+        cx.notify();
+    }
+
+    fn on_toggle_commit_animation(
+        &mut self,
+        _: &ToggleCommitAnimation,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_animate_commit_selection(!self.animate_commit_selection);
+        cx.notify();
+    }
+
+    fn on_find_next(&mut self, _: &FindNext, _window: &mut Window, cx: &mut Context<Self>) {
+        self.diff_canvas
+            .update(cx, |canvas, cx| canvas.select_next_match(cx));
+    }
+
+    fn on_find_previous(&mut self, _: &FindPrevious, _window: &mut Window, cx: &mut Context<Self>) {
+        self.diff_canvas
+            .update(cx, |canvas, cx| canvas.select_previous_match(cx));
+    }
+
+    fn on_toggle_sidebar(
+        &mut self,
+        _: &ToggleSidebar,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.sidebar_collapsed = !self.sidebar_collapsed;
+        cx.notify();
+    }
+
+    fn on_export_commit_html(
+        &mut self,
+        _: &ExportCommitHtml,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(commit) = self.selected_commit.and_then(|i| self.commits.get(i)) else {
+            warn!("Export requested with no commit selected");
+            return;
+        };
+
+        let directory = self.cwd.clone().unwrap_or_default();
+        let suggested_name = format!("{}.html", commit.short_id);
+        let path = cx.prompt_for_new_path(&directory, Some(&suggested_name));
+
+        cx.spawn(async move |this: WeakEntity<Self>, cx: &mut AsyncApp| {
+            let Ok(Ok(Some(path))) = path.await else {
+                return;
+            };
+            let _ = this.update(cx, |this: &mut Self, _cx| {
+                match this.export_commit_html(&path) {
+                    Ok(()) => info!("Exported commit diff to {:?}", path),
+                    Err(e) => warn!("Failed to export commit diff to {:?}: {}", path, e),
+                }
+            });
+        })
+        .detach();
+    }
+
     fn render_title_bar(&self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let context_lines = self.context_lines;
+        let show_commit_tree = self.show_commit_tree;
+        let show_commit_graph = self.show_commit_graph;
+        let animate_commit_selection = self.animate_commit_selection;
         TitleBar::new()
             .child(
                 h_flex()
@@ -399,6 +1094,11 @@ impl ChangeologyApp {
                                     menu.menu("Open Repository...", Box::new(OpenRepository))
                                         .menu("Close Repository", Box::new(CloseRepository))
                                         .separator()
+                                        .menu(
+                                            "Export Commit as HTML...",
+                                            Box::new(ExportCommitHtml),
+                                        )
+                                        .separator()
                                         .menu("Refresh", Box::new(Refresh))
                                         .separator()
                                         .menu("Quit", Box::new(Quit))
@@ -410,17 +1110,60 @@ impl ChangeologyApp {
                             .ghost()
                             .label("View")
                             .dropdown_menu(
-                                |menu: PopupMenu, _: &mut Window, _: &mut Context<PopupMenu>| {
+                                move |menu: PopupMenu,
+                                      _: &mut Window,
+                                      _: &mut Context<PopupMenu>| {
                                     menu.menu("Toggle Sidebar", Box::new(ToggleSidebar))
+                                        .menu(
+                                            "Toggle Side-by-Side Diffs",
+                                            Box::new(ToggleSideBySide),
+                                        )
+                                        .menu_with_check(
+                                            "Commit File Tree",
+                                            show_commit_tree,
+                                            Box::new(ToggleCommitTree),
+                                        )
+                                        .menu_with_check(
+                                            "Commit Graph",
+                                            show_commit_graph,
+                                            Box::new(ToggleCommitGraph),
+                                        )
+                                        .menu_with_check(
+                                            "Animate Commit Selection",
+                                            animate_commit_selection,
+                                            Box::new(ToggleCommitAnimation),
+                                        )
+                                        .separator()
+                                        .menu_with_check(
+                                            "Context: 0",
+                                            context_lines == 0,
+                                            Box::new(ContextLinesNone),
+                                        )
+                                        .menu_with_check(
+                                            "Context: 3",
+                                            context_lines == BufferDiff::DEFAULT_CONTEXT_LINES,
+                                            Box::new(ContextLinesDefault),
+                                        )
+                                        .menu_with_check(
+                                            "Context: 5",
+                                            context_lines == WIDE_CONTEXT,
+                                            Box::new(ContextLinesMore),
+                                        )
+                                        .menu_with_check(
+                                            "Context: Full",
+                                            context_lines == FULL_CONTEXT,
+                                            Box::new(ContextLinesFull),
+                                        )
                                 },
                             ),
                     ),
             )
             .child(
-                // Spacer + repo name centered
+                // Spacer + repo name centered, with the current branch
                 h_flex()
                     .flex_1()
                     .justify_center()
+                    .gap_2()
                     .text_sm()
                     .text_color(cx.theme().muted_foreground)
                     .child(
@@ -429,7 +1172,10 @@ impl ChangeologyApp {
                             .and_then(|p| p.file_name())
                             .map(|n| n.to_string_lossy().to_string())
                             .unwrap_or_else(|| "No Repository".to_string()),
-                    ),
+                    )
+                    .when_some(self.current_branch.clone(), |el, branch| {
+                        el.child("·").child(branch)
+                    }),
             )
     }
 
@@ -512,39 +1258,82 @@ impl ChangeologyApp {
 
     #[allow(dead_code)]
     fn render_file_tree(&self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
-        let tree_state = self.file_tree_state.clone();
+        render_tree(&self.file_tree_state, self.file_statuses.clone())
+    }
 
-        div()
+    /// Render the selected commit's full file tree, for the side panel
+    fn render_commit_tree(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
             .size_full()
-            .p_2()
-            .child(tree(&tree_state, |ix, entry, selected, _window, cx| {
-                let item = entry.item();
-                let icon = if entry.is_folder() {
-                    if entry.is_expanded() {
-                        IconName::FolderOpen
-                    } else {
-                        IconName::Folder
-                    }
-                } else {
-                    IconName::File
-                };
+            .bg(cx.theme().sidebar)
+            .text_color(cx.theme().sidebar_foreground)
+            .child(sidebar::render_section_header(
+                "FILES",
+                self.commit_tree_files,
+                cx,
+            ))
+            .child(
+                div()
+                    .flex_1()
+                    .child(render_tree(&self.commit_tree_state, Rc::default())),
+            )
+    }
 
-                ListItem::new(ix)
-                    .selected(selected)
-                    .py(px(2.))
-                    .pl(px(16.) * entry.depth() as f32 + px(12.))
-                    .child(
-                        h_flex()
-                            .gap_2()
-                            .items_center()
-                            .child(
-                                Icon::new(icon)
-                                    .small()
-                                    .text_color(cx.theme().muted_foreground),
-                            )
-                            .child(item.label.clone()),
-                    )
-            }))
+    /// Render the compared commits' merged file trees side by side, old on
+    /// the left and new on the right
+    fn render_tree_diff(&self, tree: &GitTreeDiff, cx: &mut Context<Self>) -> impl IntoElement {
+        let (old_label, new_label) = self
+            .compared_commits()
+            .map(|(old, new)| (old.short_id.clone(), new.short_id.clone()))
+            .unwrap_or_default();
+        let header = |label: String| {
+            div()
+                .w_1_2()
+                .px_2()
+                .text_xs()
+                .font_weight(FontWeight::SEMIBOLD)
+                .text_color(cx.theme().muted_foreground)
+                .child(label)
+        };
+
+        let rows = tree_diff::visible_rows(tree, &self.expanded_tree_dirs);
+        let rows = rows.into_iter().enumerate().map(|(i, row)| {
+            let expanded = self.expanded_tree_dirs.contains(&row.path);
+            let selected = self.selected_tree_file.as_ref() == Some(&row.path);
+            let (path, is_folder) = (row.path.clone(), row.is_folder);
+            ListItem::new(format!("tree-diff-{}", i))
+                .selected(selected)
+                .py(px(2.))
+                .child(
+                    h_flex()
+                        .w_full()
+                        .child(tree_diff::render_row_side(&row, row.in_old(), expanded, cx))
+                        .child(tree_diff::render_row_side(&row, row.in_new(), expanded, cx)),
+                )
+                .on_click(cx.listener(move |this, _: &gpui::ClickEvent, _window, cx| {
+                    this.on_tree_diff_row_clicked(path.clone(), is_folder, cx);
+                }))
+        });
+
+        v_flex()
+            .size_full()
+            .bg(cx.theme().sidebar)
+            .text_color(cx.theme().sidebar_foreground)
+            .child(
+                h_flex()
+                    .py_1()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(header(old_label))
+                    .child(header(new_label)),
+            )
+            .child(
+                div()
+                    .id("tree-diff-scroll")
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .child(v_flex().w_full().children(rows)),
+            )
     }
 
     fn render_history_panel(
@@ -567,23 +1356,57 @@ impl ChangeologyApp {
                     .overflow_y_scroll()
                     .track_scroll(&self.history_scroll_handle)
                     .child(if self.commits.is_empty() {
-                        sidebar::render_empty_state("No commits", cx).into_any_element()
+                        let message = if self
+                            .repository
+                            .as_ref()
+                            .is_some_and(|repo| repo.is_head_unborn())
+                        {
+                            "No commits yet"
+                        } else {
+                            "No commits"
+                        };
+                        sidebar::render_empty_state(message, cx).into_any_element()
                     } else {
-                        v_flex()
-                            .w_full()
-                            .children(self.commits.iter().enumerate().map(|(i, commit)| {
-                                let is_selected = self.selected_commit == Some(i);
-                                sidebar::render_commit_entry(i, commit, is_selected, cx)
-                                    .on_click(cx.listener(
-                                        move |this, _: &gpui::ClickEvent, _window, cx| {
-                                            this.selected_commit = Some(i);
-                                            this.load_commit_diffs(i, cx);
-                                            cx.notify();
-                                        },
-                                    ))
-                                    .into_any_element()
-                            }))
-                            .into_any_element()
+                        let now = sidebar::current_timestamp();
+                        let graph = self.show_commit_graph.then_some(&self.commit_graph);
+                        let mut current_group = None;
+                        let mut rows = Vec::with_capacity(self.commits.len());
+
+                        for (i, commit) in self.commits.iter().enumerate() {
+                            // Start a new date group whenever the bucket changes
+                            let group = DateGroup::for_timestamp(commit.time, now);
+                            if current_group != Some(group) {
+                                rows.push(
+                                    sidebar::render_date_group_header(&group.label(), cx)
+                                        .into_any_element(),
+                                );
+                                current_group = Some(group);
+                            }
+
+                            let is_selected =
+                                self.selected_commit == Some(i) || self.compare_commit == Some(i);
+                            rows.push(
+                                sidebar::render_commit_entry(
+                                    i,
+                                    commit,
+                                    graph.and_then(|graph| graph.get(i)),
+                                    is_selected,
+                                    cx,
+                                )
+                                .on_click(cx.listener(
+                                    move |this, event: &gpui::ClickEvent, _window, cx| {
+                                        if event.modifiers().shift {
+                                            this.compare_with_commit(i, cx);
+                                        } else {
+                                            this.select_commit(i, cx);
+                                        }
+                                    },
+                                ))
+                                .into_any_element(),
+                            );
+                        }
+
+                        v_flex().w_full().children(rows).into_any_element()
                     })
                     .child(Scrollbar::vertical(&self.history_scroll_handle)),
             )
@@ -619,35 +1442,260 @@ impl ChangeologyApp {
     fn render_content_area(
         &self,
         _window: &mut Window,
-        _cx: &mut Context<Self>,
+        cx: &mut Context<Self>,
     ) -> impl IntoElement {
+        let canvas = self.diff_canvas.read(cx);
+        let match_count = canvas.search_matches().len();
+        let match_label = match canvas.current_match() {
+            Some(index) => format!("{}/{}", index + 1, match_count),
+            None => format!("{} matches", match_count),
+        };
+        let has_query = !self.search_input.read(cx).value().is_empty();
+        let diffs = div()
+            .size_full()
+            .opacity(self.commit_transition.opacity())
+            .child(self.diff_canvas.clone());
+
         // Use the diff canvas view for displaying diffs
         // Wrap in a size_full div to ensure proper sizing
-        div().size_full().child(self.diff_canvas.clone())
+        v_flex()
+            .size_full()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .px_2()
+                    .py_1()
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .w(px(280.))
+                            .child(Input::new(&self.search_input).small()),
+                    )
+                    .when(has_query, |el| {
+                        el.child(
+                            div()
+                                .text_xs()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(match_label),
+                        )
+                    }),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .size_full()
+                    .child(if let Some(tree) = &self.commit_tree_diff {
+                        h_resizable("tree-diff-layout")
+                            .child(
+                                resizable_panel()
+                                    .size(px(360.))
+                                    .size_range(px(240.)..px(600.))
+                                    .child(self.render_tree_diff(tree, cx)),
+                            )
+                            .child(resizable_panel().child(diffs))
+                            .into_any_element()
+                    } else if self.show_commit_tree && self.selected_commit.is_some() {
+                        h_resizable("commit-tree-layout")
+                            .child(resizable_panel().child(diffs))
+                            .child(
+                                resizable_panel()
+                                    .size(px(240.))
+                                    .size_range(px(160.)..px(400.))
+                                    .child(self.render_commit_tree(cx)),
+                            )
+                            .into_any_element()
+                    } else {
+                        diffs.into_any_element()
+                    }),
+            )
+    }
+}
+
+/// Open the repository containing `path`, returning it with its working directory
+///
+/// `path` may be any directory inside the repository; the returned working
+/// directory is always the repository root.
+fn resolve_repository(path: &Path) -> anyhow::Result<(Repository, PathBuf)> {
+    let repository = Repository::open(path)?;
+    let work_dir = repository.work_dir().to_path_buf();
+    Ok((repository, work_dir))
+}
+
+/// Render a tree of files and folders, with icons for each
+///
+/// Nodes with an entry in `statuses`, keyed by item id, get a status badge.
+fn render_tree(
+    tree_state: &Entity<TreeState>,
+    statuses: Rc<HashMap<String, StatusKind>>,
+) -> impl IntoElement {
+    div()
+        .size_full()
+        .p_2()
+        .child(tree(tree_state, move |ix, entry, selected, _window, cx| {
+            let item = entry.item();
+            let icon = if entry.is_folder() {
+                if entry.is_expanded() {
+                    IconName::FolderOpen
+                } else {
+                    IconName::Folder
+                }
+            } else {
+                IconName::File
+            };
+
+            ListItem::new(ix)
+                .selected(selected)
+                .py(px(2.))
+                .pl(px(16.) * entry.depth() as f32 + px(12.))
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .items_center()
+                        .child(
+                            Icon::new(icon)
+                                .small()
+                                .text_color(cx.theme().muted_foreground),
+                        )
+                        .child(item.label.clone())
+                        .when_some(statuses.get(item.id.as_ref()).copied(), |row, kind| {
+                            row.child(
+                                div()
+                                    .text_xs()
+                                    .text_color(file_tree::status_color(kind, cx))
+                                    .child(file_tree::status_indicator(kind)),
+                            )
+                        }),
+                )
+        }))
+}
+
+impl Focusable for ChangeologyApp {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
     }
 }
 
 impl Render for ChangeologyApp {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let main_area = match MainLayout::for_sidebar(self.sidebar_collapsed) {
+            MainLayout::SidebarAndContent => h_resizable("main-layout")
+                .child(
+                    resizable_panel()
+                        .size(px(260.))
+                        .size_range(px(180.)..px(450.))
+                        .child(self.render_sidebar(window, cx)),
+                )
+                .child(resizable_panel().child(self.render_content_area(window, cx)))
+                .into_any_element(),
+            MainLayout::ContentOnly => div()
+                .flex_1()
+                .size_full()
+                .child(self.render_content_area(window, cx))
+                .into_any_element(),
+        };
+
         div()
             .size_full()
             .flex()
             .flex_col()
             .bg(cx.theme().background)
             .text_color(cx.theme().foreground)
+            .key_context("ChangeologyApp")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::on_open_repository))
+            .on_action(cx.listener(Self::on_toggle_sidebar))
+            .on_action(cx.listener(Self::on_toggle_side_by_side))
+            .on_action(cx.listener(Self::on_toggle_commit_tree))
+            .on_action(cx.listener(Self::on_toggle_commit_graph))
+            .on_action(cx.listener(Self::on_toggle_commit_animation))
+            .on_action(cx.listener(Self::on_context_lines_none))
+            .on_action(cx.listener(Self::on_context_lines_default))
+            .on_action(cx.listener(Self::on_context_lines_more))
+            .on_action(cx.listener(Self::on_context_lines_full))
+            .on_action(cx.listener(Self::on_export_commit_html))
+            .on_action(cx.listener(Self::on_find_next))
+            .on_action(cx.listener(Self::on_find_previous))
             .child(self.render_title_bar(window, cx))
-            .child(
-                h_resizable("main-layout")
-                    .child(
-                        resizable_panel()
-                            .size(px(260.))
-                            .size_range(px(180.)..px(450.))
-                            .child(self.render_sidebar(window, cx)),
-                    )
-                    .child(resizable_panel().child(self.render_content_area(window, cx))),
-            )
+            .child(main_area)
             // Required: Render overlay layers for dialogs/notifications
             .children(Root::render_dialog_layer(window, cx))
             .children(Root::render_notification_layer(window, cx))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_main_layout_follows_sidebar_collapsed() {
+        assert_eq!(
+            MainLayout::for_sidebar(false),
+            MainLayout::SidebarAndContent
+        );
+        assert_eq!(MainLayout::for_sidebar(true), MainLayout::ContentOnly);
+    }
+
+    #[test]
+    fn test_commit_transition_progress_and_cancellation() {
+        let mut transition = CommitTransition::default();
+        assert_eq!(transition.opacity(), 1.0);
+
+        // A run ticks until it reaches full opacity, then stops
+        let first = transition.start();
+        assert_eq!(transition.opacity(), 0.0);
+        assert!(transition.advance(first, 0.4));
+        assert!(transition.advance(first, 0.4));
+        assert!(!transition.advance(first, 0.4));
+        assert_eq!(transition.opacity(), 1.0);
+        assert!(!transition.advance(first, 0.4));
+
+        // Selecting again mid-animation restarts and cancels the old run
+        let second = transition.start();
+        assert!(transition.advance(second, 0.5));
+        let third = transition.start();
+        assert!(!transition.advance(second, 0.5));
+        assert_eq!(transition.opacity(), 0.0);
+        assert!(transition.advance(third, 0.5));
+        assert_eq!(transition.opacity(), 0.5);
+    }
+
+    #[test]
+    fn test_diff_config_context_lines() {
+        let old: String = (0..20).map(|i| format!("line {}\n", i)).collect();
+        let new = old.replace("line 10\n", "line ten\n");
+
+        // One modified line (removed and added) plus context on either side
+        let hunk_lines = |context_lines| {
+            let diff = diff_config(context_lines).diff(&old, &new).unwrap();
+            assert_eq!(diff.hunk_count(), 1);
+            diff.hunks()[0].line_types.len()
+        };
+        assert_eq!(hunk_lines(0), 2);
+        assert_eq!(hunk_lines(BufferDiff::DEFAULT_CONTEXT_LINES), 8);
+        assert_eq!(hunk_lines(WIDE_CONTEXT), 12);
+        assert_eq!(hunk_lines(FULL_CONTEXT), 21);
+    }
+
+    #[test]
+    fn test_resolve_repository_from_subdirectory() {
+        let temp = tempfile::TempDir::new().unwrap();
+        git2::Repository::init(temp.path()).unwrap();
+        let nested = temp.path().join("src/nested");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let (repository, work_dir) = resolve_repository(&nested).unwrap();
+        assert_eq!(
+            work_dir.canonicalize().unwrap(),
+            temp.path().canonicalize().unwrap()
+        );
+        assert_eq!(repository.work_dir(), work_dir);
+    }
+
+    #[test]
+    fn test_resolve_repository_rejects_non_repository() {
+        let temp = tempfile::TempDir::new().unwrap();
+        assert!(resolve_repository(temp.path()).is_err());
+    }
+}
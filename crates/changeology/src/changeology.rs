@@ -1,5 +1,9 @@
 mod app;
+mod commit_graph;
 mod diff_canvas;
+mod export;
+mod highlight;
+mod image_preview;
 mod menu;
 mod panels;
 mod sidebar;
@@ -0,0 +1,170 @@
+//! Lane layout for the commit graph drawn beside the history list
+//!
+//! Each commit gets a lane (a column), and rails connect it to its parents.
+//! Commits must be ordered children first, as `Repository::log` returns them.
+
+use git::Commit;
+
+/// The graph drawn beside one commit in the history list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphRow {
+    /// Lane of the commit's dot
+    pub lane: usize,
+    /// Lanes whose rails come down from the row above into the commit's dot
+    pub incoming: Vec<usize>,
+    /// Lanes whose rails leave the commit's dot for the row below, one per parent
+    pub outgoing: Vec<usize>,
+    /// Lanes whose rails pass straight through the row
+    pub passing: Vec<usize>,
+    /// Number of lanes the row needs to be drawn
+    pub width: usize,
+}
+
+/// Assign lanes to commits, children first
+///
+/// A commit continues the lane of its first child, so a line of history
+/// stays in one lane. Merged-in branches take the leftmost free lane and
+/// give it up once they reach a commit that's already in another lane.
+pub fn assign_lanes(commits: &[Commit]) -> Vec<GraphRow> {
+    // The commit each lane's rail is heading down to, or None if the lane is free
+    let mut lanes: Vec<Option<&str>> = Vec::new();
+    let mut rows = Vec::with_capacity(commits.len());
+
+    for commit in commits {
+        let incoming: Vec<usize> = lanes
+            .iter()
+            .enumerate()
+            .filter(|(_, waiting)| **waiting == Some(commit.id.as_str()))
+            .map(|(lane, _)| lane)
+            .collect();
+        let passing: Vec<usize> = lanes
+            .iter()
+            .enumerate()
+            .filter(|(_, waiting)| waiting.is_some_and(|id| id != commit.id))
+            .map(|(lane, _)| lane)
+            .collect();
+
+        // Continue the leftmost rail coming into the commit, or start a new one
+        for &lane in &incoming {
+            lanes[lane] = None;
+        }
+        let lane = incoming
+            .first()
+            .copied()
+            .unwrap_or_else(|| free_lane(&mut lanes));
+
+        let mut outgoing = Vec::with_capacity(commit.parent_ids.len());
+        for (i, parent) in commit.parent_ids.iter().enumerate() {
+            let parent_lane = match lanes.iter().position(|w| *w == Some(parent.as_str())) {
+                // Another rail already leads to the parent, so join it
+                Some(existing) => existing,
+                None if i == 0 => lane,
+                None => free_lane(&mut lanes),
+            };
+            lanes[parent_lane] = Some(parent);
+            outgoing.push(parent_lane);
+        }
+
+        let width = incoming
+            .iter()
+            .chain(&outgoing)
+            .chain(&passing)
+            .fold(lane + 1, |width, &lane| width.max(lane + 1));
+        rows.push(GraphRow {
+            lane,
+            incoming,
+            outgoing,
+            passing,
+            width,
+        });
+
+        while lanes.last() == Some(&None) {
+            lanes.pop();
+        }
+    }
+
+    rows
+}
+
+/// Claim the leftmost free lane, adding one if they're all in use
+fn free_lane(lanes: &mut Vec<Option<&str>>) -> usize {
+    match lanes.iter().position(Option::is_none) {
+        Some(lane) => lane,
+        None => {
+            lanes.push(None);
+            lanes.len() - 1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(id: &str, parents: &[&str]) -> Commit {
+        Commit {
+            id: id.to_string(),
+            short_id: id.to_string(),
+            message: String::new(),
+            body: String::new(),
+            author_name: String::new(),
+            author_email: String::new(),
+            author_time: 0,
+            time: 0,
+            committer_name: String::new(),
+            committer_email: String::new(),
+            committer_time: 0,
+            parent_ids: parents.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    fn lanes(rows: &[GraphRow]) -> Vec<usize> {
+        rows.iter().map(|row| row.lane).collect()
+    }
+
+    #[test]
+    fn test_branch_and_merge_lanes() {
+        //   * merge
+        //   |\
+        //   * | main
+        //   | * feature-2
+        //   | * feature-1
+        //   |/
+        //   * base
+        //   * root
+        let commits = vec![
+            commit("merge", &["main", "feature-2"]),
+            commit("main", &["base"]),
+            commit("feature-2", &["feature-1"]),
+            commit("feature-1", &["base"]),
+            commit("base", &["root"]),
+            commit("root", &[]),
+        ];
+        let rows = assign_lanes(&commits);
+
+        assert_eq!(lanes(&rows), vec![0, 0, 1, 1, 0, 0]);
+
+        assert_eq!(rows[0].outgoing, vec![0, 1]);
+        assert_eq!(rows[1].passing, vec![1]);
+        assert_eq!(rows[2].incoming, vec![1]);
+        assert_eq!(rows[2].passing, vec![0]);
+        // feature-1 joins main's rail down to base
+        assert_eq!(rows[3].outgoing, vec![0]);
+        assert_eq!(rows[4].incoming, vec![0]);
+        assert_eq!(rows[4].width, 1);
+        assert!(rows[5].outgoing.is_empty());
+
+        // Laying out the same history again gives the same lanes
+        assert_eq!(assign_lanes(&commits), rows);
+    }
+
+    #[test]
+    fn test_unrelated_histories_get_separate_lanes() {
+        let commits = vec![commit("a2", &["a1"]), commit("b1", &[]), commit("a1", &[])];
+        let rows = assign_lanes(&commits);
+
+        assert_eq!(lanes(&rows), vec![0, 1, 0]);
+        assert_eq!(rows[1].passing, vec![0]);
+        assert_eq!(rows[1].width, 2);
+    }
+}
@@ -11,10 +11,20 @@ use gpui::prelude::FluentBuilder;
 use gpui::*;
 use gpui_component::{h_flex, v_flex, ActiveTheme, Icon, IconName};
 use infinite_canvas::prelude::*;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::ops::Range;
 use std::rc::Rc;
+use std::sync::Arc;
 
-use buffer_diff::{BufferDiff, DiffLineType};
+use buffer_diff::{
+    BufferDiff, BufferDiffSnapshot, DiffHunk, DiffHunkStatus, DiffLineType, PairedLine,
+    WhitespaceChange,
+};
+
+use crate::highlight::{language_for_path, HighlightCache, HighlightSpan, TokenKind};
+use crate::image_preview::{ImagePane, ImagePreview, IMAGE_LABEL_HEIGHT, IMAGE_PANE_GAP};
 
 /// Diff data for a single file in a commit
 #[derive(Clone)]
@@ -23,6 +33,537 @@ pub struct FileDiff {
     pub old_content: String,
     pub new_content: String,
     pub buffer_diff: BufferDiff,
+    /// Before/after images, shown instead of the lines for image files
+    pub image: Option<ImagePreview>,
+}
+
+impl FileDiff {
+    /// Walk the diff's hunks and collect every line with its line numbers
+    pub fn lines(&self) -> Vec<DiffLine> {
+        let hunks = self.buffer_diff.hunks();
+        let has_word_ranges = hunks.iter().any(|hunk| hunk.word_ranges.is_some());
+
+        let mut diff_lines: Vec<DiffLine> = self
+            .buffer_diff
+            .lines()
+            .map(|line| {
+                let kind = match line.kind {
+                    DiffLineType::OldOnly => DiffLineKind::Removed,
+                    DiffLineType::NewOnly => DiffLineKind::Added,
+                    DiffLineType::Both => DiffLineKind::Context,
+                };
+                let changed_ranges = match kind {
+                    DiffLineKind::Context => Vec::new(),
+                    _ => hunks[line.hunk_index]
+                        .word_ranges
+                        .as_ref()
+                        .and_then(|ranges| ranges.get(line.line_index).cloned())
+                        .unwrap_or_default(),
+                };
+                DiffLine {
+                    old_line_number: line.old_line.map(|index| index + 1),
+                    new_line_number: line.new_line.map(|index| index + 1),
+                    content: line.content.into_owned(),
+                    kind,
+                    changed_ranges,
+                }
+            })
+            .collect();
+
+        // Diffs computed at word granularity already know which words changed
+        if has_word_ranges {
+            clear_whole_line_ranges(&mut diff_lines);
+        } else {
+            mark_changed_ranges(hunks, &mut diff_lines);
+        }
+        diff_lines
+    }
+
+    /// The row of a card laid out in `mode` that the line at `line_index` of
+    /// `lines()` is drawn on
+    ///
+    /// Unified cards draw each line on its own row. Side-by-side cards draw a
+    /// modified line on the same row as the line that replaced it (see
+    /// `side_by_side_rows`), so lines after it move up.
+    pub fn display_row(&self, line_index: usize, mode: DiffViewMode) -> usize {
+        if mode == DiffViewMode::Unified {
+            return line_index;
+        }
+
+        let lines = self.lines();
+        let Some(line) = lines.get(line_index) else {
+            return line_index;
+        };
+        side_by_side_rows(self.buffer_diff.hunks(), &lines)
+            .into_iter()
+            .position(|(old, new)| {
+                [old, new]
+                    .into_iter()
+                    .flatten()
+                    .any(|side| std::ptr::eq(side, line))
+            })
+            .unwrap_or(line_index)
+    }
+
+    /// A note for diffs that only change whitespace, e.g. "whitespace only"
+    ///
+    /// Such diffs otherwise look like they change nothing. When hunks change
+    /// whitespace in different ways, the note is the general one.
+    pub fn whitespace_note(&self) -> Option<String> {
+        let changes = self
+            .buffer_diff
+            .hunks()
+            .iter()
+            .zip(self.buffer_diff.whitespace_changes())
+            .filter(|(hunk, _)| hunk.status != DiffHunkStatus::Unchanged)
+            .map(|(_, change)| change)
+            .collect::<Option<Vec<_>>>()?;
+        let first = *changes.first()?;
+        let note = if changes.iter().all(|&change| change == first) {
+            first
+        } else {
+            WhitespaceChange::Within
+        };
+        Some(note.to_string())
+    }
+
+    /// Count the lines added and deleted in this file
+    pub fn stats(&self) -> FileDiffStats {
+        let snapshot = self.buffer_diff.snapshot();
+        FileDiffStats {
+            path: self.path.clone(),
+            additions: snapshot.added_lines(),
+            deletions: snapshot.deleted_lines(),
+        }
+    }
+}
+
+/// Added/deleted line counts for a single file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiffStats {
+    pub path: String,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+impl FileDiffStats {
+    /// Total number of changed lines
+    pub fn total(&self) -> usize {
+        self.additions + self.deletions
+    }
+}
+
+/// Added/deleted line totals for a whole commit, with a per-file breakdown
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitDiffStats {
+    pub additions: usize,
+    pub deletions: usize,
+    pub files: Vec<FileDiffStats>,
+}
+
+impl CommitDiffStats {
+    pub fn from_diffs(diffs: &[FileDiff]) -> Self {
+        let files: Vec<FileDiffStats> = diffs.iter().map(FileDiff::stats).collect();
+        Self {
+            additions: files.iter().map(|f| f.additions).sum(),
+            deletions: files.iter().map(|f| f.deletions).sum(),
+            files,
+        }
+    }
+}
+
+/// The index in `lines` of each old and new line, by 0-based line number
+fn line_indices(lines: &[DiffLine]) -> (HashMap<usize, usize>, HashMap<usize, usize>) {
+    let (mut old, mut new) = (HashMap::new(), HashMap::new());
+    for (index, line) in lines.iter().enumerate() {
+        if line.kind != DiffLineKind::Added {
+            if let Some(number) = line.old_line_number {
+                old.insert(number - 1, index);
+            }
+        }
+        if line.kind != DiffLineKind::Removed {
+            if let Some(number) = line.new_line_number {
+                new.insert(number - 1, index);
+            }
+        }
+    }
+    (old, new)
+}
+
+/// Record which part of each line changed, for the lines `hunks` pair up as
+/// modified (see `DiffHunk::paired_lines`)
+fn mark_changed_ranges(hunks: &[DiffHunk], lines: &mut [DiffLine]) {
+    let (old_indices, new_indices) = line_indices(lines);
+    for paired in hunks.iter().flat_map(DiffHunk::paired_lines) {
+        let PairedLine::Modified { old, new } = paired else {
+            continue;
+        };
+        let (Some(&old), Some(&new)) = (old_indices.get(&old), new_indices.get(&new)) else {
+            continue;
+        };
+        let (old_range, new_range) = changed_range(&lines[old].content, &lines[new].content);
+        // A line that changed entirely gets no extra emphasis
+        if old_range.len() < lines[old].content.len() {
+            lines[old].changed_ranges.push(old_range);
+        }
+        if new_range.len() < lines[new].content.len() {
+            lines[new].changed_ranges.push(new_range);
+        }
+    }
+}
+
+/// Drop changed ranges that cover a whole line
+///
+/// A line that changed entirely gets no extra emphasis, as in `mark_changed_ranges`.
+fn clear_whole_line_ranges(lines: &mut [DiffLine]) {
+    for line in lines {
+        let changed: usize = line.changed_ranges.iter().map(|range| range.len()).sum();
+        if changed >= line.content.len() {
+            line.changed_ranges.clear();
+        }
+    }
+}
+
+/// Find the differing middle of two lines, widened to whole words
+///
+/// Returns byte ranges into `old` and `new`.
+fn changed_range(old: &str, new: &str) -> (Range<usize>, Range<usize>) {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    let prefix: usize = old
+        .chars()
+        .zip(new.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    let suffix: usize = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+
+    let widen = |text: &str, start: usize, end: usize| {
+        let start = text[..start]
+            .char_indices()
+            .rev()
+            .take_while(|&(_, c)| is_word(c))
+            .last()
+            .map_or(start, |(idx, _)| idx);
+        let end = end
+            + text[end..]
+                .chars()
+                .take_while(|&c| is_word(c))
+                .map(char::len_utf8)
+                .sum::<usize>();
+        start..end
+    };
+
+    (
+        widen(old, prefix, old.len() - suffix),
+        widen(new, prefix, new.len() - suffix),
+    )
+}
+
+/// A run of text within a diff line that is drawn with a single style
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StyledSegment {
+    range: Range<usize>,
+    token: Option<TokenKind>,
+    /// Whether this text is part of the line's changed words
+    changed: bool,
+    /// Whether this text matches the current search query
+    matched: bool,
+}
+
+/// Merge syntax spans, changed-word ranges and search matches into
+/// non-overlapping segments
+///
+/// Text with no token, change or match is left out, since it uses the line's
+/// default style.
+fn styled_segments(
+    line_len: usize,
+    spans: &[HighlightSpan],
+    changed_ranges: &[Range<usize>],
+    matches: &[Range<usize>],
+) -> Vec<StyledSegment> {
+    let mut boundaries = vec![0, line_len];
+    for range in spans
+        .iter()
+        .map(|s| &s.range)
+        .chain(changed_ranges)
+        .chain(matches)
+    {
+        boundaries.push(range.start.min(line_len));
+        boundaries.push(range.end.min(line_len));
+    }
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    boundaries
+        .windows(2)
+        .filter_map(|bounds| {
+            let (start, end) = (bounds[0], bounds[1]);
+            let token = spans
+                .iter()
+                .find(|s| s.range.contains(&start))
+                .map(|s| s.kind);
+            let changed = changed_ranges.iter().any(|r| r.contains(&start));
+            let matched = matches.iter().any(|r| r.contains(&start));
+            (token.is_some() || changed || matched).then_some(StyledSegment {
+                range: start..end,
+                token,
+                changed,
+                matched,
+            })
+        })
+        .collect()
+}
+
+/// Sort diffs so the files with the largest change come first
+///
+/// Files with equal change sizes keep their original order.
+pub fn sort_by_change_size(diffs: &mut [FileDiff]) {
+    diffs.sort_by_cached_key(|diff| Reverse(diff.stats().total()));
+}
+
+/// A search hit within the rendered lines of a file diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Index of the file in the diff list
+    pub file_index: usize,
+    /// Index of the line in the file's `FileDiff::lines`
+    pub line_index: usize,
+    /// Byte range of the match within the line's content
+    pub range: Range<usize>,
+}
+
+/// Find every case-insensitive occurrence of `query` in a line
+///
+/// Matches don't overlap. An empty query matches nothing.
+fn find_in_line(content: &str, query: &str) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    // ASCII lowercasing keeps byte offsets identical to the original text
+    let content = content.to_ascii_lowercase();
+    let query = query.to_ascii_lowercase();
+    content
+        .match_indices(&query)
+        .map(|(start, matched)| start..start + matched.len())
+        .collect()
+}
+
+/// Collect the matches for `query` across all lines of all diffs, in display order
+pub fn find_matches(diffs: &[FileDiff], query: &str) -> Vec<SearchMatch> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches = Vec::new();
+    for (file_index, diff) in diffs.iter().enumerate() {
+        for (line_index, line) in diff.lines().iter().enumerate() {
+            matches.extend(find_in_line(&line.content, query).into_iter().map(|range| {
+                SearchMatch {
+                    file_index,
+                    line_index,
+                    range,
+                }
+            }));
+        }
+    }
+    matches
+}
+
+/// How diff cards are arranged on the canvas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CardGrid {
+    /// Width of each card
+    pub card_width: f32,
+    /// Gap between neighbouring cards, horizontally and vertically
+    pub card_spacing: f32,
+    /// Number of cards per row (at least 1)
+    pub columns: usize,
+    /// Height of a card's file header; clicks within it toggle the card's view mode
+    pub header_height: f32,
+    /// Height of each diff line in a card
+    pub line_height: f32,
+    /// Vertical padding and borders around a card's lines
+    pub card_padding: f32,
+}
+
+impl Default for CardGrid {
+    fn default() -> Self {
+        Self {
+            card_width: 500.0,
+            card_spacing: 30.0,
+            columns: 3,
+            header_height: 40.0,
+            line_height: 18.0,
+            card_padding: 16.0,
+        }
+    }
+}
+
+impl CardGrid {
+    /// Canvas position (x, y) of the card at `index`, given that card's estimated height
+    pub fn position(&self, index: usize, estimated_height: f32) -> (f32, f32) {
+        let columns = self.columns.max(1);
+        let row = index / columns;
+        let col = index % columns;
+
+        let x = col as f32 * (self.card_width + self.card_spacing);
+        // For now, use a fixed row height - in a real implementation
+        // we'd track actual heights
+        let y = row as f32 * (estimated_height + self.card_spacing);
+        (x, y)
+    }
+
+    /// Natural height of the card for a diff, before it's rendered
+    ///
+    /// The card shows every line of the snapshot's hunks, each `line_height`
+    /// tall, below a `header_height` header, with `card_padding` around them.
+    pub fn card_height(&self, snapshot: &BufferDiffSnapshot) -> f32 {
+        let line_count: usize = snapshot
+            .hunks()
+            .iter()
+            .map(|hunk| hunk.line_types.len())
+            .sum();
+        self.header_height + self.card_padding + line_count as f32 * self.line_height
+    }
+
+    /// Natural height of the card for an image preview
+    pub fn image_card_height(&self, preview: &ImagePreview) -> f32 {
+        self.header_height + self.card_padding + preview.height(self.image_width())
+    }
+
+    /// Width available to an image preview inside a card
+    pub fn image_width(&self) -> f32 {
+        self.card_width - 2.0 * self.card_padding
+    }
+
+    /// Offset from the top of a card to the middle of its row of lines at `row`
+    ///
+    /// Rows depend on the card's view mode (see `FileDiff::display_row`).
+    pub fn line_center(&self, row: usize) -> f32 {
+        self.header_height + (row as f32 + 0.5) * self.line_height
+    }
+
+    /// Offsets from the top of a card to the top and bottom of its row of lines at `row`
+    pub fn line_span(&self, row: usize) -> Range<f32> {
+        let top = self.header_height + row as f32 * self.line_height;
+        top..top + self.line_height
+    }
+}
+
+/// How a diff card lays out its lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiffViewMode {
+    /// Removed and added lines interleaved in one column
+    #[default]
+    Unified,
+    /// Old lines on the left, new lines on the right
+    SideBySide,
+}
+
+impl DiffViewMode {
+    /// The other mode
+    pub fn toggled(self) -> Self {
+        match self {
+            DiffViewMode::Unified => DiffViewMode::SideBySide,
+            DiffViewMode::SideBySide => DiffViewMode::Unified,
+        }
+    }
+
+    /// Short label shown in card headers
+    pub fn label(self) -> &'static str {
+        match self {
+            DiffViewMode::Unified => "Unified",
+            DiffViewMode::SideBySide => "Side by side",
+        }
+    }
+}
+
+/// The global view mode plus per-file overrides, keyed by path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffViewModes {
+    global: DiffViewMode,
+    overrides: HashMap<String, DiffViewMode>,
+}
+
+impl DiffViewModes {
+    /// The mode used by files without an override
+    pub fn global(&self) -> DiffViewMode {
+        self.global
+    }
+
+    /// Change the global mode, dropping overrides that now match it
+    pub fn set_global(&mut self, mode: DiffViewMode) {
+        self.global = mode;
+        self.overrides
+            .retain(|_, override_mode| *override_mode != mode);
+    }
+
+    /// The mode a file is shown in: its override if it has one, else the global mode
+    pub fn effective(&self, path: &str) -> DiffViewMode {
+        self.overrides.get(path).copied().unwrap_or(self.global)
+    }
+
+    /// Switch a file to the other mode
+    ///
+    /// Toggling back to the global mode removes the override, so the file
+    /// follows later changes to the global mode again.
+    pub fn toggle(&mut self, path: &str) {
+        let mode = self.effective(path).toggled();
+        if mode == self.global {
+            self.overrides.remove(path);
+        } else {
+            self.overrides.insert(path.to_string(), mode);
+        }
+    }
+}
+
+/// Pair up diff lines into side-by-side rows of (old, new)
+///
+/// Rows follow `DiffHunk::paired_lines`: context lines appear on both sides,
+/// modified lines next to the lines that replaced them, and the unpaired rest
+/// of a change on one side only.
+pub fn side_by_side_rows<'a>(
+    hunks: &[DiffHunk],
+    lines: &'a [DiffLine],
+) -> Vec<(Option<&'a DiffLine>, Option<&'a DiffLine>)> {
+    let (old_indices, new_indices) = line_indices(lines);
+    let line = |indices: &HashMap<usize, usize>, number: Option<usize>| {
+        number
+            .and_then(|number| indices.get(&number))
+            .map(|&index| &lines[index])
+    };
+
+    hunks
+        .iter()
+        .flat_map(DiffHunk::paired_lines)
+        .map(|paired| {
+            (
+                line(&old_indices, paired.old_line()),
+                line(&new_indices, paired.new_line()),
+            )
+        })
+        .filter(|(old, new)| old.is_some() || new.is_some())
+        .collect()
+}
+
+/// The diff index of a card, from its canvas item id
+fn card_index(id: &str) -> Option<usize> {
+    id.strip_prefix("diff-")?.parse().ok()
+}
+
+/// Events emitted by the diff canvas for the host view to handle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffCanvasEvent {
+    /// A card's header was clicked, asking to switch the file's view mode
+    ToggleViewMode { path: String },
 }
 
 /// A view that displays file diffs on an infinite canvas
@@ -34,15 +575,37 @@ pub struct DiffCanvasView {
     commit_info: Option<(String, String)>, // (short_hash, message)
     /// Flag to indicate that items need to be synced to the provider
     needs_sync: bool,
+    /// Syntax highlighting shared by all card factories
+    highlight_cache: Arc<HighlightCache>,
+    /// Latest canvas zoom, updated by the canvas' camera callback
+    zoom: Rc<Cell<f32>>,
+    /// The detail level the current cards were rendered at
+    card_detail: CardDetail,
+    /// Text highlighted in every diff line, empty when not searching
+    search_query: String,
+    /// Matches for `search_query` in the current diffs
+    search_matches: Vec<SearchMatch>,
+    /// Index into `search_matches` of the match last scrolled to
+    current_match: Option<usize>,
+    /// Shared camera, used to scroll search matches into view
+    canvas_handle: CanvasHandle,
+    /// Card size and arrangement
+    card_grid: CardGrid,
+    /// How each file's card lays out its lines
+    view_modes: DiffViewModes,
+    /// Card clicked on the last mouse down, handled on mouse up
+    pending_click: Rc<RefCell<Option<(ItemId, Point<Pixels>)>>>,
+    /// Card and vertical range highlighted on the canvas, e.g. the current match
+    highlight: Option<(ItemId, Range<Pixels>)>,
 }
 
+impl EventEmitter<DiffCanvasEvent> for DiffCanvasView {}
+
 impl DiffCanvasView {
     pub fn new(_window: &mut Window, _cx: &mut Context<Self>) -> Self {
+        let card_grid = CardGrid::default();
         let provider = Rc::new(RefCell::new(TexturedCanvasItemsProvider::with_sizing(
-            ItemSizing::FixedWidth {
-                width: px(500.0),
-                estimated_height: px(800.0),
-            },
+            Self::card_sizing(&card_grid),
         )));
 
         Self {
@@ -50,6 +613,69 @@ impl DiffCanvasView {
             diffs: Vec::new(),
             commit_info: None,
             needs_sync: false,
+            highlight_cache: Arc::new(HighlightCache::new()),
+            zoom: Rc::new(Cell::new(1.0)),
+            card_detail: CardDetail::Full,
+            search_query: String::new(),
+            search_matches: Vec::new(),
+            current_match: None,
+            canvas_handle: CanvasHandle::new(),
+            card_grid,
+            view_modes: DiffViewModes::default(),
+            pending_click: Rc::default(),
+            highlight: None,
+        }
+    }
+
+    /// The current card size and arrangement
+    pub fn card_grid(&self) -> CardGrid {
+        self.card_grid
+    }
+
+    /// Change the card size and arrangement, re-laying out the cards on the next frame
+    pub fn set_card_grid(&mut self, card_grid: CardGrid, cx: &mut Context<Self>) {
+        if self.card_grid != card_grid {
+            self.card_grid = card_grid;
+            self.provider
+                .borrow_mut()
+                .set_default_sizing(Self::card_sizing(&card_grid));
+            self.needs_sync = true;
+            cx.notify();
+        }
+    }
+
+    /// Set the view mode of each file, re-rendering the cards if they changed
+    pub fn set_view_modes(&mut self, view_modes: DiffViewModes, cx: &mut Context<Self>) {
+        if self.view_modes != view_modes {
+            self.view_modes = view_modes;
+            self.needs_sync = true;
+            cx.notify();
+        }
+    }
+
+    /// Ask the host to toggle a file's view mode if the last click hit its card header
+    fn handle_pending_click(&mut self, cx: &mut Context<Self>) {
+        let Some((id, position)) = self.pending_click.borrow_mut().take() else {
+            return;
+        };
+        let Some(diff) = card_index(id.as_str()).and_then(|index| self.diffs.get(index)) else {
+            return;
+        };
+        let Some(bounds) = self.provider.borrow().bounds(&id) else {
+            return;
+        };
+
+        if f32::from(position.y - bounds.origin.y) <= self.card_grid.header_height {
+            cx.emit(DiffCanvasEvent::ToggleViewMode {
+                path: diff.path.clone(),
+            });
+        }
+    }
+
+    fn card_sizing(card_grid: &CardGrid) -> ItemSizing {
+        ItemSizing::FixedWidth {
+            width: px(card_grid.card_width),
+            estimated_height: px(800.0),
         }
     }
 
@@ -63,12 +689,109 @@ impl DiffCanvasView {
     ) {
         self.diffs = diffs;
         self.commit_info = commit_info;
+        self.update_search_matches();
         self.needs_sync = true;
     }
 
+    /// Set the text to highlight in the diff lines
+    ///
+    /// Cards are re-rendered on the next frame if the query changed.
+    pub fn set_search_query(&mut self, query: &str, cx: &mut Context<Self>) {
+        if self.search_query != query {
+            self.search_query = query.to_string();
+            self.update_search_matches();
+            self.needs_sync = true;
+            cx.notify();
+        }
+    }
+
+    /// Matches for the current search query, in display order
+    pub fn search_matches(&self) -> &[SearchMatch] {
+        &self.search_matches
+    }
+
+    /// Index of the match last scrolled to, if any
+    pub fn current_match(&self) -> Option<usize> {
+        self.current_match
+    }
+
+    /// Scroll to the match after the current one, wrapping around
+    pub fn select_next_match(&mut self, cx: &mut Context<Self>) {
+        let count = self.search_matches.len();
+        if count > 0 {
+            let index = self.current_match.map_or(0, |i| (i + 1) % count);
+            self.select_match(index, cx);
+        }
+    }
+
+    /// Scroll to the match before the current one, wrapping around
+    pub fn select_previous_match(&mut self, cx: &mut Context<Self>) {
+        let count = self.search_matches.len();
+        if count > 0 {
+            let index = self
+                .current_match
+                .map_or(count - 1, |i| (i + count - 1) % count);
+            self.select_match(index, cx);
+        }
+    }
+
+    /// Draw a highlight band across a card, `y_range` below the card's top
+    ///
+    /// Replaces any previous highlight. The band moves and scales with the card
+    /// as the canvas pans and zooms.
+    pub fn highlight_region(
+        &mut self,
+        id: impl Into<ItemId>,
+        y_range: Range<Pixels>,
+        cx: &mut Context<Self>,
+    ) {
+        self.highlight = Some((id.into(), y_range));
+        cx.notify();
+    }
+
+    /// Remove the highlight set by `highlight_region`
+    pub fn clear_highlight(&mut self, cx: &mut Context<Self>) {
+        if self.highlight.take().is_some() {
+            cx.notify();
+        }
+    }
+
+    fn update_search_matches(&mut self) {
+        self.search_matches = find_matches(&self.diffs, &self.search_query);
+        self.current_match = None;
+        self.highlight = None;
+    }
+
+    fn select_match(&mut self, index: usize, cx: &mut Context<Self>) {
+        self.current_match = Some(index);
+        let search_match = &self.search_matches[index];
+        let id = format!("diff-{}", search_match.file_index);
+
+        let Some(card_bounds) = self.provider.borrow().bounds(&id) else {
+            return;
+        };
+
+        // Side-by-side cards have fewer rows than lines
+        let diff = &self.diffs[search_match.file_index];
+        let mode = self.view_modes.effective(&diff.path);
+        let row = diff.display_row(search_match.line_index, mode);
+
+        let y = card_bounds.origin.y + px(self.card_grid.line_center(row));
+        self.canvas_handle
+            .center_on(point(card_bounds.center().x, y));
+        let line = self.card_grid.line_span(row);
+        self.highlight_region(id, px(line.start)..px(line.end), cx);
+    }
+
     /// Sync the provider items with the current diffs.
     /// This is called during render when we have window access.
     fn sync_items_if_needed(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        // Re-create the cards when zooming crosses the level-of-detail threshold
+        if let Some(detail) = self.card_detail.crossed(self.zoom.get()) {
+            self.card_detail = detail;
+            self.needs_sync = true;
+        }
+
         if !self.needs_sync {
             return;
         }
@@ -78,104 +801,98 @@ impl DiffCanvasView {
         self.provider.borrow_mut().clear();
 
         // Layout diffs in a grid pattern
-        let card_width = 500.0;
-        let card_spacing = 30.0;
-        let cards_per_row = 3;
-
         for (i, diff) in self.diffs.iter().enumerate() {
-            let row = i / cards_per_row;
-            let col = i % cards_per_row;
-
-            let x = col as f32 * (card_width + card_spacing);
-            // Estimate height based on diff size
-            let estimated_height = Self::estimate_diff_height(diff);
-            let y = if row == 0 {
-                0.0
-            } else {
-                // For now, use a fixed row height - in a real implementation
-                // we'd track actual heights
-                row as f32 * (estimated_height + card_spacing)
+            // Start each card at its natural height, so the grid and fitting the
+            // camera to the cards are right before the textures are measured
+            let height = match &diff.image {
+                Some(preview) => self.card_grid.image_card_height(preview),
+                None => self.card_grid.card_height(&diff.buffer_diff.snapshot()),
             };
+            let (x, y) = self.card_grid.position(i, height);
+            let id = format!("diff-{}", i);
+            self.provider.borrow_mut().add_placeholder(
+                id.as_str(),
+                Bounds::new(
+                    point(px(x), px(y)),
+                    size(px(self.card_grid.card_width), px(height)),
+                ),
+            );
 
             let diff_clone = diff.clone();
-            self.provider.borrow_mut().add_item(
-                format!("diff-{}", i),
-                point(px(x), px(y)),
-                window,
-                cx,
-                move || Self::render_diff_card(&diff_clone),
-            );
+            let highlight_cache = self.highlight_cache.clone();
+            let detail = self.card_detail;
+            let search_query = self.search_query.clone();
+            let view_mode = self.view_modes.effective(&diff.path);
+            let image_width = self.card_grid.image_width();
+            self.provider
+                .borrow_mut()
+                .set_factory(id, window, cx, move || match (&diff_clone.image, detail) {
+                    (Some(preview), _) => {
+                        Self::render_image_card(&diff_clone.path, preview, image_width)
+                    }
+                    (None, CardDetail::Full) => Self::render_diff_card(
+                        &diff_clone,
+                        &highlight_cache,
+                        &search_query,
+                        view_mode,
+                    ),
+                    (None, CardDetail::Overview) => Self::render_diff_card_overview(&diff_clone),
+                });
         }
     }
 
-    /// Estimate the height of a diff card based on content
-    fn estimate_diff_height(diff: &FileDiff) -> f32 {
-        let line_count = diff
-            .buffer_diff
-            .hunks()
-            .iter()
-            .fold(0, |acc, hunk| acc + hunk.line_types.len());
-        // Header (40) + padding (16) + lines (18 each)
-        40.0 + 16.0 + (line_count as f32 * 18.0)
-    }
-
     /// Render a single diff as a card element
-    fn render_diff_card(diff: &FileDiff) -> AnyElement {
+    fn render_diff_card(
+        diff: &FileDiff,
+        highlight_cache: &HighlightCache,
+        search_query: &str,
+        view_mode: DiffViewMode,
+    ) -> AnyElement {
         let path = diff.path.clone();
-        let old_lines: Vec<&str> = diff.old_content.lines().collect();
-        let new_lines: Vec<&str> = diff.new_content.lines().collect();
-        let hunks = diff.buffer_diff.hunks();
-
-        // Collect all diff lines
-        let mut diff_lines: Vec<(Option<usize>, Option<usize>, String, DiffLineKind)> = Vec::new();
-
-        for hunk in hunks.iter() {
-            let mut old_offset = 0;
-            let mut new_offset = 0;
-
-            for &line_type in hunk.line_types.iter() {
-                match line_type {
-                    DiffLineType::OldOnly => {
-                        let old_line_idx = hunk.old_range.start + old_offset;
-                        if let Some(line_content) = old_lines.get(old_line_idx) {
-                            diff_lines.push((
-                                Some(old_line_idx + 1),
-                                None,
-                                line_content.to_string(),
-                                DiffLineKind::Removed,
-                            ));
-                        }
-                        old_offset += 1;
-                    }
-                    DiffLineType::NewOnly => {
-                        let new_line_idx = hunk.new_range.start + new_offset;
-                        if let Some(line_content) = new_lines.get(new_line_idx) {
-                            diff_lines.push((
-                                None,
-                                Some(new_line_idx + 1),
-                                line_content.to_string(),
-                                DiffLineKind::Added,
-                            ));
-                        }
-                        new_offset += 1;
-                    }
-                    DiffLineType::Both => {
-                        let old_line_idx = hunk.old_range.start + old_offset;
-                        let new_line_idx = hunk.new_range.start + new_offset;
-                        if let Some(line_content) = old_lines.get(old_line_idx) {
-                            diff_lines.push((
-                                Some(old_line_idx + 1),
-                                Some(new_line_idx + 1),
-                                line_content.to_string(),
-                                DiffLineKind::Context,
-                            ));
-                        }
-                        old_offset += 1;
-                        new_offset += 1;
-                    }
-                }
+
+        let (old_highlights, new_highlights) = match language_for_path(&diff.path) {
+            Some(language) => (
+                highlight_cache.highlight(&language, &diff.old_content),
+                highlight_cache.highlight(&language, &diff.new_content),
+            ),
+            None => Default::default(),
+        };
+        let spans_for = |line: &DiffLine| {
+            // Context lines are identical on both sides, so the old side's spans apply
+            let spans = match (line.kind, line.old_line_number, line.new_line_number) {
+                (DiffLineKind::Added, _, Some(n)) => new_highlights.get(n - 1),
+                (_, Some(n), _) => old_highlights.get(n - 1),
+                _ => None,
+            };
+            spans.cloned().unwrap_or_default()
+        };
+
+        let lines = diff.lines();
+        let content = match view_mode {
+            DiffViewMode::Unified => v_flex().w_full().children(lines.iter().map(|line| {
+                let matches = find_in_line(&line.content, search_query);
+                Self::render_diff_line_element(line, &spans_for(line), &matches)
+            })),
+            DiffViewMode::SideBySide => {
+                let rows = side_by_side_rows(diff.buffer_diff.hunks(), &lines);
+                v_flex()
+                    .w_full()
+                    .children(rows.into_iter().map(|(old, new)| {
+                        let half = |line: Option<&DiffLine>, number: Option<usize>| {
+                            let spans = line.map(&spans_for).unwrap_or_default();
+                            let matches = line
+                                .map(|line| find_in_line(&line.content, search_query))
+                                .unwrap_or_default();
+                            Self::render_side_by_side_half(line, number, &spans, &matches)
+                        };
+                        h_flex()
+                            .w_full()
+                            .child(half(old, old.and_then(|line| line.old_line_number)))
+                            .child(div().w(px(1.)).h_full().bg(rgb(0x3c3c3c)))
+                            .child(half(new, new.and_then(|line| line.new_line_number)))
+                    }))
             }
-        }
+        };
 
         // Build the card
         div()
@@ -186,7 +903,7 @@ impl DiffCanvasView {
             .overflow_hidden()
             .border_1()
             .border_color(rgb(0x3c3c3c))
-            // File header
+            // File header; clicking it toggles the view mode
             .child(
                 div()
                     .w_full()
@@ -202,38 +919,219 @@ impl DiffCanvasView {
                             .child(div().text_sm().text_color(rgb(0x8b949e)).child("📄"))
                             .child(
                                 div()
+                                    .flex_1()
                                     .text_sm()
                                     .font_weight(FontWeight::SEMIBOLD)
                                     .text_color(rgb(0xe6edf3))
                                     .child(path),
+                            )
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(rgb(0x8b949e))
+                                    .child(view_mode.label()),
                             ),
                     ),
             )
             // Diff content
+            .child(div().w_full().child(content))
+            .when(diff.buffer_diff.is_suppressed(), |card| {
+                card.child(
+                    div()
+                        .px_3()
+                        .py_2()
+                        .text_xs()
+                        .text_color(rgb(0x8b949e))
+                        .child(BufferDiff::SUPPRESSED_MESSAGE),
+                )
+            })
+            .when_some(diff.whitespace_note(), |card, note| {
+                card.child(
+                    div()
+                        .px_3()
+                        .py_2()
+                        .text_xs()
+                        .text_color(rgb(0x8b949e))
+                        .child(note),
+                )
+            })
+            .when(diff.buffer_diff.is_binary(), |card| {
+                card.child(
+                    div()
+                        .px_3()
+                        .py_2()
+                        .text_xs()
+                        .text_color(rgb(0x8b949e))
+                        .child(BufferDiff::BINARY_MESSAGE),
+                )
+            })
+            .into_any_element()
+    }
+
+    /// Render an image file as a card showing its before and after versions
+    fn render_image_card(path: &str, preview: &ImagePreview, width: f32) -> AnyElement {
+        let [old_pane, new_pane] = preview.panes(width);
+        let pane = |pane: ImagePane, image: &Option<Arc<image::RgbaImage>>| {
+            let rendered = image.as_ref().and_then(|image| {
+                infinite_canvas::pixels_to_render_image(
+                    image.as_raw(),
+                    image.width(),
+                    image.height(),
+                )
+            });
+            let caption = match pane.dimensions {
+                Some((w, h)) => format!("{} ({}×{})", pane.label, w, h),
+                None => pane.label.to_string(),
+            };
+            let (w, h) = pane.display_size;
+            v_flex()
+                .flex_1()
+                .gap_1()
+                .child(
+                    div()
+                        .h(px(IMAGE_LABEL_HEIGHT))
+                        .text_xs()
+                        .text_color(rgb(0x8b949e))
+                        .child(caption),
+                )
+                .when_some(rendered, |pane, rendered| {
+                    pane.child(img(Arc::new(rendered)).w(px(w)).h(px(h)))
+                })
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .bg(rgb(0x1e1e1e))
+            .rounded_lg()
+            .overflow_hidden()
+            .border_1()
+            .border_color(rgb(0x3c3c3c))
             .child(
                 div()
                     .w_full()
-                    .child(v_flex().w_full().children(diff_lines.into_iter().map(
-                        |(old_num, new_num, content, kind)| {
-                            Self::render_diff_line_element(old_num, new_num, &content, kind)
-                        },
-                    ))),
+                    .px_3()
+                    .py_2()
+                    .bg(rgb(0x2d2d2d))
+                    .border_b_1()
+                    .border_color(rgb(0x3c3c3c))
+                    .text_sm()
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .text_color(rgb(0xe6edf3))
+                    .child(path.to_string()),
+            )
+            .child(
+                h_flex()
+                    .w(px(width))
+                    .mx_auto()
+                    .py(px(8.))
+                    .gap(px(IMAGE_PANE_GAP))
+                    .items_start()
+                    .child(pane(old_pane, &preview.old))
+                    .child(pane(new_pane, &preview.new)),
             )
             .into_any_element()
     }
 
-    /// Render a single diff line
-    fn render_diff_line_element(
-        old_line_num: Option<usize>,
-        new_line_num: Option<usize>,
-        content: &str,
-        kind: DiffLineKind,
-    ) -> AnyElement {
-        let (bg_color, sign, text_color) = match kind {
+    /// Render a diff as a simplified card for low zoom levels
+    ///
+    /// Each line is drawn as a colored bar whose length follows the line's
+    /// content, which is much cheaper than shaping highlighted text. Rows keep
+    /// the same height as the full card so the layout doesn't shift.
+    fn render_diff_card_overview(diff: &FileDiff) -> AnyElement {
+        div()
+            .flex()
+            .flex_col()
+            .bg(rgb(0x1e1e1e))
+            .rounded_lg()
+            .overflow_hidden()
+            .border_1()
+            .border_color(rgb(0x3c3c3c))
+            .child(
+                div()
+                    .w_full()
+                    .px_3()
+                    .py_2()
+                    .bg(rgb(0x2d2d2d))
+                    .border_b_1()
+                    .border_color(rgb(0x3c3c3c))
+                    .text_sm()
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .text_color(rgb(0xe6edf3))
+                    .child(diff.path.clone()),
+            )
+            .child(
+                v_flex()
+                    .w_full()
+                    .children(diff.lines().into_iter().map(|line| {
+                        let bar_color = match line.kind {
+                            DiffLineKind::Added => rgb(0x3fb950),
+                            DiffLineKind::Removed => rgb(0xf85149),
+                            DiffLineKind::Context => rgb(0x3c3c3c),
+                        };
+                        let fraction =
+                            (line.content.trim_end().len() as f32 / 100.0).clamp(0.02, 1.0);
+
+                        div().w_full().h(px(18.)).px_2().py(px(5.)).child(
+                            div()
+                                .h_full()
+                                .w(relative(fraction))
+                                .rounded_sm()
+                                .bg(bar_color),
+                        )
+                    })),
+            )
+            .into_any_element()
+    }
+
+    /// Background, gutter sign and text color for a line kind
+    fn line_colors(kind: DiffLineKind) -> (Rgba, &'static str, Rgba) {
+        match kind {
             DiffLineKind::Added => (rgb(0x1a3d2e), "+", rgb(0x3fb950)),
             DiffLineKind::Removed => (rgb(0x3d1a1a), "-", rgb(0xf85149)),
             DiffLineKind::Context => (rgb(0x1e1e1e), " ", rgb(0xcccccc)),
+        }
+    }
+
+    /// The line's text, styled with syntax, changed-word and search highlights
+    fn styled_line(
+        line: &DiffLine,
+        spans: &[HighlightSpan],
+        matches: &[Range<usize>],
+    ) -> StyledText {
+        let changed_bg = match line.kind {
+            DiffLineKind::Added => rgba(0x2ea04366),
+            DiffLineKind::Removed => rgba(0xf8514966),
+            DiffLineKind::Context => rgba(0x00000000),
         };
+        let highlights = styled_segments(line.content.len(), spans, &line.changed_ranges, matches)
+            .into_iter()
+            .map(|segment| {
+                let background = if segment.matched {
+                    Some(rgba(0xd2992299).into())
+                } else {
+                    segment.changed.then_some(changed_bg.into())
+                };
+                (
+                    segment.range,
+                    HighlightStyle {
+                        color: segment.token.map(|kind| rgb(kind.color()).into()),
+                        background_color: background,
+                        ..Default::default()
+                    },
+                )
+            });
+
+        StyledText::new(line.content.clone()).with_highlights(highlights)
+    }
+
+    /// Render a single diff line
+    fn render_diff_line_element(
+        line: &DiffLine,
+        spans: &[HighlightSpan],
+        matches: &[Range<usize>],
+    ) -> AnyElement {
+        let (bg_color, sign, text_color) = Self::line_colors(line.kind);
 
         h_flex()
             .w_full()
@@ -247,11 +1145,60 @@ impl DiffCanvasView {
                     .text_color(rgb(0x6e7681))
                     .child(format!(
                         "{:>4}",
-                        old_line_num
+                        line.old_line_number
+                            .map(|n| n.to_string())
+                            .unwrap_or_else(|| " ".to_string())
+                    )),
+            )
+            .child(
+                div()
+                    .w(px(35.))
+                    .text_xs()
+                    .text_color(rgb(0x6e7681))
+                    .child(format!(
+                        "{:>4}",
+                        line.new_line_number
                             .map(|n| n.to_string())
                             .unwrap_or_else(|| " ".to_string())
                     )),
             )
+            .child(
+                div()
+                    .w(px(15.))
+                    .text_xs()
+                    .text_color(text_color)
+                    .child(sign.to_string()),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .text_xs()
+                    .font_family("monospace")
+                    .text_color(text_color)
+                    .child(Self::styled_line(line, spans, matches)),
+            )
+            .into_any_element()
+    }
+
+    /// Render one side of a side-by-side row, or a blank filler if that side has no line
+    fn render_side_by_side_half(
+        line: Option<&DiffLine>,
+        line_number: Option<usize>,
+        spans: &[HighlightSpan],
+        matches: &[Range<usize>],
+    ) -> AnyElement {
+        let Some(line) = line else {
+            return div().flex_1().h_full().bg(rgb(0x252525)).into_any_element();
+        };
+        let (bg_color, sign, text_color) = Self::line_colors(line.kind);
+
+        h_flex()
+            .flex_1()
+            .min_w_0()
+            .overflow_hidden()
+            .bg(bg_color)
+            .px_2()
+            .py_0p5()
             .child(
                 div()
                     .w(px(35.))
@@ -259,7 +1206,7 @@ impl DiffCanvasView {
                     .text_color(rgb(0x6e7681))
                     .child(format!(
                         "{:>4}",
-                        new_line_num
+                        line_number
                             .map(|n| n.to_string())
                             .unwrap_or_else(|| " ".to_string())
                     )),
@@ -277,7 +1224,7 @@ impl DiffCanvasView {
                     .text_xs()
                     .font_family("monospace")
                     .text_color(text_color)
-                    .child(content.to_string()),
+                    .child(Self::styled_line(line, spans, matches)),
             )
             .into_any_element()
     }
@@ -288,13 +1235,56 @@ impl DiffCanvasView {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum DiffLineKind {
+/// Whether a rendered diff line was added, removed, or is unchanged context
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLineKind {
     Added,
     Removed,
     Context,
 }
 
+/// A single line of a file diff, ready to be rendered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    /// 1-based line number in the old content
+    pub old_line_number: Option<usize>,
+    /// 1-based line number in the new content
+    pub new_line_number: Option<usize>,
+    pub content: String,
+    pub kind: DiffLineKind,
+    /// Byte ranges of `content` that differ from the paired line on the other side
+    pub changed_ranges: Vec<Range<usize>>,
+}
+
+/// Zoom level below which diff cards switch to the simplified overview
+pub const OVERVIEW_ZOOM_THRESHOLD: f32 = 0.4;
+
+/// How much detail diff cards are rendered with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardDetail {
+    /// Full syntax-highlighted text
+    Full,
+    /// Colored bars per line, used when zoomed out too far to read text
+    Overview,
+}
+
+impl CardDetail {
+    /// The detail level to use at a zoom level
+    pub fn for_zoom(zoom: f32) -> Self {
+        if zoom < OVERVIEW_ZOOM_THRESHOLD {
+            CardDetail::Overview
+        } else {
+            CardDetail::Full
+        }
+    }
+
+    /// The new detail level if `zoom` crosses the threshold from this level
+    pub fn crossed(self, zoom: f32) -> Option<Self> {
+        let detail = Self::for_zoom(zoom);
+        (detail != self).then_some(detail)
+    }
+}
+
 impl Render for DiffCanvasView {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         // If no content, show placeholder
@@ -337,15 +1327,36 @@ impl Render for DiffCanvasView {
             .bg(cx.theme().background)
             .overflow_hidden()
             // Canvas - using InfiniteCanvas like the textured example
-            .child(
-                InfiniteCanvas::new("diff-canvas", self.provider.clone()).options(
-                    CanvasOptions::new()
-                        .min_zoom(0.1)
-                        .max_zoom(3.0)
-                        .zoom_speed(2.0)
-                        .show_grid(true),
-                ),
+            .on_mouse_up(
+                MouseButton::Left,
+                cx.listener(|this, _event: &MouseUpEvent, _window, cx| {
+                    this.handle_pending_click(cx);
+                }),
             )
+            .child({
+                let zoom = self.zoom.clone();
+                let pending_click = self.pending_click.clone();
+                let canvas = InfiniteCanvas::new("diff-canvas", self.provider.clone())
+                    .options(
+                        CanvasOptions::new()
+                            .min_zoom(0.1)
+                            .max_zoom(3.0)
+                            .zoom_speed(2.0)
+                            .show_grid(true),
+                    )
+                    .handle(self.canvas_handle.clone())
+                    .background(cx.theme().background)
+                    .on_camera_change(move |camera| zoom.set(camera.zoom))
+                    .on_event(move |event| {
+                        if let CanvasEvent::ItemClicked { id, position } = event {
+                            *pending_click.borrow_mut() = Some((id.clone(), *position));
+                        }
+                    });
+                match self.highlight.clone() {
+                    Some((id, y_range)) => canvas.highlight_region(id, y_range),
+                    None => canvas,
+                }
+            })
             // Controls overlay - commit info
             .child(div().absolute().top_3().left_3().flex().gap_2().when_some(
                 commit_info,
@@ -373,8 +1384,371 @@ impl Render for DiffCanvasView {
                     .rounded_md()
                     .text_xs()
                     .text_color(cx.theme().muted_foreground)
-                    .child("Middle-click to pan • Scroll to zoom"),
+                    .child(
+                        "Middle-click to pan • Scroll to zoom • Click a file header to switch view",
+                    ),
             )
             .into_any_element()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_diff(path: &str, old: &str, new: &str) -> FileDiff {
+        FileDiff {
+            path: path.to_string(),
+            old_content: old.to_string(),
+            new_content: new.to_string(),
+            buffer_diff: BufferDiff::new(old, new).unwrap(),
+            image: None,
+        }
+    }
+
+    #[test]
+    fn test_card_detail_threshold_crossing() {
+        assert_eq!(CardDetail::for_zoom(1.0), CardDetail::Full);
+        assert_eq!(
+            CardDetail::for_zoom(OVERVIEW_ZOOM_THRESHOLD),
+            CardDetail::Full
+        );
+        assert_eq!(CardDetail::for_zoom(0.1), CardDetail::Overview);
+
+        // Zooming within a level doesn't trigger a swap
+        assert_eq!(CardDetail::Full.crossed(2.5), None);
+        assert_eq!(CardDetail::Full.crossed(0.5), None);
+        assert_eq!(CardDetail::Overview.crossed(0.2), None);
+
+        // Crossing the threshold in either direction does
+        assert_eq!(CardDetail::Full.crossed(0.3), Some(CardDetail::Overview));
+        assert_eq!(CardDetail::Overview.crossed(0.5), Some(CardDetail::Full));
+    }
+
+    #[test]
+    fn test_sort_by_change_size() {
+        let mut diffs = vec![
+            file_diff("small.rs", "a\nb\nc\n", "a\nB\nc\n"),
+            file_diff("large.rs", "a\nb\nc\n", "x\ny\nz\nw\n"),
+            file_diff("medium.rs", "a\nb\nc\n", "a\nb\nc\nd\ne\nf\n"),
+        ];
+
+        let stats = CommitDiffStats::from_diffs(&diffs);
+        assert_eq!(stats.files.len(), 3);
+        assert_eq!(stats.additions, 1 + 4 + 3);
+        assert_eq!(stats.deletions, 1 + 3);
+
+        sort_by_change_size(&mut diffs);
+        let paths: Vec<_> = diffs.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, vec!["large.rs", "medium.rs", "small.rs"]);
+
+        // A file too large to diff still counts every line it replaced
+        let old: String = (0..50).map(|i| format!("line {}\n", i)).collect();
+        let new = old.replace("line 7\n", "line seven\n");
+        diffs.push(FileDiff {
+            path: "huge.rs".to_string(),
+            buffer_diff: BufferDiff::suppressed(&old, &new),
+            old_content: old,
+            new_content: new,
+            image: None,
+        });
+        assert_eq!(diffs[3].stats().total(), 100);
+        sort_by_change_size(&mut diffs);
+        assert_eq!(diffs[0].path, "huge.rs");
+    }
+
+    #[test]
+    fn test_word_granularity_ranges_are_used() {
+        let (old, new) = ("a = f(x, y)\n", "a = f(x, z)\n");
+        let diff = FileDiff {
+            path: "lib.rs".to_string(),
+            old_content: old.to_string(),
+            new_content: new.to_string(),
+            buffer_diff: buffer_diff::DiffConfig::default()
+                .granularity(buffer_diff::DiffGranularity::Word)
+                .diff(old, new)
+                .unwrap(),
+            image: None,
+        };
+
+        // Only the changed argument is marked, not the widened middle of the line
+        let lines = diff.lines();
+        assert_eq!(&lines[0].content[lines[0].changed_ranges[0].clone()], "y");
+        assert_eq!(&lines[1].content[lines[1].changed_ranges[0].clone()], "z");
+        assert_eq!(lines[1].changed_ranges.len(), 1);
+    }
+
+    #[test]
+    fn test_changed_words_are_styled() {
+        let diff = file_diff(
+            "lib.rs",
+            "let total = count + 1;\n",
+            "let total = amount + 1;\n",
+        );
+        let lines = diff.lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].kind, DiffLineKind::Removed);
+        assert_eq!(lines[0].changed_ranges, vec![12..17]);
+        assert_eq!(&lines[0].content[12..17], "count");
+        assert_eq!(lines[1].changed_ranges, vec![12..18]);
+        assert_eq!(&lines[1].content[12..18], "amount");
+
+        // `let` is a keyword, `1` a number; the renamed identifier is emphasized
+        let spans = crate::highlight::HighlightCache::new()
+            .highlight(&language_for_path("lib.rs").unwrap(), &lines[1].content)[0]
+            .clone();
+        let segments = styled_segments(
+            lines[1].content.len(),
+            &spans,
+            &lines[1].changed_ranges,
+            &[],
+        );
+        assert_eq!(
+            segments,
+            vec![
+                StyledSegment {
+                    range: 0..3,
+                    token: Some(TokenKind::Keyword),
+                    changed: false,
+                    matched: false,
+                },
+                StyledSegment {
+                    range: 12..18,
+                    token: None,
+                    changed: true,
+                    matched: false,
+                },
+                StyledSegment {
+                    range: 21..22,
+                    token: Some(TokenKind::Number),
+                    changed: false,
+                    matched: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_added_run_before_modified_pair() {
+        let diff = file_diff("notes.txt", "x\nalpha beta\n", "new\nx\nalpha gamma\n");
+        let lines = diff.lines();
+        let kinds: Vec<_> = lines.iter().map(|line| line.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DiffLineKind::Added,
+                DiffLineKind::Context,
+                DiffLineKind::Removed,
+                DiffLineKind::Added,
+            ]
+        );
+
+        assert!(lines[0].changed_ranges.is_empty());
+        assert_eq!(lines[2].changed_ranges, vec![6..10]);
+        assert_eq!(lines[3].changed_ranges, vec![6..11]);
+    }
+
+    #[test]
+    fn test_fully_rewritten_line_has_no_changed_ranges() {
+        let diff = file_diff("notes.txt", "alpha\n", "omega\n");
+        assert!(diff.lines().iter().all(|l| l.changed_ranges.is_empty()));
+    }
+
+    #[test]
+    fn test_whitespace_note() {
+        let diff = file_diff("notes.txt", "alpha\nbeta\n", "alpha  \nbeta\t\n");
+        assert_eq!(
+            diff.whitespace_note().as_deref(),
+            Some("trailing whitespace only")
+        );
+
+        let diff = file_diff(
+            "notes.txt",
+            "alpha\n  beta\ngamma",
+            "alpha  \nbeta\ngamma\n",
+        );
+        assert_eq!(diff.whitespace_note().as_deref(), Some("whitespace only"));
+
+        assert_eq!(
+            file_diff("notes.txt", "alpha\n", "omega\n").whitespace_note(),
+            None
+        );
+        assert_eq!(
+            file_diff("notes.txt", "alpha\n", "alpha\n").whitespace_note(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_crlf_gutter_numbers_match_hunks() {
+        let old = "one\r\ntwo\r\nthree\r\nfour\r\n";
+        let new = "one\r\nTWO\r\nthree\r\nfour\r\nfive";
+        let diff = file_diff("crlf.txt", old, new);
+        let lines = diff.lines();
+
+        // Every line the hunks cover is rendered, without stray carriage returns
+        let hunk_lines: usize = diff
+            .buffer_diff
+            .hunks()
+            .iter()
+            .map(|hunk| hunk.line_types.len())
+            .sum();
+        assert_eq!(lines.len(), hunk_lines);
+        assert!(lines.iter().all(|line| !line.content.contains('\r')));
+
+        // Gutter numbers point at the lines the hunks say changed
+        let removed: Vec<(usize, &str)> = lines
+            .iter()
+            .filter(|line| line.kind == DiffLineKind::Removed)
+            .map(|line| (line.old_line_number.unwrap(), line.content.as_str()))
+            .collect();
+        let added: Vec<(usize, &str)> = lines
+            .iter()
+            .filter(|line| line.kind == DiffLineKind::Added)
+            .map(|line| (line.new_line_number.unwrap(), line.content.as_str()))
+            .collect();
+        assert_eq!(removed, vec![(2, "two")]);
+        assert_eq!(added, vec![(2, "TWO"), (5, "five")]);
+
+        // A new CRLF file without a trailing newline renders all of its lines
+        let diff = file_diff("new.txt", "", "one\r\ntwo");
+        let numbers: Vec<Option<usize>> = diff.lines().iter().map(|l| l.new_line_number).collect();
+        assert_eq!(numbers, vec![Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn test_find_matches_across_files() {
+        let diffs = vec![
+            file_diff("a.rs", "let foo = 1;\n", "let foo = 2;\nFOO_BAR\n"),
+            file_diff("b.rs", "nothing here\n", "food foo\n"),
+        ];
+
+        let matches = find_matches(&diffs, "Foo");
+        let found: Vec<(usize, usize, Range<usize>)> = matches
+            .into_iter()
+            .map(|m| (m.file_index, m.line_index, m.range))
+            .collect();
+
+        // a.rs renders as: -let foo = 1; / +let foo = 2; / +FOO_BAR
+        // b.rs renders as: -nothing here / +food foo
+        assert_eq!(
+            found,
+            vec![
+                (0, 0, 4..7),
+                (0, 1, 4..7),
+                (0, 2, 0..3),
+                (1, 1, 0..3),
+                (1, 1, 5..8),
+            ]
+        );
+
+        assert!(find_matches(&diffs, "").is_empty());
+        assert!(find_matches(&diffs, "missing").is_empty());
+    }
+
+    #[test]
+    fn test_card_grid_positions() {
+        let grid = CardGrid {
+            card_width: 200.0,
+            card_spacing: 10.0,
+            columns: 2,
+            ..CardGrid::default()
+        };
+
+        assert_eq!(grid.position(0, 100.0), (0.0, 0.0));
+        assert_eq!(grid.position(1, 100.0), (210.0, 0.0));
+        assert_eq!(grid.position(2, 100.0), (0.0, 110.0));
+        assert_eq!(grid.position(3, 100.0), (210.0, 110.0));
+
+        // A zero column count still lays cards out, one per row
+        let single = CardGrid { columns: 0, ..grid };
+        assert_eq!(single.position(1, 100.0), (0.0, 110.0));
+    }
+
+    #[test]
+    fn test_card_height_from_snapshot() {
+        // A new file shows all three of its lines
+        let diff = BufferDiff::new("", "one\ntwo\nthree\n").unwrap();
+        let snapshot = diff.snapshot();
+
+        let grid = CardGrid {
+            header_height: 30.0,
+            line_height: 20.0,
+            card_padding: 10.0,
+            ..CardGrid::default()
+        };
+        assert_eq!(grid.card_height(&snapshot), 30.0 + 10.0 + 3.0 * 20.0);
+        assert_eq!(grid.line_center(2), 30.0 + 2.5 * 20.0);
+
+        // The defaults match the rendered card
+        assert_eq!(
+            CardGrid::default().card_height(&snapshot),
+            40.0 + 16.0 + 3.0 * 18.0
+        );
+    }
+
+    #[test]
+    fn test_display_row_follows_view_mode() {
+        let diff = file_diff("a.txt", "a\nb\nc\nd\n", "a\nB\nC\nx\nd\n");
+        let contents: Vec<_> = diff.lines().into_iter().map(|line| line.content).collect();
+        assert_eq!(contents, vec!["a", "b", "B", "c", "C", "x", "d"]);
+
+        let rows: Vec<_> = (0..contents.len())
+            .map(|index| diff.display_row(index, DiffViewMode::SideBySide))
+            .collect();
+        assert_eq!(rows, vec![0, 1, 1, 2, 2, 3, 4]);
+        assert_eq!(diff.display_row(6, DiffViewMode::Unified), 6);
+    }
+
+    #[test]
+    fn test_view_mode_override_vs_global() {
+        let mut modes = DiffViewModes::default();
+        assert_eq!(modes.effective("data.json"), DiffViewMode::Unified);
+
+        // An override only affects its own file
+        modes.toggle("data.json");
+        assert_eq!(modes.effective("data.json"), DiffViewMode::SideBySide);
+        assert_eq!(modes.effective("main.rs"), DiffViewMode::Unified);
+
+        // Files without an override follow the global mode
+        modes.set_global(DiffViewMode::SideBySide);
+        assert_eq!(modes.effective("main.rs"), DiffViewMode::SideBySide);
+        modes.toggle("main.rs");
+        assert_eq!(modes.effective("main.rs"), DiffViewMode::Unified);
+        assert_eq!(modes.effective("README.md"), DiffViewMode::SideBySide);
+
+        // Toggling back to the global mode drops the override
+        modes.toggle("main.rs");
+        modes.set_global(DiffViewMode::Unified);
+        assert_eq!(modes.effective("main.rs"), DiffViewMode::Unified);
+        assert_eq!(modes.effective("data.json"), DiffViewMode::Unified);
+    }
+
+    #[test]
+    fn test_side_by_side_rows_pair_removed_and_added() {
+        let diff = file_diff("a.txt", "a\nb\nc\nd\n", "a\nB\nC\nx\nd\n");
+        let lines = diff.lines();
+        let rows: Vec<_> = side_by_side_rows(diff.buffer_diff.hunks(), &lines)
+            .into_iter()
+            .map(|(old, new)| {
+                (
+                    old.map(|line| line.content.as_str()),
+                    new.map(|line| line.content.as_str()),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                (Some("a"), Some("a")),
+                (Some("b"), Some("B")),
+                (Some("c"), Some("C")),
+                (None, Some("x")),
+                (Some("d"), Some("d")),
+            ]
+        );
+        assert_eq!(card_index("diff-12"), Some(12));
+        assert_eq!(card_index("other"), None);
+    }
+}
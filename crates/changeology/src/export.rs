@@ -0,0 +1,141 @@
+//! Export of commit diffs as a self-contained HTML page
+//!
+//! The generated page has no external assets, so it can be attached to a
+//! review or opened directly in a browser.
+
+use crate::diff_canvas::{DiffLine, DiffLineKind, FileDiff};
+
+const STYLE: &str = "\
+body { background: #1e1e1e; color: #cccccc; font-family: sans-serif; margin: 24px; }
+h1 { font-size: 18px; color: #e6edf3; }
+.file { border: 1px solid #3c3c3c; border-radius: 6px; margin-bottom: 24px; overflow: hidden; }
+.file-header { background: #2d2d2d; color: #e6edf3; padding: 8px 12px; font-weight: 600; }
+.file-stats { color: #8b949e; font-weight: normal; margin-left: 8px; }
+table { border-collapse: collapse; width: 100%; font-family: monospace; font-size: 12px; }
+td { padding: 1px 8px; white-space: pre; }
+td.line-number { color: #6e7681; text-align: right; width: 1%; user-select: none; }
+td.sign { width: 1%; user-select: none; }
+tr.added { background: #1a3d2e; color: #3fb950; }
+tr.removed { background: #3d1a1a; color: #f85149; }
+tr.context { background: #1e1e1e; color: #cccccc; }
+";
+
+/// Escape text for inclusion in HTML content or attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Render a single diff line as an HTML table row
+pub fn diff_line_html(line: &DiffLine) -> String {
+    let (class, sign) = match line.kind {
+        DiffLineKind::Added => ("added", "+"),
+        DiffLineKind::Removed => ("removed", "-"),
+        DiffLineKind::Context => ("context", " "),
+    };
+    let number = |n: Option<usize>| n.map(|n| n.to_string()).unwrap_or_default();
+
+    format!(
+        "<tr class=\"{}\"><td class=\"line-number\">{}</td><td class=\"line-number\">{}</td><td class=\"sign\">{}</td><td class=\"content\">{}</td></tr>",
+        class,
+        number(line.old_line_number),
+        number(line.new_line_number),
+        sign,
+        escape_html(&line.content),
+    )
+}
+
+/// Render a file's diff as an HTML section with a header and a line table
+pub fn file_diff_html(diff: &FileDiff) -> String {
+    let stats = diff.stats();
+    let mut html = String::new();
+
+    html.push_str("<div class=\"file\">\n");
+    html.push_str(&format!(
+        "<div class=\"file-header\">{}<span class=\"file-stats\">+{} -{}</span></div>\n",
+        escape_html(&diff.path),
+        stats.additions,
+        stats.deletions,
+    ));
+    html.push_str("<table>\n");
+    for line in diff.lines() {
+        html.push_str(&diff_line_html(&line));
+        html.push('\n');
+    }
+    html.push_str("</table>\n</div>\n");
+
+    html
+}
+
+/// Render a whole commit's diffs as a standalone HTML document
+pub fn commit_html(title: &str, diffs: &[FileDiff]) -> String {
+    let title = escape_html(title);
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", title));
+    html.push_str(&format!("<style>\n{}</style>\n", STYLE));
+    html.push_str("</head>\n<body>\n");
+    html.push_str(&format!("<h1>{}</h1>\n", title));
+    for diff in diffs {
+        html.push_str(&file_diff_html(diff));
+    }
+    html.push_str("</body>\n</html>\n");
+
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use buffer_diff::BufferDiff;
+
+    fn sample_diff() -> FileDiff {
+        let old = "fn main() {\n    if a < b {}\n}\n";
+        let new = "fn main() {\n    if a < b && c {}\n}\n";
+        FileDiff {
+            path: "src/<main>.rs".to_string(),
+            old_content: old.to_string(),
+            new_content: new.to_string(),
+            buffer_diff: BufferDiff::new(old, new).unwrap(),
+            image: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_line_html_classes_and_escaping() {
+        let rows: Vec<String> = sample_diff().lines().iter().map(diff_line_html).collect();
+
+        assert_eq!(
+            rows[0],
+            "<tr class=\"context\"><td class=\"line-number\">1</td><td class=\"line-number\">1</td><td class=\"sign\"> </td><td class=\"content\">fn main() {</td></tr>"
+        );
+        assert!(rows
+            .iter()
+            .any(|row| row.starts_with("<tr class=\"removed\">")
+                && row
+                    .contains("<td class=\"line-number\">2</td><td class=\"line-number\"></td>")
+                && row.contains("if a &lt; b {}")));
+        assert!(rows
+            .iter()
+            .any(|row| row.starts_with("<tr class=\"added\">")
+                && row.contains("if a &lt; b &amp;&amp; c {}")));
+        assert!(rows.iter().all(|row| !row.contains("a < b")));
+    }
+
+    #[test]
+    fn test_file_diff_html_header() {
+        let html = file_diff_html(&sample_diff());
+        assert!(html.contains("src/&lt;main&gt;.rs<span class=\"file-stats\">+1 -1</span>"));
+    }
+}
@@ -0,0 +1,317 @@
+//! Syntax highlighting for diff cards
+//!
+//! Card factories run on background threads and are re-run whenever a
+//! texture is invalidated (e.g. on zoom), so highlighted spans are kept in a
+//! shared, thread-safe cache keyed by language and content hash.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use buffer_diff::split_lines;
+
+/// The kind of a highlighted token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Number,
+    Comment,
+}
+
+impl TokenKind {
+    /// Color used to render this token kind
+    pub fn color(self) -> u32 {
+        match self {
+            TokenKind::Keyword => 0x569cd6,
+            TokenKind::String => 0xce9178,
+            TokenKind::Number => 0xb5cea8,
+            TokenKind::Comment => 0x6a9955,
+        }
+    }
+}
+
+/// A highlighted byte range within a single line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightSpan {
+    pub range: Range<usize>,
+    pub kind: TokenKind,
+}
+
+/// Highlighted spans for every line of a piece of content
+pub type HighlightedLines = Arc<Vec<Vec<HighlightSpan>>>;
+
+/// A language the highlighter knows about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Language {
+    pub name: &'static str,
+    keywords: &'static [&'static str],
+    line_comment: &'static str,
+    /// Characters that open and close string literals
+    quotes: &'static [u8],
+}
+
+const RUST: Language = Language {
+    name: "rust",
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ],
+    line_comment: "//",
+    // Single quotes are left alone so lifetimes aren't treated as strings
+    quotes: b"\"",
+};
+
+const TOML: Language = Language {
+    name: "toml",
+    keywords: &["true", "false"],
+    line_comment: "#",
+    quotes: b"\"'",
+};
+
+const PYTHON: Language = Language {
+    name: "python",
+    keywords: &[
+        "and", "as", "class", "def", "elif", "else", "False", "for", "from", "if", "import", "in",
+        "is", "lambda", "None", "not", "or", "pass", "return", "self", "True", "while", "with",
+        "yield",
+    ],
+    line_comment: "#",
+    quotes: b"\"'",
+};
+
+/// Detect the language of a file from its extension
+pub fn language_for_path(path: &str) -> Option<Language> {
+    match Path::new(path).extension()?.to_str()? {
+        "rs" => Some(RUST),
+        "toml" => Some(TOML),
+        "py" => Some(PYTHON),
+        _ => None,
+    }
+}
+
+/// Highlight a single line of code
+fn highlight_line(language: &Language, line: &str) -> Vec<HighlightSpan> {
+    let mut spans = Vec::new();
+    let bytes = line.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &line[i..];
+        let c = bytes[i];
+
+        if rest.starts_with(language.line_comment) {
+            spans.push(HighlightSpan {
+                range: i..line.len(),
+                kind: TokenKind::Comment,
+            });
+            break;
+        } else if language.quotes.contains(&c) {
+            // Scan to the matching quote, honoring escapes
+            let mut end = i + 1;
+            while end < bytes.len() && bytes[end] != c {
+                end += if bytes[end] == b'\\' { 2 } else { 1 };
+            }
+            let end = (end + 1).min(bytes.len());
+            spans.push(HighlightSpan {
+                range: i..end,
+                kind: TokenKind::String,
+            });
+            i = end;
+        } else if c.is_ascii_digit() {
+            let end = i + rest
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '.'))
+                .unwrap_or(rest.len());
+            spans.push(HighlightSpan {
+                range: i..end,
+                kind: TokenKind::Number,
+            });
+            i = end;
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            let end = i + rest
+                .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+                .unwrap_or(rest.len());
+            if language.keywords.contains(&&line[i..end]) {
+                spans.push(HighlightSpan {
+                    range: i..end,
+                    kind: TokenKind::Keyword,
+                });
+            }
+            i = end;
+        } else {
+            // Skip over the whole (possibly multi-byte) character
+            i += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+        }
+    }
+
+    spans
+}
+
+/// How many highlighted contents a cache keeps by default
+const DEFAULT_CAPACITY: usize = 256;
+
+/// A cached highlight, with when it was last used
+struct CacheEntry {
+    lines: HighlightedLines,
+    last_used: u64,
+}
+
+/// The cached highlights, and a clock ticking on every use
+#[derive(Default)]
+struct CacheEntries {
+    entries: HashMap<(&'static str, u64), CacheEntry>,
+    clock: u64,
+}
+
+/// A thread-safe cache of highlighted content, shared across diff cards
+///
+/// The cache holds at most `capacity` contents, evicting the least recently
+/// used one to make room for another, so browsing many commits doesn't keep
+/// every file ever shown in memory.
+pub struct HighlightCache {
+    entries: Mutex<CacheEntries>,
+    capacity: usize,
+    computed: AtomicUsize,
+}
+
+impl Default for HighlightCache {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl HighlightCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a cache holding at most `capacity` highlighted contents
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(CacheEntries::default()),
+            capacity: capacity.max(1),
+            computed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Highlight content, reusing cached spans if the same content was seen before
+    pub fn highlight(&self, language: &Language, content: &str) -> HighlightedLines {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let key = (language.name, hasher.finish());
+
+        {
+            let mut cache = self.entries.lock().unwrap();
+            cache.clock += 1;
+            let now = cache.clock;
+            if let Some(entry) = cache.entries.get_mut(&key) {
+                entry.last_used = now;
+                return entry.lines.clone();
+            }
+        }
+
+        // Highlight outside the lock so other cards aren't blocked
+        let lines: HighlightedLines = Arc::new(
+            split_lines(content)
+                .map(|line| highlight_line(language, line))
+                .collect(),
+        );
+        self.computed.fetch_add(1, Ordering::Relaxed);
+
+        let mut cache = self.entries.lock().unwrap();
+        cache.clock += 1;
+        let now = cache.clock;
+        if !cache.entries.contains_key(&key) && cache.entries.len() >= self.capacity {
+            let oldest = cache
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                cache.entries.remove(&oldest);
+            }
+        }
+        let entry = cache.entries.entry(key).or_insert(CacheEntry {
+            lines,
+            last_used: now,
+        });
+        entry.last_used = now;
+        entry.lines.clone()
+    }
+
+    /// Number of contents currently cached
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.lock().unwrap().entries.len()
+    }
+
+    /// Number of times content was actually highlighted (cache misses)
+    #[cfg(test)]
+    fn compute_count(&self) -> usize {
+        self.computed.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<HighlightCache>();
+    }
+
+    #[test]
+    fn test_same_content_hits_cache() {
+        let cache = HighlightCache::new();
+        let rust = language_for_path("src/main.rs").unwrap();
+        let content = "fn main() {\n    let x = 42; // answer\n}\n";
+
+        let first = cache.highlight(&rust, content);
+        let second = cache.highlight(&rust, content);
+
+        assert_eq!(cache.compute_count(), 1);
+        assert!(Arc::ptr_eq(&first, &second));
+
+        cache.highlight(&rust, "struct Foo;");
+        assert_eq!(cache.compute_count(), 2);
+    }
+
+    #[test]
+    fn test_least_recently_used_content_is_evicted() {
+        let cache = HighlightCache::with_capacity(2);
+        let rust = language_for_path("src/main.rs").unwrap();
+
+        cache.highlight(&rust, "fn a() {}");
+        cache.highlight(&rust, "fn b() {}");
+        // Using `a` again leaves `b` as the least recently used
+        cache.highlight(&rust, "fn a() {}");
+        cache.highlight(&rust, "fn c() {}");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.compute_count(), 3);
+
+        cache.highlight(&rust, "fn a() {}");
+        assert_eq!(cache.compute_count(), 3);
+        cache.highlight(&rust, "fn b() {}");
+        assert_eq!(cache.compute_count(), 4);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_highlight_line_tokens() {
+        let spans = highlight_line(&RUST, "let s = \"hi\"; // done");
+        let kinds: Vec<_> = spans.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![TokenKind::Keyword, TokenKind::String, TokenKind::Comment]
+        );
+        assert_eq!(spans[1].range, 8..12);
+    }
+}
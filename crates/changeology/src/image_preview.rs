@@ -0,0 +1,166 @@
+//! Before/after previews for image files
+//!
+//! Diffing an image's bytes as text is meaningless, so cards for changed
+//! PNG and JPEG files show the two versions side by side instead.
+
+use std::sync::Arc;
+
+use image::RgbaImage;
+
+/// File extensions that are previewed as images
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+
+/// Height of the label above each image
+pub const IMAGE_LABEL_HEIGHT: f32 = 20.0;
+
+/// Gap between the two images
+pub const IMAGE_PANE_GAP: f32 = 16.0;
+
+/// Whether a path names an image that can be previewed
+pub fn is_image_path(path: &str) -> bool {
+    path.rsplit_once('.').is_some_and(|(_, extension)| {
+        IMAGE_EXTENSIONS
+            .iter()
+            .any(|image| extension.eq_ignore_ascii_case(image))
+    })
+}
+
+/// Decode PNG or JPEG bytes into RGBA pixels
+pub fn decode_image(bytes: &[u8]) -> Option<RgbaImage> {
+    image::load_from_memory(bytes)
+        .ok()
+        .map(|image| image.to_rgba8())
+}
+
+/// One side of an image preview, laid out to fit the card
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePane {
+    /// Label shown above the image
+    pub label: &'static str,
+    /// The image's size in pixels (`None` if this side has no image)
+    pub dimensions: Option<(u32, u32)>,
+    /// The size the image is drawn at, scaled down to fit the pane
+    pub display_size: (f32, f32),
+}
+
+/// The decoded before and after versions of a changed image
+#[derive(Clone)]
+pub struct ImagePreview {
+    /// The image before the change (`None` if the file was added)
+    pub old: Option<Arc<RgbaImage>>,
+    /// The image after the change (`None` if the file was deleted)
+    pub new: Option<Arc<RgbaImage>>,
+}
+
+impl ImagePreview {
+    /// Create a preview from decoded images, if there's at least one
+    pub fn new(old: Option<RgbaImage>, new: Option<RgbaImage>) -> Option<Self> {
+        if old.is_none() && new.is_none() {
+            return None;
+        }
+        Some(Self {
+            old: old.map(Arc::new),
+            new: new.map(Arc::new),
+        })
+    }
+
+    /// Create a preview from the file's blobs on either side of the change
+    ///
+    /// Returns `None` if neither side decodes as an image.
+    pub fn from_blobs(old: Option<&[u8]>, new: Option<&[u8]>) -> Option<Self> {
+        Self::new(old.and_then(decode_image), new.and_then(decode_image))
+    }
+
+    /// Lay out the before and after images side by side in `width`
+    ///
+    /// Images are scaled down to fit their half of the width, but never up.
+    pub fn panes(&self, width: f32) -> [ImagePane; 2] {
+        let pane_width = ((width - IMAGE_PANE_GAP) / 2.0).max(0.0);
+        let pane = |label: &'static str, image: &Option<Arc<RgbaImage>>| {
+            let dimensions = image.as_ref().map(|image| image.dimensions());
+            let display_size = match dimensions {
+                Some((w, h)) if w > 0 => {
+                    let scale = (pane_width / w as f32).min(1.0);
+                    (w as f32 * scale, h as f32 * scale)
+                }
+                _ => (0.0, 0.0),
+            };
+            ImagePane {
+                label,
+                dimensions,
+                display_size,
+            }
+        };
+
+        let (old_label, new_label) = match (&self.old, &self.new) {
+            (Some(_), None) => ("Deleted", ""),
+            (None, Some(_)) => ("", "Added"),
+            _ => ("Before", "After"),
+        };
+        [pane(old_label, &self.old), pane(new_label, &self.new)]
+    }
+
+    /// Height of the preview when laid out in `width`, labels included
+    pub fn height(&self, width: f32) -> f32 {
+        let [old, new] = self.panes(width);
+        IMAGE_LABEL_HEIGHT + old.display_size.1.max(new.display_size.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode_png(image: &RgbaImage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_is_image_path() {
+        assert!(is_image_path("assets/logo.png"));
+        assert!(is_image_path("photo.JPEG"));
+        assert!(!is_image_path("src/png.rs"));
+        assert!(!is_image_path("Makefile"));
+    }
+
+    #[test]
+    fn test_image_preview_panes() {
+        let old = RgbaImage::from_pixel(800, 400, image::Rgba([255, 0, 0, 255]));
+        let new = RgbaImage::from_pixel(100, 50, image::Rgba([0, 0, 255, 255]));
+        let preview = ImagePreview::from_blobs(
+            Some(encode_png(&old).as_slice()),
+            Some(encode_png(&new).as_slice()),
+        )
+        .unwrap();
+        assert_eq!(preview.old.as_deref(), Some(&old));
+
+        let [before, after] = preview.panes(616.0);
+        assert_eq!(before.label, "Before");
+        assert_eq!(before.dimensions, Some((800, 400)));
+        // Scaled down to fit half the width...
+        assert_eq!(before.display_size, (300.0, 150.0));
+        // ...but small images aren't scaled up
+        assert_eq!(after.label, "After");
+        assert_eq!(after.display_size, (100.0, 50.0));
+        assert_eq!(preview.height(616.0), IMAGE_LABEL_HEIGHT + 150.0);
+    }
+
+    #[test]
+    fn test_image_preview_of_added_image() {
+        let new = RgbaImage::new(10, 20);
+        let preview = ImagePreview::new(None, Some(new)).unwrap();
+
+        let [before, after] = preview.panes(616.0);
+        assert_eq!(before.dimensions, None);
+        assert_eq!(after.label, "Added");
+        assert_eq!(after.display_size, (10.0, 20.0));
+
+        // Neither side decodes, so there's nothing to preview
+        assert!(ImagePreview::from_blobs(Some(b"not an image"), None).is_none());
+    }
+}
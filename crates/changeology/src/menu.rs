@@ -6,9 +6,20 @@ actions!(
     [
         OpenRepository,
         CloseRepository,
+        ExportCommitHtml,
         Refresh,
         Quit,
         ToggleSidebar,
+        ToggleSideBySide,
+        ToggleCommitTree,
+        ToggleCommitGraph,
+        ToggleCommitAnimation,
+        ContextLinesNone,
+        ContextLinesDefault,
+        ContextLinesMore,
+        ContextLinesFull,
+        FindNext,
+        FindPrevious,
     ]
 );
 
@@ -17,4 +28,10 @@ pub fn register_actions(cx: &mut App) {
     cx.on_action(|_: &Quit, cx| {
         cx.quit();
     });
+
+    // Step through search matches, unless typing in a text input
+    cx.bind_keys([
+        KeyBinding::new("n", FindNext, Some("ChangeologyApp && !Input")),
+        KeyBinding::new("shift-n", FindPrevious, Some("ChangeologyApp && !Input")),
+    ]);
 }
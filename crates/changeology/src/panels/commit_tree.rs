@@ -0,0 +1,75 @@
+//! Commit tree panel utilities
+//!
+//! Converts trees from the tree-viewer crate, such as the full file tree of a
+//! commit, into items for the tree component.
+
+use gpui_component::tree::TreeItem;
+use tree_viewer::tree::{NodeId, Tree};
+
+/// Build tree items for everything below the root of `tree`
+///
+/// Each item's id is its node's path below the root, joined with `/`. Folders
+/// start collapsed, since a commit's tree usually holds far more files than it
+/// changed.
+pub fn tree_items<T: Tree>(tree: &T) -> Vec<TreeItem> {
+    tree.children(tree.root())
+        .map(|id| tree_item(tree, id, ""))
+        .collect()
+}
+
+/// Build the tree item for a node and its descendants
+fn tree_item<T: Tree>(tree: &T, id: NodeId, parent_path: &str) -> TreeItem {
+    let name = tree.name(id).unwrap_or_default();
+    let path = if parent_path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent_path, name)
+    };
+
+    let mut item = TreeItem::new(path.clone(), name.to_string());
+    for child in tree.children(id) {
+        item = item.child(tree_item(tree, child, &path));
+    }
+    item
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git::{TreeEntry, TreeEntryKind};
+    use tree_viewer::tree::GitTreeSnapshot;
+
+    fn entry(path: &str, kind: TreeEntryKind) -> TreeEntry {
+        TreeEntry {
+            path: path.to_string(),
+            kind,
+            id: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_tree_items_follow_the_commit_tree() {
+        let tree = GitTreeSnapshot::from_entries(
+            "repo",
+            vec![
+                entry("README.md", TreeEntryKind::File),
+                entry("src", TreeEntryKind::Directory),
+                entry("src/lib.rs", TreeEntryKind::File),
+                entry("src/nested", TreeEntryKind::Directory),
+                entry("src/nested/mod.rs", TreeEntryKind::File),
+            ],
+        );
+
+        // The root itself isn't shown, just what's inside it
+        let items = tree_items(&tree);
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_ref()).collect();
+        assert_eq!(ids, vec!["README.md", "src"]);
+
+        let src = &items[1];
+        assert_eq!(src.label.as_ref(), "src");
+        let ids: Vec<&str> = src.children.iter().map(|item| item.id.as_ref()).collect();
+        assert_eq!(ids, vec!["src/lib.rs", "src/nested"]);
+        assert_eq!(src.children[1].children[0].id.as_ref(), "src/nested/mod.rs");
+        assert_eq!(src.children[1].children[0].label.as_ref(), "mod.rs");
+    }
+}
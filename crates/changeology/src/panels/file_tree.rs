@@ -77,7 +77,7 @@ struct DirNode {
     name: String,
     path: String,
     children: HashMap<String, DirNode>,
-    files: Vec<(String, String)>, // (full_path, filename)
+    files: Vec<(String, String, StatusKind)>, // (full_path, filename, status)
 }
 
 impl DirNode {
@@ -91,77 +91,129 @@ impl DirNode {
     }
 
     fn into_tree_item(self) -> TreeItem {
-        let mut item = TreeItem::new(self.path, self.name).expanded(true);
+        let mut item = TreeItem::new(self.path.clone(), self.name.clone()).expanded(true);
+        for child in self.into_children() {
+            item = item.child(child);
+        }
+        item
+    }
+
+    /// Convert the subdirectories and files into tree items, directories first
+    fn into_children(self) -> Vec<TreeItem> {
+        let mut children = Vec::new();
 
         // Add subdirectories first (sorted)
         let mut dirs: Vec<_> = self.children.into_values().collect();
         dirs.sort_by(|a, b| a.name.cmp(&b.name));
         for dir in dirs {
-            item = item.child(dir.into_tree_item());
+            children.push(dir.into_tree_item());
         }
 
         // Add files (sorted)
         let mut files = self.files;
         files.sort_by(|a, b| a.1.cmp(&b.1));
-        for (path, name) in files {
-            item = item.child(TreeItem::new(path, name));
+        for (path, name, _) in files {
+            children.push(TreeItem::new(path, name));
         }
 
-        item
+        children
+    }
+
+    /// Record the status of every folder and file below this node, keyed by path
+    ///
+    /// Returns this node's summary status: the status shared by all files
+    /// below it, `Modified` if they differ, or `None` if it has no files.
+    fn collect_statuses(&self, statuses: &mut HashMap<String, StatusKind>) -> Option<StatusKind> {
+        let mut summary = None;
+        let mut merge = |kind: StatusKind| {
+            summary = match summary {
+                None => Some(kind),
+                Some(current) if current == kind => Some(kind),
+                Some(_) => Some(StatusKind::Modified),
+            };
+        };
+
+        for dir in self.children.values() {
+            if let Some(kind) = dir.collect_statuses(statuses) {
+                statuses.insert(dir.path.clone(), kind);
+                merge(kind);
+            }
+        }
+        for (path, _, kind) in &self.files {
+            statuses.insert(path.clone(), *kind);
+            merge(*kind);
+        }
+
+        summary
     }
 }
 
-/// Build tree items with directory hierarchy
-pub fn build_nested_tree(status: &StatusList) -> Vec<TreeItem> {
-    let mut root_dirs: HashMap<String, DirNode> = HashMap::new();
-    let mut root_files: Vec<(String, String)> = Vec::new();
+/// Build the directory hierarchy for a status list, rooted at an unnamed node
+///
+/// With `changed_only`, entries whose status isn't a change (ignored or
+/// unknown files) are left out, along with folders that only contained them.
+fn build_dir_tree(status: &StatusList, changed_only: bool) -> DirNode {
+    let mut root = DirNode::new("", "");
 
     for entry in &status.entries {
-        let parts: Vec<&str> = entry.path.split('/').collect();
+        if changed_only && !entry.kind.is_changed() {
+            continue;
+        }
 
-        if parts.len() == 1 {
-            // Root level file
-            root_files.push((entry.path.clone(), parts[0].to_string()));
-        } else {
-            // File in subdirectory
-            let dir_name = parts[0];
-            let dir_node = root_dirs
-                .entry(dir_name.to_string())
-                .or_insert_with(|| DirNode::new(dir_name, dir_name));
-
-            // Navigate/create nested directories
-            let mut current = dir_node;
-            for (i, part) in parts[1..parts.len() - 1].iter().enumerate() {
-                let nested_path = parts[0..=i + 1].join("/");
-                current = current
-                    .children
-                    .entry(part.to_string())
-                    .or_insert_with(|| DirNode::new(part, &nested_path));
-            }
+        let parts: Vec<&str> = entry.path.split('/').collect();
+        let Some((filename, dirs)) = parts.split_last() else {
+            continue;
+        };
 
-            // Add the file to the deepest directory
-            let filename = parts.last().unwrap().to_string();
-            current.files.push((entry.path.clone(), filename));
+        // Navigate/create nested directories
+        let mut current = &mut root;
+        for (i, part) in dirs.iter().enumerate() {
+            let nested_path = parts[0..=i].join("/");
+            current = current
+                .children
+                .entry(part.to_string())
+                .or_insert_with(|| DirNode::new(part, &nested_path));
         }
+
+        // Add the file to the deepest directory
+        current
+            .files
+            .push((entry.path.clone(), filename.to_string(), entry.kind));
     }
 
-    // Build result: directories first, then root files
-    let mut result = Vec::new();
+    root
+}
 
-    // Add directories (sorted)
-    let mut dirs: Vec<_> = root_dirs.into_values().collect();
-    dirs.sort_by(|a, b| a.name.cmp(&b.name));
-    for dir in dirs {
-        result.push(dir.into_tree_item());
-    }
+/// Build tree items with directory hierarchy
+///
+/// Directories come first, then files, each sorted by name. With
+/// `changed_only`, only changed files and their ancestor folders are kept.
+#[allow(dead_code)]
+pub fn build_nested_tree(status: &StatusList, changed_only: bool) -> Vec<TreeItem> {
+    build_status_tree(status, changed_only).items
+}
 
-    // Add root files (sorted)
-    root_files.sort_by(|a, b| a.1.cmp(&b.1));
-    for (path, name) in root_files {
-        result.push(TreeItem::new(path, name));
-    }
+/// A nested file tree along with the status of each of its nodes
+pub struct StatusTree {
+    /// The tree items, as built by `build_nested_tree`
+    pub items: Vec<TreeItem>,
+    /// The status of every node, keyed by the item id (its path)
+    ///
+    /// Files have their own status. Folders have the status shared by every
+    /// file below them, or `Modified` if those differ.
+    pub statuses: HashMap<String, StatusKind>,
+}
+
+/// Build a nested file tree that carries each node's status, for status badges
+pub fn build_status_tree(status: &StatusList, changed_only: bool) -> StatusTree {
+    let root = build_dir_tree(status, changed_only);
+    let mut statuses = HashMap::new();
+    root.collect_statuses(&mut statuses);
 
-    result
+    StatusTree {
+        items: root.into_children(),
+        statuses,
+    }
 }
 
 #[cfg(test)]
@@ -181,6 +233,82 @@ mod tests {
         }
     }
 
+    /// Collect the paths of every folder and file under a node, depth first
+    fn tree_paths(node: &DirNode) -> Vec<String> {
+        let mut dirs: Vec<&DirNode> = node.children.values().collect();
+        dirs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut paths = Vec::new();
+        for dir in dirs {
+            paths.push(dir.path.clone());
+            paths.extend(tree_paths(dir));
+        }
+        paths.extend(node.files.iter().map(|(path, ..)| path.clone()));
+        paths
+    }
+
+    #[test]
+    fn test_changed_only_keeps_changed_files_and_ancestors() {
+        let mut status = make_status(&["src/panels/tree/deep.rs"]);
+        for (path, kind) in [
+            ("target/debug/build.log", StatusKind::Ignored),
+            ("notes.txt", StatusKind::Unknown),
+        ] {
+            status.entries.push(StatusEntry {
+                path: path.to_string(),
+                kind,
+            });
+        }
+
+        let root = build_dir_tree(&status, true);
+        assert_eq!(
+            tree_paths(&root),
+            vec![
+                "src",
+                "src/panels",
+                "src/panels/tree",
+                "src/panels/tree/deep.rs",
+            ]
+        );
+
+        // Without the filter every entry is shown
+        let root = build_dir_tree(&status, false);
+        assert_eq!(tree_paths(&root).len(), 8);
+    }
+
+    #[test]
+    fn test_statuses_propagate_to_leaves_and_summarize_folders() {
+        let status = StatusList {
+            entries: [
+                ("src/app.rs", StatusKind::Modified),
+                ("src/new/mod.rs", StatusKind::Added),
+                ("src/new/util.rs", StatusKind::Added),
+                ("docs/old.md", StatusKind::Deleted),
+            ]
+            .into_iter()
+            .map(|(path, kind)| StatusEntry {
+                path: path.to_string(),
+                kind,
+            })
+            .collect(),
+        };
+
+        let mut statuses = HashMap::new();
+        let summary = build_dir_tree(&status, true).collect_statuses(&mut statuses);
+
+        // Leaves keep their own status
+        assert_eq!(statuses["src/app.rs"], StatusKind::Modified);
+        assert_eq!(statuses["src/new/util.rs"], StatusKind::Added);
+        assert_eq!(statuses["docs/old.md"], StatusKind::Deleted);
+
+        // Folders with uniform children take their status, mixed ones are Modified
+        assert_eq!(statuses["src/new"], StatusKind::Added);
+        assert_eq!(statuses["docs"], StatusKind::Deleted);
+        assert_eq!(statuses["src"], StatusKind::Modified);
+        assert_eq!(summary, Some(StatusKind::Modified));
+        assert_eq!(statuses.len(), 7);
+    }
+
     // #[test]
     // fn test_flat_tree() {
     //     let status = make_status(&["file1.rs", "src/main.rs", "src/lib.rs"]);
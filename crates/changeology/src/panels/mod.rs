@@ -1,2 +1,4 @@
+pub mod commit_tree;
 pub mod file_tree;
 pub mod history;
+pub mod tree_diff;
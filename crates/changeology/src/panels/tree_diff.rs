@@ -0,0 +1,200 @@
+//! Tree comparison panel utilities
+//!
+//! Lays out the merged file trees of two commits as rows, with the old
+//! commit's side on the left and the new commit's on the right, each node
+//! colored by how it changed between them.
+
+use gpui::*;
+use gpui_component::{h_flex, ActiveTheme, Icon, IconName, Sizable};
+use std::collections::HashSet;
+use tree_viewer::tree::{GitTreeDiff, NodeId, Tree, TreeDiffStatus, TreeTraversal};
+
+/// A node of a tree comparison, shown as one row of the side-by-side view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeDiffRow {
+    /// The node's path relative to the repository root
+    pub path: String,
+    /// The node's name, the last component of its path
+    pub name: String,
+    /// How deeply the node is nested, 0 for top-level entries
+    pub depth: usize,
+    /// Whether the node is a folder
+    pub is_folder: bool,
+    /// How the node changed between the two commits
+    pub status: TreeDiffStatus,
+}
+
+impl TreeDiffRow {
+    /// Whether the node is in the old commit, so it's shown on the left
+    pub fn in_old(&self) -> bool {
+        self.status != TreeDiffStatus::Added
+    }
+
+    /// Whether the node is in the new commit, so it's shown on the right
+    pub fn in_new(&self) -> bool {
+        self.status != TreeDiffStatus::Removed
+    }
+}
+
+/// Get the color a node is drawn in for its change status
+pub fn status_color(status: TreeDiffStatus, cx: &App) -> Hsla {
+    match status {
+        TreeDiffStatus::Added => cx.theme().green,
+        TreeDiffStatus::Removed => cx.theme().red,
+        TreeDiffStatus::Modified => cx.theme().yellow,
+        TreeDiffStatus::Unchanged => cx.theme().muted_foreground,
+    }
+}
+
+/// The paths of the folders that changed, which start out expanded
+pub fn changed_folders(tree: &GitTreeDiff) -> HashSet<String> {
+    tree.containers()
+        .into_iter()
+        .filter_map(|id| tree.get(id))
+        .filter(|node| node.data.status != TreeDiffStatus::Unchanged)
+        .map(|node| node.data.path.clone())
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+/// Rows for the nodes below the root of `tree`, in pre-order
+///
+/// The contents of folders whose paths aren't in `expanded` are left out.
+pub fn visible_rows(tree: &GitTreeDiff, expanded: &HashSet<String>) -> Vec<TreeDiffRow> {
+    let mut rows = Vec::new();
+    push_rows(tree, tree.root(), 0, expanded, &mut rows);
+    rows
+}
+
+/// Add rows for the children of `parent`, and the contents of any expanded ones
+fn push_rows(
+    tree: &GitTreeDiff,
+    parent: NodeId,
+    depth: usize,
+    expanded: &HashSet<String>,
+    rows: &mut Vec<TreeDiffRow>,
+) {
+    for id in tree.children(parent) {
+        let Some(node) = tree.get(id) else {
+            continue;
+        };
+        rows.push(TreeDiffRow {
+            path: node.data.path.clone(),
+            name: node.name.clone(),
+            depth,
+            is_folder: node.is_container(),
+            status: node.data.status,
+        });
+        if node.is_container() && expanded.contains(&node.data.path) {
+            push_rows(tree, id, depth + 1, expanded, rows);
+        }
+    }
+}
+
+/// Render one side of a row: the node's icon and name, or a blank space if
+/// the node isn't on this side
+pub fn render_row_side(row: &TreeDiffRow, shown: bool, expanded: bool, cx: &App) -> Div {
+    let side = h_flex()
+        .w_1_2()
+        .min_w_0()
+        .gap_1()
+        .items_center()
+        .overflow_hidden()
+        .pl(px(12.) * row.depth as f32 + px(8.));
+    if !shown {
+        return side;
+    }
+
+    let icon = match (row.is_folder, expanded) {
+        (true, true) => IconName::FolderOpen,
+        (true, false) => IconName::Folder,
+        (false, _) => IconName::File,
+    };
+    side.text_color(status_color(row.status, cx))
+        .child(Icon::new(icon).small())
+        .child(div().text_sm().child(row.name.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git::{StatusEntry, StatusKind, TreeEntry, TreeEntryKind};
+
+    fn entries(paths: &[&str]) -> Vec<TreeEntry> {
+        paths
+            .iter()
+            .map(|path| TreeEntry {
+                path: path.trim_end_matches('/').to_string(),
+                kind: if path.ends_with('/') {
+                    TreeEntryKind::Directory
+                } else {
+                    TreeEntryKind::File
+                },
+                id: String::new(),
+            })
+            .collect()
+    }
+
+    fn tree() -> GitTreeDiff {
+        GitTreeDiff::from_entries(
+            "repo",
+            entries(&["README.md", "docs/", "docs/guide.md", "src/", "src/lib.rs"]),
+            entries(&["README.md", "src/", "src/lib.rs", "src/new.rs"]),
+            &[
+                StatusEntry {
+                    path: "src/lib.rs".to_string(),
+                    kind: StatusKind::Modified,
+                },
+                StatusEntry {
+                    path: "src/new.rs".to_string(),
+                    kind: StatusKind::Added,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_changed_folders_start_expanded() {
+        let tree = tree();
+        let expanded = changed_folders(&tree);
+        assert_eq!(
+            expanded,
+            HashSet::from(["docs".to_string(), "src".to_string()])
+        );
+
+        let rows = visible_rows(&tree, &expanded);
+        let summary: Vec<_> = rows
+            .iter()
+            .map(|row| (row.path.as_str(), row.depth, row.in_old(), row.in_new()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("README.md", 0, true, true),
+                ("docs", 0, true, false),
+                ("docs/guide.md", 1, true, false),
+                ("src", 0, true, true),
+                ("src/lib.rs", 1, true, true),
+                ("src/new.rs", 1, false, true),
+            ]
+        );
+        assert!(rows[3].is_folder);
+        assert_eq!(rows[4].status, TreeDiffStatus::Modified);
+    }
+
+    #[test]
+    fn test_collapsed_folders_hide_their_contents() {
+        let tree = tree();
+        let expanded = HashSet::from(["src".to_string()]);
+
+        let paths: Vec<_> = visible_rows(&tree, &expanded)
+            .into_iter()
+            .map(|row| row.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec!["README.md", "docs", "src", "src/lib.rs", "src/new.rs"]
+        );
+        assert_eq!(visible_rows(&tree, &HashSet::new()).len(), 3);
+    }
+}
@@ -2,12 +2,15 @@
 //!
 //! Contains the three panel sections: Changes (dirty files), Staged, and History
 
+use gpui::prelude::FluentBuilder;
 use gpui::*;
 
 use gpui_component::{
-    h_flex, list::ListItem, scroll::Scrollbar, v_flex, ActiveTheme, Icon, IconName,
+    clipboard::Clipboard, h_flex, list::ListItem, scroll::Scrollbar, v_flex, ActiveTheme, Icon,
+    IconName,
 };
 
+use crate::commit_graph::GraphRow;
 use crate::panels::file_tree;
 use git::{Commit, StatusEntry};
 
@@ -64,44 +67,162 @@ pub fn render_file_entry(
 }
 
 /// Render a commit entry item
-pub fn render_commit_entry(index: usize, commit: &Commit, is_selected: bool, cx: &App) -> ListItem {
-    ListItem::new(format!("commit-{}", index))
-        .selected(is_selected)
-        .py(px(2.))
+pub fn render_commit_entry(
+    index: usize,
+    commit: &Commit,
+    graph: Option<&GraphRow>,
+    is_selected: bool,
+    cx: &App,
+) -> ListItem {
+    let entry = v_flex()
+        .group(COMMIT_ENTRY_GROUP)
+        .flex_1()
+        .min_w_0()
+        .gap_1()
         .child(
-            v_flex()
+            h_flex()
                 .w_full()
-                .gap_1()
+                .gap_2()
+                .justify_between()
+                .child(
+                    div().text_sm().flex_auto().overflow_hidden().child(
+                        commit
+                            .message
+                            .lines()
+                            .next()
+                            .unwrap_or(&commit.message)
+                            .to_string(),
+                    ),
+                )
                 .child(
                     h_flex()
-                        .w_full()
-                        .gap_2()
-                        .justify_between()
-                        .child(
-                            div().text_sm().flex_auto().overflow_hidden().child(
-                                commit
-                                    .message
-                                    .lines()
-                                    .next()
-                                    .unwrap_or(&commit.message)
-                                    .to_string(),
-                            ),
-                        )
+                        .flex_shrink_0()
+                        .gap_1()
+                        .child(render_copy_buttons(index, commit))
                         .child(
                             div()
                                 .text_xs()
-                                .flex_shrink_0()
                                 .text_color(cx.theme().muted_foreground)
                                 .child(commit.short_id.clone()),
                         ),
-                )
-                .child(
-                    div()
-                        .text_xs()
-                        .text_color(cx.theme().muted_foreground)
-                        .child(format_timestamp(commit.time)),
                 ),
         )
+        .child(
+            div()
+                .text_xs()
+                .text_color(cx.theme().muted_foreground)
+                .child(format_timestamp(commit.time)),
+        );
+
+    // Plain flex rows stretch their children, so the graph spans the entry's height
+    ListItem::new(format!("commit-{}", index))
+        .selected(is_selected)
+        .py(px(2.))
+        .child(
+            div()
+                .flex()
+                .w_full()
+                .gap_2()
+                .when_some(graph, |row, graph| {
+                    row.child(render_commit_graph(graph, cx.theme().muted_foreground))
+                })
+                .child(entry),
+        )
+}
+
+/// Width of one lane of the commit graph
+const GRAPH_LANE_WIDTH: f32 = 12.0;
+
+/// Radius of a commit's dot in the commit graph
+const GRAPH_DOT_RADIUS: f32 = 3.5;
+
+/// Render a commit's row of the commit graph: its dot and the rails through it
+fn render_commit_graph(row: &GraphRow, color: Hsla) -> impl IntoElement {
+    let row = row.clone();
+    div()
+        .flex_shrink_0()
+        .w(px(row.width as f32 * GRAPH_LANE_WIDTH))
+        .child(
+            canvas(
+                |_, _, _| {},
+                move |bounds, _, window, _| paint_commit_graph(&row, bounds, color, window),
+            )
+            .size_full(),
+        )
+}
+
+/// Paint a commit graph row within `bounds`
+fn paint_commit_graph(row: &GraphRow, bounds: Bounds<Pixels>, color: Hsla, window: &mut Window) {
+    let lane_x = |lane: usize| bounds.left() + px((lane as f32 + 0.5) * GRAPH_LANE_WIDTH);
+    let dot = point(lane_x(row.lane), bounds.center().y);
+
+    let mut rail = |from: Point<Pixels>, to: Point<Pixels>| {
+        let mut path = PathBuilder::stroke(px(1.5));
+        path.move_to(from);
+        path.line_to(to);
+        if let Ok(path) = path.build() {
+            window.paint_path(path, color);
+        }
+    };
+    for &lane in &row.passing {
+        rail(
+            point(lane_x(lane), bounds.top()),
+            point(lane_x(lane), bounds.bottom()),
+        );
+    }
+    for &lane in &row.incoming {
+        rail(point(lane_x(lane), bounds.top()), dot);
+    }
+    for &lane in &row.outgoing {
+        rail(dot, point(lane_x(lane), bounds.bottom()));
+    }
+
+    let radius = px(GRAPH_DOT_RADIUS);
+    window.paint_quad(
+        fill(
+            Bounds::centered_at(dot, size(radius * 2., radius * 2.)),
+            color,
+        )
+        .corner_radii(radius),
+    );
+}
+
+/// Group name used to reveal a commit entry's copy buttons on hover
+const COMMIT_ENTRY_GROUP: &str = "commit-entry";
+
+/// What a commit entry's copy button puts on the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitCopyTarget {
+    /// The full commit id
+    Id,
+    /// The full commit message
+    Message,
+}
+
+impl CommitCopyTarget {
+    /// The text to copy for a commit
+    pub fn value(self, commit: &Commit) -> String {
+        match self {
+            CommitCopyTarget::Id => commit.id.clone(),
+            CommitCopyTarget::Message => commit.body.trim_end().to_string(),
+        }
+    }
+}
+
+/// Render the copy-id and copy-message buttons, shown while the entry is hovered
+fn render_copy_buttons(index: usize, commit: &Commit) -> impl IntoElement {
+    h_flex()
+        .gap_1()
+        .invisible()
+        .group_hover(COMMIT_ENTRY_GROUP, |style| style.visible())
+        .child(
+            Clipboard::new(format!("copy-commit-id-{}", index))
+                .value(CommitCopyTarget::Id.value(commit)),
+        )
+        .child(
+            Clipboard::new(format!("copy-commit-message-{}", index))
+                .value(CommitCopyTarget::Message.value(commit)),
+        )
 }
 
 /// Render an empty state with icon and message
@@ -121,9 +242,13 @@ pub fn render_empty_state(message: &str, cx: &App) -> impl IntoElement {
 }
 
 /// Render the history panel with scrollable commit list
+///
+/// `graph` holds the commit graph rows parallel to `commits`, as laid out by
+/// `commit_graph::assign_lanes`, or `None` to leave the graph out.
 #[allow(dead_code)]
 pub fn render_history_content(
     commits: &[Commit],
+    graph: Option<&[GraphRow]>,
     selected_commit: Option<usize>,
     scroll_handle: &ScrollHandle,
     cx: &App,
@@ -140,23 +265,119 @@ pub fn render_history_content(
                 .w_full()
                 .children(commits.iter().enumerate().map(|(i, commit)| {
                     let is_selected = selected_commit == Some(i);
-                    render_commit_entry(i, commit, is_selected, cx).into_any_element()
+                    let graph = graph.and_then(|graph| graph.get(i));
+                    render_commit_entry(i, commit, graph, is_selected, cx).into_any_element()
                 }))
                 .into_any_element()
         })
         .child(Scrollbar::vertical(scroll_handle))
 }
 
-/// Format a Unix timestamp as a human-readable relative time string
-fn format_timestamp(timestamp: i64) -> String {
+/// Render a non-selectable header for a group of commits
+pub fn render_date_group_header(label: &str, cx: &App) -> impl IntoElement {
+    div()
+        .px_2()
+        .pt_2()
+        .pb_1()
+        .text_xs()
+        .font_weight(FontWeight::SEMIBOLD)
+        .text_color(cx.theme().muted_foreground)
+        .child(label.to_string())
+}
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// The date group a commit is listed under in the history panel
+///
+/// Days are calendar days in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateGroup {
+    Today,
+    Yesterday,
+    /// Two to seven days ago
+    LastWeek,
+    /// Anything older, grouped by calendar month
+    Month {
+        year: i64,
+        month: u32,
+    },
+}
+
+impl DateGroup {
+    /// Assign a Unix timestamp to a group relative to `now`
+    ///
+    /// Timestamps in the future (e.g. from clock skew) count as today.
+    pub fn for_timestamp(timestamp: i64, now: i64) -> Self {
+        let day = timestamp.div_euclid(SECONDS_PER_DAY);
+        let today = now.div_euclid(SECONDS_PER_DAY);
+
+        match today - day {
+            ..=0 => DateGroup::Today,
+            1 => DateGroup::Yesterday,
+            2..=7 => DateGroup::LastWeek,
+            _ => {
+                let (year, month, _) = civil_from_days(day);
+                DateGroup::Month { year, month }
+            }
+        }
+    }
+
+    /// The header text for this group
+    pub fn label(&self) -> String {
+        match self {
+            DateGroup::Today => "Today".to_string(),
+            DateGroup::Yesterday => "Yesterday".to_string(),
+            DateGroup::LastWeek => "Last week".to_string(),
+            DateGroup::Month { year, month } => {
+                format!("{} {}", MONTH_NAMES[*month as usize - 1], year)
+            }
+        }
+    }
+}
+
+/// Convert days since the Unix epoch to a (year, month, day) civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's days-to-civil algorithm
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Current time as a Unix timestamp
+pub fn current_timestamp() -> i64 {
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    let now = SystemTime::now()
+    SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
-        .as_secs() as i64;
+        .as_secs() as i64
+}
 
-    let diff = now - timestamp;
+/// Format a Unix timestamp as a human-readable relative time string
+fn format_timestamp(timestamp: i64) -> String {
+    let diff = current_timestamp() - timestamp;
 
     if diff < 60 {
         "just now".to_string()
@@ -180,3 +401,105 @@ fn format_timestamp(timestamp: i64) -> String {
         format!("{} year{} ago", years, if years == 1 { "" } else { "s" })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_group_buckets() {
+        // 2024-03-15 12:00:00 UTC
+        let now = 1_710_504_000;
+        let hours = 3600;
+        let days = 86400;
+
+        assert_eq!(DateGroup::for_timestamp(now, now), DateGroup::Today);
+        // Just after midnight is still today
+        assert_eq!(
+            DateGroup::for_timestamp(now - 11 * hours, now),
+            DateGroup::Today
+        );
+        // Just before midnight is yesterday, even though it's only 13 hours ago
+        assert_eq!(
+            DateGroup::for_timestamp(now - 13 * hours, now),
+            DateGroup::Yesterday
+        );
+        assert_eq!(
+            DateGroup::for_timestamp(now - 2 * days, now),
+            DateGroup::LastWeek
+        );
+        assert_eq!(
+            DateGroup::for_timestamp(now - 7 * days, now),
+            DateGroup::LastWeek
+        );
+        assert_eq!(
+            DateGroup::for_timestamp(now - 8 * days, now),
+            DateGroup::Month {
+                year: 2024,
+                month: 3
+            }
+        );
+        assert_eq!(
+            DateGroup::for_timestamp(now - 20 * days, now),
+            DateGroup::Month {
+                year: 2024,
+                month: 2
+            }
+        );
+        assert_eq!(
+            DateGroup::for_timestamp(now - 80 * days, now),
+            DateGroup::Month {
+                year: 2023,
+                month: 12
+            }
+        );
+        // Clock skew: commits from the future count as today
+        assert_eq!(
+            DateGroup::for_timestamp(now + 2 * days, now),
+            DateGroup::Today
+        );
+    }
+
+    #[test]
+    fn test_date_group_labels() {
+        assert_eq!(DateGroup::Today.label(), "Today");
+        assert_eq!(DateGroup::LastWeek.label(), "Last week");
+        assert_eq!(
+            DateGroup::Month {
+                year: 2023,
+                month: 12
+            }
+            .label(),
+            "December 2023"
+        );
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_797), (2024, 3, 15));
+    }
+
+    #[test]
+    fn test_commit_copy_values() {
+        let commit = Commit {
+            id: "0123456789abcdef0123456789abcdef01234567".to_string(),
+            short_id: "0123456".to_string(),
+            message: "Fix the thing".to_string(),
+            body: "Fix the thing\n\nLonger explanation.\n".to_string(),
+            author_name: "Alice".to_string(),
+            author_email: "alice@example.com".to_string(),
+            author_time: 0,
+            time: 0,
+            committer_name: "Alice".to_string(),
+            committer_email: "alice@example.com".to_string(),
+            committer_time: 0,
+            parent_ids: Vec::new(),
+        };
+
+        assert_eq!(
+            CommitCopyTarget::Id.value(&commit),
+            "0123456789abcdef0123456789abcdef01234567"
+        );
+        assert_eq!(
+            CommitCopyTarget::Message.value(&commit),
+            "Fix the thing\n\nLonger explanation."
+        );
+    }
+}
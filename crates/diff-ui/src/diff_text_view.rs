@@ -3,7 +3,7 @@
 //! This component displays the diff between two text strings with colored
 //! backgrounds indicating added, deleted, and unchanged lines.
 
-use buffer_diff::{DiffHunkStatus, DiffLineType, TextDiff};
+use buffer_diff::{BufferDiff, DiffHunkStatus, DiffLineType, TextDiff};
 use gpui::{
     div, prelude::*, px, Context, IntoElement, Render, SharedString, Window,
     uniform_list,
@@ -216,6 +216,11 @@ impl DiffTextView {
                         }
                     }
                 }
+                DiffHunkStatus::Binary => {
+                    // Binary content has no lines to show
+                    self.display_lines
+                        .push(DiffDisplayLine::unchanged(BufferDiff::BINARY_MESSAGE));
+                }
             }
         }
 
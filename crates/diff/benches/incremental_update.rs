@@ -0,0 +1,71 @@
+//! Compare recomputing a whole diff against `BufferDiff::update` for
+//! single-character edits to a 10,000 line file
+//!
+//! Run with `cargo bench -p buffer-diff --bench incremental_update`.
+
+use buffer_diff::{BufferDiff, TextEdit};
+use std::time::{Duration, Instant};
+
+const LINES: usize = 10_000;
+const EDITS: usize = 200;
+
+fn main() {
+    let old: String = (0..LINES)
+        .map(|i| format!("    let value_{} = compute({}, {});\n", i, i, i * 7))
+        .collect();
+    // A few scattered changes, so there are hunks to keep
+    let new = old
+        .replace("value_1000 ", "renamed_1000 ")
+        .replace("value_5000 ", "renamed_5000 ")
+        .replace("value_9000 ", "renamed_9000 ");
+
+    // Type one character at a time, spread over the file
+    let edits: Vec<usize> = (0..EDITS).map(|i| (i * 7919) % LINES).collect();
+    let offset = |text: &str, line: usize| -> usize {
+        text.split_inclusive('\n')
+            .take(line)
+            .map(str::len)
+            .sum::<usize>()
+            + 4
+    };
+
+    let mut text = new.clone();
+    let mut full = Duration::ZERO;
+    for &line in &edits {
+        let at = offset(&text, line);
+        text.insert(at, 'x');
+        let start = Instant::now();
+        let diff = BufferDiff::new(&old, &text).unwrap();
+        full += start.elapsed();
+        std::hint::black_box(diff);
+    }
+
+    let mut diff = BufferDiff::new(&old, &new).unwrap();
+    let mut text = new.clone();
+    let mut incremental = Duration::ZERO;
+    for &line in &edits {
+        let at = offset(&text, line);
+        text.insert(at, 'x');
+        let edit = TextEdit::new(at..at, "x");
+        let start = Instant::now();
+        diff.update(&edit).unwrap();
+        incremental += start.elapsed();
+    }
+    assert_eq!(diff.apply(&old).unwrap(), text);
+
+    println!("{} single-character edits to a {} line file:", EDITS, LINES);
+    println!(
+        "  full recompute: {:>10.2?} total, {:>10.2?} per edit",
+        full,
+        full / EDITS as u32
+    );
+    println!(
+        "  incremental:    {:>10.2?} total, {:>10.2?} per edit",
+        incremental,
+        incremental / EDITS as u32
+    );
+    println!(
+        "  speedup:        {:.1}x",
+        full.as_secs_f64() / incremental.as_secs_f64()
+    );
+}
@@ -1,13 +1,26 @@
 use anyhow::Result;
 use rayon::prelude::*;
 use ropey::Rope;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-
-use crate::diff_hunk::{DiffHunk, DiffHunkStatus};
+use similar::DiffableStr;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::diff_hunk::{
+    DiffHunk, DiffHunkRange, DiffHunkSecondaryStatus, DiffHunkStatus, WhitespaceChange,
+};
+use crate::text_diff::{
+    line_count, split_lines, DiffAlgorithm, DiffConfig, DiffGranularity, WhitespaceMode,
+};
+
+/// Marker emitted in unified diffs after a last line that has no trailing newline
+pub const NO_NEWLINE_MARKER: &str = "\\ No newline at end of file";
 
 /// Represents a diff between two buffers (text documents)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BufferDiff {
     /// The old version of the text
     old_text: Rope,
@@ -17,6 +30,162 @@ pub struct BufferDiff {
 
     /// The hunks in this diff
     hunks: Vec<DiffHunk>,
+
+    /// Whether computing the diff was skipped (see `BufferDiff::suppressed`)
+    suppressed: bool,
+
+    /// Context lines the hunks were computed with, for `update`
+    context_lines: usize,
+
+    /// Whitespace changes ignored when comparing lines, for `update`
+    whitespace: WhitespaceMode,
+
+    /// Algorithm used to match up lines, for `update`
+    algorithm: DiffAlgorithm,
+
+    /// How long diffing may search for a minimal diff, for `update`
+    timeout: Option<Duration>,
+
+    /// Whether the timeout expired while diffing (see `BufferDiff::timed_out`)
+    timed_out: bool,
+
+    /// Passes run over the hunks after diffing, for `update`
+    post_passes: PostPasses,
+}
+
+/// When line diffs should settle for a non-minimal diff, and whether one did
+///
+/// Chunks of large files are diffed in parallel against the same deadline,
+/// so expiry is recorded atomically.
+struct Deadline {
+    at: Option<Instant>,
+    expired: AtomicBool,
+}
+
+impl Deadline {
+    /// A deadline `timeout` from now, or none
+    fn after(timeout: Option<Duration>) -> Self {
+        Self {
+            at: timeout.map(|timeout| Instant::now() + timeout),
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    /// Record whether the deadline has passed, right after a line diff
+    fn check(&self) {
+        if self.at.is_some_and(|at| Instant::now() >= at) {
+            self.expired.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether a line diff finished after the deadline
+    fn expired(&self) -> bool {
+        self.expired.load(Ordering::Relaxed)
+    }
+}
+
+/// The `DiffConfig` options that `DiffConfig::diff` applies to the hunks once
+/// the lines are diffed
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PostPasses {
+    coalesce_hunks: bool,
+    align_reordered: bool,
+    granularity: DiffGranularity,
+    max_hunks: Option<usize>,
+    detect_moves: bool,
+}
+
+impl From<&DiffConfig> for PostPasses {
+    fn from(config: &DiffConfig) -> Self {
+        Self {
+            coalesce_hunks: config.coalesce_hunks,
+            align_reordered: config.align_reordered,
+            granularity: config.granularity,
+            max_hunks: config.max_hunks,
+            detect_moves: config.detect_moves,
+        }
+    }
+}
+
+/// No passes, as for a default `DiffConfig`
+impl Default for PostPasses {
+    fn default() -> Self {
+        Self::from(&DiffConfig::default())
+    }
+}
+
+/// An edit to the new text of a `BufferDiff`, for `BufferDiff::update`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte range of the new text to replace
+    pub range: Range<usize>,
+
+    /// Text to replace the range with
+    pub new_text: String,
+}
+
+impl TextEdit {
+    /// Create an edit replacing `range` of the new text with `new_text`
+    pub fn new(range: Range<usize>, new_text: impl Into<String>) -> Self {
+        Self {
+            range,
+            new_text: new_text.into(),
+        }
+    }
+}
+
+/// Split a rope into lines as `split_lines` does, borrowing each line from
+/// the rope where it doesn't straddle chunks
+fn rope_lines(rope: &Rope) -> Vec<Cow<'_, str>> {
+    let mut ends = Vec::new();
+    let mut offset = 0;
+    for chunk in rope.chunks() {
+        ends.extend(chunk.match_indices('\n').map(|(index, _)| offset + index));
+        offset += chunk.len();
+    }
+    if offset > ends.last().map_or(0, |end| end + 1) {
+        ends.push(offset);
+    }
+
+    let mut start = 0;
+    ends.into_iter()
+        .map(|end| {
+            let line = Cow::from(rope.byte_slice(start..end));
+            start = end + 1;
+            match line {
+                Cow::Borrowed(line) => Cow::Borrowed(line.strip_suffix('\r').unwrap_or(line)),
+                Cow::Owned(mut line) => {
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                    Cow::Owned(line)
+                }
+            }
+        })
+        .collect()
+}
+
+/// A line of a diff, ready to be rendered, from `BufferDiff::lines`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine<'a> {
+    /// Line index in the old text, unless the line was added
+    pub old_line: Option<usize>,
+
+    /// Line index in the new text, unless the line was deleted
+    pub new_line: Option<usize>,
+
+    /// The line's text, without its line ending. Unchanged lines show the
+    /// old text.
+    pub content: Cow<'a, str>,
+
+    /// Which side of the diff the line belongs to
+    pub kind: crate::diff_hunk::DiffLineType,
+
+    /// Index of the hunk the line belongs to
+    pub hunk_index: usize,
+
+    /// Index of the line within its hunk's `line_types` (and `word_ranges`)
+    pub line_index: usize,
 }
 
 /// An immutable snapshot of a buffer diff
@@ -30,17 +199,103 @@ pub struct BufferDiffSnapshot {
 
     /// The number of lines in the new text
     pub new_line_count: usize,
+
+    /// Whether computing the diff was skipped because the input was too large
+    pub suppressed: bool,
+
+    /// Whether the diff timed out, so its hunks may not be minimal
+    pub timed_out: bool,
+
+    /// The text of each hunk's deleted lines, in order, parallel to `hunks`
+    ///
+    /// Lets `reconstruct_old` rebuild the old text from the new text alone.
+    /// Only filled in by `BufferDiff::snapshot_with_deleted_text`, and empty
+    /// otherwise.
+    pub deleted_text: Vec<Vec<String>>,
+}
+
+/// Line and hunk tallies for a diff, from `BufferDiffSnapshot::stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    /// Lines only in the new text
+    pub added_lines: usize,
+
+    /// Lines only in the old text
+    pub deleted_lines: usize,
+
+    /// Hunks that only add lines
+    pub added_hunks: usize,
+
+    /// Hunks that only delete lines
+    pub deleted_hunks: usize,
+
+    /// Hunks that replace old lines with new ones
+    pub modified_hunks: usize,
+}
+
+impl DiffStats {
+    /// Get the number of hunks with changes
+    pub fn changed_hunks(&self) -> usize {
+        self.added_hunks + self.deleted_hunks + self.modified_hunks
+    }
 }
 
 impl BufferDiff {
     /// Default chunk size for large file diffing (in lines)
     const DEFAULT_CHUNK_SIZE: usize = 1000;
 
-    /// Maximum number of concurrent chunks to process
+    /// Maximum number of chunks diffed at once (every chunk is diffed)
     const MAX_CONCURRENT_CHUNKS: usize = 8;
 
+    /// Default number of unchanged lines shown around each hunk's changes
+    pub const DEFAULT_CONTEXT_LINES: usize = 3;
+
+    /// Most hunks an edit can touch before `update` recomputes the whole diff
+    pub const MAX_INCREMENTAL_HUNKS: usize = 8;
+
+    /// Number of leading bytes searched for NUL bytes to detect binary content
+    pub const BINARY_CHECK_BYTES: usize = 8000;
+
     /// Create a new buffer diff between two texts
     pub fn new(old_text: &str, new_text: &str) -> Result<Self> {
+        Self::with_context_lines(old_text, new_text, Self::DEFAULT_CONTEXT_LINES)
+    }
+
+    /// Create a new buffer diff with up to `context_lines` unchanged lines
+    /// around each hunk's changes
+    ///
+    /// With 0, hunks contain only changed lines. Context never extends past the
+    /// start or end of the texts.
+    pub fn with_context_lines(
+        old_text: &str,
+        new_text: &str,
+        context_lines: usize,
+    ) -> Result<Self> {
+        Self::with_options(old_text, new_text, context_lines, WhitespaceMode::None)
+    }
+
+    /// Create a new buffer diff with `context_lines` of context, ignoring the
+    /// whitespace changes `whitespace` selects
+    ///
+    /// Lines are compared with whitespace normalized, but the diff keeps the
+    /// original texts, so lines display as they were written.
+    pub fn with_options(
+        old_text: &str,
+        new_text: &str,
+        context_lines: usize,
+        whitespace: WhitespaceMode,
+    ) -> Result<Self> {
+        let config = DiffConfig::default()
+            .context_lines(context_lines)
+            .ignore_whitespace(whitespace);
+        Self::compute(old_text, new_text, &config)
+    }
+
+    /// Create a buffer diff of the texts as given, with the context,
+    /// whitespace, algorithm and timeout settings of `config`
+    ///
+    /// `DiffConfig::diff` is the public way to pick the algorithm and timeout.
+    pub(crate) fn compute(old_text: &str, new_text: &str, config: &DiffConfig) -> Result<Self> {
         let old_rope = Rope::from_str(old_text);
         let new_rope = Rope::from_str(new_text);
 
@@ -48,16 +303,358 @@ impl BufferDiff {
             old_text: old_rope,
             new_text: new_rope,
             hunks: Vec::new(),
+            suppressed: false,
+            context_lines: config.context_lines,
+            whitespace: config.ignore_whitespace,
+            algorithm: config.algorithm,
+            timeout: config.timeout,
+            timed_out: false,
+            post_passes: PostPasses::from(config),
         };
 
         // Compute the hunks
-        diff.compute_hunks()?;
+        diff.compute_hunks(diff.context_lines, diff.whitespace)?;
 
         Ok(diff)
     }
 
+    /// Create a diff of only the given line ranges of two texts
+    ///
+    /// Ranges are 0-based, end-exclusive line numbers and are clamped to the
+    /// texts. Lines outside the ranges are ignored. The returned diff holds the
+    /// full texts, and its hunk line numbers are in whole-text coordinates.
+    pub fn new_range(
+        old_text: &str,
+        new_text: &str,
+        old_range: Range<usize>,
+        new_range: Range<usize>,
+        config: &DiffConfig,
+    ) -> Result<Self> {
+        let old_rope = Rope::from_str(old_text);
+        let new_rope = Rope::from_str(new_text);
+
+        let old_range = Self::clamp_line_range(&old_rope, old_range);
+        let new_range = Self::clamp_line_range(&new_rope, new_range);
+
+        let old_slice = old_rope
+            .slice(old_rope.line_to_char(old_range.start)..old_rope.line_to_char(old_range.end))
+            .to_string();
+        let new_slice = new_rope
+            .slice(new_rope.line_to_char(new_range.start)..new_rope.line_to_char(new_range.end))
+            .to_string();
+
+        let region = config.diff(&old_slice, &new_slice)?;
+
+        // Shift hunks from region-relative to whole-text line numbers
+        let hunks = region
+            .hunks
+            .into_iter()
+            .map(|mut hunk| {
+                hunk.old_range.start += old_range.start;
+                hunk.new_range.start += new_range.start;
+                hunk
+            })
+            .collect();
+
+        Ok(Self {
+            old_text: old_rope,
+            new_text: new_rope,
+            hunks,
+            suppressed: region.suppressed,
+            context_lines: config.context_lines,
+            whitespace: config.ignore_whitespace,
+            algorithm: config.algorithm,
+            timeout: config.timeout,
+            timed_out: region.timed_out,
+            post_passes: PostPasses::default(),
+        })
+    }
+
+    /// Clamp a line range to the lines of a rope
+    fn clamp_line_range(rope: &Rope, range: Range<usize>) -> Range<usize> {
+        let end = range.end.min(rope.len_lines());
+        range.start.min(end)..end
+    }
+
+    /// Message shown in place of a diff that was too large to compute
+    pub const SUPPRESSED_MESSAGE: &'static str = "diff suppressed (too many lines)";
+
+    /// Create a diff that skips computation and summarizes the change instead
+    ///
+    /// The result has a single `Modified` hunk spanning both texts with no
+    /// per-line information, and `is_suppressed()` returns true. Its snapshot
+    /// counts every old line as deleted and every new line as added.
+    pub fn suppressed(old_text: &str, new_text: &str) -> Self {
+        let mut hunk = DiffHunk::new(
+            DiffHunkStatus::Modified,
+            0,
+            line_count(old_text),
+            0,
+            line_count(new_text),
+        );
+        hunk.line_types.clear();
+
+        Self {
+            old_text: Rope::from_str(old_text),
+            new_text: Rope::from_str(new_text),
+            hunks: vec![hunk],
+            suppressed: true,
+            context_lines: Self::DEFAULT_CONTEXT_LINES,
+            whitespace: WhitespaceMode::None,
+            algorithm: DiffAlgorithm::default(),
+            timeout: DiffConfig::default().timeout,
+            timed_out: false,
+            post_passes: PostPasses::default(),
+        }
+    }
+
+    /// Check if computing this diff was skipped because the input was too large
+    pub fn is_suppressed(&self) -> bool {
+        self.suppressed
+    }
+
+    /// Check if the diff timeout expired while matching up lines
+    ///
+    /// The line diff gives up on finding a minimal diff when its timeout
+    /// expires (see `DiffConfig::timeout`), so the hunks are still correct
+    /// but may be larger or more fragmented than necessary. Only the line
+    /// diff itself is timed: building hunks and later passes don't count.
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// Message shown in place of the lines of a binary diff
+    pub const BINARY_MESSAGE: &'static str = "Binary file changed";
+
+    /// Check if this diff is between different binary contents
+    ///
+    /// Text with a NUL byte in its first `BINARY_CHECK_BYTES` bytes is treated
+    /// as binary. Such diffs aren't computed by line: they have a single
+    /// `Binary` hunk spanning both texts with no per-line information. Equal
+    /// binary contents get an `Unchanged` hunk instead, also without lines.
+    pub fn is_binary(&self) -> bool {
+        self.hunks
+            .iter()
+            .any(|hunk| hunk.status == DiffHunkStatus::Binary)
+    }
+
+    /// Check whether text looks binary, from a NUL byte near its start
+    fn looks_binary(rope: &Rope) -> bool {
+        rope.bytes()
+            .take(Self::BINARY_CHECK_BYTES)
+            .any(|byte| byte == 0)
+    }
+
+    /// Apply an edit to the new text and update the hunks to match
+    ///
+    /// Only the hunks the edit touches and their neighbours are recomputed:
+    /// the lines from the start of the hunk before the edit to the end of the
+    /// hunk after it are diffed again, and hunks further away are kept, just
+    /// shifted by the lines the edit added or removed. If the edit touches
+    /// more than `MAX_INCREMENTAL_HUNKS` hunks, the whole diff is recomputed.
+    ///
+    /// The `DiffConfig` options applied after diffing are applied again:
+    /// recomputed hunks are coalesced, aligned and get word ranges, then hunk
+    /// limits and move tags are redone for the whole diff. Fails if the edit's
+    /// range is out of bounds or doesn't fall on character boundaries.
+    pub fn update(&mut self, edit: &TextEdit) -> Result<()> {
+        let Range { start, end } = edit.range;
+        let len = self.new_text.len_bytes();
+        anyhow::ensure!(
+            start <= end && end <= len,
+            "edit range {}..{} is out of bounds for {} bytes",
+            start,
+            end,
+            len
+        );
+        let start_char = self.new_text.byte_to_char(start);
+        let end_char = self.new_text.byte_to_char(end);
+        anyhow::ensure!(
+            self.new_text.char_to_byte(start_char) == start
+                && self.new_text.char_to_byte(end_char) == end,
+            "edit range {}..{} is not on character boundaries",
+            start,
+            end
+        );
+
+        // Lines of the new text the edit touches, before it's applied
+        let first_line = self.new_text.char_to_line(start_char);
+        let last_line = self.new_text.char_to_line(end_char);
+        let lines_before = self.new_text.len_lines();
+
+        self.new_text.remove(start_char..end_char);
+        self.new_text.insert(start_char, &edit.new_text);
+        let line_delta = self.new_text.len_lines() as isize - lines_before as isize;
+
+        if self.suppressed {
+            *self = Self::suppressed(&self.old_text.to_string(), &self.new_text.to_string());
+            return Ok(());
+        }
+
+        // Binary content isn't diffed by line, so there are no hunks to patch
+        let was_binary = self.is_binary() || Self::looks_binary(&self.old_text);
+        if was_binary || Self::looks_binary(&self.new_text) {
+            return self.recompute();
+        }
+
+        // Hunks touching the edit's lines, or right next to them
+        self.hunks.retain(DiffHunk::has_changes);
+        let touched_start = self
+            .hunks
+            .partition_point(|hunk| hunk.new_range.end() < first_line);
+        let touched_end = self
+            .hunks
+            .partition_point(|hunk| hunk.new_range.start <= last_line);
+        if touched_end.saturating_sub(touched_start) > Self::MAX_INCREMENTAL_HUNKS {
+            return self.recompute();
+        }
+
+        // Recompute from the start of the hunk before the touched ones to the
+        // end of the hunk after them. Lines outside those hunks are unchanged,
+        // so the region starts and ends at the same lines of both texts.
+        let replaced_start = touched_start.saturating_sub(1);
+        let replaced_end = (touched_end + 1).min(self.hunks.len());
+        let (old_start, new_start) = match touched_start.checked_sub(1) {
+            Some(before) => {
+                let hunk = &self.hunks[before];
+                (hunk.old_range.start, hunk.new_range.start)
+            }
+            None => (0, 0),
+        };
+        let (old_end, new_end) = match self.hunks.get(touched_end) {
+            Some(hunk) => (
+                hunk.old_range.end(),
+                hunk.new_range.end().saturating_add_signed(line_delta),
+            ),
+            None => (self.old_text.len_lines(), self.new_text.len_lines()),
+        };
+
+        let old_region = self.extract_chunk_text(&self.old_text, old_start, old_end);
+        let new_region = self.extract_chunk_text(&self.new_text, new_start, new_end);
+        let config = DiffConfig::default()
+            .context_lines(self.context_lines)
+            .ignore_whitespace(self.whitespace)
+            .algorithm(self.algorithm)
+            .timeout(self.timeout);
+        let mut region = Self::compute(&old_region, &new_region, &config)?;
+        region.post_passes = self.post_passes;
+        region.run_hunk_passes();
+        self.timed_out |= region.timed_out;
+        let region_hunks =
+            region
+                .hunks
+                .into_iter()
+                .filter(DiffHunk::has_changes)
+                .map(|mut hunk| {
+                    hunk.old_range.start += old_start;
+                    hunk.new_range.start += new_start;
+                    hunk
+                });
+
+        // Hunks after the region only move by the lines the edit added or removed
+        for hunk in &mut self.hunks[replaced_end..] {
+            hunk.new_range.start = hunk.new_range.start.saturating_add_signed(line_delta);
+        }
+        self.hunks
+            .splice(replaced_start..replaced_end, region_hunks);
+
+        if self.hunks.is_empty() {
+            self.hunks.push(DiffHunk::new(
+                DiffHunkStatus::Unchanged,
+                0,
+                line_count(&self.old_text.to_string()),
+                0,
+                line_count(&self.new_text.to_string()),
+            ));
+        }
+
+        // Move tags point at hunk indices, which no longer line up
+        for hunk in &mut self.hunks {
+            if matches!(hunk.secondary_status, DiffHunkSecondaryStatus::Moved { .. }) {
+                hunk.secondary_status = DiffHunkSecondaryStatus::None;
+            }
+        }
+        self.run_diff_passes();
+
+        Ok(())
+    }
+
+    /// Recompute every hunk from scratch, with the diff's post-passes
+    fn recompute(&mut self) -> Result<()> {
+        self.hunks.clear();
+        self.compute_hunks(self.context_lines, self.whitespace)?;
+        self.run_post_passes();
+        Ok(())
+    }
+
+    /// Apply the `DiffConfig` options the diff was created with to its hunks
+    pub(crate) fn run_post_passes(&mut self) {
+        self.run_hunk_passes();
+        self.run_diff_passes();
+    }
+
+    /// Run the post-passes that work on hunks and the lines between them
+    fn run_hunk_passes(&mut self) {
+        if self.post_passes.coalesce_hunks {
+            self.coalesce_hunks(self.context_lines.saturating_mul(2));
+        }
+        // Alignment merges hunks, so it runs before anything that works per hunk
+        if self.post_passes.align_reordered {
+            self.align_reordered();
+        }
+        match self.post_passes.granularity {
+            DiffGranularity::Line => {}
+            DiffGranularity::Word => self.compute_word_ranges(),
+            DiffGranularity::Char => self.compute_char_ranges(),
+        }
+    }
+
+    /// Run the post-passes that look at the diff's hunks as a whole
+    fn run_diff_passes(&mut self) {
+        if let Some(max_hunks) = self.post_passes.max_hunks {
+            self.limit_hunks(max_hunks);
+        }
+        if self.post_passes.detect_moves {
+            self.detect_moves();
+        }
+    }
+
     /// Compute the hunks between the old and new text
-    fn compute_hunks(&mut self) -> Result<()> {
+    ///
+    /// Hunks include up to `context_lines` unchanged lines around their changes.
+    fn compute_hunks(&mut self, context_lines: usize, whitespace: WhitespaceMode) -> Result<()> {
+        let deadline = Deadline::after(self.timeout);
+        let result = self.compute_hunks_until(context_lines, whitespace, &deadline);
+        self.timed_out = deadline.expired();
+        result
+    }
+
+    /// Compute the hunks, letting line diffs give up at `deadline`
+    fn compute_hunks_until(
+        &mut self,
+        context_lines: usize,
+        whitespace: WhitespaceMode,
+        deadline: &Deadline,
+    ) -> Result<()> {
+        // Binary content has no meaningful lines, so skip diffing it
+        if Self::looks_binary(&self.old_text) || Self::looks_binary(&self.new_text) {
+            let status = if self.old_text == self.new_text {
+                DiffHunkStatus::Unchanged
+            } else {
+                DiffHunkStatus::Binary
+            };
+            let mut hunk = DiffHunk::new(
+                status,
+                0,
+                line_count(&self.old_text.to_string()),
+                0,
+                line_count(&self.new_text.to_string()),
+            );
+            hunk.line_types.clear();
+            self.hunks.push(hunk);
+            return Ok(());
+        }
+
         // Check for large files and apply chunking if needed
         if self.old_text.len_chars() > 100_000 || self.new_text.len_chars() > 100_000 {
             // Get line counts
@@ -66,54 +663,53 @@ impl BufferDiff {
 
             // If one or both files are empty, handle as special cases
             if old_line_count <= 1 || new_line_count <= 1 {
-                return self.compute_hunks_simple();
-            }
-
-            // Determine chunk boundaries for the old text
-            let old_chunks = self.calculate_chunk_boundaries(old_line_count);
-            let new_chunks = self.calculate_chunk_boundaries(new_line_count);
-
-            // Create a shared container for the results
-            let all_hunks = Arc::new(Mutex::new(Vec::new()));
-
-            // Determine the number of chunks to process (capped at MAX_CONCURRENT_CHUNKS)
-            let num_chunks = old_chunks.len().min(new_chunks.len()).min(Self::MAX_CONCURRENT_CHUNKS);
-
-            // Process chunks in parallel
-            (0..num_chunks).into_par_iter().for_each(|i| {
-                // Get chunk boundaries
-                let old_chunk = old_chunks.get(i).cloned().unwrap_or((0, old_line_count));
-                let new_chunk = new_chunks.get(i).cloned().unwrap_or((0, new_line_count));
-
-                // Extract chunk text
-                let old_chunk_text = self.extract_chunk_text(&self.old_text, old_chunk.0, old_chunk.1);
-                let new_chunk_text = self.extract_chunk_text(&self.new_text, new_chunk.0, new_chunk.1);
-
-                // Generate diff for this chunk
-                if let Ok(chunk_hunks) = self.diff_chunk(
-                    &old_chunk_text,
-                    &new_chunk_text,
-                    old_chunk.0,
-                    new_chunk.0
-                ) {
-                    // Add results to the shared container
-                    if let Ok(mut all_hunks_guard) = all_hunks.lock() {
-                        all_hunks_guard.extend(chunk_hunks);
-                    }
-                }
-            });
+                return self.compute_hunks_simple(context_lines, whitespace, deadline);
+            }
 
-            // Get the final results and sort by position
-            let mut final_hunks = match all_hunks.lock() {
-                Ok(guard) => guard.clone(),
-                Err(_) => Vec::new(),
+            // Split both texts into corresponding chunks
+            let pairs = self.chunk_pairs(old_line_count, new_line_count);
+
+            // Diff every chunk, with rayon spreading them over at most
+            // MAX_CONCURRENT_CHUNKS threads
+            let diff_chunks = || {
+                pairs
+                    .par_iter()
+                    .map(|&(old_chunk, new_chunk)| {
+                        // Extract chunk text
+                        let old_chunk_text =
+                            self.extract_chunk_text(&self.old_text, old_chunk.0, old_chunk.1);
+                        let new_chunk_text =
+                            self.extract_chunk_text(&self.new_text, new_chunk.0, new_chunk.1);
+
+                        // Generate diff for this chunk
+                        self.diff_chunk(
+                            &old_chunk_text,
+                            &new_chunk_text,
+                            old_chunk.0,
+                            new_chunk.0,
+                            whitespace,
+                            deadline,
+                        )
+                    })
+                    .collect::<Result<Vec<_>>>()
             };
+            let chunk_hunks = match chunk_pool() {
+                Some(pool) => pool.install(diff_chunks)?,
+                None => diff_chunks()?,
+            };
+            let mut final_hunks: Vec<DiffHunk> = chunk_hunks.into_iter().flatten().collect();
 
             // Sort hunks by their position in the original text
             final_hunks.sort_by_key(|hunk| hunk.old_range.start);
 
-            // Merge adjacent or overlapping hunks
-            self.hunks = self.merge_adjacent_hunks(final_hunks);
+            // Merge adjacent or overlapping hunks, then give them their context
+            let hunks = self.merge_adjacent_hunks(final_hunks);
+            self.hunks = Self::add_context(
+                hunks,
+                context_lines,
+                line_count(&self.old_text.to_string()),
+                line_count(&self.new_text.to_string()),
+            );
 
             // If no hunks were created, create an unchanged hunk
             if self.hunks.is_empty() {
@@ -133,11 +729,15 @@ impl BufferDiff {
         let old_text_str = self.old_text.to_string();
         let new_text_str = self.new_text.to_string();
 
-        // Get diff from similar crate with a timeout
-        let diff = similar::TextDiff::configure()
-            .algorithm(similar::Algorithm::Myers)
-            .timeout(std::time::Duration::from_secs(5))
-            .diff_lines(&old_text_str, &new_text_str);
+        // Get diff from similar crate with a timeout, comparing normalized lines
+        let old_lines = comparison_lines(&old_text_str, whitespace);
+        let new_lines = comparison_lines(&new_text_str, whitespace);
+        let old_refs: Vec<&str> = old_lines.iter().map(AsRef::as_ref).collect();
+        let new_refs: Vec<&str> = new_lines.iter().map(AsRef::as_ref).collect();
+        let diff = self
+            .line_diff_config(deadline.at)
+            .diff_slices(&old_refs, &new_refs);
+        deadline.check();
 
         // Special case: if both are empty
         if old_text_str.is_empty() && new_text_str.is_empty() {
@@ -148,39 +748,21 @@ impl BufferDiff {
 
         // Special case: if old is empty but new is not, this is an added file
         if old_text_str.is_empty() && !new_text_str.is_empty() {
-            let new_line_count = self.new_text.len_lines().saturating_sub(1);
-            if new_line_count == 0 {
-                // Single line with no newline
-                let new_line_count = 1;
-                let mut hunk = DiffHunk::new(DiffHunkStatus::Added, 0, 0, 0, new_line_count);
-                // Set all lines to NewOnly
-                hunk.line_types = vec![crate::diff_hunk::DiffLineType::NewOnly; new_line_count];
-                self.hunks.push(hunk);
-            } else {
-                let mut hunk = DiffHunk::new(DiffHunkStatus::Added, 0, 0, 0, new_line_count);
-                // Set all lines to NewOnly
-                hunk.line_types = vec![crate::diff_hunk::DiffLineType::NewOnly; new_line_count];
-                self.hunks.push(hunk);
-            }
+            let new_line_count = line_count(&new_text_str);
+            let mut hunk = DiffHunk::new(DiffHunkStatus::Added, 0, 0, 0, new_line_count);
+            // Set all lines to NewOnly
+            hunk.line_types = vec![crate::diff_hunk::DiffLineType::NewOnly; new_line_count];
+            self.hunks.push(hunk);
             return Ok(());
         }
 
         // Special case: if new is empty but old is not, this is a deleted file
         if !old_text_str.is_empty() && new_text_str.is_empty() {
-            let old_line_count = self.old_text.len_lines().saturating_sub(1);
-            if old_line_count == 0 {
-                // Single line with no newline
-                let old_line_count = 1;
-                let mut hunk = DiffHunk::new(DiffHunkStatus::Deleted, 0, old_line_count, 0, 0);
-                // Set all lines to OldOnly
-                hunk.line_types = vec![crate::diff_hunk::DiffLineType::OldOnly; old_line_count];
-                self.hunks.push(hunk);
-            } else {
-                let mut hunk = DiffHunk::new(DiffHunkStatus::Deleted, 0, old_line_count, 0, 0);
-                // Set all lines to OldOnly
-                hunk.line_types = vec![crate::diff_hunk::DiffLineType::OldOnly; old_line_count];
-                self.hunks.push(hunk);
-            }
+            let old_line_count = line_count(&old_text_str);
+            let mut hunk = DiffHunk::new(DiffHunkStatus::Deleted, 0, old_line_count, 0, 0);
+            // Set all lines to OldOnly
+            hunk.line_types = vec![crate::diff_hunk::DiffLineType::OldOnly; old_line_count];
+            self.hunks.push(hunk);
             return Ok(());
         }
 
@@ -189,20 +771,8 @@ impl BufferDiff {
             .iter_all_changes()
             .any(|c| c.tag() != similar::ChangeTag::Equal)
         {
-            let old_line_count = self.old_text.len_lines().saturating_sub(1);
-            let new_line_count = self.new_text.len_lines().saturating_sub(1);
-
-            // Ensure we have at least 1 line if the text contains content
-            let old_line_count = if old_line_count == 0 && !old_text_str.is_empty() {
-                1
-            } else {
-                old_line_count
-            };
-            let new_line_count = if new_line_count == 0 && !new_text_str.is_empty() {
-                1
-            } else {
-                new_line_count
-            };
+            let old_line_count = line_count(&old_text_str);
+            let new_line_count = line_count(&new_text_str);
 
             let mut hunk = DiffHunk::new(
                 DiffHunkStatus::Unchanged,
@@ -220,17 +790,27 @@ impl BufferDiff {
         }
 
         // Process diffs to create hunks
-        self.process_diffs(diff)?;
+        self.process_diffs(diff, context_lines)?;
 
         Ok(())
     }
 
     /// Process the diffs to create hunks
-    fn process_diffs<'a>(&mut self, diff: similar::TextDiff<'a, 'a, 'a, str>) -> Result<()> {
-        // Keep track of unchanged lines for context
+    ///
+    /// Each hunk gets up to `context_lines` unchanged lines before and after its
+    /// changes. Context lines are never shared between hunks: when two changes
+    /// are closer together than that, the first hunk's trailing context ends
+    /// where the second hunk begins.
+    fn process_diffs<'a>(
+        &mut self,
+        diff: similar::TextDiff<'a, 'a, '_, str>,
+        context_lines: usize,
+    ) -> Result<()> {
+        // Unchanged lines since the last hunk, for context before the next one
         let mut unchanged_lines: Vec<String> = Vec::new();
-        let mut unchanged_start_old = 0;
-        let mut unchanged_start_new = 0;
+
+        // Unchanged lines after the current changes, for context after them
+        let mut trailing_lines: Vec<String> = Vec::new();
 
         // Track current position in both texts
         let mut old_pos = 0;
@@ -242,147 +822,77 @@ impl BufferDiff {
 
         // Process each change in the diff
         for change in diff.iter_all_changes() {
+            let has_changes = !old_changes.is_empty() || !new_changes.is_empty();
+
             match change.tag() {
                 similar::ChangeTag::Equal => {
-                    // If we had changes collected, create a hunk with some context
-                    if !old_changes.is_empty() || !new_changes.is_empty() {
-                        // Create a hunk with context from unchanged lines
-                        let context_lines = 3; // Number of context lines before/after changes
-
-                        // Add context before if available
-                        let before_context = unchanged_lines.len().min(context_lines);
-                        let context_start = unchanged_lines.len() - before_context;
-
-                        // Include some unchanged lines before as context
-                        let mut context_old_changes = Vec::new();
-                        let mut context_new_changes = Vec::new();
-
-                        // Add unchanged lines as context
-                        for line in unchanged_lines.iter().skip(context_start) {
-                            context_old_changes.push(line.clone());
-                            context_new_changes.push(line.clone());
+                    if has_changes && trailing_lines.len() < context_lines {
+                        // Still within the context after the current changes
+                        trailing_lines.push(change.value().to_string());
+                    } else {
+                        // The context after the current changes is complete
+                        if has_changes {
+                            self.create_hunk_from_changes(
+                                (old_pos, new_pos),
+                                &unchanged_lines,
+                                std::mem::take(&mut old_changes),
+                                std::mem::take(&mut new_changes),
+                                std::mem::take(&mut trailing_lines),
+                            )?;
+                            unchanged_lines.clear();
                         }
 
-                        // Add actual changes
-                        context_old_changes.extend(old_changes.clone());
-                        context_new_changes.extend(new_changes.clone());
-
-                        // Add the current line as context after if it's equal
-                        context_old_changes.push(change.value().to_string());
-                        context_new_changes.push(change.value().to_string());
-
-                        // Calculate start positions with context
-                        let old_start =
-                            (unchanged_start_old + context_start).min(old_pos - old_changes.len());
-                        let new_start =
-                            (unchanged_start_new + context_start).min(new_pos - new_changes.len());
-
-                        // Create the hunk with context
-                        self.create_hunk_with_context(
-                            old_start,
-                            context_old_changes,
-                            new_start,
-                            context_new_changes,
-                            before_context,
-                            1,
-                        )?;
-
-                        // Reset collections
-                        old_changes = Vec::new();
-                        new_changes = Vec::new();
-                        unchanged_lines = Vec::new();
-                    }
-
-                    // Track this unchanged line
-                    if unchanged_lines.is_empty() {
-                        unchanged_start_old = old_pos;
-                        unchanged_start_new = new_pos;
+                        // Keep only the lines that can be context for the next hunk
+                        unchanged_lines.push(change.value().to_string());
+                        if unchanged_lines.len() > context_lines {
+                            unchanged_lines.remove(0);
+                        }
                     }
-                    unchanged_lines.push(change.value().to_string());
 
                     // Move positions forward
                     old_pos += 1;
                     new_pos += 1;
                 }
-                similar::ChangeTag::Delete => {
-                    // Reset unchanged tracking on first change
-                    if old_changes.is_empty() && new_changes.is_empty() {
-                        // Keep only recent context lines
-                        let context_lines = 3;
-                        if unchanged_lines.len() > context_lines {
-                            let keep = unchanged_lines.len() - context_lines;
-                            unchanged_lines.drain(0..keep);
-                            unchanged_start_old += keep;
-                            unchanged_start_new += keep;
-                        }
+                similar::ChangeTag::Delete | similar::ChangeTag::Insert => {
+                    // A change within the current hunk's trailing context ends that hunk
+                    if !trailing_lines.is_empty() {
+                        self.create_hunk_from_changes(
+                            (old_pos, new_pos),
+                            &unchanged_lines,
+                            std::mem::take(&mut old_changes),
+                            std::mem::take(&mut new_changes),
+                            std::mem::take(&mut trailing_lines),
+                        )?;
+                        unchanged_lines.clear();
                     }
 
-                    // Collect the deleted line
-                    old_changes.push(change.value().to_string());
-                    old_pos += 1;
-                }
-                similar::ChangeTag::Insert => {
-                    // Reset unchanged tracking on first change
-                    if old_changes.is_empty() && new_changes.is_empty() {
-                        // Keep only recent context lines
-                        let context_lines = 3;
-                        if unchanged_lines.len() > context_lines {
-                            let keep = unchanged_lines.len() - context_lines;
-                            unchanged_lines.drain(0..keep);
-                            unchanged_start_old += keep;
-                            unchanged_start_new += keep;
-                        }
+                    // Collect the changed line
+                    if change.tag() == similar::ChangeTag::Delete {
+                        old_changes.push(change.value().to_string());
+                        old_pos += 1;
+                    } else {
+                        new_changes.push(change.value().to_string());
+                        new_pos += 1;
                     }
-
-                    // Collect the added line
-                    new_changes.push(change.value().to_string());
-                    new_pos += 1;
                 }
             }
         }
 
         // If we have changes left, create a final hunk
         if !old_changes.is_empty() || !new_changes.is_empty() {
-            // Create a hunk with context from unchanged lines
-            let context_lines = 3;
-
-            // Add context before if available
-            let before_context = unchanged_lines.len().min(context_lines);
-            let context_start = unchanged_lines.len() - before_context;
-
-            // Include some unchanged lines before as context
-            let mut context_old_changes = Vec::new();
-            let mut context_new_changes = Vec::new();
-
-            // Add unchanged lines as context
-            for line in unchanged_lines.iter().skip(context_start) {
-                context_old_changes.push(line.clone());
-                context_new_changes.push(line.clone());
-            }
-
-            // Add actual changes
-            context_old_changes.extend(old_changes.clone());
-            context_new_changes.extend(new_changes.clone());
-
-            // Calculate start positions with context
-            let old_start = (unchanged_start_old + context_start).min(old_pos - old_changes.len());
-            let new_start = (unchanged_start_new + context_start).min(new_pos - new_changes.len());
-
-            // Create the hunk with context
-            self.create_hunk_with_context(
-                old_start,
-                context_old_changes,
-                new_start,
-                context_new_changes,
-                before_context,
-                0,
+            self.create_hunk_from_changes(
+                (old_pos, new_pos),
+                &unchanged_lines,
+                old_changes,
+                new_changes,
+                trailing_lines,
             )?;
         }
 
         // If no hunks were created, create an unchanged hunk
         if self.hunks.is_empty() {
-            let old_line_count = self.old_text.len_lines().saturating_sub(1);
-            let new_line_count = self.new_text.len_lines().saturating_sub(1);
+            let old_line_count = line_count(&self.old_text.to_string());
+            let new_line_count = line_count(&self.new_text.to_string());
 
             self.hunks.push(DiffHunk::new(
                 DiffHunkStatus::Unchanged,
@@ -396,6 +906,40 @@ impl BufferDiff {
         Ok(())
     }
 
+    /// Create a hunk from collected changes and the unchanged lines around them
+    ///
+    /// `end` is the position in the old and new texts just past the trailing
+    /// context lines.
+    fn create_hunk_from_changes(
+        &mut self,
+        end: (usize, usize),
+        before: &[String],
+        old_changes: Vec<String>,
+        new_changes: Vec<String>,
+        after: Vec<String>,
+    ) -> Result<()> {
+        let old_start = end.0 - after.len() - old_changes.len() - before.len();
+        let new_start = end.1 - after.len() - new_changes.len() - before.len();
+        let (before_context, after_context) = (before.len(), after.len());
+
+        let mut context_old_changes = before.to_vec();
+        context_old_changes.extend(old_changes);
+        context_old_changes.extend(after.iter().cloned());
+
+        let mut context_new_changes = before.to_vec();
+        context_new_changes.extend(new_changes);
+        context_new_changes.extend(after);
+
+        self.create_hunk_with_context(
+            old_start,
+            context_old_changes,
+            new_start,
+            context_new_changes,
+            before_context,
+            after_context,
+        )
+    }
+
     /// Create a hunk from collected old and new changes with context lines
     fn create_hunk_with_context(
         &mut self,
@@ -424,8 +968,10 @@ impl BufferDiff {
 
             for i in before_context..(old_changes.len() - after_context) {
                 let j = i - before_context;
-                if i < old_changes.len() && j + before_context < new_changes.len()
-                    && old_changes[i] != new_changes[j + before_context] {
+                if i < old_changes.len()
+                    && j + before_context < new_changes.len()
+                    && old_changes[i] != new_changes[j + before_context]
+                {
                     is_modified = true;
                     break;
                 }
@@ -520,9 +1066,685 @@ impl BufferDiff {
             hunks: self.hunks.clone(),
             old_line_count: self.old_text.len_lines(),
             new_line_count: self.new_text.len_lines(),
+            suppressed: self.suppressed,
+            timed_out: self.timed_out,
+            deleted_text: Vec::new(),
+        }
+    }
+
+    /// Get a snapshot of the current diff that also holds the text of each
+    /// hunk's deleted lines, for `BufferDiffSnapshot::reconstruct_old`
+    pub fn snapshot_with_deleted_text(&self) -> BufferDiffSnapshot {
+        BufferDiffSnapshot {
+            deleted_text: self.hunks.iter().map(|h| self.deleted_text(h)).collect(),
+            ..self.snapshot()
+        }
+    }
+
+    /// Collect the old text of a hunk's deleted lines, in order
+    fn deleted_text(&self, hunk: &DiffHunk) -> Vec<String> {
+        let mut old_line = hunk.old_range.start;
+        let mut deleted = Vec::new();
+
+        for line_type in &hunk.line_types {
+            match line_type {
+                crate::diff_hunk::DiffLineType::Both => old_line += 1,
+                crate::diff_hunk::DiffLineType::OldOnly => {
+                    if old_line < self.old_text.len_lines() {
+                        let line = self.old_text.line(old_line).to_string();
+                        let line = line.strip_suffix('\n').unwrap_or(&line);
+                        deleted.push(line.strip_suffix('\r').unwrap_or(line).to_string());
+                    }
+                    old_line += 1;
+                }
+                crate::diff_hunk::DiffLineType::NewOnly => {}
+            }
+        }
+
+        deleted
+    }
+
+    /// Collect the new text of a hunk's added lines, in order
+    fn added_text(&self, hunk: &DiffHunk) -> Vec<String> {
+        let mut new_line = hunk.new_range.start;
+        let mut added = Vec::new();
+
+        for line_type in &hunk.line_types {
+            match line_type {
+                crate::diff_hunk::DiffLineType::Both => new_line += 1,
+                crate::diff_hunk::DiffLineType::NewOnly => {
+                    if new_line < self.new_text.len_lines() {
+                        let line = self.new_text.line(new_line).to_string();
+                        let line = line.strip_suffix('\n').unwrap_or(&line);
+                        added.push(line.strip_suffix('\r').unwrap_or(line).to_string());
+                    }
+                    new_line += 1;
+                }
+                crate::diff_hunk::DiffLineType::OldOnly => {}
+            }
+        }
+
+        added
+    }
+
+    /// Tag `Deleted` hunks whose lines reappear as an `Added` hunk elsewhere
+    ///
+    /// Both hunks of a pair get `DiffHunkSecondaryStatus::Moved`, pointing at
+    /// each other. Deleted hunks are hashed by their lines, so each added hunk
+    /// finds its partner in one lookup; when several deleted hunks match, they
+    /// pair with added hunks in order. Blocks of only blank lines are never
+    /// considered moved.
+    pub fn detect_moves(&mut self) {
+        let mut deleted: HashMap<Vec<String>, VecDeque<usize>> = HashMap::new();
+        for (index, hunk) in self.hunks.iter().enumerate() {
+            if hunk.status == DiffHunkStatus::Deleted {
+                let lines = self.deleted_text(hunk);
+                if lines.iter().any(|line| !line.trim().is_empty()) {
+                    deleted.entry(lines).or_default().push_back(index);
+                }
+            }
+        }
+        if deleted.is_empty() {
+            return;
+        }
+
+        for index in 0..self.hunks.len() {
+            if self.hunks[index].status != DiffHunkStatus::Added {
+                continue;
+            }
+            let lines = self.added_text(&self.hunks[index]);
+            if let Some(partner) = deleted.get_mut(&lines).and_then(VecDeque::pop_front) {
+                self.hunks[index].secondary_status = DiffHunkSecondaryStatus::Moved {
+                    partner_index: partner,
+                };
+                self.hunks[partner].secondary_status = DiffHunkSecondaryStatus::Moved {
+                    partner_index: index,
+                };
+            }
         }
     }
 
+    /// Mark lines that moved within a block of changes without changing
+    ///
+    /// The diff keeps one of a set of reordered lines in place and splits the
+    /// rest into touching added and deleted hunks. Each run of touching hunks
+    /// is merged into one `Modified` hunk, and every deleted line in it is
+    /// matched with an identical added line, regardless of order. Matches are
+    /// recorded in the hunk's `reordered` list; runs without any match are
+    /// left as they were. Unchanged lines between the first and last matched
+    /// line are part of the reordered block, so the line the diff kept in
+    /// place is matched too. Lines are compared like the diff compared them,
+    /// with the diff's whitespace handling. When a line repeats, copies pair
+    /// up in order. Blank lines are never considered reordered.
+    pub fn align_reordered(&mut self) {
+        let old_text = self.old_text.to_string();
+        let new_text = self.new_text.to_string();
+        let old_lines: Vec<&str> = split_lines(&old_text).collect();
+        let new_lines: Vec<&str> = split_lines(&new_text).collect();
+
+        let mut hunks = std::mem::take(&mut self.hunks).into_iter().peekable();
+        while let Some(first) = hunks.next() {
+            if first.status == DiffHunkStatus::Unchanged {
+                self.hunks.push(first);
+                continue;
+            }
+
+            // Collect the run of hunks touching this one
+            let mut run = vec![first];
+            while let Some(next) = hunks.next_if(|next| {
+                let last = run.last().unwrap();
+                next.status != DiffHunkStatus::Unchanged
+                    && next.old_range.start <= last.old_range.end()
+                    && next.new_range.start <= last.new_range.end()
+            }) {
+                run.push(next);
+            }
+
+            let mut merged = run
+                .iter()
+                .cloned()
+                .reduce(|merged, next| self.merge_hunks(merged, next))
+                .unwrap();
+            let mut reordered = self.reordered_pairs(&merged, &old_lines, &new_lines);
+            if reordered.is_empty() {
+                self.hunks.extend(run);
+                continue;
+            }
+
+            // Lines the diff kept in place between moved lines moved within
+            // the block too, so pair them up as well
+            let first = reordered
+                .iter()
+                .map(|&(old, new)| old.min(new))
+                .min()
+                .unwrap();
+            let last = reordered
+                .iter()
+                .map(|&(old, new)| old.max(new))
+                .max()
+                .unwrap();
+            let block = &merged.line_types[first..=last];
+            if block.contains(&crate::diff_hunk::DiffLineType::Both) {
+                let old_count = block
+                    .iter()
+                    .filter(|&&t| t != crate::diff_hunk::DiffLineType::NewOnly)
+                    .count();
+                let new_count = block
+                    .iter()
+                    .filter(|&&t| t != crate::diff_hunk::DiffLineType::OldOnly)
+                    .count();
+                let block =
+                    std::iter::repeat_n(crate::diff_hunk::DiffLineType::OldOnly, old_count).chain(
+                        std::iter::repeat_n(crate::diff_hunk::DiffLineType::NewOnly, new_count),
+                    );
+                merged.line_types.splice(first..=last, block);
+                merged.word_ranges = None;
+                reordered = self.reordered_pairs(&merged, &old_lines, &new_lines);
+            }
+
+            merged.status = DiffHunkStatus::Modified;
+            merged.reordered = reordered;
+            self.hunks.push(merged);
+        }
+    }
+
+    /// Pairs of line indices in `hunk` where a deleted line reappears as an
+    /// added line, for `align_reordered`
+    fn reordered_pairs(
+        &self,
+        hunk: &DiffHunk,
+        old_lines: &[&str],
+        new_lines: &[&str],
+    ) -> Vec<(usize, usize)> {
+        // Deleted lines by their text, in order
+        let mut deleted: HashMap<Cow<str>, VecDeque<usize>> = HashMap::new();
+        let (mut old_line, mut new_line) = (hunk.old_range.start, hunk.new_range.start);
+        let mut added = Vec::new();
+        for (index, line_type) in hunk.line_types.iter().enumerate() {
+            match line_type {
+                crate::diff_hunk::DiffLineType::Both => {
+                    old_line += 1;
+                    new_line += 1;
+                }
+                crate::diff_hunk::DiffLineType::OldOnly => {
+                    if let Some(line) = old_lines.get(old_line) {
+                        if !line.trim().is_empty() {
+                            let key = self.whitespace.normalize_line(line);
+                            deleted.entry(key).or_default().push_back(index);
+                        }
+                    }
+                    old_line += 1;
+                }
+                crate::diff_hunk::DiffLineType::NewOnly => {
+                    if let Some(line) = new_lines.get(new_line) {
+                        added.push((index, *line));
+                    }
+                    new_line += 1;
+                }
+            }
+        }
+
+        added
+            .into_iter()
+            .filter_map(|(index, line)| {
+                let key = self.whitespace.normalize_line(line);
+                let old_index = deleted.get_mut(&key)?.pop_front()?;
+                Some((old_index, index))
+            })
+            .collect()
+    }
+
+    /// For each hunk, what it changes if it only changes whitespace
+    ///
+    /// Parallel to `hunks`. Each hunk's deleted lines are compared with its
+    /// added lines in order, even in whitespace the diff itself didn't ignore:
+    /// lines that differ only in a missing final newline give `EofNewline`,
+    /// only in trailing whitespace `Trailing`, and only in whitespace anywhere
+    /// `Within`. Hunks that change anything else, or no lines, give `None`.
+    pub fn whitespace_changes(&self) -> Vec<Option<WhitespaceChange>> {
+        let old_text = self.old_text.to_string();
+        let new_text = self.new_text.to_string();
+        // Numbered like `split_lines`, but keeping line endings
+        let old_lines: Vec<&str> = old_text.split_inclusive('\n').collect();
+        let new_lines: Vec<&str> = new_text.split_inclusive('\n').collect();
+
+        self.hunks
+            .iter()
+            .map(|hunk| {
+                let (mut old_line, mut new_line) = (hunk.old_range.start, hunk.new_range.start);
+                let (mut deleted, mut added) = (Vec::new(), Vec::new());
+                for line_type in &hunk.line_types {
+                    match line_type {
+                        crate::diff_hunk::DiffLineType::Both => {
+                            old_line += 1;
+                            new_line += 1;
+                        }
+                        crate::diff_hunk::DiffLineType::OldOnly => {
+                            deleted.extend(old_lines.get(old_line).copied());
+                            old_line += 1;
+                        }
+                        crate::diff_hunk::DiffLineType::NewOnly => {
+                            added.extend(new_lines.get(new_line).copied());
+                            new_line += 1;
+                        }
+                    }
+                }
+                whitespace_change(&deleted, &added)
+            })
+            .collect()
+    }
+
+    /// Fill in each hunk's `word_ranges` with the words changed within its lines
+    pub fn compute_word_ranges(&mut self) {
+        self.compute_changed_ranges(DiffHunk::compute_word_ranges);
+    }
+
+    /// Fill in each hunk's `word_ranges` with the characters changed within its
+    /// lines
+    pub fn compute_char_ranges(&mut self) {
+        self.compute_changed_ranges(DiffHunk::compute_char_ranges);
+    }
+
+    /// Fill in each hunk's `word_ranges` with `compute`, given every line of
+    /// both texts
+    fn compute_changed_ranges(&mut self, compute: fn(&mut DiffHunk, &[&str], &[&str])) {
+        let old_text = self.old_text.to_string();
+        let new_text = self.new_text.to_string();
+        let old_lines: Vec<&str> = split_lines(&old_text).collect();
+        let new_lines: Vec<&str> = split_lines(&new_text).collect();
+
+        for hunk in &mut self.hunks {
+            compute(hunk, &old_lines, &new_lines);
+        }
+    }
+
+    /// Get the diff that undoes this one, from the new text back to the old
+    ///
+    /// The texts swap and each hunk is reversed (see `DiffHunk::reversed`).
+    pub fn reversed(&self) -> BufferDiff {
+        BufferDiff {
+            old_text: self.new_text.clone(),
+            new_text: self.old_text.clone(),
+            hunks: self.hunks.iter().map(DiffHunk::reversed).collect(),
+            suppressed: self.suppressed,
+            context_lines: self.context_lines,
+            whitespace: self.whitespace,
+            algorithm: self.algorithm,
+            timeout: self.timeout,
+            timed_out: self.timed_out,
+            post_passes: self.post_passes,
+        }
+    }
+
+    /// Merge consecutive hunks with the same status that are at most `max_gap`
+    /// unchanged lines apart
+    ///
+    /// Diffing large files chunk by chunk can split one change into a run of
+    /// hunks with the same status, separated only by a few unchanged lines.
+    /// Each such run becomes a single hunk of that status, with the lines
+    /// between them as unchanged lines within it. Unchanged and binary hunks
+    /// are never merged, and neither are hunks whose gaps differ between the
+    /// old and new text.
+    pub fn coalesce_hunks(&mut self, max_gap: usize) {
+        let mut hunks = std::mem::take(&mut self.hunks).into_iter();
+        let Some(mut current) = hunks.next() else {
+            return;
+        };
+
+        for next in hunks {
+            let old_gap = next.old_range.start.checked_sub(current.old_range.end());
+            let new_gap = next.new_range.start.checked_sub(current.new_range.end());
+            let mergeable = next.status == current.status
+                && !matches!(
+                    current.status,
+                    DiffHunkStatus::Unchanged | DiffHunkStatus::Binary
+                )
+                && old_gap.is_some_and(|gap| gap <= max_gap)
+                && old_gap == new_gap;
+            if !mergeable {
+                self.hunks.push(std::mem::replace(&mut current, next));
+                continue;
+            }
+
+            let gap = old_gap.unwrap_or_default();
+            current.old_range.count = next.old_range.end() - current.old_range.start;
+            current.new_range.count = next.new_range.end() - current.new_range.start;
+            current.line_types.extend(std::iter::repeat_n(
+                crate::diff_hunk::DiffLineType::Both,
+                gap,
+            ));
+            let offset = current.line_types.len();
+            current.line_types.extend(&next.line_types);
+            current.reordered.extend(
+                next.reordered
+                    .iter()
+                    .map(|&(old, new)| (old + offset, new + offset)),
+            );
+            current.word_ranges = match (current.word_ranges.take(), &next.word_ranges) {
+                (Some(mut ranges), Some(next_ranges)) => {
+                    ranges.extend(std::iter::repeat_n(Vec::new(), gap));
+                    ranges.extend(next_ranges.iter().cloned());
+                    Some(ranges)
+                }
+                _ => None,
+            };
+        }
+        self.hunks.push(current);
+    }
+
+    /// Coalesce hunks beyond the first `max_hunks` into one trailing hunk
+    ///
+    /// The last hunk kept is `Modified` and spans from the first coalesced hunk
+    /// to the end of the last one. Lines between the coalesced hunks become
+    /// unchanged lines within it, so the line information stays complete. A
+    /// limit of 0 is treated as 1.
+    pub fn limit_hunks(&mut self, max_hunks: usize) {
+        let max_hunks = max_hunks.max(1);
+        if self.hunks.len() <= max_hunks {
+            return;
+        }
+
+        let rest = self.hunks.split_off(max_hunks - 1);
+        let first = &rest[0];
+        let last = &rest[rest.len() - 1];
+        let mut coalesced = DiffHunk::new(
+            DiffHunkStatus::Modified,
+            first.old_range.start,
+            last.old_range.end() - first.old_range.start,
+            first.new_range.start,
+            last.new_range.end() - first.new_range.start,
+        );
+        coalesced.line_types.clear();
+        let mut word_ranges = rest
+            .iter()
+            .all(|hunk| hunk.word_ranges.is_some())
+            .then(Vec::new);
+
+        let mut old_end = first.old_range.start;
+        for hunk in &rest {
+            // Unchanged lines between this hunk and the one before it
+            let gap = hunk.old_range.start.saturating_sub(old_end);
+            coalesced.line_types.extend(std::iter::repeat_n(
+                crate::diff_hunk::DiffLineType::Both,
+                gap,
+            ));
+            let offset = coalesced.line_types.len();
+            coalesced.line_types.extend(&hunk.line_types);
+            coalesced.reordered.extend(
+                hunk.reordered
+                    .iter()
+                    .map(|&(old, new)| (old + offset, new + offset)),
+            );
+            if let (Some(ranges), Some(hunk_ranges)) = (&mut word_ranges, &hunk.word_ranges) {
+                ranges.extend(std::iter::repeat_n(Vec::new(), gap));
+                ranges.extend(hunk_ranges.iter().cloned());
+            }
+            old_end = hunk.old_range.end();
+        }
+        coalesced.word_ranges = word_ranges;
+
+        self.hunks.push(coalesced);
+    }
+
+    /// Apply the diff to `old_text`, returning the new text
+    ///
+    /// Walks the hunks in order, copying unchanged lines from `old_text` and
+    /// splicing in each hunk's new lines. Line endings are kept as they are.
+    /// Fails if a hunk's old lines don't match `old_text` at its `old_range`,
+    /// or if the diff was suppressed or is binary and has no line information.
+    pub fn apply(&self, old_text: &str) -> Result<String> {
+        if self.suppressed {
+            anyhow::bail!("cannot apply a suppressed diff");
+        }
+        if self.is_binary() {
+            anyhow::bail!("cannot apply a binary diff");
+        }
+
+        let diff_old_text = self.old_text.to_string();
+        let diff_new_text = self.new_text.to_string();
+        let diff_old_lines: Vec<&str> = diff_old_text.split_inclusive('\n').collect();
+        let new_lines: Vec<&str> = diff_new_text.split_inclusive('\n').collect();
+        let old_lines: Vec<&str> = old_text.split_inclusive('\n').collect();
+
+        let mut result = String::with_capacity(diff_new_text.len());
+        let (mut old_index, mut new_index) = (0, 0);
+
+        for (i, hunk) in self.hunks.iter().enumerate() {
+            let old_start = hunk.old_range.start;
+            let lined_up = old_start >= old_index
+                && hunk.new_range.start == new_index + (old_start - old_index);
+            if !lined_up {
+                anyhow::bail!("hunk {} doesn't line up with the hunks before it", i);
+            }
+            if hunk.old_range.end() > old_lines.len() {
+                anyhow::bail!(
+                    "hunk {} ends at line {}, past the end of the text ({} lines)",
+                    i,
+                    hunk.old_range.end(),
+                    old_lines.len()
+                );
+            }
+
+            // Unchanged lines before the hunk
+            for line in &old_lines[old_index..old_start] {
+                result.push_str(line);
+            }
+            new_index += old_start - old_index;
+            old_index = old_start;
+
+            for line_type in &hunk.line_types {
+                match line_type {
+                    crate::diff_hunk::DiffLineType::Both
+                    | crate::diff_hunk::DiffLineType::OldOnly => {
+                        if old_lines.get(old_index) != diff_old_lines.get(old_index) {
+                            anyhow::bail!(
+                                "hunk {} doesn't match the text at line {}",
+                                i,
+                                old_index + 1
+                            );
+                        }
+                        if *line_type == crate::diff_hunk::DiffLineType::Both {
+                            result.push_str(old_lines[old_index]);
+                            new_index += 1;
+                        }
+                        old_index += 1;
+                    }
+                    crate::diff_hunk::DiffLineType::NewOnly => {
+                        let line = new_lines.get(new_index).ok_or_else(|| {
+                            anyhow::anyhow!("hunk {} adds lines past the end of the new text", i)
+                        })?;
+                        result.push_str(line);
+                        new_index += 1;
+                    }
+                }
+            }
+        }
+
+        // Unchanged lines after the last hunk
+        for line in &old_lines[old_index.min(old_lines.len())..] {
+            result.push_str(line);
+        }
+
+        Ok(result)
+    }
+
+    /// Format the diff in unified format, as `git diff` prints it
+    ///
+    /// Changes are regrouped with up to `context` unchanged lines around them,
+    /// whatever context the hunks were computed with. Hunk headers use 1-based
+    /// line numbers and omit a count of 1, as git does. Returns an empty string
+    /// if nothing changed or the diff was suppressed. Binary diffs print git's
+    /// "Binary files ... differ" line instead.
+    pub fn to_unified(&self, old_path: &str, new_path: &str, context: usize) -> String {
+        if self.is_binary() {
+            return format!("Binary files {} and {} differ\n", old_path, new_path);
+        }
+
+        let hunks = self.unified_lines(Some(context));
+        if hunks.is_empty() {
+            return String::new();
+        }
+        format!("--- {}\n+++ {}\n{}", old_path, new_path, hunks)
+    }
+
+    /// Format the diff's lines for a unified diff, without a file header
+    ///
+    /// With `Some(context)`, changes are grouped with up to `context` unchanged
+    /// lines around them, each group under an `@@` hunk header, and nothing is
+    /// printed if nothing changed. With `None`, every line of both texts is
+    /// printed in one piece without a header, as `DiffConfig::unified_diff`
+    /// prints line diffs.
+    pub(crate) fn unified_lines(&self, context: Option<usize>) -> String {
+        let old_text = self.old_text.to_string();
+        let new_text = self.new_text.to_string();
+        let old_lines: Vec<&str> = old_text.split_inclusive('\n').collect();
+        let new_lines: Vec<&str> = new_text.split_inclusive('\n').collect();
+
+        let ops = self.edit_script(old_lines.len(), new_lines.len());
+
+        // Group changes whose context would touch or overlap into one hunk
+        let mut groups: Vec<Range<usize>> = Vec::new();
+        match context {
+            Some(context) => {
+                for (i, op) in ops.iter().enumerate() {
+                    if op.tag == similar::ChangeTag::Equal {
+                        continue;
+                    }
+                    let start = i.saturating_sub(context);
+                    let end = i.saturating_add(1).saturating_add(context).min(ops.len());
+                    match groups.last_mut() {
+                        Some(last) if start <= last.end => last.end = end,
+                        _ => groups.push(start..end),
+                    }
+                }
+            }
+            None => groups.push(0..ops.len()),
+        }
+
+        let mut result = String::new();
+        for group in groups {
+            let ops = &ops[group];
+            if context.is_some() {
+                let old_count = ops
+                    .iter()
+                    .filter(|op| op.tag != similar::ChangeTag::Insert)
+                    .count();
+                let new_count = ops
+                    .iter()
+                    .filter(|op| op.tag != similar::ChangeTag::Delete)
+                    .count();
+                result.push_str(&format!(
+                    "@@ -{} +{} @@\n",
+                    Self::unified_range(ops[0].old_index, old_count),
+                    Self::unified_range(ops[0].new_index, new_count)
+                ));
+            }
+
+            for op in ops {
+                let (sign, line) = match op.tag {
+                    similar::ChangeTag::Equal => (' ', old_lines[op.old_index]),
+                    similar::ChangeTag::Delete => ('-', old_lines[op.old_index]),
+                    similar::ChangeTag::Insert => ('+', new_lines[op.new_index]),
+                };
+                result.push(sign);
+                result.push_str(line);
+                if !line.ends_with('\n') {
+                    result.push('\n');
+                    result.push_str(NO_NEWLINE_MARKER);
+                    result.push('\n');
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Format a 0-based line range for a unified diff hunk header
+    ///
+    /// An empty range is given by the line before it, so `0,0` is the start of
+    /// the file.
+    fn unified_range(start: usize, count: usize) -> String {
+        match count {
+            0 => format!("{},0", start),
+            1 => format!("{}", start + 1),
+            _ => format!("{},{}", start + 1, count),
+        }
+    }
+
+    /// Expand the hunks into a line-by-line edit script over the whole texts
+    ///
+    /// Within each run of changed lines, deletions come before insertions.
+    fn edit_script(&self, old_len: usize, new_len: usize) -> Vec<EditOp> {
+        let mut ops = Vec::new();
+        let (mut old_index, mut new_index) = (0, 0);
+
+        // Emit a run of changed lines, starting at the given positions
+        let flush = |ops: &mut Vec<EditOp>, (old_start, new_start), (old_end, new_end)| {
+            for old_index in old_start..old_end {
+                ops.push(EditOp {
+                    tag: similar::ChangeTag::Delete,
+                    old_index,
+                    new_index: new_start,
+                });
+            }
+            for new_index in new_start..new_end {
+                ops.push(EditOp {
+                    tag: similar::ChangeTag::Insert,
+                    old_index: old_end,
+                    new_index,
+                });
+            }
+        };
+
+        for hunk in &self.hunks {
+            // Unchanged lines before the hunk
+            while old_index < hunk.old_range.start.min(old_len)
+                && new_index < hunk.new_range.start.min(new_len)
+            {
+                ops.push(EditOp {
+                    tag: similar::ChangeTag::Equal,
+                    old_index,
+                    new_index,
+                });
+                old_index += 1;
+                new_index += 1;
+            }
+
+            let mut run_start = (old_index, new_index);
+            for line_type in &hunk.line_types {
+                match line_type {
+                    crate::diff_hunk::DiffLineType::Both => {
+                        flush(&mut ops, run_start, (old_index, new_index));
+                        ops.push(EditOp {
+                            tag: similar::ChangeTag::Equal,
+                            old_index,
+                            new_index,
+                        });
+                        old_index += 1;
+                        new_index += 1;
+                        run_start = (old_index, new_index);
+                    }
+                    crate::diff_hunk::DiffLineType::OldOnly => old_index += 1,
+                    crate::diff_hunk::DiffLineType::NewOnly => new_index += 1,
+                }
+            }
+            flush(&mut ops, run_start, (old_index, new_index));
+        }
+
+        // Unchanged lines after the last hunk
+        while old_index < old_len && new_index < new_len {
+            ops.push(EditOp {
+                tag: similar::ChangeTag::Equal,
+                old_index,
+                new_index,
+            });
+            old_index += 1;
+            new_index += 1;
+        }
+
+        ops
+    }
+
     /// Get the old text
     pub fn old_text(&self) -> &Rope {
         &self.old_text
@@ -533,6 +1755,53 @@ impl BufferDiff {
         &self.new_text
     }
 
+    /// Walk every hunk's lines, resolving each to its line numbers and text
+    ///
+    /// Lines are numbered like `split_lines` numbers them, so the numbers line
+    /// up with hunk ranges. Binary hunks have no lines.
+    pub fn lines(&self) -> impl Iterator<Item = DiffLine<'_>> {
+        let old_lines = rope_lines(&self.old_text);
+        let new_lines = rope_lines(&self.new_text);
+
+        let mut lines = Vec::new();
+        for (hunk_index, hunk) in self.hunks.iter().enumerate() {
+            let (mut old_line, mut new_line) = (hunk.old_range.start, hunk.new_range.start);
+            for (line_index, &kind) in hunk.line_types.iter().enumerate() {
+                let (old, new, content) = match kind {
+                    crate::diff_hunk::DiffLineType::OldOnly => {
+                        old_line += 1;
+                        (Some(old_line - 1), None, old_lines.get(old_line - 1))
+                    }
+                    crate::diff_hunk::DiffLineType::NewOnly => {
+                        new_line += 1;
+                        (None, Some(new_line - 1), new_lines.get(new_line - 1))
+                    }
+                    crate::diff_hunk::DiffLineType::Both => {
+                        old_line += 1;
+                        new_line += 1;
+                        (
+                            Some(old_line - 1),
+                            Some(new_line - 1),
+                            old_lines.get(old_line - 1),
+                        )
+                    }
+                };
+                let Some(content) = content else {
+                    continue;
+                };
+                lines.push(DiffLine {
+                    old_line: old,
+                    new_line: new,
+                    content: content.clone(),
+                    kind,
+                    hunk_index,
+                    line_index,
+                });
+            }
+        }
+        lines.into_iter()
+    }
+
     /// Get the hunks
     pub fn hunks(&self) -> &[DiffHunk] {
         &self.hunks
@@ -548,6 +1817,116 @@ impl BufferDiff {
         self.hunks.get(index)
     }
 
+    /// Split the old and new texts into pairs of corresponding chunks
+    ///
+    /// Chunks are split at anchors, lines that appear exactly once in each
+    /// text, so that a chunk of the old text lines up with the same content in
+    /// the new text even when lines were inserted or removed before it. Only
+    /// if there are no anchors are both texts split at the same line numbers.
+    /// Either way the pairs cover both texts from start to end.
+    fn chunk_pairs(
+        &self,
+        old_line_count: usize,
+        new_line_count: usize,
+    ) -> Vec<((usize, usize), (usize, usize))> {
+        let anchors = self.chunk_anchors();
+        if anchors.is_empty() {
+            return self.fixed_chunk_pairs(old_line_count, new_line_count);
+        }
+
+        // Start a new chunk at the first anchor past the chunk size on either side
+        let mut pairs = Vec::new();
+        let (mut old_start, mut new_start) = (0, 0);
+        for (old_line, new_line) in anchors {
+            if old_line - old_start >= Self::DEFAULT_CHUNK_SIZE
+                || new_line - new_start >= Self::DEFAULT_CHUNK_SIZE
+            {
+                pairs.push(((old_start, old_line), (new_start, new_line)));
+                (old_start, new_start) = (old_line, new_line);
+            }
+        }
+        pairs.push(((old_start, old_line_count), (new_start, new_line_count)));
+
+        pairs
+    }
+
+    /// Find the lines that appear exactly once in both texts, as (old, new)
+    /// line number pairs
+    ///
+    /// Anchors are returned in order in both texts: of the unique lines, the
+    /// longest run that keeps the same order in both is kept, so moved lines
+    /// can't cross chunk boundaries.
+    fn chunk_anchors(&self) -> Vec<(usize, usize)> {
+        // For each line: its count and position in the old text, then the new
+        let mut lines: HashMap<Cow<'_, str>, (usize, usize, usize, usize)> = HashMap::new();
+        for (index, line) in self.old_text.lines().enumerate() {
+            let entry = lines.entry(Cow::from(line)).or_default();
+            entry.0 += 1;
+            entry.1 = index;
+        }
+        for (index, line) in self.new_text.lines().enumerate() {
+            if let Some(entry) = lines.get_mut(&Cow::from(line)) {
+                entry.2 += 1;
+                entry.3 = index;
+            }
+        }
+
+        let mut unique: Vec<(usize, usize)> = lines
+            .into_values()
+            .filter(|&(old_count, _, new_count, _)| old_count == 1 && new_count == 1)
+            .map(|(_, old_line, _, new_line)| (old_line, new_line))
+            .collect();
+        unique.sort_unstable();
+
+        // Longest increasing run of new line numbers, by patience sorting:
+        // `tails[k]` ends the best run of length k + 1 found so far
+        let mut tails: Vec<usize> = Vec::new();
+        let mut previous: Vec<Option<usize>> = vec![None; unique.len()];
+        for (i, &(_, new_line)) in unique.iter().enumerate() {
+            let k = tails.partition_point(|&tail| unique[tail].1 < new_line);
+            previous[i] = k.checked_sub(1).map(|k| tails[k]);
+            if k == tails.len() {
+                tails.push(i);
+            } else {
+                tails[k] = i;
+            }
+        }
+
+        let mut anchors = Vec::with_capacity(tails.len());
+        let mut next = tails.last().copied();
+        while let Some(i) = next {
+            anchors.push(unique[i]);
+            next = previous[i];
+        }
+        anchors.reverse();
+        anchors
+    }
+
+    /// Split both texts at the same line numbers, for texts without anchors
+    ///
+    /// The last pair runs to the end of both texts, so all of each is diffed
+    /// even when they split into different numbers of chunks.
+    fn fixed_chunk_pairs(
+        &self,
+        old_line_count: usize,
+        new_line_count: usize,
+    ) -> Vec<((usize, usize), (usize, usize))> {
+        let old_chunks = self.calculate_chunk_boundaries(old_line_count);
+        let new_chunks = self.calculate_chunk_boundaries(new_line_count);
+
+        let num_chunks = old_chunks.len().min(new_chunks.len());
+        (0..num_chunks)
+            .map(|i| {
+                let (mut old_chunk, mut new_chunk) = (old_chunks[i], new_chunks[i]);
+                if i == num_chunks - 1 {
+                    old_chunk.1 = old_line_count;
+                    new_chunk.1 = new_line_count;
+                }
+                (old_chunk, new_chunk)
+            })
+            .collect()
+    }
+
     /// Calculate chunk boundaries for parallel processing
     fn calculate_chunk_boundaries(&self, line_count: usize) -> Vec<(usize, usize)> {
         if line_count <= Self::DEFAULT_CHUNK_SIZE {
@@ -586,27 +1965,44 @@ impl BufferDiff {
         rope.slice(start_byte..end_byte).to_string()
     }
 
+    /// Configure `similar` to diff lines with this diff's algorithm, giving up
+    /// on a minimal diff at `deadline`
+    fn line_diff_config(&self, deadline: Option<Instant>) -> similar::TextDiffConfig {
+        let mut config = similar::TextDiff::configure();
+        config.algorithm(self.algorithm.into());
+        if let Some(deadline) = deadline {
+            config.deadline(deadline);
+        }
+        config
+    }
+
     /// Diff a single chunk
     fn diff_chunk(
         &self,
         old_chunk: &str,
         new_chunk: &str,
         old_offset: usize,
-        new_offset: usize
+        new_offset: usize,
+        whitespace: WhitespaceMode,
+        deadline: &Deadline,
     ) -> Result<Vec<DiffHunk>> {
         // Skip empty chunks
         if old_chunk.is_empty() && new_chunk.is_empty() {
             return Ok(Vec::new());
         }
 
-        // Use similar with a timeout to diff the chunk
-        let diff = similar::TextDiff::configure()
-            .algorithm(similar::Algorithm::Myers)
-            .timeout(Duration::from_secs(2))
-            .diff_lines(old_chunk, new_chunk);
-
-        // Process operations to create hunks with context
-        let _context_lines = 3; // Default context size
+        // Use similar with the shared deadline to diff the chunk's normalized lines
+        let old_lines = comparison_lines(old_chunk, whitespace);
+        let new_lines = comparison_lines(new_chunk, whitespace);
+        let old_refs: Vec<&str> = old_lines.iter().map(AsRef::as_ref).collect();
+        let new_refs: Vec<&str> = new_lines.iter().map(AsRef::as_ref).collect();
+        let diff = self
+            .line_diff_config(deadline.at)
+            .diff_slices(&old_refs, &new_refs);
+        deadline.check();
+
+        // Process operations to create hunks of changed lines only; context is
+        // added once the chunks' hunks have been merged
         let ops = diff.ops();
 
         // Create hunks from operations
@@ -615,49 +2011,56 @@ impl BufferDiff {
         // We need to handle each operation separately since the similar API is different
         for op in ops {
             match op {
-                similar::DiffOp::Equal { old_index: _, new_index: _, len: _ } => {
+                similar::DiffOp::Equal {
+                    old_index: _,
+                    new_index: _,
+                    len: _,
+                } => {
                     // Unchanged content, used for context
                     // We'll handle this when creating the hunks
-                },
-                similar::DiffOp::Delete { old_index, old_len, new_index } => {
+                }
+                similar::DiffOp::Delete {
+                    old_index,
+                    old_len,
+                    new_index,
+                } => {
                     // Content was deleted
                     let old_start = old_index + old_offset;
                     let new_start = new_index + new_offset;
 
                     // Create a delete hunk
-                    let mut hunk = DiffHunk::new(
-                        DiffHunkStatus::Deleted,
-                        old_start,
-                        *old_len,
-                        new_start,
-                        0
-                    );
+                    let mut hunk =
+                        DiffHunk::new(DiffHunkStatus::Deleted, old_start, *old_len, new_start, 0);
 
                     // Set line types
                     hunk.line_types = vec![crate::diff_hunk::DiffLineType::OldOnly; *old_len];
 
                     hunks.push(hunk);
-                },
-                similar::DiffOp::Insert { old_index, new_index, new_len } => {
+                }
+                similar::DiffOp::Insert {
+                    old_index,
+                    new_index,
+                    new_len,
+                } => {
                     // Content was inserted
                     let old_start = old_index + old_offset;
                     let new_start = new_index + new_offset;
 
                     // Create an add hunk
-                    let mut hunk = DiffHunk::new(
-                        DiffHunkStatus::Added,
-                        old_start,
-                        0,
-                        new_start,
-                        *new_len
-                    );
+                    let mut hunk =
+                        DiffHunk::new(DiffHunkStatus::Added, old_start, 0, new_start, *new_len);
 
                     // Set line types
                     hunk.line_types = vec![crate::diff_hunk::DiffLineType::NewOnly; *new_len];
 
                     hunks.push(hunk);
-                },
-                similar::DiffOp::Replace { old_index, old_len, new_index, new_len } => {
+                }
+                similar::DiffOp::Replace {
+                    old_index,
+                    old_len,
+                    new_index,
+                    new_len,
+                } => {
                     // Content was replaced
                     let old_start = old_index + old_offset;
                     let new_start = new_index + new_offset;
@@ -668,7 +2071,7 @@ impl BufferDiff {
                         old_start,
                         *old_len,
                         new_start,
-                        *new_len
+                        *new_len,
                     );
 
                     // Set line types - this is simplified, could be improved with word-level diff
@@ -708,9 +2111,8 @@ impl BufferDiff {
 
         for next in hunks {
             // Check if hunks are adjacent or overlapping
-            if current.old_range.end() >= next.old_range.start ||
-               current.new_range.end() >= next.new_range.start ||
-               next.old_range.start - current.old_range.end() <= 3 // Within 3 lines
+            if current.old_range.end() >= next.old_range.start
+                || current.new_range.end() >= next.new_range.start
             {
                 // Merge the hunks
                 current = self.merge_hunks(current, next);
@@ -727,6 +2129,74 @@ impl BufferDiff {
         merged
     }
 
+    /// Give hunks of changed lines up to `context_lines` unchanged lines on each
+    /// side, the way `process_diffs` does: context isn't shared, so when two
+    /// hunks are closer than that, the second only gets what the first's
+    /// trailing context left over
+    fn add_context(
+        mut hunks: Vec<DiffHunk>,
+        context_lines: usize,
+        old_line_count: usize,
+        new_line_count: usize,
+    ) -> Vec<DiffHunk> {
+        if context_lines == 0 {
+            return hunks;
+        }
+
+        // The unchanged lines before each hunk, and after the last one
+        let gaps: Vec<usize> = (0..=hunks.len())
+            .map(|i| {
+                let (old_start, new_start) = match hunks.get(i) {
+                    Some(hunk) => (hunk.old_range.start, hunk.new_range.start),
+                    None => (old_line_count, new_line_count),
+                };
+                let (old_end, new_end) = match i.checked_sub(1) {
+                    Some(prev) => (hunks[prev].old_range.end(), hunks[prev].new_range.end()),
+                    None => (0, 0),
+                };
+                old_start
+                    .saturating_sub(old_end)
+                    .min(new_start.saturating_sub(new_end))
+            })
+            .collect();
+
+        for (i, hunk) in hunks.iter_mut().enumerate() {
+            let leading = if i == 0 {
+                gaps[0].min(context_lines)
+            } else {
+                (gaps[i] - gaps[i].min(context_lines)).min(context_lines)
+            };
+            let trailing = gaps[i + 1].min(context_lines);
+            if leading == 0 && trailing == 0 {
+                continue;
+            }
+
+            hunk.old_range.start -= leading;
+            hunk.old_range.count += leading + trailing;
+            hunk.new_range.start -= leading;
+            hunk.new_range.count += leading + trailing;
+
+            let both = crate::diff_hunk::DiffLineType::Both;
+            let mut line_types = vec![both; leading];
+            line_types.append(&mut hunk.line_types);
+            line_types.extend(std::iter::repeat_n(both, trailing));
+            hunk.line_types = line_types;
+
+            if let Some(word_ranges) = hunk.word_ranges.as_mut() {
+                let mut padded = vec![Vec::new(); leading];
+                padded.append(word_ranges);
+                padded.resize_with(padded.len() + trailing, Vec::new);
+                *word_ranges = padded;
+            }
+            for (old, new) in &mut hunk.reordered {
+                *old += leading;
+                *new += leading;
+            }
+        }
+
+        hunks
+    }
+
     /// Merge two hunks into one
     fn merge_hunks(&self, first: DiffHunk, second: DiffHunk) -> DiffHunk {
         // Calculate the new ranges
@@ -736,7 +2206,9 @@ impl BufferDiff {
         let new_end = first.new_range.end().max(second.new_range.end());
 
         // Determine the merged status
-        let status = if first.status == DiffHunkStatus::Unchanged && second.status == DiffHunkStatus::Unchanged {
+        let status = if first.status == DiffHunkStatus::Unchanged
+            && second.status == DiffHunkStatus::Unchanged
+        {
             DiffHunkStatus::Unchanged
         } else {
             DiffHunkStatus::Modified
@@ -751,14 +2223,43 @@ impl BufferDiff {
             new_end - new_start,
         );
 
-        // Combine line types (this is a simplified approach)
+        // Combine line types, filling the gap between the hunks so the merged
+        // hunk covers every line of its ranges
         let mut line_types = Vec::new();
 
         // Add line types from the first hunk
         line_types.extend(first.line_types.iter().cloned());
 
-        // Add line types from the second hunk
+        // Lines between the hunks are unchanged. If the gaps differ between the
+        // texts (chunks diffed separately can disagree), the extra lines on
+        // either side are counted as removed or added.
+        let old_gap = second.old_range.start.saturating_sub(first.old_range.end());
+        let new_gap = second.new_range.start.saturating_sub(first.new_range.end());
+        let unchanged = old_gap.min(new_gap);
+        line_types.extend(std::iter::repeat_n(
+            crate::diff_hunk::DiffLineType::Both,
+            unchanged,
+        ));
+        line_types.extend(std::iter::repeat_n(
+            crate::diff_hunk::DiffLineType::OldOnly,
+            old_gap - unchanged,
+        ));
+        line_types.extend(std::iter::repeat_n(
+            crate::diff_hunk::DiffLineType::NewOnly,
+            new_gap - unchanged,
+        ));
+
+        // Add line types from the second hunk, keeping both hunks' reordered
+        // lines at their new indices
+        let offset = line_types.len();
         line_types.extend(second.line_types.iter().cloned());
+        merged.reordered = first.reordered;
+        merged.reordered.extend(
+            second
+                .reordered
+                .iter()
+                .map(|&(old, new)| (old + offset, new + offset)),
+        );
 
         // Set line types on the merged hunk
         merged.line_types = line_types;
@@ -767,20 +2268,26 @@ impl BufferDiff {
     }
 
     /// Compute hunks using the simple approach for special cases
-    fn compute_hunks_simple(&mut self) -> Result<()> {
+    fn compute_hunks_simple(
+        &mut self,
+        context_lines: usize,
+        whitespace: WhitespaceMode,
+        deadline: &Deadline,
+    ) -> Result<()> {
         // Convert entire ropes to strings
         let old_text_str = self.old_text.to_string();
         let new_text_str = self.new_text.to_string();
 
         // Special case: if both are empty
         if old_text_str.is_empty() && new_text_str.is_empty() {
-            self.hunks.push(DiffHunk::new(DiffHunkStatus::Unchanged, 0, 0, 0, 0));
+            self.hunks
+                .push(DiffHunk::new(DiffHunkStatus::Unchanged, 0, 0, 0, 0));
             return Ok(());
         }
 
         // Special case: if old is empty but new is not, this is an added file
         if old_text_str.is_empty() && !new_text_str.is_empty() {
-            let new_line_count = self.new_text.len_lines().saturating_sub(1).max(1);
+            let new_line_count = line_count(&new_text_str);
             let mut hunk = DiffHunk::new(DiffHunkStatus::Added, 0, 0, 0, new_line_count);
             hunk.line_types = vec![crate::diff_hunk::DiffLineType::NewOnly; new_line_count];
             self.hunks.push(hunk);
@@ -789,7 +2296,7 @@ impl BufferDiff {
 
         // Special case: if new is empty but old is not, this is a deleted file
         if !old_text_str.is_empty() && new_text_str.is_empty() {
-            let old_line_count = self.old_text.len_lines().saturating_sub(1).max(1);
+            let old_line_count = line_count(&old_text_str);
             let mut hunk = DiffHunk::new(DiffHunkStatus::Deleted, 0, old_line_count, 0, 0);
             hunk.line_types = vec![crate::diff_hunk::DiffLineType::OldOnly; old_line_count];
             self.hunks.push(hunk);
@@ -797,18 +2304,87 @@ impl BufferDiff {
         }
 
         // For other cases, use the standard diff with a timeout
-        let diff = similar::TextDiff::configure()
-            .algorithm(similar::Algorithm::Myers)
-            .timeout(Duration::from_secs(5))
-            .diff_lines(&old_text_str, &new_text_str);
+        let old_lines = comparison_lines(&old_text_str, whitespace);
+        let new_lines = comparison_lines(&new_text_str, whitespace);
+        let old_refs: Vec<&str> = old_lines.iter().map(AsRef::as_ref).collect();
+        let new_refs: Vec<&str> = new_lines.iter().map(AsRef::as_ref).collect();
+        let diff = self
+            .line_diff_config(deadline.at)
+            .diff_slices(&old_refs, &new_refs);
+        deadline.check();
 
         // Process the diff using the existing code path
-        self.process_diffs(diff)?;
+        self.process_diffs(diff, context_lines)?;
 
         Ok(())
     }
 }
 
+/// Classify how a hunk's deleted lines differ from its added lines, both with
+/// their line endings, for `BufferDiff::whitespace_changes`
+fn whitespace_change(deleted: &[&str], added: &[&str]) -> Option<WhitespaceChange> {
+    if deleted.is_empty() || deleted.len() != added.len() || deleted == added {
+        return None;
+    }
+
+    let same_under = |mode: WhitespaceMode| {
+        deleted.iter().zip(added).all(|(old, new)| {
+            let old = old.trim_end_matches(['\r', '\n']);
+            let new = new.trim_end_matches(['\r', '\n']);
+            mode.normalize_line(old) == mode.normalize_line(new)
+        })
+    };
+    if same_under(WhitespaceMode::None) {
+        // Only line endings differ, which counts when the last line gained or
+        // lost its newline, but not for e.g. `\r\n` becoming `\n`
+        let eof_newline = deleted
+            .iter()
+            .zip(added)
+            .any(|(old, new)| old.ends_with('\n') != new.ends_with('\n'));
+        eof_newline.then_some(WhitespaceChange::EofNewline)
+    } else if same_under(WhitespaceMode::Trailing) {
+        Some(WhitespaceChange::Trailing)
+    } else if same_under(WhitespaceMode::All) {
+        Some(WhitespaceChange::Within)
+    } else {
+        None
+    }
+}
+
+/// The thread pool large diffs are chunked onto, built on first use
+///
+/// Sharing one pool avoids spawning threads for every large diff. `None` if
+/// the pool couldn't be built, in which case rayon's global pool is used.
+fn chunk_pool() -> Option<&'static rayon::ThreadPool> {
+    static POOL: OnceLock<Option<rayon::ThreadPool>> = OnceLock::new();
+    POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(BufferDiff::MAX_CONCURRENT_CHUNKS)
+            .build()
+            .ok()
+    })
+    .as_ref()
+}
+
+/// Split text into lines as `similar` does, normalized for comparison
+///
+/// Lines keep their line endings, so a line that's empty after normalizing
+/// still compares as a line.
+fn comparison_lines(text: &str, whitespace: WhitespaceMode) -> Vec<Cow<'_, str>> {
+    text.tokenize_lines()
+        .into_iter()
+        .map(|line| whitespace.normalize_line(line))
+        .collect()
+}
+
+/// One line of an edit script, with the positions in both texts it applies at
+#[derive(Debug, Clone, Copy)]
+struct EditOp {
+    tag: similar::ChangeTag,
+    old_index: usize,
+    new_index: usize,
+}
+
 impl BufferDiffSnapshot {
     /// Create a new empty diff snapshot
     pub fn empty() -> Self {
@@ -816,6 +2392,9 @@ impl BufferDiffSnapshot {
             hunks: Vec::new(),
             old_line_count: 0,
             new_line_count: 0,
+            suppressed: false,
+            timed_out: false,
+            deleted_text: Vec::new(),
         }
     }
 
@@ -841,16 +2420,147 @@ impl BufferDiffSnapshot {
 
     /// Get the number of added lines
     pub fn added_lines(&self) -> usize {
-        self.hunks.iter().map(|h| h.added_lines()).sum()
+        self.hunks.iter().map(|h| self.changed_lines(h).0).sum()
     }
 
     /// Get the number of deleted lines
     pub fn deleted_lines(&self) -> usize {
-        self.hunks.iter().map(|h| h.deleted_lines()).sum()
+        self.hunks.iter().map(|h| self.changed_lines(h).1).sum()
+    }
+
+    /// Get a hunk's added and deleted line counts
+    ///
+    /// A suppressed diff's hunk has no line types, so it counts as replacing
+    /// every old line with every new one.
+    fn changed_lines(&self, hunk: &DiffHunk) -> (usize, usize) {
+        if self.suppressed {
+            (hunk.new_range.count, hunk.old_range.count)
+        } else {
+            (hunk.added_lines(), hunk.deleted_lines())
+        }
     }
 
     /// Get the number of unchanged lines
     pub fn unchanged_lines(&self) -> usize {
         self.hunks.iter().map(|h| h.unchanged_lines()).sum()
     }
+
+    /// Get the number of hunks that only add lines
+    pub fn added_hunks(&self) -> usize {
+        self.count_hunks(DiffHunkStatus::Added)
+    }
+
+    /// Get the number of hunks that only delete lines
+    pub fn deleted_hunks(&self) -> usize {
+        self.count_hunks(DiffHunkStatus::Deleted)
+    }
+
+    /// Get the number of hunks that replace old lines with new ones
+    pub fn modified_hunks(&self) -> usize {
+        self.count_hunks(DiffHunkStatus::Modified)
+    }
+
+    fn count_hunks(&self, status: DiffHunkStatus) -> usize {
+        self.hunks.iter().filter(|h| h.status == status).count()
+    }
+
+    /// Get the line and hunk counts together, in a single pass over the hunks
+    pub fn stats(&self) -> DiffStats {
+        let mut stats = DiffStats::default();
+        for hunk in &self.hunks {
+            let (added, deleted) = self.changed_lines(hunk);
+            stats.added_lines += added;
+            stats.deleted_lines += deleted;
+            match hunk.status {
+                DiffHunkStatus::Added => stats.added_hunks += 1,
+                DiffHunkStatus::Deleted => stats.deleted_hunks += 1,
+                DiffHunkStatus::Modified => stats.modified_hunks += 1,
+                DiffHunkStatus::Unchanged | DiffHunkStatus::Binary => {}
+            }
+        }
+        stats
+    }
+
+    /// Rebuild the old text's lines from the new text's lines
+    ///
+    /// Walks each hunk's line types, taking unchanged lines from `new_lines`
+    /// and deleted lines from `deleted_text`. Lines outside hunks are copied
+    /// as is, and context shared by adjacent hunks is only emitted once.
+    /// Returns `None` if `new_lines` doesn't cover the hunks' new ranges, or
+    /// the snapshot is missing deleted text for a hunk, as snapshots not taken
+    /// with `BufferDiff::snapshot_with_deleted_text` are.
+    pub fn reconstruct_old(&self, new_lines: &[&str]) -> Option<Vec<String>> {
+        let mut old_lines: Vec<String> = Vec::with_capacity(new_lines.len());
+        let mut new_pos = 0;
+
+        for (index, hunk) in self.hunks.iter().enumerate() {
+            if hunk.new_range.end() > new_lines.len() {
+                return None;
+            }
+
+            // Unchanged lines between the previous hunk and this one
+            while old_lines.len() < hunk.old_range.start {
+                old_lines.push(new_lines.get(new_pos)?.to_string());
+                new_pos += 1;
+            }
+
+            let mut deleted = self.deleted_text.get(index).into_iter().flatten();
+            let (mut old_line, mut new_line) = (hunk.old_range.start, hunk.new_range.start);
+            for line_type in &hunk.line_types {
+                let line = match line_type {
+                    crate::diff_hunk::DiffLineType::Both => {
+                        new_line += 1;
+                        new_lines.get(new_line - 1)?.to_string()
+                    }
+                    crate::diff_hunk::DiffLineType::OldOnly => deleted.next()?.clone(),
+                    crate::diff_hunk::DiffLineType::NewOnly => {
+                        new_line += 1;
+                        continue;
+                    }
+                };
+                // Lines before the end of the output were emitted by the previous hunk
+                if old_line >= old_lines.len() {
+                    old_lines.push(line);
+                }
+                old_line += 1;
+            }
+
+            if old_line != hunk.old_range.end() || new_line != hunk.new_range.end() {
+                return None;
+            }
+            new_pos = new_pos.max(new_line);
+        }
+
+        old_lines.extend(
+            new_lines
+                .get(new_pos..)?
+                .iter()
+                .map(|line| line.to_string()),
+        );
+        Some(old_lines)
+    }
+
+    /// Get the index of the hunk whose new range contains the given line (0-based)
+    pub fn hunk_at_new_line(&self, line: usize) -> Option<usize> {
+        Self::hunk_containing(&self.hunks, line, |hunk| &hunk.new_range)
+    }
+
+    /// Get the index of the hunk whose old range contains the given line (0-based)
+    pub fn hunk_at_old_line(&self, line: usize) -> Option<usize> {
+        Self::hunk_containing(&self.hunks, line, |hunk| &hunk.old_range)
+    }
+
+    /// Binary search for the hunk containing a line, given hunks sorted by position
+    fn hunk_containing(
+        hunks: &[DiffHunk],
+        line: usize,
+        range: impl Fn(&DiffHunk) -> &DiffHunkRange,
+    ) -> Option<usize> {
+        // Ranges don't overlap, so the first hunk ending after the line is the only candidate
+        let index = hunks.partition_point(|hunk| range(hunk).end() <= line);
+        hunks
+            .get(index)
+            .filter(|hunk| range(hunk).contains(line))
+            .map(|_| index)
+    }
 }
@@ -1,4 +1,6 @@
 use derive_more::Display;
+use similar::{ChangeTag, TextDiff as SimilarTextDiff};
+use std::collections::{HashMap, HashSet};
 use std::ops::Range;
 
 #[cfg(feature = "serde")]
@@ -23,6 +25,11 @@ pub enum DiffHunkStatus {
     /// The hunk represents unchanged content (exists in both versions and identical)
     #[display(fmt = "Unchanged")]
     Unchanged,
+
+    /// The hunk stands for a change to binary content, which isn't diffed by
+    /// line (see `BufferDiff::is_binary`)
+    #[display(fmt = "Binary")]
+    Binary,
 }
 
 /// Represents the secondary status of a diff hunk in the context of git
@@ -40,6 +47,32 @@ pub enum DiffHunkSecondaryStatus {
     /// The hunk has no secondary status
     #[display(fmt = "None")]
     None,
+
+    /// The hunk's lines were moved, and `partner_index` is the index of the
+    /// hunk on the other end of the move (see `BufferDiff::detect_moves`)
+    #[display(fmt = "Moved")]
+    Moved { partner_index: usize },
+}
+
+/// What a hunk changes when it only changes whitespace, from
+/// `BufferDiff::whitespace_changes`
+///
+/// Such hunks are easy to misread as changing nothing, so renderers can label
+/// them with the display text, e.g. "whitespace only".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WhitespaceChange {
+    /// A newline was added or removed at the end of the file
+    #[display(fmt = "newline at end of file only")]
+    EofNewline,
+
+    /// Whitespace was added or removed at the end of lines
+    #[display(fmt = "trailing whitespace only")]
+    Trailing,
+
+    /// Whitespace changed within lines, e.g. indentation
+    #[display(fmt = "whitespace only")]
+    Within,
 }
 
 /// Represents a range of lines in a diff
@@ -102,6 +135,75 @@ pub enum DiffLineType {
     Both,
 }
 
+/// A line of a hunk with modified lines paired up, from `DiffHunk::paired_lines`
+///
+/// Line numbers are 0-based indices into the old and new texts, like the
+/// hunk ranges. `Modified` stands for an `OldOnly` line and the `NewOnly`
+/// line that replaced it, so renderers can show them side by side without
+/// pairing them up themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PairedLine {
+    /// Line only exists in the old version
+    OldOnly { old: usize },
+
+    /// Line only exists in the new version
+    NewOnly { new: usize },
+
+    /// Line exists in both versions
+    Both { old: usize, new: usize },
+
+    /// Old line replaced by a new line at the same position
+    Modified { old: usize, new: usize },
+
+    /// Line that moved within the hunk, unchanged
+    /// (see `BufferDiff::align_reordered`)
+    Reordered { old: usize, new: usize },
+}
+
+impl PairedLine {
+    /// The unpaired line types this line stands for
+    ///
+    /// `Modified` is an `OldOnly` line followed by a `NewOnly` line, so
+    /// flattening a hunk's paired lines gives line types for the same change.
+    /// A run of several removed lines followed by the lines that replaced them
+    /// comes back interleaved, one removed line before each added one.
+    /// `Reordered` is likewise the `OldOnly` line and the `NewOnly` line it
+    /// moved to, so the old line comes back at its new position.
+    pub fn line_types(&self) -> &'static [DiffLineType] {
+        match self {
+            PairedLine::OldOnly { .. } => &[DiffLineType::OldOnly],
+            PairedLine::NewOnly { .. } => &[DiffLineType::NewOnly],
+            PairedLine::Both { .. } => &[DiffLineType::Both],
+            PairedLine::Modified { .. } | PairedLine::Reordered { .. } => {
+                &[DiffLineType::OldOnly, DiffLineType::NewOnly]
+            }
+        }
+    }
+
+    /// The line's number in the old text, if it's in the old version
+    pub fn old_line(&self) -> Option<usize> {
+        match *self {
+            PairedLine::NewOnly { .. } => None,
+            PairedLine::OldOnly { old }
+            | PairedLine::Both { old, .. }
+            | PairedLine::Modified { old, .. }
+            | PairedLine::Reordered { old, .. } => Some(old),
+        }
+    }
+
+    /// The line's number in the new text, if it's in the new version
+    pub fn new_line(&self) -> Option<usize> {
+        match *self {
+            PairedLine::OldOnly { .. } => None,
+            PairedLine::NewOnly { new }
+            | PairedLine::Both { new, .. }
+            | PairedLine::Modified { new, .. }
+            | PairedLine::Reordered { new, .. } => Some(new),
+        }
+    }
+}
+
 /// Represents a hunk of changes between two versions of text
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -120,6 +222,22 @@ pub struct DiffHunk {
 
     /// Line-by-line mapping of line types within this hunk
     pub line_types: Vec<DiffLineType>,
+
+    /// Byte ranges of the changed words within each line, parallel to `line_types`
+    ///
+    /// Only computed for `DiffGranularity::Word` and `DiffGranularity::Char`,
+    /// and `None` otherwise. Lines paired up as `Modified` by `paired_lines`
+    /// get the ranges of the words, or characters, that differ between them.
+    /// Other lines get no ranges.
+    pub word_ranges: Option<Vec<Vec<Range<usize>>>>,
+
+    /// Lines that moved within the hunk without changing, as pairs of indices
+    /// into `line_types` of an `OldOnly` line and the identical `NewOnly` line
+    ///
+    /// Only filled in by `BufferDiff::align_reordered`. Reordered lines keep
+    /// their line types, so the hunk still applies, but aren't counted as
+    /// added or deleted.
+    pub reordered: Vec<(usize, usize)>,
 }
 
 impl DiffHunk {
@@ -151,6 +269,10 @@ impl DiffHunk {
                 // All lines are both
                 vec![DiffLineType::Both; old_count]
             }
+            DiffHunkStatus::Binary => {
+                // Binary content has no lines
+                Vec::new()
+            }
         };
 
         Self {
@@ -159,6 +281,8 @@ impl DiffHunk {
             old_range: DiffHunkRange::new(old_start, old_count),
             new_range: DiffHunkRange::new(new_start, new_count),
             line_types,
+            word_ranges: None,
+            reordered: Vec::new(),
         }
     }
 
@@ -167,20 +291,31 @@ impl DiffHunk {
         self.status != DiffHunkStatus::Unchanged
     }
 
-    /// Get the number of added lines in this hunk
+    /// Get the number of added lines in this hunk, not counting reordered lines
     pub fn added_lines(&self) -> usize {
-        self.line_types
+        let new_only = self
+            .line_types
             .iter()
             .filter(|&&t| t == DiffLineType::NewOnly)
-            .count()
+            .count();
+        new_only - self.reordered.len()
     }
 
-    /// Get the number of deleted lines in this hunk
+    /// Get the number of deleted lines in this hunk, not counting reordered lines
     pub fn deleted_lines(&self) -> usize {
-        self.line_types
+        let old_only = self
+            .line_types
             .iter()
             .filter(|&&t| t == DiffLineType::OldOnly)
-            .count()
+            .count();
+        old_only - self.reordered.len()
+    }
+
+    /// Check if the line at the given index moved within the hunk unchanged
+    pub fn is_reordered(&self, index: usize) -> bool {
+        self.reordered
+            .iter()
+            .any(|&(old, new)| old == index || new == index)
     }
 
     /// Get the number of unchanged lines in this hunk
@@ -191,6 +326,90 @@ impl DiffHunk {
             .count()
     }
 
+    /// Get the hunk's lines with modified lines paired up
+    ///
+    /// Each run of `OldOnly` lines is paired with the `NewOnly` lines after it,
+    /// as a modification of the same lines; `word_ranges` are computed for
+    /// these pairs. Paired lines come first as `Modified`, followed by the
+    /// unpaired rest of the longer side. Reordered lines are left out of the
+    /// pairing and come back as `Reordered` where the new line is.
+    pub fn paired_lines(&self) -> Vec<PairedLine> {
+        let len = self.line_types.len();
+        let mut lines = Vec::with_capacity(len);
+        let (mut old, mut new) = (self.old_range.start, self.new_range.start);
+
+        // Old line number of each reordered line, by the index of its new line
+        let mut old_lines = Vec::with_capacity(len);
+        let mut old_line = self.old_range.start;
+        for line_type in &self.line_types {
+            old_lines.push(old_line);
+            if *line_type != DiffLineType::NewOnly {
+                old_line += 1;
+            }
+        }
+        let reordered_old: HashMap<usize, usize> = self
+            .reordered
+            .iter()
+            .map(|&(old_index, new_index)| (new_index, old_lines[old_index]))
+            .collect();
+        let moved_old: HashSet<usize> = self.reordered.iter().map(|&(old, _)| old).collect();
+
+        let mut i = 0;
+        while i < len {
+            if self.line_types[i] == DiffLineType::Both {
+                lines.push(PairedLine::Both { old, new });
+                old += 1;
+                new += 1;
+                i += 1;
+                continue;
+            }
+
+            let removed_start = i;
+            while i < len && self.line_types[i] == DiffLineType::OldOnly {
+                i += 1;
+            }
+            let added_start = i;
+            while i < len && self.line_types[i] == DiffLineType::NewOnly {
+                i += 1;
+            }
+            let removed: Vec<usize> = (removed_start..added_start)
+                .filter(|index| !moved_old.contains(index))
+                .map(|index| old + index - removed_start)
+                .collect();
+            let added: Vec<usize> = (added_start..i)
+                .filter(|index| !reordered_old.contains_key(index))
+                .map(|index| new + index - added_start)
+                .collect();
+
+            let paired = removed.len().min(added.len());
+            lines.extend(
+                removed
+                    .iter()
+                    .zip(&added)
+                    .map(|(&old, &new)| PairedLine::Modified { old, new }),
+            );
+            lines.extend(
+                removed[paired..]
+                    .iter()
+                    .map(|&old| PairedLine::OldOnly { old }),
+            );
+            let mut unpaired = added[paired..].iter().peekable();
+            for index in added_start..i {
+                let line = new + index - added_start;
+                if let Some(&old) = reordered_old.get(&index) {
+                    lines.push(PairedLine::Reordered { old, new: line });
+                } else if unpaired.next_if(|&&new| new == line).is_some() {
+                    lines.push(PairedLine::NewOnly { new: line });
+                }
+            }
+
+            old += added_start - removed_start;
+            new += i - added_start;
+        }
+
+        lines
+    }
+
     /// Set the line type at the given index
     pub fn set_line_type(&mut self, index: usize, line_type: DiffLineType) {
         if index < self.line_types.len() {
@@ -207,4 +426,261 @@ impl DiffHunk {
     pub fn set_secondary_status(&mut self, status: DiffHunkSecondaryStatus) {
         self.secondary_status = status;
     }
+
+    /// Get the hunk that undoes this one
+    ///
+    /// The old and new ranges swap, `OldOnly` and `NewOnly` lines swap, and
+    /// `Added` and `Deleted` swap. Other statuses stay the same. Each run of
+    /// changed lines is put back in diff order, with its `OldOnly` lines
+    /// before its `NewOnly` lines.
+    pub fn reversed(&self) -> DiffHunk {
+        let status = match self.status {
+            DiffHunkStatus::Added => DiffHunkStatus::Deleted,
+            DiffHunkStatus::Deleted => DiffHunkStatus::Added,
+            status => status,
+        };
+        let swapped = |line_type: DiffLineType| match line_type {
+            DiffLineType::OldOnly => DiffLineType::NewOnly,
+            DiffLineType::NewOnly => DiffLineType::OldOnly,
+            DiffLineType::Both => DiffLineType::Both,
+        };
+
+        // The original index of each reversed line: within a run of changed
+        // lines, the lines that become `OldOnly` move ahead of the rest
+        let len = self.line_types.len();
+        let mut order = Vec::with_capacity(len);
+        let mut i = 0;
+        while i < len {
+            if self.line_types[i] == DiffLineType::Both {
+                order.push(i);
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < len && self.line_types[i] != DiffLineType::Both {
+                i += 1;
+            }
+            order.extend((start..i).filter(|&j| self.line_types[j] == DiffLineType::NewOnly));
+            order.extend((start..i).filter(|&j| self.line_types[j] == DiffLineType::OldOnly));
+        }
+        let mut position = vec![0; len];
+        for (reversed_index, &index) in order.iter().enumerate() {
+            position[index] = reversed_index;
+        }
+
+        DiffHunk {
+            status,
+            secondary_status: self.secondary_status,
+            old_range: self.new_range.clone(),
+            new_range: self.old_range.clone(),
+            line_types: order.iter().map(|&j| swapped(self.line_types[j])).collect(),
+            word_ranges: self
+                .word_ranges
+                .as_ref()
+                .map(|ranges| order.iter().map(|&j| ranges[j].clone()).collect()),
+            reordered: self
+                .reordered
+                .iter()
+                .map(|&(old, new)| (position[new], position[old]))
+                .collect(),
+        }
+    }
+
+    /// Get a copy of this hunk with at most `max` context lines at each end
+    ///
+    /// Leading and trailing `Both` lines beyond `max` are dropped and the
+    /// ranges are adjusted to match. A hunk with no changes keeps its first
+    /// `max` lines.
+    pub fn trim_context(&self, max: usize) -> DiffHunk {
+        let len = self.line_types.len();
+        let leading = self
+            .line_types
+            .iter()
+            .take_while(|&&t| t == DiffLineType::Both)
+            .count();
+
+        let (skip_start, skip_end) = if leading == len {
+            (0, len.saturating_sub(max))
+        } else {
+            let trailing = self
+                .line_types
+                .iter()
+                .rev()
+                .take_while(|&&t| t == DiffLineType::Both)
+                .count();
+            (leading.saturating_sub(max), trailing.saturating_sub(max))
+        };
+
+        let mut hunk = self.clone();
+        hunk.line_types = self.line_types[skip_start..len - skip_end].to_vec();
+        hunk.word_ranges = self
+            .word_ranges
+            .as_ref()
+            .map(|ranges| ranges[skip_start..len - skip_end].to_vec());
+        for pair in &mut hunk.reordered {
+            pair.0 -= skip_start;
+            pair.1 -= skip_start;
+        }
+        for range in [&mut hunk.old_range, &mut hunk.new_range] {
+            range.start += skip_start;
+            range.count = range.count.saturating_sub(skip_start + skip_end);
+        }
+        hunk
+    }
+
+    /// Fill in `word_ranges` from the text of the old and new versions
+    ///
+    /// `old_lines` and `new_lines` are every line of each version, as split
+    /// by `split_lines`.
+    pub fn compute_word_ranges(&mut self, old_lines: &[&str], new_lines: &[&str]) {
+        self.compute_changed_ranges(old_lines, new_lines, changed_word_ranges);
+    }
+
+    /// Fill in `word_ranges` with the changed characters, from the text of the
+    /// old and new versions
+    ///
+    /// Like `compute_word_ranges`, but paired lines are compared character by
+    /// character.
+    pub fn compute_char_ranges(&mut self, old_lines: &[&str], new_lines: &[&str]) {
+        self.compute_changed_ranges(old_lines, new_lines, changed_char_ranges);
+    }
+
+    /// Fill in `word_ranges` by comparing paired lines with `changed`
+    fn compute_changed_ranges(
+        &mut self,
+        old_lines: &[&str],
+        new_lines: &[&str],
+        changed: fn(&str, &str) -> ChangedRanges,
+    ) {
+        let len = self.line_types.len();
+        let mut ranges = vec![Vec::new(); len];
+
+        // Added and deleted hunks have no line pairs to compare
+        if matches!(self.status, DiffHunkStatus::Added | DiffHunkStatus::Deleted) {
+            self.word_ranges = Some(ranges);
+            return;
+        }
+        // Index into `line_types` of each of the hunk's old and new lines
+        let (mut old_indices, mut new_indices) = (Vec::new(), Vec::new());
+        for (index, line_type) in self.line_types.iter().enumerate() {
+            if *line_type != DiffLineType::NewOnly {
+                old_indices.push(index);
+            }
+            if *line_type != DiffLineType::OldOnly {
+                new_indices.push(index);
+            }
+        }
+
+        for line in self.paired_lines() {
+            let PairedLine::Modified { old, new } = line else {
+                continue;
+            };
+            let (Some(old_text), Some(new_text)) = (old_lines.get(old), new_lines.get(new)) else {
+                continue;
+            };
+            let (old_ranges, new_ranges) = changed(old_text, new_text);
+            ranges[old_indices[old - self.old_range.start]] = old_ranges;
+            ranges[new_indices[new - self.new_range.start]] = new_ranges;
+        }
+
+        self.word_ranges = Some(ranges);
+    }
+}
+
+/// Changed byte ranges within an old line and a new line
+type ChangedRanges = (Vec<Range<usize>>, Vec<Range<usize>>);
+
+/// Split a line into words, runs of whitespace and single punctuation characters
+fn word_tokens(line: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Punctuation,
+    }
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Punctuation
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut previous = None;
+    for (idx, c) in line.char_indices() {
+        let current = class(c);
+        // Punctuation is always a token of its own
+        if idx > start && (previous.as_ref() != Some(&current) || current == Class::Punctuation) {
+            tokens.push(&line[start..idx]);
+            start = idx;
+        }
+        previous = Some(current);
+    }
+    if start < line.len() {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+/// Find the byte ranges of the words that differ between two lines
+///
+/// Lines are compared by words, runs of whitespace and single punctuation
+/// characters, so `f(x, y)` to `f(x, z)` only marks `y` and `z`. Returns
+/// ranges into `old` and `new`, in order, with neighbouring changed tokens
+/// merged into one range. Ranges always fall on character boundaries.
+pub fn changed_word_ranges(old: &str, new: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let old_tokens = word_tokens(old);
+    let new_tokens = word_tokens(new);
+    let diff = SimilarTextDiff::configure().diff_slices(&old_tokens, &new_tokens);
+    changed_ranges(&diff)
+}
+
+/// Find the byte ranges of the characters that differ between two lines
+///
+/// Like `changed_word_ranges`, but lines are compared character by character,
+/// so `colour` to `color` only marks the `u`. Ranges always fall on character
+/// boundaries, though a combining mark can be marked apart from the character
+/// it combines with.
+pub fn changed_char_ranges(old: &str, new: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let diff = SimilarTextDiff::configure().diff_chars(old, new);
+    changed_ranges(&diff)
+}
+
+/// Collect the byte ranges of a diff's deleted and inserted tokens, merging
+/// neighbouring ones
+fn changed_ranges<'a>(
+    diff: &SimilarTextDiff<'a, 'a, '_, str>,
+) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let mut old_ranges: Vec<Range<usize>> = Vec::new();
+    let mut new_ranges: Vec<Range<usize>> = Vec::new();
+    let (mut old_pos, mut new_pos) = (0, 0);
+
+    let push = |ranges: &mut Vec<Range<usize>>, range: Range<usize>| match ranges.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => ranges.push(range),
+    };
+
+    for change in diff.iter_all_changes() {
+        let len = change.value().len();
+        match change.tag() {
+            ChangeTag::Equal => {
+                old_pos += len;
+                new_pos += len;
+            }
+            ChangeTag::Delete => {
+                push(&mut old_ranges, old_pos..old_pos + len);
+                old_pos += len;
+            }
+            ChangeTag::Insert => {
+                push(&mut new_ranges, new_pos..new_pos + len);
+                new_pos += len;
+            }
+        }
+    }
+
+    (old_ranges, new_ranges)
 }
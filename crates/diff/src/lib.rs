@@ -4,9 +4,17 @@
 mod buffer_diff;
 mod diff_hunk;
 mod text_diff;
+mod three_way;
 
-pub use buffer_diff::{BufferDiff, BufferDiffSnapshot};
+pub use buffer_diff::{
+    BufferDiff, BufferDiffSnapshot, DiffLine, DiffStats, TextEdit, NO_NEWLINE_MARKER,
+};
 pub use diff_hunk::{
-    DiffHunk, DiffHunkRange, DiffHunkSecondaryStatus, DiffHunkStatus, DiffLineType,
+    changed_char_ranges, changed_word_ranges, DiffHunk, DiffHunkRange, DiffHunkSecondaryStatus,
+    DiffHunkStatus, DiffLineType, PairedLine, WhitespaceChange,
+};
+pub use text_diff::{
+    line_count, split_lines, DiffAlgorithm, DiffConfig, DiffGranularity, LineEndingMode,
+    RecordSplitter, SplitMode, TextDiff, WhitespaceMode,
 };
-pub use text_diff::{DiffConfig, DiffGranularity, LineEndingMode, TextDiff};
+pub use three_way::{ThreeWayDiff, ThreeWayRegion, ThreeWayRegionKind};
@@ -1,9 +1,33 @@
 use anyhow::Result;
-use similar::{Algorithm, ChangeTag, TextDiff as SimilarTextDiff};
+use similar::{Algorithm, ChangeTag, DiffableStr, TextDiff as SimilarTextDiff};
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
 use std::time::Duration;
 
 use crate::buffer_diff::BufferDiff;
 
+/// Split text into lines the same way diff hunks count them
+///
+/// Lines end at `\n`, and a `\r` right before it is dropped, so CRLF text
+/// yields the same lines as LF text. A final line without a newline still
+/// counts, but a trailing newline doesn't add an empty line. Other Unicode
+/// line separators (lone `\r`, U+2028, ...) don't start a new line, because
+/// the line diff doesn't split on them either. Renderers should use this
+/// rather than `str::lines` or rope line counts so line numbers line up with
+/// hunk ranges.
+pub fn split_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split_inclusive('\n').map(|line| {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        line.strip_suffix('\r').unwrap_or(line)
+    })
+}
+
+/// Count the lines in text, as split by `split_lines`
+pub fn line_count(text: &str) -> usize {
+    split_lines(text).count()
+}
+
 /// Line ending types for text normalization
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineEndingMode {
@@ -19,6 +43,134 @@ pub enum LineEndingMode {
     Preserve,
 }
 
+/// Whitespace differences a diff ignores when comparing lines
+///
+/// Lines are only normalized for comparison: the diff's text, and so every
+/// line it displays, keeps its original whitespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WhitespaceMode {
+    /// Compare lines exactly (default)
+    #[default]
+    None,
+    /// Ignore whitespace at the end of lines
+    Trailing,
+    /// Ignore all whitespace within lines
+    All,
+}
+
+impl WhitespaceMode {
+    /// Normalize a line for comparison, keeping its line ending
+    ///
+    /// A line that's all whitespace normalizes to just its line ending, so it
+    /// still matches other blank lines but not a missing one.
+    pub fn normalize_line<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[content.len()..];
+        match self {
+            WhitespaceMode::None => Cow::Borrowed(line),
+            WhitespaceMode::Trailing => {
+                let trimmed = content.trim_end();
+                if trimmed.len() == content.len() {
+                    Cow::Borrowed(line)
+                } else {
+                    Cow::Owned(format!("{trimmed}{ending}"))
+                }
+            }
+            WhitespaceMode::All => {
+                if !content.contains(char::is_whitespace) {
+                    return Cow::Borrowed(line);
+                }
+                let mut normalized: String =
+                    content.chars().filter(|c| !c.is_whitespace()).collect();
+                normalized.push_str(ending);
+                Cow::Owned(normalized)
+            }
+        }
+    }
+}
+
+/// Text with whitespace normalized away for comparison, remembering where
+/// each of its bytes came from so the original text can be printed instead
+struct NormalizedText<'a> {
+    /// The text as written
+    original: &'a str,
+    /// The text as compared: each line normalized by a `WhitespaceMode`
+    text: String,
+    /// The offset in `original` of each byte of `text`
+    origins: Vec<usize>,
+}
+
+impl<'a> NormalizedText<'a> {
+    /// Normalize each line of `original` the way `WhitespaceMode::normalize_line` does
+    fn new(original: &'a str, mode: WhitespaceMode) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut origins = Vec::with_capacity(original.len());
+        let mut line_start = 0;
+
+        for line in original.tokenize_lines() {
+            let content = line.trim_end_matches(['\r', '\n']);
+            let trimmed_len = content.trim_end().len();
+            for (index, c) in line.char_indices() {
+                let ignored = index < content.len()
+                    && match mode {
+                        WhitespaceMode::None => false,
+                        WhitespaceMode::Trailing => index >= trimmed_len,
+                        WhitespaceMode::All => c.is_whitespace(),
+                    };
+                if !ignored {
+                    text.push(c);
+                    let origin = line_start + index;
+                    origins.extend(origin..origin + c.len_utf8());
+                }
+            }
+            line_start += line.len();
+        }
+
+        Self {
+            original,
+            text,
+            origins,
+        }
+    }
+
+    /// The byte ranges of the compared text's words or characters
+    fn tokens(&self, granularity: DiffGranularity) -> Vec<std::ops::Range<usize>> {
+        let tokens = match granularity {
+            DiffGranularity::Word => self.text.tokenize_words(),
+            _ => self.text.tokenize_chars(),
+        };
+        let mut start = 0;
+        tokens
+            .into_iter()
+            .map(|token| {
+                start += token.len();
+                start - token.len()..start
+            })
+            .collect()
+    }
+
+    /// The original text from `*next` up to the end of a compared token,
+    /// moving `*next` past it
+    ///
+    /// Whitespace left out of the comparison before the token is included.
+    fn take(&self, token: &std::ops::Range<usize>, next: &mut usize) -> &'a str {
+        let start = *next;
+        *next = self.origins[token.end - 1] + 1;
+        &self.original[start..*next]
+    }
+}
+
+/// `true` ignores all whitespace, `false` none
+impl From<bool> for WhitespaceMode {
+    fn from(ignore: bool) -> Self {
+        if ignore {
+            WhitespaceMode::All
+        } else {
+            WhitespaceMode::None
+        }
+    }
+}
+
 /// Granularity for diff operations
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DiffGranularity {
@@ -27,43 +179,161 @@ pub enum DiffGranularity {
     /// Diff by words (more detailed)
     Word,
     /// Diff by characters (highest detail)
-    Character,
+    Char,
+}
+
+impl DiffGranularity {
+    /// The old name of `DiffGranularity::Char`
+    #[deprecated(note = "renamed to `DiffGranularity::Char`")]
+    #[allow(non_upper_case_globals)]
+    pub const Character: Self = Self::Char;
+}
+
+/// A function returning the records of a text, for `SplitMode::Custom`
+pub type RecordSplitter = Arc<dyn Fn(&str) -> Vec<String> + Send + Sync>;
+
+/// How text is split into the records a diff compares
+///
+/// Hunk ranges, line types and line numbers all count records: with
+/// `Commas`, `old_range` of `2..3` is the third field, not the third line.
+/// The diff's old and new text hold each record on its own line, so
+/// `split_lines` on them yields the records in order.
+#[derive(Clone, Default)]
+pub enum SplitMode {
+    /// Split at newlines (default)
+    #[default]
+    Lines,
+    /// Split at commas and newlines, so each field of delimited data such as
+    /// CSV is its own record
+    Commas,
+    /// Split with a custom function returning the records of a text
+    ///
+    /// Records shouldn't contain newlines; any that do are split further at them.
+    Custom(RecordSplitter),
+}
+
+impl SplitMode {
+    /// Create a split mode from a function returning the records of a text
+    pub fn custom(split: impl Fn(&str) -> Vec<String> + Send + Sync + 'static) -> Self {
+        Self::Custom(Arc::new(split))
+    }
+
+    /// Rewrite text with each record on its own line
+    ///
+    /// Text is returned unchanged for `Lines`.
+    pub fn to_record_lines<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            SplitMode::Lines => Cow::Borrowed(text),
+            SplitMode::Commas => Cow::Owned(text.replace(',', "\n")),
+            SplitMode::Custom(split) => Cow::Owned(
+                split(text)
+                    .into_iter()
+                    .map(|record| record + "\n")
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl fmt::Debug for SplitMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SplitMode::Lines => write!(f, "Lines"),
+            SplitMode::Commas => write!(f, "Commas"),
+            SplitMode::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+/// The algorithm used to match up lines
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiffAlgorithm {
+    /// Shortest edit script; fast, but can match up common lines such as
+    /// braces and blank lines between unrelated code
+    #[default]
+    Myers,
+    /// Anchors on lines that are unique in both texts, which keeps moved or
+    /// reordered blocks of code together
+    Patience,
+    /// Longest common subsequence; slow on large inputs
+    Lcs,
+}
+
+impl From<DiffAlgorithm> for Algorithm {
+    fn from(algorithm: DiffAlgorithm) -> Self {
+        match algorithm {
+            DiffAlgorithm::Myers => Algorithm::Myers,
+            DiffAlgorithm::Patience => Algorithm::Patience,
+            DiffAlgorithm::Lcs => Algorithm::Lcs,
+        }
+    }
+}
+
+impl From<Algorithm> for DiffAlgorithm {
+    fn from(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Myers => DiffAlgorithm::Myers,
+            Algorithm::Patience => DiffAlgorithm::Patience,
+            Algorithm::Lcs => DiffAlgorithm::Lcs,
+        }
+    }
 }
 
 /// Configuration for diff operations
 #[derive(Debug, Clone)]
 pub struct DiffConfig {
     /// The algorithm to use for diffing
-    pub algorithm: Algorithm,
+    pub algorithm: DiffAlgorithm,
     /// The granularity of the diff
     pub granularity: DiffGranularity,
-    /// The timeout for diffing operations (in seconds)
-    pub timeout_seconds: u64,
+    /// How long line matching may take before settling for a non-minimal
+    /// diff, or `None` to always find a minimal one
+    pub timeout: Option<Duration>,
     /// The number of context lines to include
     pub context_lines: usize,
-    /// Whether to ignore whitespace changes
-    pub ignore_whitespace: bool,
+    /// Which whitespace changes to ignore when comparing lines
+    pub ignore_whitespace: WhitespaceMode,
     /// Line ending normalization mode
     pub line_ending_mode: LineEndingMode,
+    /// Skip diffing when either side has more lines than this
+    pub max_lines: Option<usize>,
+    /// How text is split into records before diffing
+    pub split_on: SplitMode,
+    /// Coalesce changes beyond this many hunks into one trailing hunk
+    pub max_hunks: Option<usize>,
+    /// Tag deleted and added hunks with the same lines as moved
+    pub detect_moves: bool,
+    /// Match up lines reordered within a modified hunk
+    pub align_reordered: bool,
+    /// Merge runs of same-status hunks whose context would overlap
+    pub coalesce_hunks: bool,
 }
 
 impl Default for DiffConfig {
     fn default() -> Self {
         Self {
-            algorithm: Algorithm::Myers,        // Myers is usually the best default
+            algorithm: DiffAlgorithm::Myers, // Myers is usually the best default
             granularity: DiffGranularity::Line, // Line-level diffing by default
-            timeout_seconds: 5,                 // 5 second timeout
-            context_lines: 3,                   // Default context lines
-            ignore_whitespace: false,           // Don't ignore whitespace by default
+            timeout: Some(Duration::from_secs(5)), // 5 second timeout
+            context_lines: 3,                // Default context lines
+            ignore_whitespace: WhitespaceMode::None, // Don't ignore whitespace by default
             line_ending_mode: LineEndingMode::Auto, // Auto-detect line endings by default
+            max_lines: None,                 // No size limit by default
+            split_on: SplitMode::Lines,      // Diff by lines by default
+            max_hunks: None,                 // No hunk limit by default
+            detect_moves: false,             // Don't look for moved blocks by default
+            align_reordered: false,          // Don't look for reordered lines by default
+            coalesce_hunks: false,           // Keep hunks as the diff found them by default
         }
     }
 }
 
 impl DiffConfig {
     /// Set the diff algorithm
-    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
-        self.algorithm = algorithm;
+    ///
+    /// Accepts a `DiffAlgorithm` or the equivalent `similar::Algorithm`
+    pub fn algorithm(mut self, algorithm: impl Into<DiffAlgorithm>) -> Self {
+        self.algorithm = algorithm.into();
         self
     }
 
@@ -73,9 +343,12 @@ impl DiffConfig {
         self
     }
 
-    /// Set the timeout in seconds
-    pub fn timeout(mut self, seconds: u64) -> Self {
-        self.timeout_seconds = seconds;
+    /// Set the timeout, or disable it with `None`
+    ///
+    /// A diff that times out is still correct, just not minimal; check
+    /// `BufferDiff::timed_out`
+    pub fn timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.timeout = timeout.into();
         self
     }
 
@@ -85,9 +358,11 @@ impl DiffConfig {
         self
     }
 
-    /// Set whether to ignore whitespace
-    pub fn ignore_whitespace(mut self, ignore: bool) -> Self {
-        self.ignore_whitespace = ignore;
+    /// Set which whitespace changes to ignore
+    ///
+    /// Accepts a `WhitespaceMode`, or a bool where `true` means `All`.
+    pub fn ignore_whitespace(mut self, mode: impl Into<WhitespaceMode>) -> Self {
+        self.ignore_whitespace = mode.into();
         self
     }
 
@@ -97,110 +372,157 @@ impl DiffConfig {
         self
     }
 
+    /// Set the maximum number of lines on either side before diffing is skipped
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = Some(max_lines);
+        self
+    }
+
+    /// Set how text is split into records before diffing
+    pub fn split_on(mut self, mode: SplitMode) -> Self {
+        self.split_on = mode;
+        self
+    }
+
+    /// Set the maximum number of hunks, coalescing the remaining changes into
+    /// one trailing `Modified` hunk (see `BufferDiff::limit_hunks`)
+    pub fn max_hunks(mut self, max_hunks: usize) -> Self {
+        self.max_hunks = Some(max_hunks);
+        self
+    }
+
+    /// Set whether to tag moved blocks of lines (see `BufferDiff::detect_moves`)
+    pub fn detect_moves(mut self, detect: bool) -> Self {
+        self.detect_moves = detect;
+        self
+    }
+
+    /// Set whether to match up identical lines reordered within a modified
+    /// hunk (see `BufferDiff::align_reordered`)
+    pub fn align_reordered(mut self, align: bool) -> Self {
+        self.align_reordered = align;
+        self
+    }
+
+    /// Set whether to merge consecutive hunks with the same status that are
+    /// at most twice `context_lines` apart, so their context would overlap
+    /// (see `BufferDiff::coalesce_hunks`)
+    pub fn coalesce_hunks(mut self, coalesce: bool) -> Self {
+        self.coalesce_hunks = coalesce;
+        self
+    }
+
     /// Create a diff between two texts using this configuration
+    ///
+    /// If either text exceeds `max_lines`, the diff is not computed and a
+    /// suppressed diff is returned instead (see `BufferDiff::suppressed`).
+    ///
+    /// With a `split_on` mode other than `Lines`, the diff compares records
+    /// rather than lines, and `max_lines` limits the number of records.
     pub fn diff(&self, old_text: &str, new_text: &str) -> Result<BufferDiff> {
-        // Step 1: Apply whitespace handling if needed
-        let (old_after_whitespace, new_after_whitespace) = if self.ignore_whitespace {
-            (
-                self.normalize_whitespace(old_text),
-                self.normalize_whitespace(new_text),
-            )
-        } else {
-            (old_text.to_string(), new_text.to_string())
-        };
+        let old_text = &*self.split_on.to_record_lines(old_text);
+        let new_text = &*self.split_on.to_record_lines(new_text);
+
+        if let Some(max_lines) = self.max_lines {
+            if line_count(old_text) > max_lines || line_count(new_text) > max_lines {
+                return Ok(BufferDiff::suppressed(old_text, new_text));
+            }
+        }
 
-        // Step 2: Apply line ending normalization
+        // Apply line ending normalization. Whitespace is only ignored when
+        // comparing lines, so the diff keeps it for display.
         let (old_processed, new_processed) = match self.line_ending_mode {
-            LineEndingMode::Preserve => (old_after_whitespace, new_after_whitespace),
+            LineEndingMode::Preserve => (old_text.to_string(), new_text.to_string()),
             _ => (
-                self.normalize_line_endings(&old_after_whitespace),
-                self.normalize_line_endings(&new_after_whitespace),
+                self.normalize_line_endings(old_text),
+                self.normalize_line_endings(new_text),
             ),
         };
 
-        // Delegate to the appropriate diff method based on granularity
-        match self.granularity {
-            DiffGranularity::Line => TextDiff::diff(&old_processed, &new_processed),
-            DiffGranularity::Word => TextDiff::diff_words(&old_processed, &new_processed),
-            DiffGranularity::Character => TextDiff::diff_chars(&old_processed, &new_processed),
-        }
+        // Diff the lines, then apply the options that work on the hunks
+        let mut diff = BufferDiff::compute(&old_processed, &new_processed, self)?;
+        diff.run_post_passes();
+        Ok(diff)
     }
 
     /// Generate a unified diff string using this configuration
+    ///
+    /// With a `split_on` mode other than `Lines`, each record is printed on its
+    /// own line.
     pub fn unified_diff(&self, old_text: &str, new_text: &str) -> String {
-        // Step 1: Apply whitespace handling if needed
-        let (old_after_whitespace, new_after_whitespace) = if self.ignore_whitespace {
-            (
-                self.normalize_whitespace(old_text),
-                self.normalize_whitespace(new_text),
-            )
-        } else {
-            (old_text.to_string(), new_text.to_string())
-        };
+        let old_text = &*self.split_on.to_record_lines(old_text);
+        let new_text = &*self.split_on.to_record_lines(new_text);
 
-        // Step 2: Apply line ending normalization
+        // Apply line ending normalization
         let (old_processed, new_processed) = match self.line_ending_mode {
-            LineEndingMode::Preserve => (old_after_whitespace, new_after_whitespace),
+            LineEndingMode::Preserve => (old_text.to_string(), new_text.to_string()),
             _ => (
-                self.normalize_line_endings(&old_after_whitespace),
-                self.normalize_line_endings(&new_after_whitespace),
+                self.normalize_line_endings(old_text),
+                self.normalize_line_endings(new_text),
             ),
         };
 
-        // Apply the granularity based on configuration
-        let diff = match self.granularity {
-            DiffGranularity::Line => SimilarTextDiff::configure()
-                .algorithm(self.algorithm)
-                .timeout(Duration::from_secs(self.timeout_seconds))
-                .diff_lines(&old_processed, &new_processed),
-            DiffGranularity::Word => SimilarTextDiff::configure()
-                .algorithm(self.algorithm)
-                .timeout(Duration::from_secs(self.timeout_seconds))
-                .diff_words(&old_processed, &new_processed),
-            DiffGranularity::Character => SimilarTextDiff::configure()
-                .algorithm(self.algorithm)
-                .timeout(Duration::from_secs(self.timeout_seconds))
-                .diff_chars(&old_processed, &new_processed),
-        };
+        // Lines are printed the way a line diff's hunks describe them, compared
+        // with whitespace normalized but printed as they were
+        if self.granularity == DiffGranularity::Line {
+            return BufferDiff::compute(&old_processed, &new_processed, self)
+                .map(|diff| diff.unified_lines(None))
+                .unwrap_or_default();
+        }
+
+        let mut config = SimilarTextDiff::configure();
+        config.algorithm(self.algorithm.into());
+        if let Some(timeout) = self.timeout {
+            config.timeout(timeout);
+        }
+
+        // Words and characters are compared with whitespace normalized away
+        // line by line, but printed as written: whitespace left out of the
+        // comparison is printed along with the token after it
+        let old = NormalizedText::new(&old_processed, self.ignore_whitespace);
+        let new = NormalizedText::new(&new_processed, self.ignore_whitespace);
+        let (old_tokens, new_tokens) = (old.tokens(self.granularity), new.tokens(self.granularity));
+        let old_refs: Vec<&str> = old_tokens.iter().map(|t| &old.text[t.clone()]).collect();
+        let new_refs: Vec<&str> = new_tokens.iter().map(|t| &new.text[t.clone()]).collect();
+        let diff = config.diff_slices(&old_refs, &new_refs);
 
         // Generate the unified diff
         let mut result = String::new();
+        let (mut old_next, mut new_next) = (0, 0);
         for change in diff.iter_all_changes() {
-            let sign = match change.tag() {
-                ChangeTag::Delete => "-",
-                ChangeTag::Insert => "+",
-                ChangeTag::Equal => " ",
+            let value = match (change.old_index(), change.new_index()) {
+                (Some(old_index), new_index) => {
+                    // Equal tokens print the old text, skipping over the new
+                    if let Some(new_index) = new_index {
+                        new.take(&new_tokens[new_index], &mut new_next);
+                    }
+                    old.take(&old_tokens[old_index], &mut old_next)
+                }
+                (None, Some(new_index)) => new.take(&new_tokens[new_index], &mut new_next),
+                (None, None) => continue,
             };
+            result.push_str(Self::unified_sign(change.tag()));
+            result.push_str(value);
+            result.push('\n');
+        }
 
-            // Add the sign and the value
-            result.push_str(sign);
-            result.push_str(change.value());
+        // Ignored whitespace after the last token is unchanged
+        if old_next < old_processed.len() {
+            result.push_str(Self::unified_sign(ChangeTag::Equal));
+            result.push_str(&old_processed[old_next..]);
             result.push('\n');
         }
 
         result
     }
 
-    /// Normalize whitespace in a string (for ignore_whitespace option)
-    fn normalize_whitespace(&self, text: &str) -> String {
-        // Replace all consecutive whitespace with a single space and trim
-        let mut result = String::new();
-        let mut in_whitespace = false;
-
-        for c in text.chars() {
-            if c.is_whitespace() {
-                if !in_whitespace {
-                    result.push(' ');
-                    in_whitespace = true;
-                }
-            } else {
-                result.push(c);
-                in_whitespace = false;
-            }
+    /// The prefix of a change's lines in a unified diff
+    fn unified_sign(tag: ChangeTag) -> &'static str {
+        match tag {
+            ChangeTag::Delete => "-",
+            ChangeTag::Insert => "+",
+            ChangeTag::Equal => " ",
         }
-
-        // Trim leading and trailing whitespace
-        result.trim().to_string()
     }
 
     /// Normalize line endings in a string based on the configured mode
@@ -278,127 +600,6 @@ impl TextDiff {
             .diff(old_text, new_text)
     }
 
-    /// Create a diff between two texts, at the word level
-    fn diff_words(old_text: &str, new_text: &str) -> Result<BufferDiff> {
-        // Convert to lines first to maintain structure
-        let old_lines: Vec<&str> = old_text.lines().collect();
-        let new_lines: Vec<&str> = new_text.lines().collect();
-
-        // Process each line pair with word-level diffing
-        let mut processed_old = Vec::new();
-        let mut processed_new = Vec::new();
-
-        // We'll compare corresponding lines and expand them with word markers
-        let max_lines = old_lines.len().max(new_lines.len());
-
-        for i in 0..max_lines {
-            if i < old_lines.len() && i < new_lines.len() {
-                // If both lines exist, do word-level diff
-                let old_line = old_lines[i];
-                let new_line = new_lines[i];
-
-                if old_line == new_line {
-                    // Lines are identical, keep as is
-                    processed_old.push(old_line.to_string());
-                    processed_new.push(new_line.to_string());
-                } else {
-                    // Lines differ, expand to word-level diff
-                    Self::expand_line_to_words(
-                        old_line,
-                        new_line,
-                        &mut processed_old,
-                        &mut processed_new,
-                    );
-                }
-            } else if i < old_lines.len() {
-                // Only old line exists
-                processed_old.push(old_lines[i].to_string());
-            } else if i < new_lines.len() {
-                // Only new line exists
-                processed_new.push(new_lines[i].to_string());
-            }
-        }
-
-        // Rejoin the processed lines
-        let processed_old_text = processed_old.join("\n");
-        let processed_new_text = processed_new.join("\n");
-
-        // Create diff using the processed texts
-        BufferDiff::new(&processed_old_text, &processed_new_text)
-    }
-
-    /// Expand a line to word-level differences
-    fn expand_line_to_words(
-        old_line: &str,
-        new_line: &str,
-        processed_old: &mut Vec<String>,
-        processed_new: &mut Vec<String>,
-    ) {
-        // Use similar to do word-level diffing for this line
-        let line_diff = SimilarTextDiff::configure()
-            .algorithm(Algorithm::Myers)
-            .timeout(Duration::from_secs(1))
-            .diff_words(old_line, new_line);
-
-        let mut old_expanded = String::new();
-        let mut new_expanded = String::new();
-
-        // Process each word change
-        for change in line_diff.iter_all_changes() {
-            match change.tag() {
-                ChangeTag::Equal => {
-                    // Same words in both lines
-                    old_expanded.push_str(change.value());
-                    new_expanded.push_str(change.value());
-                }
-                ChangeTag::Delete => {
-                    // Word exists only in old line
-                    old_expanded.push_str(change.value());
-                }
-                ChangeTag::Insert => {
-                    // Word exists only in new line
-                    new_expanded.push_str(change.value());
-                }
-            }
-        }
-
-        // Add the expanded lines
-        processed_old.push(old_expanded);
-        processed_new.push(new_expanded);
-    }
-
-    /// Create a diff between two texts, at the character level
-    fn diff_chars(old_text: &str, new_text: &str) -> Result<BufferDiff> {
-        // For character level diffing, we'll use similar directly to avoid excessive line expansion
-        let diff = SimilarTextDiff::configure()
-            .algorithm(Algorithm::Myers)
-            .timeout(Duration::from_secs(5))
-            .diff_chars(old_text, new_text);
-
-        // Convert back to lines for our BufferDiff
-        let mut processed_old = String::new();
-        let mut processed_new = String::new();
-
-        // Process each change
-        for change in diff.iter_all_changes() {
-            match change.tag() {
-                ChangeTag::Equal => {
-                    processed_old.push_str(change.value());
-                    processed_new.push_str(change.value());
-                }
-                ChangeTag::Delete => {
-                    processed_old.push_str(change.value());
-                }
-                ChangeTag::Insert => {
-                    processed_new.push_str(change.value());
-                }
-            }
-        }
-
-        // Create diff using the processed texts
-        BufferDiff::new(&processed_old, &processed_new)
-    }
-
     /// Generate a unified diff string (like git diff) with default settings
     pub fn unified_diff(old_text: &str, new_text: &str, context_lines: usize) -> String {
         Self::configure()
@@ -0,0 +1,197 @@
+use anyhow::Result;
+use similar::{DiffTag, TextDiff as SimilarTextDiff};
+use std::ops::Range;
+
+use crate::buffer_diff::BufferDiff;
+use crate::diff_hunk::DiffHunkRange;
+use crate::text_diff::{split_lines, DiffConfig};
+
+/// How a region of a three-way diff differs from the base
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreeWayRegionKind {
+    /// Neither side changed the region
+    Unchanged,
+    /// Only ours changed the region, or both sides made the same change
+    Ours,
+    /// Only theirs changed the region
+    Theirs,
+    /// Both sides changed the region in different ways
+    Conflict,
+}
+
+/// A region of a three-way diff, with the lines it covers in each version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreeWayRegion {
+    /// How the region was changed
+    pub kind: ThreeWayRegionKind,
+    /// The lines of the region in the base version
+    pub base_range: DiffHunkRange,
+    /// The lines of the region in our version
+    pub ours_range: DiffHunkRange,
+    /// The lines of the region in their version
+    pub theirs_range: DiffHunkRange,
+}
+
+/// A diff of two versions of a text against their common base
+///
+/// The regions cover all three texts in order, so a merge view can render
+/// them side by side in three columns.
+#[derive(Debug, Clone)]
+pub struct ThreeWayDiff {
+    /// The regions of the diff, in order
+    pub regions: Vec<ThreeWayRegion>,
+}
+
+impl ThreeWayDiff {
+    /// Iterate over the regions both sides changed differently
+    pub fn conflicts(&self) -> impl Iterator<Item = &ThreeWayRegion> {
+        self.regions
+            .iter()
+            .filter(|region| region.kind == ThreeWayRegionKind::Conflict)
+    }
+
+    /// Check if the two sides can't be merged without resolving conflicts
+    pub fn has_conflicts(&self) -> bool {
+        self.conflicts().next().is_some()
+    }
+}
+
+/// A changed region of one side, relative to the base
+struct Change {
+    base: Range<usize>,
+    side: Range<usize>,
+}
+
+/// Collect the changed regions between the base lines and one side's lines
+fn side_changes(base: &[&str], side: &[&str], config: &DiffConfig) -> Vec<Change> {
+    let mut diff_config = SimilarTextDiff::configure();
+    diff_config.algorithm(config.algorithm.into());
+    if let Some(timeout) = config.timeout {
+        diff_config.timeout(timeout);
+    }
+    diff_config
+        .diff_slices(base, side)
+        .ops()
+        .iter()
+        .filter(|op| op.tag() != DiffTag::Equal)
+        .map(|op| Change {
+            base: op.old_range(),
+            side: op.new_range(),
+        })
+        .collect()
+}
+
+/// The lines of one side covering a base range, given the side's changes in it
+///
+/// `side_start` is the side's line aligned with `base.start`.
+fn side_range(changes: &[Change], base: &Range<usize>, side_start: usize) -> Range<usize> {
+    match changes.last() {
+        // Lines after the last change map one-to-one to the base
+        Some(last) => side_start..last.side.end + (base.end - last.base.end),
+        None => side_start..side_start + base.len(),
+    }
+}
+
+impl BufferDiff {
+    /// Create a three-way diff of our and their versions against a common base
+    ///
+    /// Each side is diffed against the base by line. Changes that overlap or
+    /// touch in the base are grouped into one region, which is a conflict if
+    /// both sides changed it and the results differ.
+    pub fn three_way(
+        base: &str,
+        ours: &str,
+        theirs: &str,
+        config: &DiffConfig,
+    ) -> Result<ThreeWayDiff> {
+        let base_lines: Vec<&str> = split_lines(base).collect();
+        let ours_lines: Vec<&str> = split_lines(ours).collect();
+        let theirs_lines: Vec<&str> = split_lines(theirs).collect();
+
+        let ours_changes = side_changes(&base_lines, &ours_lines, config);
+        let theirs_changes = side_changes(&base_lines, &theirs_lines, config);
+
+        let mut regions = Vec::new();
+        let region =
+            |kind, base: Range<usize>, ours: Range<usize>, theirs: Range<usize>| ThreeWayRegion {
+                kind,
+                base_range: DiffHunkRange::from_range(base),
+                ours_range: DiffHunkRange::from_range(ours),
+                theirs_range: DiffHunkRange::from_range(theirs),
+            };
+
+        // Lines consumed so far in each text; they stay aligned between regions
+        let (mut base_pos, mut ours_pos, mut theirs_pos) = (0, 0, 0);
+        let (mut i, mut j) = (0, 0);
+
+        while i < ours_changes.len() || j < theirs_changes.len() {
+            let start = match (ours_changes.get(i), theirs_changes.get(j)) {
+                (Some(a), Some(b)) => a.base.start.min(b.base.start),
+                (Some(a), None) => a.base.start,
+                (None, Some(b)) => b.base.start,
+                (None, None) => unreachable!(),
+            };
+
+            // Grow the group while either side has a change overlapping or touching it
+            let (first_i, first_j) = (i, j);
+            let mut end = start;
+            loop {
+                let touches = |change: &&Change| change.base.start <= end;
+                if let Some(change) = ours_changes.get(i).filter(touches) {
+                    end = end.max(change.base.end);
+                    i += 1;
+                } else if let Some(change) = theirs_changes.get(j).filter(touches) {
+                    end = end.max(change.base.end);
+                    j += 1;
+                } else {
+                    break;
+                }
+            }
+
+            if start > base_pos {
+                let len = start - base_pos;
+                regions.push(region(
+                    ThreeWayRegionKind::Unchanged,
+                    base_pos..start,
+                    ours_pos..ours_pos + len,
+                    theirs_pos..theirs_pos + len,
+                ));
+                ours_pos += len;
+                theirs_pos += len;
+            }
+
+            let base_range = start..end;
+            let ours_group = &ours_changes[first_i..i];
+            let theirs_group = &theirs_changes[first_j..j];
+            let ours_range = side_range(ours_group, &base_range, ours_pos);
+            let theirs_range = side_range(theirs_group, &base_range, theirs_pos);
+
+            let kind = if theirs_group.is_empty() {
+                ThreeWayRegionKind::Ours
+            } else if ours_group.is_empty() {
+                ThreeWayRegionKind::Theirs
+            } else if ours_lines[ours_range.clone()] == theirs_lines[theirs_range.clone()] {
+                ThreeWayRegionKind::Ours
+            } else {
+                ThreeWayRegionKind::Conflict
+            };
+
+            base_pos = end;
+            ours_pos = ours_range.end;
+            theirs_pos = theirs_range.end;
+            regions.push(region(kind, base_range, ours_range, theirs_range));
+        }
+
+        if base_pos < base_lines.len() {
+            let len = base_lines.len() - base_pos;
+            regions.push(region(
+                ThreeWayRegionKind::Unchanged,
+                base_pos..base_lines.len(),
+                ours_pos..ours_pos + len,
+                theirs_pos..theirs_pos + len,
+            ));
+        }
+
+        Ok(ThreeWayDiff { regions })
+    }
+}
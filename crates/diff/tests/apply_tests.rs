@@ -0,0 +1,64 @@
+use buffer_diff::{BufferDiff, DiffConfig};
+use proptest::prelude::*;
+
+#[test]
+fn test_apply_reconstructs_new_text() {
+    let old = "fn main() {\n    let x = 1;\n    let y = 2;\n    println!(\"{}\", x);\n}\n";
+    let new =
+        "fn main() {\n    let x = 10;\n    println!(\"{}\", x);\n    println!(\"done\");\n}\n";
+
+    let diff = BufferDiff::new(old, new).unwrap();
+    assert_eq!(diff.apply(old).unwrap(), new);
+
+    // Line endings and a missing final newline are kept
+    let old = "a\r\nb\r\nc";
+    let new = "a\r\nB\r\nc";
+    assert_eq!(BufferDiff::new(old, new).unwrap().apply(old).unwrap(), new);
+}
+
+#[test]
+fn test_apply_rejects_mismatched_text() {
+    let old = "one\ntwo\nthree\nfour\nfive\n";
+    let new = "one\ntwo\nTHREE\nfour\nfive\n";
+    let diff = DiffConfig::default()
+        .context_lines(0)
+        .diff(old, new)
+        .unwrap();
+
+    // The hunk's old line isn't where the diff expects it
+    assert!(diff.apply("zero\none\ntwo\nthree\nfour\nfive\n").is_err());
+    // The text is too short for the hunk
+    assert!(diff.apply("one\ntwo\n").is_err());
+    // Lines outside the hunks don't need to match
+    assert_eq!(
+        diff.apply("1\n2\nthree\n4\n5\n").unwrap(),
+        "1\n2\nTHREE\n4\n5\n"
+    );
+
+    let suppressed = DiffConfig::default().max_lines(1).diff(old, new).unwrap();
+    assert!(suppressed.apply(old).is_err());
+}
+
+/// Short lines from a small alphabet, so texts share plenty of lines
+fn text() -> impl Strategy<Value = String> {
+    (
+        prop::collection::vec("[abc]{0,2}", 0..30),
+        any::<bool>(),
+        any::<bool>(),
+    )
+        .prop_map(|(lines, crlf, trailing_newline)| {
+            let mut text = lines.join(if crlf { "\r\n" } else { "\n" });
+            if trailing_newline && !text.is_empty() {
+                text.push('\n');
+            }
+            text
+        })
+}
+
+proptest! {
+    #[test]
+    fn prop_apply_round_trips(old in text(), new in text(), context in 0usize..5) {
+        let diff = BufferDiff::with_context_lines(&old, &new, context).unwrap();
+        prop_assert_eq!(diff.apply(&old).unwrap(), new);
+    }
+}
@@ -1,15 +1,15 @@
-use buffer_diff::{BufferDiff, DiffHunkStatus};
+use buffer_diff::{BufferDiff, DiffConfig, DiffHunkStatus, DiffLineType};
 
 #[test]
 fn test_large_file_chunking() {
     // Create a large text file (over the chunking threshold)
     let mut old_text = String::new();
     let mut new_text = String::new();
-    
+
     // Create content big enough to trigger chunking
     for i in 0..2000 {
         old_text.push_str(&format!("Line {} of the old text\n", i));
-        
+
         // Make some deliberate changes to test diff accuracy
         if i % 100 == 0 {
             // Every 100th line is different
@@ -18,32 +18,36 @@ fn test_large_file_chunking() {
             new_text.push_str(&format!("Line {} of the old text\n", i));
         }
     }
-    
+
     // Create a diff
     let buffer_diff = BufferDiff::new(&old_text, &new_text).unwrap();
-    
+
     // Get a snapshot
     let snapshot = buffer_diff.snapshot();
-    
+
     // Verify that we have the expected number of hunks (we should have changes)
     assert!(snapshot.has_changes());
-    
+
     // We should have exactly 20 modified hunks (one per 100 lines)
-    let modified_hunks = snapshot.hunks().iter()
+    let modified_hunks = snapshot
+        .hunks()
+        .iter()
         .filter(|h| h.status == DiffHunkStatus::Modified)
         .count();
-    
+
     assert_eq!(modified_hunks, 20);
-    
+
     // Verify total line counts
     assert_eq!(snapshot.old_line_count, 2001); // 2000 lines + final newline
     assert_eq!(snapshot.new_line_count, 2001); // 2000 lines + final newline
-    
+
     // The number of changes should match our pattern (20 lines modified)
-    let total_changes = snapshot.hunks().iter()
+    let total_changes = snapshot
+        .hunks()
+        .iter()
         .filter(|h| h.status != DiffHunkStatus::Unchanged)
         .fold(0, |acc, h| acc + h.line_types.len());
-        
+
     assert!(total_changes >= 20); // At least one line per changed hunk
 }
 
@@ -51,22 +55,262 @@ fn test_large_file_chunking() {
 fn test_merge_adjacent_hunks() {
     // Create text with changes that will generate adjacent hunks
     let old_text = "Line 1\nLine 2\nLine 3\nLine 4\n";
-    
+
     // Modify lines 2 and 3 to ensure they're close enough to merge
     let new_text = "Line 1\nModified 2\nModified 3\nLine 4\n";
-    
+
     // Create a diff
     let buffer_diff = BufferDiff::new(old_text, new_text).unwrap();
-    
+
     // Get a snapshot
     let snapshot = buffer_diff.snapshot();
-    
+
     // For this small file, with changes this close together, we should have a single hunk
-    assert!(snapshot.hunk_count() == 1, 
-        "Expected 1 merged hunk, got {} hunks", snapshot.hunk_count());
-    
+    assert!(
+        snapshot.hunk_count() == 1,
+        "Expected 1 merged hunk, got {} hunks",
+        snapshot.hunk_count()
+    );
+
     // The hunk should contain both modified lines
     let hunk = &snapshot.hunks()[0];
     assert!(hunk.old_range.contains(1), "Hunk should contain line 2"); // Line 2
     assert!(hunk.old_range.contains(2), "Hunk should contain line 3"); // Line 3
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_merged_chunk_hunks_cover_their_ranges() {
+    // Edits scattered through a file large enough to be diffed in chunks,
+    // some close enough together for their hunks to be merged
+    let old_text: String = (0..5000)
+        .map(|i| format!("Line {} of the old text\n", i))
+        .collect();
+    let new_text: String = (0..5000)
+        .map(|i| {
+            if i % 250 == 0 || i % 250 == 3 {
+                format!("Edited line {}\n", i)
+            } else {
+                format!("Line {} of the old text\n", i)
+            }
+        })
+        .collect();
+
+    let diff = BufferDiff::new(&old_text, &new_text).unwrap();
+    assert!(diff.hunk_count() > 1);
+
+    for hunk in diff.hunks() {
+        let side_lines = |other| hunk.line_types.iter().filter(|t| **t != other).count();
+        let old_lines = side_lines(DiffLineType::NewOnly);
+        let new_lines = side_lines(DiffLineType::OldOnly);
+        assert_eq!(
+            old_lines, hunk.old_range.count,
+            "old lines of {:?}",
+            hunk.old_range
+        );
+        assert_eq!(
+            new_lines, hunk.new_range.count,
+            "new lines of {:?}",
+            hunk.new_range
+        );
+    }
+
+    // The diff still applies, so every hunk lines up with the text
+    assert_eq!(diff.apply(&old_text).unwrap(), new_text);
+}
+
+#[test]
+fn test_chunked_diff_covers_the_whole_file() {
+    // Far more chunks than are diffed at once, with the only change at the end
+    let old_text: String = (0..20_000).map(|i| format!("Line {}\n", i)).collect();
+    let new_text = old_text.replace("Line 19999\n", "Last line\n");
+
+    let diff = BufferDiff::new(&old_text, &new_text).unwrap();
+    let changed: Vec<_> = diff
+        .hunks()
+        .iter()
+        .filter(|hunk| hunk.status != DiffHunkStatus::Unchanged)
+        .collect();
+    assert_eq!(changed.len(), 1);
+    assert!(changed[0].old_range.contains(19_999));
+    assert!(changed[0].new_range.contains(19_999));
+    assert_eq!(diff.apply(&old_text).unwrap(), new_text);
+
+    // Texts that split into different numbers of chunks are diffed to the end
+    let longer = format!("{}{}", new_text, "Extra line\n".repeat(1500));
+    let diff = BufferDiff::new(&old_text, &longer).unwrap();
+    assert_eq!(diff.snapshot().added_lines(), 1501);
+    assert_eq!(diff.apply(&old_text).unwrap(), longer);
+}
+
+#[test]
+fn test_chunks_align_after_an_insertion() {
+    // Lines inserted near the top shift everything after them, so chunks
+    // split at the same line numbers wouldn't line up
+    let lines: Vec<String> = (0..5000)
+        .map(|i| format!("Line {} of the old text\n", i))
+        .collect();
+    let old_text: String = lines.concat();
+    let inserted: String = (0..10).map(|i| format!("Inserted line {}\n", i)).collect();
+    let new_text = format!("{}{}{}", lines[..5].concat(), inserted, lines[5..].concat());
+
+    let diff = BufferDiff::new(&old_text, &new_text).unwrap();
+    let snapshot = diff.snapshot();
+    assert_eq!(snapshot.added_lines(), 10);
+    assert_eq!(snapshot.deleted_lines(), 0);
+    let changed: Vec<_> = snapshot
+        .hunks()
+        .iter()
+        .filter(|hunk| hunk.status != DiffHunkStatus::Unchanged)
+        .collect();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].status, DiffHunkStatus::Added);
+    assert_eq!(diff.apply(&old_text).unwrap(), new_text);
+}
+
+#[test]
+fn test_chunks_without_anchors_fall_back_to_fixed_splits() {
+    // No line is unique, so there's nothing to anchor chunks to
+    let old_text = "Same line of text\n".repeat(8000);
+    let new_text = format!("Same line of text\n{}", old_text);
+
+    let diff = BufferDiff::new(&old_text, &new_text).unwrap();
+    assert_eq!(diff.snapshot().added_lines(), 1);
+    assert_eq!(diff.apply(&old_text).unwrap(), new_text);
+}
+
+#[test]
+fn test_coalesce_hunks_reduces_fragmentation() {
+    // Large enough to be diffed in chunks, with every sixth line of a region
+    // deleted: each deletion is its own hunk, five unchanged lines apart
+    let old_text: String = (0..6000)
+        .map(|i| format!("Line {} of the old text\n", i))
+        .collect();
+    let new_text: String = old_text
+        .lines()
+        .enumerate()
+        .filter(|(i, _)| !(1000..1300).contains(i) || i % 6 != 0)
+        .map(|(_, line)| format!("{}\n", line))
+        .collect();
+
+    let changed = |diff: &BufferDiff| {
+        diff.hunks()
+            .iter()
+            .filter(|hunk| hunk.status != DiffHunkStatus::Unchanged)
+            .count()
+    };
+
+    let fragmented = DiffConfig::default().diff(&old_text, &new_text).unwrap();
+    assert_eq!(changed(&fragmented), 50);
+
+    let diff = DiffConfig::default()
+        .coalesce_hunks(true)
+        .diff(&old_text, &new_text)
+        .unwrap();
+    assert_eq!(changed(&diff), 1);
+    let hunk = &diff.hunks()[0];
+    assert_eq!(hunk.status, DiffHunkStatus::Deleted);
+    assert_eq!(hunk.old_range.start, 999);
+    assert_eq!(hunk.old_range.end(), 1300);
+    assert_eq!(diff.snapshot().deleted_lines(), 50);
+    assert_eq!(diff.apply(&old_text).unwrap(), new_text);
+
+    // Deletions further apart than twice the context stay separate
+    let diff = DiffConfig::default()
+        .context_lines(1)
+        .coalesce_hunks(true)
+        .diff(&old_text, &new_text)
+        .unwrap();
+    assert_eq!(changed(&diff), 50);
+}
+
+#[test]
+fn test_coalesce_hunks_with_full_context() {
+    // Full context, as an app showing whole files asks for, merges every change
+    let old_text = "a\nb\nc\nd\ne\n";
+    let new_text = "a\nB\nc\nD\ne\n";
+    let diff = DiffConfig::default()
+        .context_lines(usize::MAX)
+        .coalesce_hunks(true)
+        .diff(old_text, new_text)
+        .unwrap();
+
+    let changed: Vec<_> = diff
+        .hunks()
+        .iter()
+        .filter(|hunk| hunk.status != DiffHunkStatus::Unchanged)
+        .collect();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].old_range.start, 0);
+    assert_eq!(changed[0].old_range.end(), 5);
+    assert_eq!(diff.apply(old_text).unwrap(), new_text);
+}
+
+#[test]
+fn test_chunked_diff_honors_context_lines() {
+    // Large enough to be diffed in chunks, with one line changed on its own
+    // and two more changed four lines apart
+    let old_text: String = (0..6000)
+        .map(|i| format!("Line {} of the old text\n", i))
+        .collect();
+    let new_text: String = old_text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match i {
+            1000 | 3000 | 3005 => format!("Changed line {}\n", i),
+            _ => format!("{}\n", line),
+        })
+        .collect();
+
+    let changed_hunks = |context_lines: usize| {
+        let diff = DiffConfig::default()
+            .context_lines(context_lines)
+            .diff(&old_text, &new_text)
+            .unwrap();
+        assert_eq!(diff.apply(&old_text).unwrap(), new_text);
+        diff.hunks()
+            .iter()
+            .filter(|hunk| hunk.status != DiffHunkStatus::Unchanged)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let both = |hunk: &buffer_diff::DiffHunk| {
+        let is_both = |t: &&DiffLineType| **t == DiffLineType::Both;
+        let leading = hunk.line_types.iter().take_while(is_both).count();
+        let trailing = hunk.line_types.iter().rev().take_while(is_both).count();
+        (leading, trailing)
+    };
+
+    // Without context, hunks hold only the changed lines
+    let hunks = changed_hunks(0);
+    assert_eq!(hunks.len(), 3);
+    for (hunk, line) in hunks.iter().zip([1000, 3000, 3005]) {
+        assert_eq!(hunk.old_range.start, line);
+        assert_eq!(hunk.old_range.count, 1);
+        assert_eq!(both(hunk), (0, 0));
+    }
+
+    // With five lines of context, the close hunks don't share theirs
+    let hunks = changed_hunks(5);
+    assert_eq!(hunks.len(), 3);
+    assert_eq!(hunks[0].old_range.start, 995);
+    assert_eq!(hunks[0].old_range.count, 11);
+    assert_eq!(both(&hunks[0]), (5, 5));
+    assert_eq!(hunks[1].old_range.start, 2995);
+    assert_eq!(both(&hunks[1]), (5, 4));
+    assert_eq!(hunks[2].old_range.start, 3005);
+    assert_eq!(both(&hunks[2]), (0, 5));
+    for hunk in &hunks {
+        let old_lines = hunk
+            .line_types
+            .iter()
+            .filter(|t| **t != DiffLineType::NewOnly)
+            .count();
+        let new_lines = hunk
+            .line_types
+            .iter()
+            .filter(|t| **t != DiffLineType::OldOnly)
+            .count();
+        assert_eq!(old_lines, hunk.old_range.count);
+        assert_eq!(new_lines, hunk.new_range.count);
+    }
+}
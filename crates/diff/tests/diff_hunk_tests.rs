@@ -1,4 +1,8 @@
-use buffer_diff::{DiffHunk, DiffHunkSecondaryStatus, DiffHunkStatus, DiffLineType};
+use buffer_diff::{
+    changed_char_ranges, changed_word_ranges, BufferDiff, BufferDiffSnapshot, DiffConfig,
+    DiffGranularity, DiffHunk, DiffHunkRange, DiffHunkSecondaryStatus, DiffHunkStatus,
+    DiffLineType, PairedLine,
+};
 
 #[test]
 fn test_diff_hunk_creation() {
@@ -125,3 +129,274 @@ fn test_secondary_status() {
     hunk.set_secondary_status(DiffHunkSecondaryStatus::Unstaged);
     assert_eq!(hunk.secondary_status, DiffHunkSecondaryStatus::Unstaged);
 }
+
+#[test]
+fn test_hunk_at_line() {
+    let snapshot = BufferDiffSnapshot {
+        hunks: vec![
+            DiffHunk::new(DiffHunkStatus::Modified, 2, 2, 2, 3),
+            DiffHunk::new(DiffHunkStatus::Added, 10, 0, 11, 2),
+            DiffHunk::new(DiffHunkStatus::Deleted, 20, 2, 25, 0),
+        ],
+        old_line_count: 30,
+        new_line_count: 33,
+        suppressed: false,
+        timed_out: false,
+        deleted_text: Vec::new(),
+    };
+
+    // Inside hunks
+    assert_eq!(snapshot.hunk_at_new_line(2), Some(0));
+    assert_eq!(snapshot.hunk_at_new_line(4), Some(0));
+    assert_eq!(snapshot.hunk_at_new_line(12), Some(1));
+    assert_eq!(snapshot.hunk_at_old_line(3), Some(0));
+    assert_eq!(snapshot.hunk_at_old_line(21), Some(2));
+
+    // Between hunks, including range ends
+    assert_eq!(snapshot.hunk_at_new_line(5), None);
+    assert_eq!(snapshot.hunk_at_new_line(13), None);
+    assert_eq!(snapshot.hunk_at_old_line(4), None);
+
+    // Empty ranges never contain a line
+    assert_eq!(snapshot.hunk_at_new_line(25), None);
+    assert_eq!(snapshot.hunk_at_old_line(10), None);
+
+    // Outside all hunks
+    assert_eq!(snapshot.hunk_at_new_line(0), None);
+    assert_eq!(snapshot.hunk_at_new_line(100), None);
+    assert_eq!(snapshot.hunk_at_old_line(22), None);
+    assert_eq!(BufferDiffSnapshot::empty().hunk_at_new_line(0), None);
+}
+
+#[test]
+fn test_snapshot_hunk_counts() {
+    let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+    let new = "a\nB\nc\nd\ne\nf\nnew\ng\nh\ni\nj\nk\n";
+    let diff = DiffConfig::default()
+        .context_lines(0)
+        .diff(old, new)
+        .unwrap();
+    let snapshot = diff.snapshot();
+
+    assert_eq!(snapshot.added_hunks(), 1);
+    assert_eq!(snapshot.deleted_hunks(), 1);
+    assert_eq!(snapshot.modified_hunks(), 1);
+
+    let stats = snapshot.stats();
+    assert_eq!(stats.added_lines, snapshot.added_lines());
+    assert_eq!(stats.deleted_lines, snapshot.deleted_lines());
+    assert_eq!((stats.added_lines, stats.deleted_lines), (2, 2));
+    assert_eq!(stats.changed_hunks(), 3);
+}
+
+#[test]
+fn test_snapshot_stats_without_changes() {
+    let snapshot = BufferDiff::new("same\n", "same\n").unwrap().snapshot();
+    assert_eq!(snapshot.stats(), Default::default());
+    assert_eq!(BufferDiffSnapshot::empty().stats().changed_hunks(), 0);
+}
+
+#[test]
+fn test_trim_context() {
+    // 10 context lines, a replaced line, then 4 more context lines
+    let mut line_types = vec![DiffLineType::Both; 10];
+    line_types.extend([DiffLineType::OldOnly, DiffLineType::NewOnly]);
+    line_types.extend([DiffLineType::Both; 4]);
+
+    let mut hunk = DiffHunk::new(DiffHunkStatus::Modified, 20, 15, 30, 15);
+    hunk.line_types = line_types;
+
+    let trimmed = hunk.trim_context(3);
+
+    assert_eq!(trimmed.old_range, DiffHunkRange::new(27, 7));
+    assert_eq!(trimmed.new_range, DiffHunkRange::new(37, 7));
+    assert_eq!(trimmed.line_types.len(), 8);
+    assert_eq!(trimmed.line_types[3], DiffLineType::OldOnly);
+    assert_eq!(trimmed.added_lines(), hunk.added_lines());
+    assert_eq!(trimmed.deleted_lines(), hunk.deleted_lines());
+
+    // Hunks already within the limit are unchanged
+    assert_eq!(hunk.trim_context(10), hunk);
+
+    // A hunk without changes keeps only its first lines
+    let unchanged = DiffHunk::new(DiffHunkStatus::Unchanged, 0, 10, 0, 10).trim_context(3);
+    assert_eq!(unchanged.old_range, DiffHunkRange::new(0, 3));
+    assert_eq!(unchanged.new_range, DiffHunkRange::new(0, 3));
+    assert_eq!(unchanged.line_types.len(), 3);
+}
+
+#[test]
+fn test_paired_lines_single_line_edit() {
+    let diff = DiffConfig::default()
+        .diff("a\nb\nc\n", "a\nB\nc\n")
+        .unwrap();
+    let hunk = diff.hunk(0).unwrap();
+
+    // One modified pair rather than a removed line and an added line
+    assert_eq!(
+        hunk.paired_lines(),
+        vec![
+            PairedLine::Both { old: 0, new: 0 },
+            PairedLine::Modified { old: 1, new: 1 },
+            PairedLine::Both { old: 2, new: 2 },
+        ]
+    );
+
+    // Flattening the pairs gives back the line types
+    let line_types: Vec<DiffLineType> = hunk
+        .paired_lines()
+        .iter()
+        .flat_map(|line| line.line_types().iter().copied())
+        .collect();
+    assert_eq!(line_types, hunk.line_types);
+}
+
+#[test]
+fn test_paired_lines_unequal_runs() {
+    // Two lines replaced by three: two pairs, then the extra added line
+    let mut hunk = DiffHunk::new(DiffHunkStatus::Modified, 10, 3, 20, 4);
+    hunk.line_types = vec![
+        DiffLineType::Both,
+        DiffLineType::OldOnly,
+        DiffLineType::OldOnly,
+        DiffLineType::NewOnly,
+        DiffLineType::NewOnly,
+        DiffLineType::NewOnly,
+    ];
+    assert_eq!(
+        hunk.paired_lines(),
+        vec![
+            PairedLine::Both { old: 10, new: 20 },
+            PairedLine::Modified { old: 11, new: 21 },
+            PairedLine::Modified { old: 12, new: 22 },
+            PairedLine::NewOnly { new: 23 },
+        ]
+    );
+
+    // Pure additions and deletions have nothing to pair
+    let added = DiffHunk::new(DiffHunkStatus::Added, 5, 0, 5, 2);
+    assert_eq!(
+        added.paired_lines(),
+        vec![
+            PairedLine::NewOnly { new: 5 },
+            PairedLine::NewOnly { new: 6 }
+        ]
+    );
+    let deleted = DiffHunk::new(DiffHunkStatus::Deleted, 5, 1, 5, 0);
+    assert_eq!(deleted.paired_lines(), vec![PairedLine::OldOnly { old: 5 }]);
+}
+
+#[test]
+fn test_word_ranges_for_modified_lines() {
+    let old_text = "fn main() {\n    let x = 1;\n}\n";
+    let new_text = "fn main() {\n    let y = 1;\n}\n";
+
+    let diff = DiffConfig::default()
+        .granularity(DiffGranularity::Word)
+        .diff(old_text, new_text)
+        .unwrap();
+    let hunk = diff.hunk(0).unwrap();
+
+    assert_eq!(
+        hunk.line_types,
+        vec![
+            DiffLineType::Both,
+            DiffLineType::OldOnly,
+            DiffLineType::NewOnly,
+            DiffLineType::Both,
+        ]
+    );
+    // Only the renamed variable is marked, on both sides
+    assert_eq!(
+        hunk.word_ranges,
+        Some(vec![vec![], vec![8..9], vec![8..9], vec![]])
+    );
+
+    // Line granularity doesn't compute them
+    let diff = DiffConfig::default().diff(old_text, new_text).unwrap();
+    assert_eq!(diff.hunk(0).unwrap().word_ranges, None);
+}
+
+/// The text covered by each range
+fn words<'a>(text: &'a str, ranges: &[std::ops::Range<usize>]) -> Vec<&'a str> {
+    ranges.iter().map(|range| &text[range.clone()]).collect()
+}
+
+#[test]
+fn test_changed_word_ranges_multibyte_and_empty() {
+    // Ranges are byte offsets on character boundaries
+    let (old, new) = changed_word_ranges("café au lait", "café au thé");
+    assert_eq!(words("café au lait", &old), vec!["lait"]);
+    assert_eq!(words("café au thé", &new), vec!["thé"]);
+
+    let (old, new) = changed_word_ranges("naïve 日本 test", "naïve 中国 test");
+    assert_eq!(words("naïve 日本 test", &old), vec!["日本"]);
+    assert_eq!(words("naïve 中国 test", &new), vec!["中国"]);
+
+    assert_eq!(changed_word_ranges("", ""), (vec![], vec![]));
+    let (old, new) = changed_word_ranges("", "new");
+    assert!(old.is_empty());
+    assert_eq!(words("new", &new), vec!["new"]);
+
+    // An emptied line pairs with the old line without panicking
+    let diff = DiffConfig::default()
+        .granularity(DiffGranularity::Word)
+        .diff("a\nold words\nb\n", "a\n\nb\n")
+        .unwrap();
+    let ranges = diff.hunk(0).unwrap().word_ranges.clone().unwrap();
+    assert_eq!(words("old words", &ranges[1]), vec!["old words"]);
+    assert!(ranges[2].is_empty());
+}
+
+#[test]
+fn test_char_ranges_for_modified_lines() {
+    let old_text = "let colour = 1;\nkeep\n";
+    let new_text = "let color = 1;\nkeep\nadded line\n";
+
+    let diff = DiffConfig::default()
+        .granularity(DiffGranularity::Char)
+        .context_lines(0)
+        .diff(old_text, new_text)
+        .unwrap();
+
+    // Only the dropped `u` is marked, and nothing on the new side
+    let modified = diff.hunk(0).unwrap();
+    assert_eq!(modified.status, DiffHunkStatus::Modified);
+    assert_eq!(modified.word_ranges, Some(vec![vec![8..9], vec![]]));
+
+    // A pure addition has no old line to compare against
+    let added = diff.hunk(1).unwrap();
+    assert_eq!(added.status, DiffHunkStatus::Added);
+    assert_eq!(added.word_ranges, Some(vec![vec![]]));
+}
+
+#[test]
+fn test_changed_char_ranges_cjk_and_combining() {
+    // CJK characters are three bytes each
+    let (old, new) = changed_char_ranges("日本語のテキスト", "日本人のテキスト");
+    assert_eq!(words("日本語のテキスト", &old), vec!["語"]);
+    assert_eq!(words("日本人のテキスト", &new), vec!["人"]);
+
+    // "e" followed by a combining acute accent, against a precomposed "é"
+    let (decomposed, precomposed) = ("cafe\u{301} noir", "caf\u{e9} noir");
+    let (old, new) = changed_char_ranges(decomposed, precomposed);
+    assert_eq!(words(decomposed, &old), vec!["e\u{301}"]);
+    assert_eq!(words(precomposed, &new), vec!["\u{e9}"]);
+
+    // Only the combining mark changes
+    let (old, new) = changed_char_ranges("e\u{301}", "e\u{300}");
+    assert_eq!(words("e\u{301}", &old), vec!["\u{301}"]);
+    assert_eq!(words("e\u{300}", &new), vec!["\u{300}"]);
+
+    // Whole diffs slice lines on character boundaries without panicking
+    for (old_line, new_line) in [("中文文本", "中文字符"), ("Zoe\u{308}", "Zoë")] {
+        let diff = DiffConfig::default()
+            .granularity(DiffGranularity::Char)
+            .diff(&format!("{}\n", old_line), &format!("{}\n", new_line))
+            .unwrap();
+        let ranges = diff.hunk(0).unwrap().word_ranges.clone().unwrap();
+        assert_eq!(ranges.len(), 2);
+        assert!(!words(old_line, &ranges[0]).is_empty());
+        assert!(!words(new_line, &ranges[1]).is_empty());
+    }
+}
@@ -1,4 +1,8 @@
-use buffer_diff::{BufferDiff, DiffHunkStatus, DiffLineType, TextDiff};
+use buffer_diff::{
+    split_lines, BufferDiff, DiffAlgorithm, DiffConfig, DiffGranularity, DiffHunkSecondaryStatus,
+    DiffHunkStatus, DiffLineType, PairedLine, TextDiff, WhitespaceChange,
+};
+use std::time::Duration;
 
 #[test]
 fn test_empty_files() {
@@ -178,7 +182,7 @@ fn test_line_types() {
         // Check if the line types match our expectations
         let has_old_only = hunk.line_types.contains(&DiffLineType::OldOnly);
         let has_new_only = hunk.line_types.contains(&DiffLineType::NewOnly);
-        
+
         assert!(has_old_only);
         assert!(has_new_only);
         // Note: it's okay if there's no "Both" type depending on implementation
@@ -240,3 +244,666 @@ fn test_range_methods() {
     let std_range2 = range2.to_range();
     assert_eq!(std_range2, 5..10);
 }
+
+#[test]
+fn test_max_lines_suppresses_large_diffs() {
+    use buffer_diff::DiffConfig;
+
+    let old: String = (0..20).map(|i| format!("line {}\n", i)).collect();
+    let new: String = (0..20).map(|i| format!("line {}\n", i * 2)).collect();
+
+    // Over the threshold: a single summary hunk, no per-line information
+    let diff = DiffConfig::default()
+        .max_lines(10)
+        .diff(&old, &new)
+        .unwrap();
+    assert!(diff.is_suppressed());
+    assert_eq!(diff.hunk_count(), 1);
+    assert_eq!(diff.hunks()[0].status, DiffHunkStatus::Modified);
+    assert!(diff.hunks()[0].line_types.is_empty());
+    assert!(diff.snapshot().suppressed);
+    // The whole file counts as replaced
+    let stats = diff.snapshot().stats();
+    assert_eq!((stats.added_lines, stats.deleted_lines), (20, 20));
+    assert_eq!(diff.snapshot().added_lines(), 20);
+    assert_eq!(diff.snapshot().deleted_lines(), 20);
+
+    // Under the threshold: computed normally
+    let diff = DiffConfig::default()
+        .max_lines(100)
+        .diff(&old, &new)
+        .unwrap();
+    assert!(!diff.is_suppressed());
+    assert!(!diff.snapshot().suppressed);
+    assert!(diff.snapshot().added_lines() > 0);
+    assert!(diff.snapshot().deleted_lines() > 0);
+
+    // Only one side needs to exceed the limit
+    let diff = DiffConfig::default()
+        .max_lines(10)
+        .diff("a\n", &new)
+        .unwrap();
+    assert!(diff.is_suppressed());
+}
+
+#[test]
+fn test_new_range_offsets_hunks_to_global_lines() {
+    use buffer_diff::DiffConfig;
+
+    let old: String = (0..30).map(|i| format!("line {}\n", i)).collect();
+    let changed = |lines: &[usize]| -> String {
+        (0..30)
+            .map(|i| {
+                if lines.contains(&i) {
+                    format!("changed {}\n", i)
+                } else {
+                    format!("line {}\n", i)
+                }
+            })
+            .collect()
+    };
+    // Line 15 is inside the reviewed region, lines 2 and 25 are outside it
+    let new = changed(&[2, 15, 25]);
+
+    let diff = BufferDiff::new_range(&old, &new, 10..20, 10..20, &DiffConfig::default()).unwrap();
+
+    // Same hunk as a whole-file diff that only changes line 15
+    let expected = BufferDiff::new(&old, &changed(&[15])).unwrap();
+    assert_eq!(expected.hunk_count(), 1);
+    assert_eq!(diff.hunks(), expected.hunks());
+
+    let hunk = &diff.hunks()[0];
+    assert!(hunk.old_range.start >= 10 && hunk.old_range.end() <= 20);
+    assert!(hunk.old_range.start <= 15 && hunk.old_range.end() > 15);
+
+    // The full texts are kept, so hunk lines index into them directly
+    assert_eq!(diff.old_text().len_lines(), old.lines().count() + 1);
+    assert_eq!(diff.new_text().line(15).to_string(), "changed 15\n");
+}
+
+#[test]
+fn test_new_range_with_different_offsets() {
+    use buffer_diff::DiffConfig;
+
+    // Two lines inserted at the top shift the region by two in the new text
+    let old: String = (0..30).map(|i| format!("line {}\n", i)).collect();
+    let new: String = ["header a\n".to_string(), "header b\n".to_string()]
+        .into_iter()
+        .chain((0..30).map(|i| match i {
+            15 => "line fifteen\n".to_string(),
+            _ => format!("line {}\n", i),
+        }))
+        .collect();
+
+    let diff = BufferDiff::new_range(&old, &new, 10..20, 12..22, &DiffConfig::default()).unwrap();
+
+    assert_eq!(diff.hunk_count(), 1);
+    let hunk = &diff.hunks()[0];
+    assert_eq!(hunk.new_range.start, hunk.old_range.start + 2);
+    assert_eq!(hunk.old_range.count, hunk.new_range.count);
+
+    // Ranges past the end are clamped instead of panicking
+    let diff = BufferDiff::new_range(&old, &new, 25..100, 27..100, &DiffConfig::default()).unwrap();
+    assert!(!diff.snapshot().has_changes());
+}
+
+#[test]
+fn test_reconstruct_old_from_new_text() {
+    use buffer_diff::split_lines;
+
+    let old = "fn main() {\n    let x = 1;\n    let y = 2;\n    println!(\"{}\", x);\n}\n";
+    let new =
+        "fn main() {\n    let x = 10;\n    println!(\"{}\", x);\n    println!(\"done\");\n}\n";
+
+    let diff = BufferDiff::new(old, new).unwrap();
+    let snapshot = diff.snapshot_with_deleted_text();
+    let new_lines: Vec<&str> = split_lines(new).collect();
+    let old_lines: Vec<&str> = split_lines(old).collect();
+
+    assert_eq!(snapshot.reconstruct_old(&new_lines).unwrap(), old_lines);
+
+    // Plain snapshots don't carry the deleted lines
+    assert!(diff.snapshot().deleted_text.is_empty());
+    assert_eq!(diff.snapshot().reconstruct_old(&new_lines), None);
+
+    // New lines that don't reach the end of a hunk can't be used
+    assert_eq!(snapshot.reconstruct_old(&new_lines[..2]), None);
+}
+
+#[test]
+fn test_context_lines() {
+    use buffer_diff::DiffConfig;
+
+    let old: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+    let new = old.replace("line 10\n", "line ten\n");
+
+    // No context: only the changed lines
+    let diff = DiffConfig::default()
+        .context_lines(0)
+        .diff(&old, &new)
+        .unwrap();
+    assert_eq!(diff.hunk_count(), 1);
+    let hunk = &diff.hunks()[0];
+    assert_eq!((hunk.old_range.start, hunk.old_range.count), (9, 1));
+    assert_eq!(
+        hunk.line_types,
+        vec![DiffLineType::OldOnly, DiffLineType::NewOnly]
+    );
+
+    // Default: three lines either side
+    let diff = BufferDiff::new(&old, &new).unwrap();
+    let hunk = &diff.hunks()[0];
+    assert_eq!((hunk.old_range.start, hunk.old_range.count), (6, 7));
+    assert_eq!(hunk.unchanged_lines(), 6);
+
+    // More context than the file has is clamped to the whole file
+    let diff = DiffConfig::default()
+        .context_lines(100)
+        .diff(&old, &new)
+        .unwrap();
+    assert_eq!(diff.hunk_count(), 1);
+    let hunk = &diff.hunks()[0];
+    assert_eq!((hunk.old_range.start, hunk.old_range.count), (0, 20));
+    assert_eq!((hunk.new_range.start, hunk.new_range.count), (0, 20));
+    assert_eq!(hunk.unchanged_lines(), 19);
+}
+
+#[test]
+fn test_context_lines_not_shared_between_hunks() {
+    let old = "a\nb\nc\nd\ne\n";
+    let new = "A\nb\nc\nD\ne\n";
+
+    let diff = BufferDiff::with_context_lines(old, new, 3).unwrap();
+
+    // Every old line appears in exactly one hunk
+    let covered: Vec<usize> = diff
+        .hunks()
+        .iter()
+        .flat_map(|hunk| hunk.old_range.start..hunk.old_range.end())
+        .collect();
+    assert_eq!(covered, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn test_to_unified_matches_git_diff() {
+    // Expected output is from `git diff --no-index`, minus its `diff --git`,
+    // `index` and function context lines
+    let old: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+    let new = format!("first\n{}last\n", old);
+    let diff = BufferDiff::new(&old, &new).unwrap();
+    assert_eq!(
+        diff.to_unified("a/f.txt", "b/f.txt", 1),
+        "--- a/f.txt\n+++ b/f.txt\n\
+         @@ -1 +1,2 @@\n+first\n line 1\n\
+         @@ -30 +31,2 @@\n line 30\n+last\n"
+    );
+
+    // Missing newlines at the end of the file are marked
+    let diff = BufferDiff::new("a\nb\nc", "a\nb\nC").unwrap();
+    assert_eq!(
+        diff.to_unified("a/f", "b/f", 1),
+        "--- a/f\n+++ b/f\n@@ -2,2 +2,2 @@\n b\n\
+         -c\n\\ No newline at end of file\n\
+         +C\n\\ No newline at end of file\n"
+    );
+
+    // Empty ranges are given by the line before them
+    let diff = BufferDiff::new("", "a\nb\nc\n").unwrap();
+    assert_eq!(
+        diff.to_unified("/dev/null", "b/f", 3),
+        "--- /dev/null\n+++ b/f\n@@ -0,0 +1,3 @@\n+a\n+b\n+c\n"
+    );
+
+    // Nothing changed, nothing to print
+    assert_eq!(
+        BufferDiff::new("a\n", "a\n")
+            .unwrap()
+            .to_unified("a/f", "b/f", 3),
+        ""
+    );
+}
+
+#[test]
+fn test_to_unified_context_is_independent_of_hunk_context() {
+    let old: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+    let new = old.replace("line 10\n", "line ten\n");
+
+    let expected = "--- a/f\n+++ b/f\n@@ -9,3 +9,3 @@\n line 9\n-line 10\n+line ten\n line 11\n";
+    for hunk_context in [0, 3, 100] {
+        let diff = BufferDiff::with_context_lines(&old, &new, hunk_context).unwrap();
+        assert_eq!(diff.to_unified("a/f", "b/f", 1), expected);
+    }
+}
+
+#[test]
+fn test_to_unified_with_full_context() {
+    // Context beyond the file, up to usize::MAX, prints the whole file in one hunk
+    let diff = BufferDiff::new("a\nb\nc\n", "a\nB\nc\n").unwrap();
+    let expected = "--- a/f\n+++ b/f\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n";
+    assert_eq!(diff.to_unified("a/f", "b/f", 10), expected);
+    assert_eq!(diff.to_unified("a/f", "b/f", usize::MAX), expected);
+}
+
+#[test]
+fn test_max_hunks_coalesces_the_tail() {
+    use buffer_diff::DiffConfig;
+
+    // 50 scattered one-line changes, far enough apart to be separate hunks
+    let old: String = (0..500).map(|i| format!("line {}\n", i)).collect();
+    let new: String = (0..500)
+        .map(|i| match i % 10 {
+            5 => format!("changed {}\n", i),
+            _ => format!("line {}\n", i),
+        })
+        .collect();
+
+    let config = DiffConfig::default().context_lines(1);
+    assert_eq!(config.diff(&old, &new).unwrap().hunk_count(), 50);
+
+    let diff = config.max_hunks(10).diff(&old, &new).unwrap();
+    assert_eq!(diff.hunk_count(), 10);
+
+    // The first hunks are untouched
+    let ninth = &diff.hunks()[8];
+    assert_eq!((ninth.old_range.start, ninth.old_range.count), (84, 3));
+
+    // The last one covers every remaining change, through to the last hunk's end
+    let tail = &diff.hunks()[9];
+    assert_eq!(tail.status, DiffHunkStatus::Modified);
+    assert_eq!(tail.old_range.start, 94);
+    assert_eq!(tail.old_range.end(), 497);
+    assert_eq!(tail.new_range.end(), 497);
+    assert_eq!(tail.line_types.len(), 403 + 41);
+    assert_eq!(tail.deleted_lines(), 41);
+    assert_eq!(tail.added_lines(), 41);
+    assert_eq!(diff.apply(&old).unwrap(), new);
+}
+
+#[test]
+fn test_reversed_diff() {
+    let old = "keep\nremoved\nkeep\nold line\nkeep\n";
+    let new = "keep\nkeep\nnew line\nkeep\nadded\n";
+    let diff = BufferDiff::new(old, new).unwrap();
+
+    let reversed = diff.reversed();
+    assert_eq!(reversed.old_text().to_string(), new);
+    assert_eq!(reversed.new_text().to_string(), old);
+    for (hunk, reversed_hunk) in diff.hunks().iter().zip(reversed.hunks()) {
+        assert_eq!(reversed_hunk.old_range, hunk.new_range);
+        assert_eq!(reversed_hunk.added_lines(), hunk.deleted_lines());
+        assert_eq!(reversed_hunk.deleted_lines(), hunk.added_lines());
+    }
+
+    // Each run of changed lines still lists its removed lines first
+    for hunk in reversed.hunks() {
+        assert!(!hunk
+            .line_types
+            .windows(2)
+            .any(|pair| pair == [DiffLineType::NewOnly, DiffLineType::OldOnly]));
+    }
+
+    // Applying the reversed diff to the new text gives back the old one
+    assert_eq!(reversed.apply(new).unwrap(), old);
+    assert_eq!(reversed.reversed(), diff);
+
+    // Word ranges move along with their lines
+    let words = DiffConfig::default()
+        .granularity(DiffGranularity::Word)
+        .diff("a bb\n", "a c\n")
+        .unwrap()
+        .reversed();
+    let hunk = &words.hunks()[0];
+    assert_eq!(
+        hunk.line_types,
+        vec![DiffLineType::OldOnly, DiffLineType::NewOnly]
+    );
+    assert_eq!(hunk.word_ranges, Some(vec![vec![2..3], vec![2..4]]));
+
+    // Added and deleted files swap
+    let added = BufferDiff::new("", "a\nb\n").unwrap().reversed();
+    assert_eq!(added.hunks()[0].status, DiffHunkStatus::Deleted);
+    assert_eq!(added.hunks()[0].line_types, vec![DiffLineType::OldOnly; 2]);
+}
+
+#[test]
+fn test_detect_moves() {
+    let helper = "fn helper() {\n    work();\n}\n";
+    let filler: String = (0..10).map(|i| format!("line {}\n", i)).collect();
+    let old = format!("{helper}\n{filler}\nfn main() {{}}\n");
+    let new = format!("\n{filler}\nfn main() {{}}\n{helper}");
+
+    let diff = DiffConfig::default()
+        .detect_moves(true)
+        .diff(&old, &new)
+        .unwrap();
+    let statuses: Vec<_> = diff.hunks().iter().map(|hunk| hunk.status).collect();
+    assert_eq!(
+        statuses,
+        vec![DiffHunkStatus::Deleted, DiffHunkStatus::Added]
+    );
+    assert_eq!(
+        diff.hunks()[0].secondary_status,
+        DiffHunkSecondaryStatus::Moved { partner_index: 1 }
+    );
+    assert_eq!(
+        diff.hunks()[1].secondary_status,
+        DiffHunkSecondaryStatus::Moved { partner_index: 0 }
+    );
+
+    // Without the flag, nothing is tagged
+    let diff = BufferDiff::new(&old, &new).unwrap();
+    assert!(diff
+        .hunks()
+        .iter()
+        .all(|hunk| hunk.secondary_status == DiffHunkSecondaryStatus::None));
+
+    // Lines that changed on the way aren't a move
+    let edited = new.replace("work()", "rest()");
+    let mut diff = BufferDiff::new(&old, &edited).unwrap();
+    diff.detect_moves();
+    assert!(diff
+        .hunks()
+        .iter()
+        .all(|hunk| hunk.secondary_status == DiffHunkSecondaryStatus::None));
+}
+
+#[test]
+fn test_binary_content_is_not_diffed_by_line() {
+    // The start of a PNG file: its header includes NUL bytes
+    let png = "\u{fffd}PNG\r\n\u{1a}\n\0\0\0\rIHDR\0\0\0\u{1}\0\0\0\u{1}\u{8}\u{6}\0\0\0";
+    let changed_png = png.replace("IHDR", "IHDX");
+
+    let diff = BufferDiff::new(png, &changed_png).unwrap();
+    assert!(diff.is_binary());
+    assert_eq!(diff.hunk_count(), 1);
+    let hunk = diff.hunk(0).unwrap();
+    assert_eq!(hunk.status, DiffHunkStatus::Binary);
+    assert!(hunk.line_types.is_empty());
+    assert!(diff.snapshot().has_changes());
+    assert!(diff.apply(png).is_err());
+    assert_eq!(
+        diff.to_unified("a/icon.png", "b/icon.png", 3),
+        "Binary files a/icon.png and b/icon.png differ\n"
+    );
+
+    // Binary on only one side is still binary
+    assert!(BufferDiff::new("", png).unwrap().is_binary());
+    assert!(BufferDiff::new("text\n", png).unwrap().is_binary());
+
+    // Identical binary content is unchanged
+    let same = BufferDiff::new(png, png).unwrap();
+    assert!(!same.is_binary());
+    assert!(!same.snapshot().has_changes());
+
+    // Text is diffed by line as usual
+    let text = BufferDiff::new("one\ntwo\n", "one\n2\n").unwrap();
+    assert!(!text.is_binary());
+    assert_eq!(text.snapshot().added_lines(), 1);
+    assert_eq!(text.snapshot().deleted_lines(), 1);
+}
+
+/// A function whose body shares most of its lines with every other one
+fn boilerplate_fn(name: &str) -> String {
+    format!(
+        "fn {name}() {{\n    let mut out = Vec::new();\n    for item in items {{\n        \
+         out.push(item.{name});\n    }}\n\n    out\n}}\n\n"
+    )
+}
+
+#[test]
+fn test_patience_keeps_reordered_functions_together() {
+    let old = ["alpha", "beta", "gamma"].map(boilerplate_fn).concat();
+    let new = ["beta", "alpha", "gamma"].map(boilerplate_fn).concat();
+    let config = DiffConfig::default().context_lines(0);
+
+    // Myers matches the shared lines, splitting both functions into edits
+    let myers = config
+        .clone()
+        .algorithm(DiffAlgorithm::Myers)
+        .diff(&old, &new)
+        .unwrap();
+    assert_eq!(myers.hunk_count(), 4);
+    assert!(myers
+        .hunks()
+        .iter()
+        .all(|hunk| hunk.status == DiffHunkStatus::Modified));
+
+    // Patience anchors on the unique lines and moves one function whole
+    let patience = config
+        .algorithm(DiffAlgorithm::Patience)
+        .diff(&old, &new)
+        .unwrap();
+    let statuses: Vec<_> = patience.hunks().iter().map(|hunk| hunk.status).collect();
+    assert_eq!(statuses, [DiffHunkStatus::Added, DiffHunkStatus::Deleted]);
+    assert!(patience
+        .hunks()
+        .iter()
+        .all(|hunk| hunk.line_types.len() == 9));
+    assert_eq!(patience.apply(&old).unwrap(), new);
+}
+
+#[test]
+fn test_algorithms_share_whole_file_special_cases() {
+    for algorithm in [
+        DiffAlgorithm::Myers,
+        DiffAlgorithm::Patience,
+        DiffAlgorithm::Lcs,
+    ] {
+        let config = DiffConfig::default().algorithm(algorithm);
+        let status = |old, new| config.diff(old, new).unwrap().hunk(0).unwrap().status;
+
+        assert_eq!(status("", ""), DiffHunkStatus::Unchanged);
+        assert_eq!(status("", "a\nb\n"), DiffHunkStatus::Added);
+        assert_eq!(status("a\nb\n", ""), DiffHunkStatus::Deleted);
+        assert_eq!(config.diff("", "a\nb\n").unwrap().hunk_count(), 1);
+    }
+
+    // similar's algorithm type still works with the builder
+    let config = DiffConfig::default().algorithm(similar::Algorithm::Patience);
+    assert_eq!(config.algorithm, DiffAlgorithm::Patience);
+}
+
+/// Lines from a tiny alphabet in a scrambled order, so two of these share many
+/// lines but few long runs, which is the worst case for the line diff
+fn scrambled_lines(count: usize, seed: u64) -> String {
+    let mut state = seed;
+    (0..count)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            format!("{}\n", (state >> 60) % 4)
+        })
+        .collect()
+}
+
+#[test]
+fn test_timeout_flags_degraded_diff() {
+    let old = scrambled_lines(3000, 1);
+    let new = scrambled_lines(3000, 2);
+
+    let rushed = DiffConfig::default()
+        .timeout(Duration::from_micros(1))
+        .diff(&old, &new)
+        .unwrap();
+    assert!(rushed.timed_out());
+    assert!(rushed.snapshot().timed_out);
+    // A degraded diff is still a correct one
+    assert_eq!(rushed.apply(&old).unwrap(), new);
+
+    let unlimited = DiffConfig::default()
+        .timeout(None)
+        .diff(&old, &new)
+        .unwrap();
+    assert!(!unlimited.timed_out());
+    assert!(!BufferDiff::new("a\n", "b\n").unwrap().timed_out());
+}
+
+#[test]
+fn test_align_reordered_lines_within_hunk() {
+    let old = "fn main() {\n    let a = 1;\n    let b = 2;\n    let c = 3;\n    run();\n}\n";
+    let new = "fn main() {\n    let c = 3;\n    let b = 2;\n    let a = 1;\n    run();\n}\n";
+
+    // Without alignment, `a` stays put and `b` and `c` are deleted and added
+    // again, in separate hunks
+    let plain = DiffConfig::default().diff(old, new).unwrap();
+    assert_eq!(plain.hunk_count(), 2);
+    assert_eq!(plain.snapshot().added_lines(), 2);
+    assert!(plain.hunk(0).unwrap().reordered.is_empty());
+
+    let diff = DiffConfig::default()
+        .align_reordered(true)
+        .diff(old, new)
+        .unwrap();
+    assert_eq!(diff.hunk_count(), 1);
+    let hunk = diff.hunk(0).unwrap();
+    assert_eq!(hunk.status, DiffHunkStatus::Modified);
+    assert_eq!((hunk.added_lines(), hunk.deleted_lines()), (0, 0));
+    for (index, line_type) in hunk.line_types.iter().enumerate() {
+        assert_eq!(hunk.is_reordered(index), *line_type != DiffLineType::Both);
+    }
+
+    // All three lines moved, including `b`, which the diff kept in place
+    let reordered: Vec<_> = hunk
+        .paired_lines()
+        .into_iter()
+        .filter(|line| matches!(line, PairedLine::Reordered { .. }))
+        .collect();
+    assert_eq!(reordered.len(), 3);
+    assert!(reordered.contains(&PairedLine::Reordered { old: 1, new: 3 }));
+    assert!(reordered.contains(&PairedLine::Reordered { old: 2, new: 2 }));
+    assert!(reordered.contains(&PairedLine::Reordered { old: 3, new: 1 }));
+
+    // The line types still describe the change, so the diff still applies
+    assert_eq!(diff.apply(old).unwrap(), new);
+    assert_eq!(diff.reversed().hunk(0).unwrap().reordered.len(), 3);
+}
+
+#[test]
+fn test_align_reordered_survives_later_passes() {
+    let mut lines: Vec<String> = (0..30).map(|i| format!("line {}\n", i)).collect();
+    let old = lines.concat();
+    lines.swap(2, 4);
+    lines[20] = "line twenty\n".to_string();
+    lines[26] = "line 26 changed\n".to_string();
+    let new = lines.concat();
+
+    // Limiting the hunks merges the reordered hunk with the others
+    let diff = DiffConfig::default()
+        .context_lines(1)
+        .align_reordered(true)
+        .max_hunks(1)
+        .diff(&old, &new)
+        .unwrap();
+    assert_eq!(diff.hunk_count(), 1);
+    let hunk = diff.hunk(0).unwrap();
+    assert_eq!(hunk.reordered.len(), 3);
+    assert_eq!((hunk.added_lines(), hunk.deleted_lines()), (2, 2));
+    assert_eq!(diff.apply(&old).unwrap(), new);
+
+    // Reordered lines aren't paired with other lines for word highlights
+    let diff = DiffConfig::default()
+        .align_reordered(true)
+        .granularity(DiffGranularity::Word)
+        .diff("a\nb\nc\nkeep\nold words\n", "c\nb\na\nkeep\nnew words\n")
+        .unwrap();
+    let hunk = diff.hunk(0).unwrap();
+    let word_ranges = hunk.word_ranges.as_ref().unwrap();
+    for (index, ranges) in word_ranges.iter().enumerate() {
+        if hunk.is_reordered(index) {
+            assert!(ranges.is_empty());
+        }
+    }
+    assert!(word_ranges.iter().any(|ranges| !ranges.is_empty()));
+}
+
+#[test]
+fn test_lines_resolve_hunk_lines() {
+    let old = "one\ntwo\nthree\nfour\nfive\nsix\n";
+    let new = "one\n2\nthree\nfour\nfive\nsix\nseven";
+    let diff = DiffConfig::default()
+        .context_lines(1)
+        .diff(old, new)
+        .unwrap();
+    assert_eq!(diff.hunk_count(), 2);
+
+    let lines: Vec<_> = diff
+        .lines()
+        .map(|line| {
+            (
+                line.old_line,
+                line.new_line,
+                line.content.into_owned(),
+                line.kind,
+            )
+        })
+        .collect();
+    let line = |old, new, content: &str, kind| (old, new, content.to_string(), kind);
+    assert_eq!(
+        lines,
+        vec![
+            line(Some(0), Some(0), "one", DiffLineType::Both),
+            line(Some(1), None, "two", DiffLineType::OldOnly),
+            line(None, Some(1), "2", DiffLineType::NewOnly),
+            line(Some(2), Some(2), "three", DiffLineType::Both),
+            line(Some(5), Some(5), "six", DiffLineType::Both),
+            line(None, Some(6), "seven", DiffLineType::NewOnly),
+        ]
+    );
+    let last = diff.lines().last().unwrap();
+    assert_eq!((last.hunk_index, last.line_index), (1, 1));
+
+    // Long CRLF texts span several rope chunks; lines still match `split_lines`
+    let old: String = (0..2000).map(|i| format!("line {}\r\n", i)).collect();
+    let new = old.replace("line 1000\r\n", "changed\r\n");
+    let diff = DiffConfig::default()
+        .context_lines(2000)
+        .diff(&old, &new)
+        .unwrap();
+    let contents: Vec<_> = diff
+        .lines()
+        .filter(|line| line.kind != DiffLineType::OldOnly)
+        .map(|line| line.content)
+        .collect();
+    assert_eq!(contents, split_lines(&new).collect::<Vec<_>>());
+}
+
+/// `whitespace_changes` for each changed hunk of a diff without context
+fn changed_hunk_whitespace(old: &str, new: &str) -> Vec<Option<WhitespaceChange>> {
+    let diff = DiffConfig::default()
+        .context_lines(0)
+        .diff(old, new)
+        .unwrap();
+    diff.hunks()
+        .iter()
+        .zip(diff.whitespace_changes())
+        .filter(|(hunk, _)| hunk.status != DiffHunkStatus::Unchanged)
+        .map(|(_, change)| change)
+        .collect()
+}
+
+#[test]
+fn test_whitespace_only_hunks() {
+    // Trailing spaces on one line, a real change on another
+    let old = "one\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+    let new = "one\ntwo  \nthree\nfour\nfive\nsix\nSEVEN\n";
+    assert_eq!(
+        changed_hunk_whitespace(old, new),
+        vec![Some(WhitespaceChange::Trailing), None]
+    );
+
+    assert_eq!(
+        changed_hunk_whitespace("fn a() {\nrun();\n}\n", "fn a() {\n    run();\n}\n"),
+        vec![Some(WhitespaceChange::Within)]
+    );
+
+    let eof = changed_hunk_whitespace("a\nb", "a\nb\n");
+    assert_eq!(eof, vec![Some(WhitespaceChange::EofNewline)]);
+    assert_eq!(
+        WhitespaceChange::EofNewline.to_string(),
+        "newline at end of file only"
+    );
+
+    // Added blank lines aren't whitespace-only
+    assert_eq!(changed_hunk_whitespace("a\nb\n", "a\n\nb\n"), vec![None]);
+}
@@ -1,4 +1,4 @@
-use buffer_diff::{BufferDiff, TextDiff};
+use buffer_diff::{line_count, split_lines, BufferDiff, TextDiff, NO_NEWLINE_MARKER};
 
 #[test]
 fn test_newlines_at_end() {
@@ -35,6 +35,47 @@ fn test_newlines_at_end() {
     assert!(diff4.snapshot().hunk_count() > 0);
 }
 
+#[test]
+fn test_unified_diff_no_newline_marker() {
+    let marker = format!("{}\n", NO_NEWLINE_MARKER);
+
+    // Both sides end with a newline: no marker
+    let unified = TextDiff::unified_diff("Line 1\nLine 2\n", "Line 1\nLine X\n", 3);
+    assert_eq!(unified, " Line 1\n-Line 2\n+Line X\n");
+
+    // Only old lacks the trailing newline
+    let unified = TextDiff::unified_diff("Line 1\nLine 2", "Line 1\nLine X\n", 3);
+    assert_eq!(unified, format!(" Line 1\n-Line 2\n{}+Line X\n", marker));
+
+    // Only new lacks the trailing newline
+    let unified = TextDiff::unified_diff("Line 1\nLine 2\n", "Line 1\nLine X", 3);
+    assert_eq!(unified, format!(" Line 1\n-Line 2\n+Line X\n{}", marker));
+
+    // Neither side has a trailing newline: marker on both sides
+    let unified = TextDiff::unified_diff("Line 1\nLine 2", "Line 1\nLine X", 3);
+    assert_eq!(
+        unified,
+        format!(" Line 1\n-Line 2\n{}+Line X\n{}", marker, marker)
+    );
+}
+
+#[test]
+fn test_unified_diff_no_newline_only_change() {
+    // Adding just the trailing newline shows up as a changed last line
+    let unified = TextDiff::unified_diff("Line 1\nLine 2", "Line 1\nLine 2\n", 3);
+    assert_eq!(
+        unified,
+        format!(" Line 1\n-Line 2\n{}\n+Line 2\n", NO_NEWLINE_MARKER)
+    );
+
+    // An unchanged last line without a newline gets a single marker
+    let unified = TextDiff::unified_diff("Line 1\nLine 2", "Line X\nLine 2", 3);
+    assert_eq!(
+        unified,
+        format!("-Line 1\n+Line X\n Line 2\n{}\n", NO_NEWLINE_MARKER)
+    );
+}
+
 #[test]
 fn test_very_large_diff() {
     // Create large strings (but not too large for testing)
@@ -128,3 +169,28 @@ fn test_snapshot_clone() {
     assert_eq!(snapshot1.deleted_lines(), snapshot2.deleted_lines());
     assert_eq!(snapshot1.unchanged_lines(), snapshot2.unchanged_lines());
 }
+
+#[test]
+fn test_split_lines_crlf() {
+    let lines: Vec<&str> = split_lines("one\r\ntwo\r\n\r\nthree").collect();
+    assert_eq!(lines, vec!["one", "two", "", "three"]);
+
+    // A trailing newline doesn't add an empty line, and a lone CR isn't a break
+    assert_eq!(line_count("one\r\n"), 1);
+    assert_eq!(line_count("one\rstill one\n"), 1);
+    assert_eq!(line_count(""), 0);
+}
+
+#[test]
+fn test_crlf_line_counts_match_hunks() {
+    // Added file without a trailing newline
+    let diff = BufferDiff::new("", "one\r\ntwo\r\nthree").unwrap();
+    let hunks = diff.hunks();
+    assert_eq!(hunks[0].new_range.count, 3);
+    assert_eq!(hunks[0].line_types.len(), line_count("one\r\ntwo\r\nthree"));
+
+    // Unchanged CRLF file
+    let text = "one\r\ntwo\r\n";
+    let diff = BufferDiff::new(text, text).unwrap();
+    assert_eq!(diff.hunks()[0].old_range.count, line_count(text));
+}
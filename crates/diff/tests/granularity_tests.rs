@@ -1,38 +1,50 @@
-use buffer_diff::{DiffConfig, DiffGranularity, TextDiff};
+use buffer_diff::{DiffConfig, DiffGranularity, SplitMode, TextDiff, WhitespaceMode};
 use similar::Algorithm;
 
 #[test]
 fn test_line_level_diff() {
     let old_text = "First line\nSecond line\nThird line\nFourth line\n";
     let new_text = "First line\nSecond line modified\nThird line\nFourth line\n";
-    
+
     // Create a line-level diff
     let config = DiffConfig::default()
         .algorithm(Algorithm::Myers)
         .granularity(DiffGranularity::Line);
-    
+
     let diff = config.diff(old_text, new_text).unwrap();
     let snapshot = diff.snapshot();
-    
+
     // We should have one hunk
     assert_eq!(snapshot.hunk_count(), 1);
-    
+
     // The hunk should contain the modified line
     let hunk = snapshot.hunk(0).unwrap();
     assert!(hunk.old_range.contains(1)); // Second line (0-indexed)
     assert!(hunk.new_range.contains(1)); // Second line (0-indexed)
-    
+
     // Verify line types (should have at least one modified line)
-    let old_only_count = snapshot.hunks()
+    let old_only_count = snapshot
+        .hunks()
         .iter()
-        .map(|h| h.line_types.iter().filter(|&&t| t == buffer_diff::DiffLineType::OldOnly).count())
+        .map(|h| {
+            h.line_types
+                .iter()
+                .filter(|&&t| t == buffer_diff::DiffLineType::OldOnly)
+                .count()
+        })
         .sum::<usize>();
-    
-    let new_only_count = snapshot.hunks()
+
+    let new_only_count = snapshot
+        .hunks()
         .iter()
-        .map(|h| h.line_types.iter().filter(|&&t| t == buffer_diff::DiffLineType::NewOnly).count())
+        .map(|h| {
+            h.line_types
+                .iter()
+                .filter(|&&t| t == buffer_diff::DiffLineType::NewOnly)
+                .count()
+        })
         .sum::<usize>();
-    
+
     assert_eq!(old_only_count, 1); // One line removed
     assert_eq!(new_only_count, 1); // One line added
 }
@@ -41,81 +53,228 @@ fn test_line_level_diff() {
 fn test_word_level_diff() {
     let old_text = "The quick brown fox jumps over the lazy dog";
     let new_text = "The quick red fox jumps over the lazy dog";
-    
+
     // Create a word-level diff
     let diff = TextDiff::diff_with_granularity(old_text, new_text, DiffGranularity::Word).unwrap();
     let snapshot = diff.snapshot();
-    
+
     // Ensure the diff detected the change
     assert!(snapshot.has_changes());
-    
+
     // Create a line-level diff for comparison
-    let line_diff = TextDiff::diff_with_granularity(old_text, new_text, DiffGranularity::Line).unwrap();
+    let line_diff =
+        TextDiff::diff_with_granularity(old_text, new_text, DiffGranularity::Line).unwrap();
     let line_snapshot = line_diff.snapshot();
-    
+
     // Both should detect changes
     assert!(line_snapshot.has_changes());
-    
+
     // Get the unified diff output and check that it contains word-level changes
-    let unified_diff = TextDiff::unified_diff_with_granularity(old_text, new_text, 3, DiffGranularity::Word);
-    
+    let unified_diff =
+        TextDiff::unified_diff_with_granularity(old_text, new_text, 3, DiffGranularity::Word);
+
     // The word-level diff should show only "brown" and "red" as changed, not the entire line
     assert!(unified_diff.contains("-brown"));
     assert!(unified_diff.contains("+red"));
 }
 
+// `Character` is the old name of `Char`, and still works
 #[test]
+#[allow(deprecated)]
 fn test_character_level_diff() {
     let old_text = "testing123";
     let new_text = "testing456";
-    
+
     // Create a character-level diff
-    let diff = TextDiff::diff_with_granularity(old_text, new_text, DiffGranularity::Character).unwrap();
+    let diff =
+        TextDiff::diff_with_granularity(old_text, new_text, DiffGranularity::Character).unwrap();
     let snapshot = diff.snapshot();
-    
+
     // Ensure the diff detected the change
     assert!(snapshot.has_changes());
-    
+
     // Create a line-level diff for comparison
     let line_diff = TextDiff::diff(old_text, new_text).unwrap();
     let line_snapshot = line_diff.snapshot();
-    
+
     // Both should detect changes
     assert!(line_snapshot.has_changes());
-    
+
     // Get the unified diff output and check that it contains character-level changes
-    let unified_diff = TextDiff::unified_diff_with_granularity(old_text, new_text, 0, DiffGranularity::Character);
-    
+    let unified_diff =
+        TextDiff::unified_diff_with_granularity(old_text, new_text, 0, DiffGranularity::Character);
+
     // Print the unified diff for debugging
     println!("Unified diff: {}", unified_diff);
-    
+
     // The character-level diff should show only "123" and "456" as changed, not the entire string
-    assert!(unified_diff.contains("-1") && unified_diff.contains("-2") && unified_diff.contains("-3"),
-            "Expected to find '-1', '-2', '-3' in the diff");
-    assert!(unified_diff.contains("+4") && unified_diff.contains("+5") && unified_diff.contains("+6"),
-            "Expected to find '+4', '+5', '+6' in the diff");
+    assert!(
+        unified_diff.contains("-1") && unified_diff.contains("-2") && unified_diff.contains("-3"),
+        "Expected to find '-1', '-2', '-3' in the diff"
+    );
+    assert!(
+        unified_diff.contains("+4") && unified_diff.contains("+5") && unified_diff.contains("+6"),
+        "Expected to find '+4', '+5', '+6' in the diff"
+    );
 }
 
 #[test]
 fn test_whitespace_ignoring() {
     let old_text = "  This has  extra spaces   ";
     let new_text = "This has extra spaces";
-    
+
     // Normal diff should detect changes
     let normal_diff = TextDiff::diff(old_text, new_text).unwrap();
     let normal_snapshot = normal_diff.snapshot();
     assert!(normal_snapshot.has_changes());
-    
+
     // Whitespace-ignoring diff should not detect changes
     let ws_diff = DiffConfig::default()
         .ignore_whitespace(true)
         .diff(old_text, new_text)
         .unwrap();
-    
+
     let ws_snapshot = ws_diff.snapshot();
-    
+
     // Since we're ignoring whitespace, these should be considered the same
     // Note: This assertion might not always hold depending on how normalize_whitespace is implemented
     // If it treats all leading/trailing space as significant, this might need updating
-    assert!(!ws_snapshot.has_changes() || ws_snapshot.hunks()[0].status == buffer_diff::DiffHunkStatus::Unchanged);
-}
\ No newline at end of file
+    assert!(
+        !ws_snapshot.has_changes()
+            || ws_snapshot.hunks()[0].status == buffer_diff::DiffHunkStatus::Unchanged
+    );
+}
+
+#[test]
+fn test_whitespace_modes() {
+    use buffer_diff::DiffLineType::*;
+
+    let old_text = "fn main() {\n    let x = 1;\n    print(x);\n}\n";
+    let new_text = "fn main() {\n\tlet x = 1;   \n    print(x); \n}\n";
+
+    let line_types = |mode: WhitespaceMode| {
+        let diff = DiffConfig::default()
+            .ignore_whitespace(mode)
+            .diff(old_text, new_text)
+            .unwrap();
+        // Lines are compared normalized but kept as written
+        assert_eq!(diff.new_text().to_string(), new_text);
+        let snapshot = diff.snapshot();
+        snapshot
+            .hunks()
+            .iter()
+            .flat_map(|hunk| hunk.line_types.clone())
+            .filter(|line_type| *line_type != Both)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(line_types(WhitespaceMode::None).len(), 4);
+    // Only the reindented line differs once trailing whitespace is ignored
+    assert_eq!(line_types(WhitespaceMode::Trailing), vec![OldOnly, NewOnly]);
+    assert!(line_types(WhitespaceMode::All).is_empty());
+
+    // The unified diff prints the lines that differ as they were written
+    let unified = DiffConfig::default()
+        .ignore_whitespace(WhitespaceMode::Trailing)
+        .unified_diff(old_text, new_text);
+    assert!(unified.contains("-    let x = 1;\n+\tlet x = 1;   \n"));
+    assert!(unified.contains("     print(x);\n"));
+}
+
+#[test]
+fn test_whitespace_ignored_word_and_char_diffs_print_original_text() {
+    let config = DiffConfig::default().ignore_whitespace(WhitespaceMode::All);
+
+    // Whitespace is left out of the comparison but still printed
+    let unified = config
+        .clone()
+        .granularity(DiffGranularity::Word)
+        .unified_diff("let x = 1;\nfoo bar\n", "let x = 2;\nfoo  bar\n");
+    assert!(
+        unified.contains("-let x = 1;\n"),
+        "unified diff: {}",
+        unified
+    );
+    assert!(
+        unified.contains("+let x = 2;\n"),
+        "unified diff: {}",
+        unified
+    );
+    assert!(unified.contains(" foo bar\n"), "unified diff: {}", unified);
+
+    // Ignored whitespace is printed along with the character after it
+    let unified = config
+        .granularity(DiffGranularity::Char)
+        .unified_diff("a b\n", "a  c\n");
+    assert!(unified.starts_with(" a\n"), "unified diff: {}", unified);
+    assert!(unified.contains("- b\n"), "unified diff: {}", unified);
+    assert!(unified.contains("+  c\n"), "unified diff: {}", unified);
+}
+
+#[test]
+fn test_whitespace_only_lines() {
+    let config = DiffConfig::default().ignore_whitespace(WhitespaceMode::Trailing);
+
+    // A line that's empty after normalizing still matches a blank line...
+    let diff = config.diff("a\n   \nb\n", "a\n\nb\n").unwrap();
+    assert!(!diff.snapshot().has_changes());
+
+    // ...but not a missing one
+    let diff = config.diff("a\n   \nb\n", "a\nb\n").unwrap();
+    let snapshot = diff.snapshot();
+    assert_eq!(snapshot.deleted_lines(), 1);
+    assert_eq!(snapshot.added_lines(), 0);
+
+    // Whitespace-only files are still added and deleted files
+    let diff = config.diff("", "   ").unwrap();
+    assert_eq!(
+        diff.snapshot().hunks()[0].status,
+        buffer_diff::DiffHunkStatus::Added
+    );
+    let diff = config.diff("  \n", "").unwrap();
+    assert_eq!(
+        diff.snapshot().hunks()[0].status,
+        buffer_diff::DiffHunkStatus::Deleted
+    );
+}
+
+#[test]
+fn test_split_on_commas_diffs_csv_fields() {
+    use buffer_diff::DiffLineType::*;
+
+    let old_text = "42,alice,alice@example.com,30\n";
+    let new_text = "42,alice,alice@example.org,30\n";
+
+    let diff = DiffConfig::default()
+        .split_on(SplitMode::Commas)
+        .diff(old_text, new_text)
+        .unwrap();
+    let snapshot = diff.snapshot();
+
+    // Only the email field (record 2) changed, not the whole row
+    assert_eq!(snapshot.hunk_count(), 1);
+    let hunk = snapshot.hunk(0).unwrap();
+    assert_eq!(hunk.line_types, vec![Both, Both, OldOnly, NewOnly, Both]);
+    let deleted_text = diff.snapshot_with_deleted_text().deleted_text;
+    assert_eq!(deleted_text, vec![vec!["alice@example.com".to_string()]]);
+    assert_eq!(diff.new_text().line(2).to_string(), "alice@example.org\n");
+
+    // Diffing by lines replaces the whole row
+    let diff = DiffConfig::default().diff(old_text, new_text).unwrap();
+    let hunk = diff.snapshot().hunk(0).unwrap().clone();
+    assert_eq!(hunk.line_types, vec![OldOnly, NewOnly]);
+}
+
+#[test]
+fn test_split_on_custom_records() {
+    use buffer_diff::DiffLineType::*;
+
+    let split = SplitMode::custom(|text| text.split(';').map(str::to_string).collect());
+    let diff = DiffConfig::default()
+        .split_on(split)
+        .diff("a;b;c", "a;B;c")
+        .unwrap();
+
+    let hunk = diff.snapshot().hunk(0).unwrap().clone();
+    assert_eq!(hunk.line_types, vec![Both, OldOnly, NewOnly, Both]);
+}
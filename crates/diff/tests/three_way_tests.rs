@@ -0,0 +1,92 @@
+use buffer_diff::{BufferDiff, DiffConfig, DiffHunkRange, ThreeWayRegionKind};
+use std::ops::Range;
+
+const BASE: &str = "one\ntwo\nthree\nfour\nfive\n";
+
+/// A region as (kind, base lines, our lines, their lines)
+type Region = (ThreeWayRegionKind, Range<usize>, Range<usize>, Range<usize>);
+
+/// Diff both sides against `BASE` and summarize the regions
+fn regions(ours: &str, theirs: &str) -> Vec<Region> {
+    BufferDiff::three_way(BASE, ours, theirs, &DiffConfig::default())
+        .unwrap()
+        .regions
+        .into_iter()
+        .map(|region| {
+            (
+                region.kind,
+                region.base_range.to_range(),
+                region.ours_range.to_range(),
+                region.theirs_range.to_range(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_three_way_unchanged() {
+    use ThreeWayRegionKind::*;
+
+    assert_eq!(regions(BASE, BASE), vec![(Unchanged, 0..5, 0..5, 0..5)]);
+}
+
+#[test]
+fn test_three_way_clean_changes_on_each_side() {
+    use ThreeWayRegionKind::*;
+
+    // Ours edits line 2, theirs deletes line 5 and appends two lines
+    let ours = "one\nTWO\nthree\nfour\nfive\n";
+    let theirs = "one\ntwo\nthree\nfour\nsix\nseven\n";
+
+    assert_eq!(
+        regions(ours, theirs),
+        vec![
+            (Unchanged, 0..1, 0..1, 0..1),
+            (Ours, 1..2, 1..2, 1..2),
+            (Unchanged, 2..4, 2..4, 2..4),
+            (Theirs, 4..5, 4..5, 4..6),
+        ]
+    );
+
+    let diff = BufferDiff::three_way(BASE, ours, theirs, &DiffConfig::default()).unwrap();
+    assert!(!diff.has_conflicts());
+}
+
+#[test]
+fn test_three_way_overlapping_conflict() {
+    use ThreeWayRegionKind::*;
+
+    // Both sides rewrite line 3; theirs also drops line 4
+    let ours = "one\ntwo\nTHREE\nfour\nfive\n";
+    let theirs = "one\ntwo\n3\nfive\n";
+
+    assert_eq!(
+        regions(ours, theirs),
+        vec![
+            (Unchanged, 0..2, 0..2, 0..2),
+            (Conflict, 2..4, 2..4, 2..3),
+            (Unchanged, 4..5, 4..5, 3..4),
+        ]
+    );
+
+    let diff = BufferDiff::three_way(BASE, ours, theirs, &DiffConfig::default()).unwrap();
+    let conflicts: Vec<_> = diff.conflicts().collect();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].ours_range, DiffHunkRange::new(2, 2));
+}
+
+#[test]
+fn test_three_way_identical_changes_are_clean() {
+    use ThreeWayRegionKind::*;
+
+    let both = "one\ntwo\nTHREE\nfour\nfive\n";
+
+    assert_eq!(
+        regions(both, both),
+        vec![
+            (Unchanged, 0..2, 0..2, 0..2),
+            (Ours, 2..3, 2..3, 2..3),
+            (Unchanged, 3..5, 3..5, 3..5),
+        ]
+    );
+}
@@ -0,0 +1,205 @@
+use buffer_diff::{
+    BufferDiff, DiffConfig, DiffGranularity, DiffHunkSecondaryStatus, DiffHunkStatus, TextEdit,
+};
+use proptest::prelude::*;
+
+/// Numbered lines, so every line is distinct
+fn numbered_lines(count: usize) -> String {
+    (0..count).map(|i| format!("line {}\n", i)).collect()
+}
+
+/// Byte offset of the start of `line` in `text`
+fn line_offset(text: &str, line: usize) -> usize {
+    text.split_inclusive('\n').take(line).map(str::len).sum()
+}
+
+/// Apply an edit to a string, as `BufferDiff::update` applies it to the new text
+fn edited(text: &str, edit: &TextEdit) -> String {
+    let mut text = text.to_string();
+    text.replace_range(edit.range.clone(), &edit.new_text);
+    text
+}
+
+#[test]
+fn test_update_matches_full_diff() {
+    let old = numbered_lines(60);
+    let new = old
+        .replace("line 5\n", "line five\n")
+        .replace("line 40\n", "line forty\n");
+    let mut diff = BufferDiff::new(&old, &new).unwrap();
+    assert_eq!(diff.hunk_count(), 2);
+    let first_hunk = diff.hunk(0).unwrap().clone();
+
+    // Change a character near the second hunk
+    let offset = line_offset(&new, 41);
+    let edit = TextEdit::new(offset..offset + 1, "L");
+    diff.update(&edit).unwrap();
+
+    let expected_new = edited(&new, &edit);
+    assert_eq!(diff.new_text().to_string(), expected_new);
+    assert_eq!(
+        diff.hunks(),
+        BufferDiff::new(&old, &expected_new).unwrap().hunks()
+    );
+    // The distant hunk was left alone
+    assert_eq!(diff.hunk(0).unwrap(), &first_hunk);
+}
+
+#[test]
+fn test_update_shifts_later_hunks() {
+    let old = numbered_lines(60);
+    let new = old.replace("line 40\n", "line forty\n");
+    let mut diff = BufferDiff::new(&old, &new).unwrap();
+    let hunk = diff.hunk(0).unwrap().clone();
+
+    // Split line 2 in two, far above the hunk
+    let offset = line_offset(&new, 2) + 4;
+    diff.update(&TextEdit::new(offset..offset, "\n")).unwrap();
+
+    assert_eq!(diff.hunk_count(), 2);
+    let shifted = diff.hunk(1).unwrap();
+    assert_eq!(shifted.old_range, hunk.old_range);
+    assert_eq!(shifted.new_range.start, hunk.new_range.start + 1);
+    assert_eq!(shifted.line_types, hunk.line_types);
+}
+
+#[test]
+fn test_update_reverting_change_leaves_no_changes() {
+    let old = numbered_lines(20);
+    let new = old.replace("line 10\n", "line ten\n");
+    let mut diff = BufferDiff::new(&old, &new).unwrap();
+
+    let offset = line_offset(&new, 10) + 5;
+    let edit = TextEdit::new(offset..offset + 3, "10");
+    diff.update(&edit).unwrap();
+
+    assert_eq!(diff.new_text().to_string(), old);
+    assert_eq!(diff.hunk_count(), 1);
+    assert_eq!(diff.hunk(0).unwrap().status, DiffHunkStatus::Unchanged);
+    assert!(!diff.snapshot().has_changes());
+}
+
+#[test]
+fn test_update_falls_back_to_full_recompute() {
+    // A change on every fourth line, each its own hunk
+    let old = numbered_lines(100);
+    let new: String = old
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match i % 4 {
+            0 => format!("{} changed\n", line),
+            _ => format!("{}\n", line),
+        })
+        .collect();
+    let config = DiffConfig::default().context_lines(0);
+    let mut diff = config.diff(&old, &new).unwrap();
+    assert_eq!(diff.hunk_count(), 25);
+
+    // Replacing most of the text touches far more hunks than the limit
+    let edit = TextEdit::new(10..new.len() - 10, "gone\n");
+    diff.update(&edit).unwrap();
+
+    let expected_new = edited(&new, &edit);
+    assert_eq!(
+        diff.hunks(),
+        config.diff(&old, &expected_new).unwrap().hunks()
+    );
+    assert_eq!(diff.apply(&old).unwrap(), expected_new);
+}
+
+#[test]
+fn test_update_rejects_invalid_ranges() {
+    let mut diff = BufferDiff::new("a\n", "héllo\n").unwrap();
+
+    assert!(diff.update(&TextEdit::new(0..100, "x")).is_err());
+    #[allow(clippy::reversed_empty_ranges)]
+    let backwards = TextEdit::new(3..1, "x");
+    assert!(diff.update(&backwards).is_err());
+    // Byte 2 is inside the two-byte `é`
+    assert!(diff.update(&TextEdit::new(2..3, "x")).is_err());
+    assert_eq!(diff.new_text().to_string(), "héllo\n");
+}
+
+#[test]
+fn test_update_suppressed_diff() {
+    let old = numbered_lines(10);
+    let mut diff = DiffConfig::default().max_lines(5).diff(&old, &old).unwrap();
+
+    diff.update(&TextEdit::new(0..0, "new\n")).unwrap();
+    assert!(diff.is_suppressed());
+    assert_eq!(diff.hunk(0).unwrap().new_range.count, 11);
+}
+
+/// Short lines from a small alphabet, so texts share plenty of lines
+fn text() -> impl Strategy<Value = String> {
+    prop::collection::vec("[abc]{0,2}\n", 0..30).prop_map(|lines| lines.concat())
+}
+
+proptest! {
+    #[test]
+    fn prop_update_applies_edits(
+        old in text(),
+        new in text(),
+        start in 0.0f64..=1.0,
+        len in 0.0f64..=1.0,
+        inserted in "[ab\n]{0,4}",
+    ) {
+        // Pick an edit range within the new text; the texts are ASCII, so
+        // every offset is a character boundary
+        let start = (new.len() as f64 * start) as usize;
+        let end = start + ((new.len() - start) as f64 * len) as usize;
+        let edit = TextEdit::new(start..end, inserted);
+
+        let mut diff = BufferDiff::new(&old, &new).unwrap();
+        diff.update(&edit).unwrap();
+
+        let expected_new = edited(&new, &edit);
+        prop_assert_eq!(diff.new_text().to_string(), expected_new.clone());
+        prop_assert_eq!(diff.apply(&old).unwrap(), expected_new.clone());
+
+        let full = BufferDiff::new(&old, &expected_new).unwrap();
+        prop_assert_eq!(diff.snapshot().has_changes(), full.snapshot().has_changes());
+    }
+
+    #[test]
+    fn prop_update_reapplies_post_passes(
+        old in text(),
+        new in text(),
+        start in 0.0f64..=1.0,
+        len in 0.0f64..=1.0,
+        inserted in "[ab\n]{0,4}",
+    ) {
+        let start = (new.len() as f64 * start) as usize;
+        let end = start + ((new.len() - start) as f64 * len) as usize;
+        let edit = TextEdit::new(start..end, inserted);
+
+        let config = DiffConfig::default()
+            .context_lines(1)
+            .granularity(DiffGranularity::Word)
+            .max_hunks(3)
+            .detect_moves(true);
+        let mut diff = config.diff(&old, &new).unwrap();
+        diff.update(&edit).unwrap();
+
+        let expected_new = edited(&new, &edit);
+        prop_assert_eq!(diff.apply(&old).unwrap(), expected_new);
+        let hunks = diff.hunks();
+        prop_assert!(hunks.len() <= 3);
+
+        // Only the trailing hunk `max_hunks` coalesces goes without word ranges
+        for hunk in &hunks[..hunks.len() - 1] {
+            let ranges = hunk.word_ranges.as_ref();
+            prop_assert_eq!(ranges.map(Vec::len), Some(hunk.line_types.len()));
+        }
+
+        // Move tags point at each other, at their new indices
+        for (index, hunk) in hunks.iter().enumerate() {
+            if let DiffHunkSecondaryStatus::Moved { partner_index } = hunk.secondary_status {
+                prop_assert_eq!(
+                    hunks[partner_index].secondary_status,
+                    DiffHunkSecondaryStatus::Moved { partner_index: index }
+                );
+            }
+        }
+    }
+}
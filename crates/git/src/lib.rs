@@ -4,5 +4,8 @@
 mod repository;
 mod status;
 
-pub use repository::{Commit, Repository};
+pub use repository::{
+    AnnotatedFile, AnnotatedLine, BlameLine, BranchInfo, Commit, CommitFileDiff, LineAnnotation,
+    Repository, TreeEntry, TreeEntryKind,
+};
 pub use status::{FileStatus, StatusEntry, StatusKind, StatusList};
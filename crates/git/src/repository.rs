@@ -1,28 +1,203 @@
 use anyhow::{anyhow, Context, Result};
-use git2::{Diff, DiffOptions, Repository as Git2Repository, Sort};
+use git2::{
+    BlameOptions, BranchType, Delta, Diff, DiffFile, DiffFindOptions, DiffOptions, FileMode,
+    IndexAddOption, ObjectType, Patch, ReferenceType, Repository as Git2Repository, Signature,
+    Sort, TreeWalkMode, TreeWalkResult,
+};
 use std::path::{Path, PathBuf};
 
 use crate::status::{StatusEntry, StatusKind, StatusList};
 
 /// Represents a git commit
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Commit {
     /// The commit's SHA-1 hash
     pub id: String,
     /// The commit's short hash (first 7 characters)
     pub short_id: String,
-    /// The commit message
+    /// The commit message's summary (its first line)
     pub message: String,
+    /// The full commit message, including the summary line
+    pub body: String,
     /// The commit author name
     pub author_name: String,
     /// The commit author email
     pub author_email: String,
-    /// The commit timestamp (seconds since epoch)
+    /// The author timestamp (seconds since epoch): when the change was
+    /// originally made, which differs from `time` after a rebase or cherry-pick
+    pub author_time: i64,
+    /// The commit timestamp (seconds since epoch), the committer's time
     pub time: i64,
+    /// The committer name
+    pub committer_name: String,
+    /// The committer email
+    pub committer_email: String,
+    /// The committer timestamp (seconds since epoch), the same as `time`
+    pub committer_time: i64,
     /// Parent commit IDs
     pub parent_ids: Vec<String>,
 }
 
+impl Commit {
+    /// Copy the details of a libgit2 commit
+    fn from_git(commit: &git2::Commit) -> Self {
+        let oid = commit.id();
+        let body = commit.message().unwrap_or("").to_string();
+        let message = body.lines().next().unwrap_or("").to_string();
+
+        let author = commit.author();
+        let committer = commit.committer();
+
+        Commit {
+            id: oid.to_string(),
+            short_id: format!("{:.7}", oid),
+            message,
+            body,
+            author_name: author.name().unwrap_or("Unknown").to_string(),
+            author_email: author.email().unwrap_or("").to_string(),
+            author_time: author.when().seconds(),
+            time: commit.time().seconds(),
+            committer_name: committer.name().unwrap_or("Unknown").to_string(),
+            committer_email: committer.email().unwrap_or("").to_string(),
+            committer_time: committer.when().seconds(),
+            parent_ids: commit.parent_ids().map(|id| id.to_string()).collect(),
+        }
+    }
+}
+
+/// How a line of a file changed in a commit, relative to the commit's first parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineAnnotation {
+    /// The line was added or modified by the commit
+    Added,
+    /// The line is the same as in the parent
+    Unchanged,
+}
+
+/// A single line of an annotated file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotatedLine {
+    /// The line number (1-based)
+    pub line_number: usize,
+    /// The line content, without its line ending
+    pub content: String,
+    /// How the line changed in the commit
+    pub annotation: LineAnnotation,
+}
+
+/// The full content of a file at a commit, with each line tagged by how it changed
+#[derive(Debug, Clone)]
+pub struct AnnotatedFile {
+    /// The file's path relative to the repository root
+    pub path: String,
+    /// Every line of the file at the commit
+    pub lines: Vec<AnnotatedLine>,
+}
+
+impl AnnotatedFile {
+    /// Iterate over the lines added or modified by the commit
+    pub fn added_lines(&self) -> impl Iterator<Item = &AnnotatedLine> {
+        self.lines
+            .iter()
+            .filter(|line| line.annotation == LineAnnotation::Added)
+    }
+}
+
+/// A line of a file with the commit that last changed it, from `Repository::blame`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    /// The line number (1-based)
+    pub line: usize,
+    /// The SHA-1 hash of the commit that last changed the line, or
+    /// `BlameLine::UNCOMMITTED_ID` for a line changed in the working tree
+    pub commit_id: String,
+    /// The commit's short hash (first 7 characters)
+    pub short_id: String,
+    /// The commit author name
+    pub author_name: String,
+    /// The commit author timestamp (seconds since epoch), 0 for uncommitted lines
+    pub time: i64,
+}
+
+impl BlameLine {
+    /// Commit id of lines that aren't committed yet, as `git blame` shows them
+    pub const UNCOMMITTED_ID: &'static str = "0000000000000000000000000000000000000000";
+
+    /// A line changed in the working tree
+    fn uncommitted(line: usize) -> Self {
+        BlameLine {
+            line,
+            commit_id: Self::UNCOMMITTED_ID.to_string(),
+            short_id: Self::UNCOMMITTED_ID[..7].to_string(),
+            author_name: "Not Committed Yet".to_string(),
+            time: 0,
+        }
+    }
+
+    /// Whether the line is changed in the working tree rather than committed
+    pub fn is_uncommitted(&self) -> bool {
+        self.commit_id == Self::UNCOMMITTED_ID
+    }
+}
+
+/// The kind of an entry in a commit's tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeEntryKind {
+    /// A directory (a git tree object)
+    Directory,
+    /// A file or symlink (a git blob object)
+    File,
+}
+
+/// A file or directory present in a commit's tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEntry {
+    /// The entry's path relative to the repository root, using `/` separators
+    pub path: String,
+    /// Whether the entry is a file or a directory
+    pub kind: TreeEntryKind,
+    /// The SHA-1 hash of the entry's object
+    pub id: String,
+}
+
+impl TreeEntry {
+    /// The entry's name, the last component of its path
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// A local or remote-tracking branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchInfo {
+    /// The branch's short name, e.g. `main` or `origin/main` for a remote-tracking branch
+    pub name: String,
+    /// Whether HEAD points to this branch
+    pub is_head: bool,
+    /// Whether this is a remote-tracking branch
+    pub is_remote: bool,
+    /// The short name of the branch this one tracks, if it has an upstream
+    pub upstream: Option<String>,
+}
+
+/// A file changed by a commit, with its content before and after
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitFileDiff {
+    /// The file's path after the commit (its old path if it was deleted)
+    pub path: String,
+    /// The file's path before the commit, if it was renamed or copied
+    pub old_path: Option<String>,
+    /// How the file changed
+    pub status: StatusKind,
+    /// The content before the commit, or `None` if the file was added or
+    /// wasn't a blob (a submodule)
+    pub old_content: Option<String>,
+    /// The content after the commit, or `None` if the file was deleted or
+    /// isn't a blob (a submodule)
+    pub new_content: Option<String>,
+}
+
 /// A wrapper around git2::Repository with additional functionality
 pub struct Repository {
     /// The underlying git2 repository
@@ -54,6 +229,62 @@ impl Repository {
         &self.work_dir
     }
 
+    /// Whether HEAD points to a branch with no commits yet, as in a freshly initialized repository
+    pub fn is_head_unborn(&self) -> bool {
+        match self.inner.head() {
+            Ok(_) => false,
+            Err(e) => matches!(
+                e.code(),
+                git2::ErrorCode::UnbornBranch | git2::ErrorCode::NotFound
+            ),
+        }
+    }
+
+    /// Get the name of the branch HEAD points to, or `None` if HEAD is detached
+    ///
+    /// A freshly initialized repository reports the branch its first commit
+    /// will go on, even though the branch doesn't exist yet.
+    pub fn current_branch(&self) -> Result<Option<String>> {
+        let head = self.inner.find_reference("HEAD")?;
+        Ok(head
+            .symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .map(str::to_string))
+    }
+
+    /// List the local branches followed by the remote-tracking branches, each
+    /// sorted by name
+    ///
+    /// Symbolic remote refs such as `origin/HEAD` are skipped, as are branches
+    /// whose names aren't valid UTF-8. A repository without commits has no
+    /// branches yet.
+    pub fn branches(&self) -> Result<Vec<BranchInfo>> {
+        let mut branches = Vec::new();
+        for branch in self.inner.branches(None)? {
+            let (branch, branch_type) = branch?;
+            if branch.get().kind() == Some(ReferenceType::Symbolic) {
+                continue;
+            }
+            let Some(name) = branch.name()? else {
+                continue;
+            };
+            let upstream = match branch.upstream() {
+                Ok(upstream) => upstream.name()?.map(str::to_string),
+                Err(_) => None,
+            };
+
+            branches.push(BranchInfo {
+                name: name.to_string(),
+                is_head: branch.is_head(),
+                is_remote: branch_type == BranchType::Remote,
+                upstream,
+            });
+        }
+
+        branches.sort_by(|a, b| (a.is_remote, &a.name).cmp(&(b.is_remote, &b.name)));
+        Ok(branches)
+    }
+
     /// Get the status of the repository
     pub fn status(&self) -> Result<StatusList> {
         let mut opts = git2::StatusOptions::new();
@@ -115,8 +346,7 @@ impl Repository {
     /// Get staged (index) changes
     pub fn staged_changes(&self) -> Result<Vec<StatusEntry>> {
         let mut opts = git2::StatusOptions::new();
-        opts.include_untracked(false)
-            .include_ignored(false);
+        opts.include_untracked(false).include_ignored(false);
 
         let status = self.inner.statuses(Some(&mut opts))?;
 
@@ -143,6 +373,95 @@ impl Repository {
         Ok(entries)
     }
 
+    /// Stage a file's working tree state in the index
+    ///
+    /// A file missing from the working tree is staged as deleted
+    pub fn stage(&self, path: &str) -> Result<()> {
+        let mut index = self.index()?;
+        if self.work_dir.join(path).exists() {
+            index
+                .add_path(Path::new(path))
+                .with_context(|| format!("Failed to stage {}", path))?;
+        } else {
+            index
+                .remove_path(Path::new(path))
+                .with_context(|| format!("Failed to stage deletion of {}", path))?;
+        }
+        index.write()?;
+        Ok(())
+    }
+
+    /// Reset a file's index entry to HEAD, keeping its working tree changes
+    ///
+    /// Files that aren't in HEAD (or any file before the first commit) are removed from
+    /// the index, leaving them untracked
+    pub fn unstage(&self, path: &str) -> Result<()> {
+        let head = if self.is_head_unborn() {
+            None
+        } else {
+            Some(self.inner.head()?.peel_to_commit()?)
+        };
+        // Reset works on the repository's shared index, so refresh that first
+        self.index()?;
+        self.inner
+            .reset_default(head.as_ref().map(|commit| commit.as_object()), [path])
+            .with_context(|| format!("Failed to unstage {}", path))?;
+        Ok(())
+    }
+
+    /// Stage every change in the working tree, including new and deleted files
+    pub fn stage_all(&self) -> Result<()> {
+        let mut index = self.index()?;
+        index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+        // `add_all` doesn't pick up deletions
+        index.update_all(["*"], None)?;
+        index.write()?;
+        Ok(())
+    }
+
+    /// Commit the index on top of HEAD, returning the new commit's id
+    ///
+    /// `signature` is used as both author and committer. Without one, the
+    /// repository's configured `user.name` and `user.email` are used. On an
+    /// unborn HEAD this creates the first commit, with no parent.
+    pub fn commit(&self, message: &str, signature: Option<Signature>) -> Result<String> {
+        let signature = match signature {
+            Some(signature) => signature,
+            None => self
+                .inner
+                .signature()
+                .context("No signature given, and user.name and user.email aren't configured")?,
+        };
+
+        let tree = self.inner.find_tree(self.index()?.write_tree()?)?;
+        let parent = if self.is_head_unborn() {
+            None
+        } else {
+            Some(self.inner.head()?.peel_to_commit()?)
+        };
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+        let id = self
+            .inner
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .context("Failed to create commit")?;
+        Ok(id.to_string())
+    }
+
+    /// The repository's index, reloaded so changes made by other processes aren't overwritten
+    fn index(&self) -> Result<git2::Index> {
+        let mut index = self.inner.index()?;
+        index.read(true)?;
+        Ok(index)
+    }
+
     /// Get the content of a file from the repository HEAD
     pub fn get_head_content(&self, path: &str) -> Result<Option<String>> {
         self.get_content_at_revision("HEAD", path)
@@ -150,6 +469,16 @@ impl Repository {
 
     /// Get the content of a file at a specific commit/revision
     pub fn get_content_at_revision(&self, revision: &str, path: &str) -> Result<Option<String>> {
+        Ok(self
+            .get_blob_at_revision(revision, path)?
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string()))
+    }
+
+    /// Get the raw bytes of a file at a specific commit/revision
+    ///
+    /// Unlike `get_content_at_revision`, the content isn't decoded as text, so
+    /// this is the one to use for binary files such as images.
+    pub fn get_blob_at_revision(&self, revision: &str, path: &str) -> Result<Option<Vec<u8>>> {
         let obj = match self.inner.revparse_single(revision) {
             Ok(obj) => obj,
             Err(_) => return Ok(None),
@@ -164,9 +493,177 @@ impl Repository {
         };
 
         let blob = entry.to_object(&self.inner)?.peel_to_blob()?;
-        let content = String::from_utf8_lossy(blob.content()).to_string();
 
-        Ok(Some(content))
+        Ok(Some(blob.content().to_vec()))
+    }
+
+    /// Find the commit that last changed each line of a file
+    ///
+    /// With `at` set, blames the file as of that revision. Otherwise blames the
+    /// working tree file against HEAD, so lines changed since HEAD (or every
+    /// line, for a file HEAD doesn't have) are reported as uncommitted. Fails
+    /// if the file doesn't exist at the revision or in the working tree.
+    pub fn blame(&self, path: &str, at: Option<&str>) -> Result<Vec<BlameLine>> {
+        let (content, newest) = match at {
+            Some(revision) => {
+                let commit = self.inner.revparse_single(revision)?.peel_to_commit()?;
+                let content = self
+                    .get_blob_at_revision(revision, path)?
+                    .with_context(|| format!("File {} not found at {}", path, revision))?;
+                (content, Some(commit))
+            }
+            None => {
+                let full_path = self.work_dir.join(path);
+                let content = std::fs::read(&full_path)
+                    .with_context(|| format!("Failed to read file {}", full_path.display()))?;
+                let head = if self.is_head_unborn() {
+                    None
+                } else {
+                    Some(self.inner.head()?.peel_to_commit()?)
+                };
+                (content, head)
+            }
+        };
+
+        // Blame the committed history, unless the file only exists in the working tree
+        let committed = match newest {
+            Some(commit) if commit.tree()?.get_path(Path::new(path)).is_ok() => {
+                let mut opts = BlameOptions::new();
+                opts.newest_commit(commit.id());
+                Some(self.inner.blame_file(Path::new(path), Some(&mut opts))?)
+            }
+            _ => None,
+        };
+        // Lines changed in the working tree show up with a zero commit id
+        let working = match (&committed, at) {
+            (Some(blame), None) => Some(blame.blame_buffer(&content)?),
+            _ => None,
+        };
+        let blame = working.as_ref().or(committed.as_ref());
+
+        let line_count = String::from_utf8_lossy(&content).lines().count();
+        let lines = (1..=line_count)
+            .map(|line| match blame.and_then(|blame| blame.get_line(line)) {
+                Some(hunk) if !hunk.final_commit_id().is_zero() => {
+                    let id = hunk.final_commit_id();
+                    let author = hunk.final_signature();
+                    BlameLine {
+                        line,
+                        commit_id: id.to_string(),
+                        short_id: format!("{:.7}", id),
+                        author_name: author.name().unwrap_or("Unknown").to_string(),
+                        time: author.when().seconds(),
+                    }
+                }
+                _ => BlameLine::uncommitted(line),
+            })
+            .collect();
+
+        Ok(lines)
+    }
+
+    /// Get the commits that changed lines `start_line..=end_line` of a file, newest first
+    ///
+    /// Like `git log -L`: the range is taken from the file at HEAD and traced back through
+    /// each commit's diff against its first parent, so it follows the lines as edits above
+    /// them shift them around. Line numbers are 1-based. Tracing stops at the commit that
+    /// added the file, or once every line in the range is traced to the commit adding it
+    pub fn line_log(
+        &self,
+        path: &str,
+        start_line: usize,
+        end_line: usize,
+        limit: Option<usize>,
+    ) -> Result<Vec<Commit>> {
+        let mut commit = self.inner.head()?.peel_to_commit()?;
+        let mut blob_id = commit
+            .tree()?
+            .get_path(Path::new(path))
+            .with_context(|| format!("File {} not found at HEAD", path))?
+            .id();
+
+        let line_count = String::from_utf8_lossy(self.inner.find_blob(blob_id)?.content())
+            .lines()
+            .count();
+        if start_line == 0 || end_line < start_line || end_line > line_count {
+            return Err(anyhow!(
+                "Invalid line range {}..={} for {} ({} lines)",
+                start_line,
+                end_line,
+                path,
+                line_count
+            ));
+        }
+
+        // Only changed lines matter for tracing, so skip context
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.context_lines(0);
+
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut commits = Vec::new();
+        let mut range = Some((start_line, end_line));
+
+        while let Some((start, end)) = range {
+            if commits.len() >= limit {
+                break;
+            }
+
+            let parent = if commit.parent_count() > 0 {
+                Some(commit.parent(0)?)
+            } else {
+                None
+            };
+            let parent_blob_id = match &parent {
+                Some(parent) => parent
+                    .tree()?
+                    .get_path(Path::new(path))
+                    .ok()
+                    .map(|e| e.id()),
+                None => None,
+            };
+
+            match parent_blob_id {
+                // The file is unchanged in this commit
+                Some(id) if id == blob_id => {}
+                Some(id) => {
+                    let old_blob = self.inner.find_blob(id)?;
+                    let new_blob = self.inner.find_blob(blob_id)?;
+                    let patch = Patch::from_blobs(
+                        &old_blob,
+                        Some(Path::new(path)),
+                        &new_blob,
+                        Some(Path::new(path)),
+                        Some(&mut diff_opts),
+                    )?;
+                    let hunks = (0..patch.num_hunks())
+                        .map(|i| patch.hunk(i).map(|(hunk, _)| hunk))
+                        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+                    if hunks
+                        .iter()
+                        .any(|hunk| hunk_touches_range(hunk, start, end))
+                    {
+                        commits.push(Commit::from_git(&commit));
+                    }
+                    let start = map_line_to_parent(&hunks, start, true);
+                    let end = map_line_to_parent(&hunks, end, false);
+                    range = (start <= end).then_some((start, end));
+                    blob_id = id;
+                }
+                // The file was added in this commit
+                None => {
+                    commits.push(Commit::from_git(&commit));
+                    range = None;
+                }
+            }
+
+            match parent {
+                Some(parent) => commit = parent,
+                None => break,
+            }
+        }
+
+        Ok(commits)
     }
 
     /// Get the content of a file from the working directory
@@ -219,6 +716,47 @@ impl Repository {
         Ok(diff)
     }
 
+    /// Get the files that differ between two revisions, with how each changed
+    ///
+    /// Deleted files are listed under their old path, everything else under
+    /// its new path. Renames aren't detected, so a renamed file shows up as
+    /// deleted at its old path and added at its new one.
+    pub fn diff_revisions(
+        &self,
+        old_revision: &str,
+        new_revision: &str,
+    ) -> Result<Vec<StatusEntry>> {
+        let old_tree = self.inner.revparse_single(old_revision)?.peel_to_tree()?;
+        let new_tree = self.inner.revparse_single(new_revision)?.peel_to_tree()?;
+
+        let mut diff_opts = DiffOptions::new();
+        let diff =
+            self.inner
+                .diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut diff_opts))?;
+
+        let entries = diff
+            .deltas()
+            .filter_map(|delta| {
+                let kind = match delta.status() {
+                    Delta::Added => StatusKind::Added,
+                    Delta::Deleted => StatusKind::Deleted,
+                    Delta::Modified | Delta::Typechange => StatusKind::Modified,
+                    Delta::Renamed => StatusKind::Renamed,
+                    Delta::Copied => StatusKind::Copied,
+                    _ => return None,
+                };
+                let file = match kind {
+                    StatusKind::Deleted => delta.old_file(),
+                    _ => delta.new_file(),
+                };
+                let path = file.path()?.to_string_lossy().to_string();
+                Some(StatusEntry { path, kind })
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
     /// Get the diff between the index and the working directory for a file
     pub fn diff_index_to_workdir(&self, path: &str) -> Result<Diff<'_>> {
         let mut diff_opts = DiffOptions::new();
@@ -249,7 +787,13 @@ impl Repository {
     }
 
     /// Get the commit history, optionally limited to a maximum count
+    ///
+    /// Returns an empty history if the repository has no commits yet
     pub fn log(&self, max_count: Option<usize>) -> Result<Vec<Commit>> {
+        if self.is_head_unborn() {
+            return Ok(Vec::new());
+        }
+
         let mut revwalk = self.inner.revwalk()?;
         revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
         revwalk.push_head()?;
@@ -262,66 +806,26 @@ impl Repository {
                 break;
             }
 
-            let oid = oid_result?;
-            let commit = self.inner.find_commit(oid)?;
-
-            let message = commit
-                .message()
-                .unwrap_or("")
-                .lines()
-                .next()
-                .unwrap_or("")
-                .to_string();
-
-            let author = commit.author();
-            let author_name = author.name().unwrap_or("Unknown").to_string();
-            let author_email = author.email().unwrap_or("").to_string();
-
-            let parent_ids: Vec<String> = commit.parent_ids().map(|id| id.to_string()).collect();
-
-            commits.push(Commit {
-                id: oid.to_string(),
-                short_id: format!("{:.7}", oid),
-                message,
-                author_name,
-                author_email,
-                time: commit.time().seconds(),
-                parent_ids,
-            });
+            let commit = self.inner.find_commit(oid_result?)?;
+            commits.push(Commit::from_git(&commit));
         }
 
         Ok(commits)
     }
 
+    /// Get the commit history as a JSON array of commits, newest first
+    ///
+    /// Same commits as `log`; timestamps are serialized as integer seconds
+    #[cfg(feature = "serde")]
+    pub fn log_json(&self, max_count: Option<usize>) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.log(max_count)?)?)
+    }
+
     /// Get a specific commit by its ID (can be short or full hash)
     pub fn get_commit(&self, id: &str) -> Result<Commit> {
         let obj = self.inner.revparse_single(id)?;
         let commit = obj.peel_to_commit()?;
-        let oid = commit.id();
-
-        let message = commit
-            .message()
-            .unwrap_or("")
-            .lines()
-            .next()
-            .unwrap_or("")
-            .to_string();
-
-        let author = commit.author();
-        let author_name = author.name().unwrap_or("Unknown").to_string();
-        let author_email = author.email().unwrap_or("").to_string();
-
-        let parent_ids: Vec<String> = commit.parent_ids().map(|id| id.to_string()).collect();
-
-        Ok(Commit {
-            id: oid.to_string(),
-            short_id: format!("{:.7}", oid),
-            message,
-            author_name,
-            author_email,
-            time: commit.time().seconds(),
-            parent_ids,
-        })
+        Ok(Commit::from_git(&commit))
     }
 
     /// Get the files changed in a commit
@@ -358,4 +862,237 @@ impl Repository {
 
         Ok(files)
     }
+
+    /// Get every file a commit changed, relative to its first parent, with the
+    /// file's content on both sides
+    ///
+    /// Renames are detected by content similarity, as `git log -M` does, and
+    /// report the old path in `old_path`. A root commit has no parent, so it
+    /// is diffed by `commit_full_content` and all of its files are added.
+    pub fn diff_commit(&self, commit_id: &str) -> Result<Vec<CommitFileDiff>> {
+        let commit = self.inner.revparse_single(commit_id)?.peel_to_commit()?;
+        if commit.parent_count() == 0 {
+            return self.commit_full_content(commit_id);
+        }
+        self.diff_trees(Some(&commit.parent(0)?.tree()?), &commit.tree()?)
+    }
+
+    /// Get every file in a commit as added, by diffing its tree against the
+    /// empty tree
+    ///
+    /// Unlike `diff_commit`, this ignores the commit's parents: every file in
+    /// the tree is listed, with its whole content as `new_content`. For a root
+    /// commit the two are the same.
+    pub fn commit_full_content(&self, commit_id: &str) -> Result<Vec<CommitFileDiff>> {
+        let commit = self.inner.revparse_single(commit_id)?.peel_to_commit()?;
+        self.diff_trees(None, &commit.tree()?)
+    }
+
+    /// Diff two trees into changed files with their content, for `diff_commit`
+    /// and `commit_full_content`; a missing old tree is the empty tree
+    fn diff_trees(
+        &self,
+        old_tree: Option<&git2::Tree>,
+        new_tree: &git2::Tree,
+    ) -> Result<Vec<CommitFileDiff>> {
+        let mut diff_opts = DiffOptions::new();
+        let mut diff =
+            self.inner
+                .diff_tree_to_tree(old_tree, Some(new_tree), Some(&mut diff_opts))?;
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+        let mut files = Vec::new();
+        for delta in diff.deltas() {
+            let status = match delta.status() {
+                Delta::Added => StatusKind::Added,
+                Delta::Deleted => StatusKind::Deleted,
+                Delta::Modified | Delta::Typechange => StatusKind::Modified,
+                Delta::Renamed => StatusKind::Renamed,
+                Delta::Copied => StatusKind::Copied,
+                _ => continue,
+            };
+            let file_path = |file: DiffFile| file.path().map(|p| p.to_string_lossy().to_string());
+            let (Some(old_path), Some(new_path)) =
+                (file_path(delta.old_file()), file_path(delta.new_file()))
+            else {
+                continue;
+            };
+
+            files.push(CommitFileDiff {
+                path: match status {
+                    StatusKind::Deleted => old_path.clone(),
+                    _ => new_path.clone(),
+                },
+                old_path: (old_path != new_path).then_some(old_path),
+                status,
+                old_content: self.diff_file_content(&delta.old_file())?,
+                new_content: self.diff_file_content(&delta.new_file())?,
+            });
+        }
+
+        Ok(files)
+    }
+
+    /// Read one side of a diff delta, or `None` if the file doesn't exist there
+    ///
+    /// Submodules are gitlinks to commits in another repository, with no blob
+    /// to read, so they have no content either.
+    fn diff_file_content(&self, file: &DiffFile) -> Result<Option<String>> {
+        if !file.exists() || matches!(file.mode(), FileMode::Commit | FileMode::Tree) {
+            return Ok(None);
+        }
+        let blob = self.inner.find_blob(file.id())?;
+        Ok(Some(String::from_utf8_lossy(blob.content()).to_string()))
+    }
+
+    /// List every file and directory in a commit's tree, not just the changed ones
+    ///
+    /// Entries are listed in pre-order: each directory comes before its
+    /// contents, and the entries of a directory are sorted by name as git
+    /// stores them. Submodules are skipped.
+    pub fn tree_entries(&self, commit_id: &str) -> Result<Vec<TreeEntry>> {
+        let commit = self.inner.revparse_single(commit_id)?.peel_to_commit()?;
+        let tree = commit.tree()?;
+
+        let mut entries = Vec::new();
+        tree.walk(TreeWalkMode::PreOrder, |parent, entry| {
+            let kind = match entry.kind() {
+                Some(ObjectType::Tree) => TreeEntryKind::Directory,
+                Some(ObjectType::Blob) => TreeEntryKind::File,
+                _ => return TreeWalkResult::Skip,
+            };
+            let Some(name) = entry.name() else {
+                return TreeWalkResult::Skip;
+            };
+
+            // `parent` is the directory's path with a trailing slash, or empty at the root
+            entries.push(TreeEntry {
+                path: format!("{}{}", parent, name),
+                kind,
+                id: entry.id().to_string(),
+            });
+            TreeWalkResult::Ok
+        })?;
+
+        Ok(entries)
+    }
+
+    /// Get the full content of a file at a commit, with each line tagged as
+    /// added or unchanged relative to the commit's first parent
+    ///
+    /// If the file doesn't exist in the parent (or the commit has no parent),
+    /// every line is tagged as added.
+    pub fn annotated_file_at_commit(&self, id: &str, path: &str) -> Result<AnnotatedFile> {
+        let commit = self.inner.revparse_single(id)?.peel_to_commit()?;
+
+        let new_blob = commit
+            .tree()?
+            .get_path(Path::new(path))
+            .with_context(|| format!("File {} not found in commit {}", path, id))?
+            .to_object(&self.inner)?
+            .peel_to_blob()?;
+
+        let old_blob = if commit.parent_count() > 0 {
+            match commit.parent(0)?.tree()?.get_path(Path::new(path)) {
+                Ok(entry) => Some(entry.to_object(&self.inner)?.peel_to_blob()?),
+                Err(_) => None,
+            }
+        } else {
+            None
+        };
+
+        // Only changed lines are needed, so skip context
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.context_lines(0);
+
+        let patch = Patch::from_buffers(
+            old_blob
+                .as_ref()
+                .map(|blob| blob.content())
+                .unwrap_or_default(),
+            Some(Path::new(path)),
+            new_blob.content(),
+            Some(Path::new(path)),
+            Some(&mut diff_opts),
+        )?;
+
+        let mut added = std::collections::HashSet::new();
+        for hunk_idx in 0..patch.num_hunks() {
+            for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
+                let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                if line.origin() == '+' {
+                    if let Some(line_number) = line.new_lineno() {
+                        added.insert(line_number as usize);
+                    }
+                }
+            }
+        }
+
+        let content = String::from_utf8_lossy(new_blob.content());
+        let lines = content
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let line_number = i + 1;
+                AnnotatedLine {
+                    line_number,
+                    content: line.to_string(),
+                    annotation: if added.contains(&line_number) {
+                        LineAnnotation::Added
+                    } else {
+                        LineAnnotation::Unchanged
+                    },
+                }
+            })
+            .collect();
+
+        Ok(AnnotatedFile {
+            path: path.to_string(),
+            lines,
+        })
+    }
+}
+
+/// Whether a zero-context diff hunk changes any line in `start..=end` of the new file
+fn hunk_touches_range(hunk: &git2::DiffHunk, start: usize, end: usize) -> bool {
+    let new_start = hunk.new_start() as usize;
+    let new_lines = hunk.new_lines() as usize;
+    if new_lines == 0 {
+        // A pure deletion sits after line `new_start`, so it only counts inside the range
+        start <= new_start && new_start < end
+    } else {
+        new_start <= end && start < new_start + new_lines
+    }
+}
+
+/// Map a line of the new file to the old file through zero-context diff hunks
+///
+/// A changed line maps to the first (or, if not `first`, the last) old line of its hunk.
+/// For a pure insertion that is the line after (or before) the insertion point, so a
+/// range made only of inserted lines maps to an empty range
+fn map_line_to_parent(hunks: &[git2::DiffHunk], line: usize, first: bool) -> usize {
+    let mut offset = 0isize;
+    for hunk in hunks {
+        let old_start = hunk.old_start() as usize;
+        let old_lines = hunk.old_lines() as usize;
+        let new_start = hunk.new_start() as usize;
+        let new_lines = hunk.new_lines() as usize;
+
+        if new_lines == 0 {
+            if new_start >= line {
+                break;
+            }
+        } else if new_start > line {
+            break;
+        } else if line < new_start + new_lines {
+            return match (first, old_lines) {
+                (true, 0) => old_start + 1,
+                (true, _) => old_start,
+                (false, 0) => old_start,
+                (false, _) => old_start + old_lines - 1,
+            };
+        }
+        offset += old_lines as isize - new_lines as isize;
+    }
+    line.saturating_add_signed(offset)
 }
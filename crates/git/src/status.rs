@@ -92,6 +92,11 @@ impl StatusKind {
 
         StatusKind::Unknown
     }
+
+    /// Whether the file differs from the repository, i.e. isn't ignored or of unknown status
+    pub fn is_changed(self) -> bool {
+        !matches!(self, StatusKind::Ignored | StatusKind::Unknown)
+    }
 }
 
 /// Entry in a status list
@@ -0,0 +1,654 @@
+use git::{
+    BlameLine, BranchInfo, Commit, LineAnnotation, Repository, StatusEntry, StatusKind,
+    TreeEntryKind,
+};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Create an empty repository in a temporary directory
+fn init_repo() -> (TempDir, git2::Repository) {
+    let temp = TempDir::new().unwrap();
+    let repo = git2::Repository::init(temp.path()).unwrap();
+    (temp, repo)
+}
+
+/// Write files into the working directory and commit them, returning the commit id
+fn commit_files(repo: &git2::Repository, files: &[(&str, &str)], message: &str) -> String {
+    let work_dir = repo.workdir().unwrap().to_path_buf();
+    let mut index = repo.index().unwrap();
+
+    for (path, content) in files {
+        let full_path = work_dir.join(path);
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(&full_path, content).unwrap();
+        index.add_path(Path::new(path)).unwrap();
+    }
+    index.write().unwrap();
+
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .unwrap()
+    .to_string()
+}
+
+#[test]
+fn test_annotated_file_at_commit() {
+    let (temp, git_repo) = init_repo();
+    commit_files(
+        &git_repo,
+        &[("src/lib.rs", "one\ntwo\nthree\nfour\nfive\n")],
+        "Initial",
+    );
+    let id = commit_files(
+        &git_repo,
+        &[("src/lib.rs", "one\nTWO\nthree\nfour\nfive\nsix\n")],
+        "Change lines",
+    );
+
+    let repo = Repository::open(temp.path()).unwrap();
+    let file = repo.annotated_file_at_commit(&id, "src/lib.rs").unwrap();
+
+    assert_eq!(file.path, "src/lib.rs");
+    assert_eq!(file.lines.len(), 6);
+
+    let contents: Vec<&str> = file
+        .lines
+        .iter()
+        .map(|line| line.content.as_str())
+        .collect();
+    assert_eq!(contents, vec!["one", "TWO", "three", "four", "five", "six"]);
+
+    let added: Vec<usize> = file.added_lines().map(|line| line.line_number).collect();
+    assert_eq!(added, vec![2, 6]);
+    assert_eq!(file.lines[0].annotation, LineAnnotation::Unchanged);
+}
+
+#[test]
+fn test_annotated_file_in_root_commit_is_all_added() {
+    let (temp, git_repo) = init_repo();
+    let id = commit_files(&git_repo, &[("README.md", "hello\nworld\n")], "Initial");
+
+    let repo = Repository::open(temp.path()).unwrap();
+    let file = repo.annotated_file_at_commit(&id, "README.md").unwrap();
+
+    assert!(file
+        .lines
+        .iter()
+        .all(|line| line.annotation == LineAnnotation::Added));
+    assert_eq!(file.lines.len(), 2);
+
+    assert!(repo.annotated_file_at_commit(&id, "missing.txt").is_err());
+}
+
+#[test]
+fn test_empty_repository_has_no_history() {
+    let (temp, _git_repo) = init_repo();
+    fs::write(temp.path().join("new.txt"), "hello\n").unwrap();
+
+    let repo = Repository::open(temp.path()).unwrap();
+
+    assert!(repo.is_head_unborn());
+    assert!(repo.log(None).unwrap().is_empty());
+    assert_eq!(repo.get_head_content("new.txt").unwrap(), None);
+
+    let status = repo.status().unwrap();
+    assert_eq!(status.entries.len(), 1);
+    assert_eq!(status.entries[0].path, "new.txt");
+}
+
+#[test]
+fn test_head_is_born_after_first_commit() {
+    let (temp, git_repo) = init_repo();
+    commit_files(&git_repo, &[("README.md", "hello\n")], "Initial");
+
+    let repo = Repository::open(temp.path()).unwrap();
+
+    assert!(!repo.is_head_unborn());
+    assert_eq!(repo.log(None).unwrap().len(), 1);
+}
+
+#[test]
+fn test_tree_entries_lists_all_files() {
+    let (temp, git_repo) = init_repo();
+    commit_files(
+        &git_repo,
+        &[("README.md", "hello\n"), ("src/lib.rs", "one\n")],
+        "Initial",
+    );
+    let id = commit_files(&git_repo, &[("src/main.rs", "two\n")], "Add main");
+
+    let repo = Repository::open(temp.path()).unwrap();
+    let entries: Vec<_> = repo
+        .tree_entries(&id)
+        .unwrap()
+        .into_iter()
+        .map(|entry| (entry.path, entry.kind))
+        .collect();
+
+    // Files unchanged by the commit are listed too
+    assert_eq!(
+        entries,
+        vec![
+            ("README.md".to_string(), TreeEntryKind::File),
+            ("src".to_string(), TreeEntryKind::Directory),
+            ("src/lib.rs".to_string(), TreeEntryKind::File),
+            ("src/main.rs".to_string(), TreeEntryKind::File),
+        ]
+    );
+}
+
+#[test]
+fn test_get_blob_at_revision_keeps_binary_content() {
+    let (temp, git_repo) = init_repo();
+    let bytes = [0x89, b'P', b'N', b'G', 0x00, 0xff, 0xfe];
+    fs::write(temp.path().join("image.png"), bytes).unwrap();
+    let mut index = git_repo.index().unwrap();
+    index.add_path(Path::new("image.png")).unwrap();
+    index.write().unwrap();
+    let id = commit_files(&git_repo, &[], "Add image");
+
+    let repo = Repository::open(temp.path()).unwrap();
+    assert_eq!(
+        repo.get_blob_at_revision(&id, "image.png").unwrap(),
+        Some(bytes.to_vec())
+    );
+    assert_eq!(repo.get_blob_at_revision(&id, "missing.png").unwrap(), None);
+}
+
+#[test]
+fn test_diff_revisions() {
+    let (temp, git_repo) = init_repo();
+    let old = commit_files(
+        &git_repo,
+        &[
+            ("keep.txt", "same\n"),
+            ("edit.txt", "before\n"),
+            ("gone.txt", "bye\n"),
+        ],
+        "Initial",
+    );
+    fs::remove_file(temp.path().join("gone.txt")).unwrap();
+    let mut index = git_repo.index().unwrap();
+    index.remove_path(Path::new("gone.txt")).unwrap();
+    index.write().unwrap();
+    let new = commit_files(
+        &git_repo,
+        &[("edit.txt", "after\n"), ("src/new.txt", "hello\n")],
+        "Change files",
+    );
+
+    let repo = Repository::open(temp.path()).unwrap();
+    let entry = |path: &str, kind| StatusEntry {
+        path: path.to_string(),
+        kind,
+    };
+    assert_eq!(
+        repo.diff_revisions(&old, &new).unwrap(),
+        vec![
+            entry("edit.txt", StatusKind::Modified),
+            entry("gone.txt", StatusKind::Deleted),
+            entry("src/new.txt", StatusKind::Added),
+        ]
+    );
+
+    // Swapping the revisions swaps additions and deletions
+    let reversed = repo.diff_revisions(&new, &old).unwrap();
+    assert_eq!(reversed[1], entry("gone.txt", StatusKind::Added));
+    assert_eq!(reversed[2], entry("src/new.txt", StatusKind::Deleted));
+
+    assert!(repo.diff_revisions(&old, &old).unwrap().is_empty());
+}
+
+#[test]
+fn test_diff_commit() {
+    let (temp, git_repo) = init_repo();
+    let long_file: String = (0..20).map(|i| format!("line {}\n", i)).collect();
+    let root = commit_files(
+        &git_repo,
+        &[
+            ("old_name.txt", &long_file),
+            ("gone.txt", "bye\n"),
+            ("edit.txt", "a\n"),
+        ],
+        "First",
+    );
+
+    let work_dir = temp.path();
+    fs::rename(work_dir.join("old_name.txt"), work_dir.join("new_name.txt")).unwrap();
+    fs::remove_file(work_dir.join("gone.txt")).unwrap();
+    let mut index = git_repo.index().unwrap();
+    index.remove_path(Path::new("old_name.txt")).unwrap();
+    index.remove_path(Path::new("gone.txt")).unwrap();
+    let renamed = long_file.replace("line 3\n", "line three\n");
+    let id = commit_files(
+        &git_repo,
+        &[("new_name.txt", &renamed), ("edit.txt", "b\n")],
+        "Second",
+    );
+
+    let repo = Repository::open(temp.path()).unwrap();
+    let mut files = repo.diff_commit(&id).unwrap();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    let summary: Vec<_> = files
+        .iter()
+        .map(|file| (file.path.as_str(), file.old_path.as_deref(), file.status))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("edit.txt", None, StatusKind::Modified),
+            ("gone.txt", None, StatusKind::Deleted),
+            ("new_name.txt", Some("old_name.txt"), StatusKind::Renamed),
+        ]
+    );
+
+    assert_eq!(files[0].old_content.as_deref(), Some("a\n"));
+    assert_eq!(files[0].new_content.as_deref(), Some("b\n"));
+    assert_eq!(files[1].old_content.as_deref(), Some("bye\n"));
+    assert_eq!(files[1].new_content, None);
+    assert_eq!(files[2].old_content.as_deref(), Some(long_file.as_str()));
+    assert_eq!(files[2].new_content.as_deref(), Some(renamed.as_str()));
+
+    // The root commit adds every file
+    let added = repo.diff_commit(&root).unwrap();
+    assert_eq!(added.len(), 3);
+    assert!(added
+        .iter()
+        .all(|file| file.status == StatusKind::Added && file.old_content.is_none()));
+}
+
+#[test]
+fn test_diff_commit_with_submodule() {
+    let (temp, git_repo) = init_repo();
+    let root = commit_files(&git_repo, &[("a.txt", "one\n")], "First");
+
+    // Add a submodule: a gitlink entry pointing at a commit, with no blob
+    let head = git_repo.head().unwrap().peel_to_commit().unwrap();
+    let mut builder = git_repo.treebuilder(Some(&head.tree().unwrap())).unwrap();
+    let target = git2::Oid::from_str(&root).unwrap();
+    builder.insert("sub", target, 0o160000).unwrap();
+    let tree = git_repo.find_tree(builder.write().unwrap()).unwrap();
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+    let id = git_repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Add submodule",
+            &tree,
+            &[&head],
+        )
+        .unwrap()
+        .to_string();
+
+    let repo = Repository::open(temp.path()).unwrap();
+    let files = repo.diff_commit(&id).unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "sub");
+    assert_eq!(files[0].status, StatusKind::Added);
+    assert_eq!(files[0].old_content, None);
+    assert_eq!(files[0].new_content, None);
+
+    // Listing the whole commit doesn't fail on it either
+    let mut files = repo.commit_full_content(&id).unwrap();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    assert_eq!(files[0].new_content.as_deref(), Some("one\n"));
+    assert_eq!(files[1].new_content, None);
+}
+
+#[test]
+fn test_commit_full_content() {
+    let (temp, git_repo) = init_repo();
+    let root = commit_files(
+        &git_repo,
+        &[("a.txt", "one\ntwo\n"), ("src/lib.rs", "fn lib() {}\n")],
+        "First",
+    );
+    let second = commit_files(&git_repo, &[("a.txt", "one\n2\n")], "Second");
+    let repo = Repository::open(temp.path()).unwrap();
+
+    let summary = |id: &str| {
+        let mut files = repo.commit_full_content(id).unwrap();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        assert!(files.iter().all(|file| file.status == StatusKind::Added
+            && file.old_path.is_none()
+            && file.old_content.is_none()));
+        files
+            .into_iter()
+            .map(|file| (file.path, file.new_content.unwrap()))
+            .collect::<Vec<_>>()
+    };
+
+    // Every file of the root commit is fully added, as in its diff
+    assert_eq!(
+        summary(&root),
+        vec![
+            ("a.txt".to_string(), "one\ntwo\n".to_string()),
+            ("src/lib.rs".to_string(), "fn lib() {}\n".to_string()),
+        ]
+    );
+    assert_eq!(repo.diff_commit(&root).unwrap().len(), 2);
+
+    // Later commits list unchanged files too, at their current content
+    assert_eq!(
+        summary(&second),
+        vec![
+            ("a.txt".to_string(), "one\n2\n".to_string()),
+            ("src/lib.rs".to_string(), "fn lib() {}\n".to_string()),
+        ]
+    );
+    assert!(repo.commit_full_content("not-a-commit").is_err());
+}
+
+#[test]
+fn test_blame() {
+    let (temp, git_repo) = init_repo();
+    let first = commit_files(&git_repo, &[("a.txt", "one\ntwo\nthree\n")], "First");
+    let second = commit_files(&git_repo, &[("a.txt", "one\n2\nthree\n")], "Second");
+    let repo = Repository::open(temp.path()).unwrap();
+
+    let ids = |lines: Vec<BlameLine>| -> Vec<String> {
+        lines.into_iter().map(|line| line.commit_id).collect()
+    };
+    let at_head = repo.blame("a.txt", Some("HEAD")).unwrap();
+    assert_eq!(at_head[1].line, 2);
+    assert_eq!(at_head[1].short_id, second[..7]);
+    assert_eq!(at_head[1].author_name, "Test");
+    assert_eq!(
+        ids(at_head),
+        vec![first.clone(), second.clone(), first.clone()]
+    );
+    assert_eq!(
+        ids(repo.blame("a.txt", Some(&first)).unwrap()),
+        vec![first.clone(); 3]
+    );
+
+    // Working tree changes are uncommitted
+    fs::write(temp.path().join("a.txt"), "one\n2\nthree\nfour\n").unwrap();
+    let working = repo.blame("a.txt", None).unwrap();
+    assert_eq!(working.len(), 4);
+    assert!(!working[2].is_uncommitted());
+    assert!(working[3].is_uncommitted());
+    assert_eq!(working[3].commit_id, BlameLine::UNCOMMITTED_ID);
+
+    fs::write(temp.path().join("new.txt"), "fresh\n").unwrap();
+    assert!(repo.blame("new.txt", None).unwrap()[0].is_uncommitted());
+
+    assert!(repo.blame("new.txt", Some("HEAD")).is_err());
+    assert!(repo.blame("missing.txt", None).is_err());
+}
+
+#[test]
+fn test_line_log() {
+    let (temp, git_repo) = init_repo();
+    let first = commit_files(&git_repo, &[("a.txt", "a\nb\nc\nd\ne\n")], "First");
+    let second = commit_files(&git_repo, &[("a.txt", "a\nB\nc\nd\ne\n")], "Second");
+    let third = commit_files(&git_repo, &[("a.txt", "a\nB\nc\nd\nE\n")], "Third");
+    // Push every line down two, then touch an unrelated file
+    let fourth = commit_files(&git_repo, &[("a.txt", "x\ny\na\nB\nc\nd\nE\n")], "Fourth");
+    commit_files(&git_repo, &[("other.txt", "other\n")], "Fifth");
+    let repo = Repository::open(temp.path()).unwrap();
+
+    let ids = |commits: Vec<Commit>| -> Vec<String> {
+        commits.into_iter().map(|commit| commit.id).collect()
+    };
+    // "B" is line 4 at HEAD, and line 2 before the insertion
+    assert_eq!(
+        ids(repo.line_log("a.txt", 4, 4, None).unwrap()),
+        vec![second.clone(), first.clone()]
+    );
+    assert_eq!(
+        ids(repo.line_log("a.txt", 7, 7, None).unwrap()),
+        vec![third.clone(), first.clone()]
+    );
+    assert_eq!(
+        ids(repo.line_log("a.txt", 5, 6, None).unwrap()),
+        vec![first.clone()]
+    );
+    // Lines added by a commit trace no further back
+    assert_eq!(
+        ids(repo.line_log("a.txt", 1, 2, None).unwrap()),
+        vec![fourth.clone()]
+    );
+    assert_eq!(
+        ids(repo.line_log("a.txt", 1, 7, None).unwrap()),
+        vec![fourth, third, second.clone(), first]
+    );
+    assert_eq!(
+        ids(repo.line_log("a.txt", 4, 4, Some(1)).unwrap()),
+        vec![second]
+    );
+
+    assert!(repo.line_log("a.txt", 0, 1, None).is_err());
+    assert!(repo.line_log("a.txt", 3, 2, None).is_err());
+    assert!(repo.line_log("a.txt", 1, 8, None).is_err());
+    assert!(repo.line_log("missing.txt", 1, 1, None).is_err());
+}
+
+#[test]
+fn test_stage_and_unstage() {
+    let (temp, git_repo) = init_repo();
+    let repo = Repository::open(temp.path()).unwrap();
+    let kind = |path: &str| -> Option<StatusKind> {
+        let status = repo.status().unwrap();
+        let entry = status.entries.iter().find(|entry| entry.path == path);
+        entry.map(|entry| entry.kind)
+    };
+    let in_index = |path: &str| -> bool {
+        let mut index = git_repo.index().unwrap();
+        index.read(true).unwrap();
+        index.get_path(Path::new(path), 0).is_some()
+    };
+
+    // Staging works before the first commit
+    fs::write(temp.path().join("new.txt"), "new\n").unwrap();
+    assert_eq!(kind("new.txt"), Some(StatusKind::Untracked));
+    repo.stage("new.txt").unwrap();
+    assert!(in_index("new.txt"));
+    assert_eq!(kind("new.txt"), Some(StatusKind::Added));
+    repo.unstage("new.txt").unwrap();
+    assert!(!in_index("new.txt"));
+    assert_eq!(kind("new.txt"), Some(StatusKind::Untracked));
+
+    commit_files(
+        &git_repo,
+        &[("a.txt", "one\n"), ("b.txt", "two\n")],
+        "First",
+    );
+    fs::write(temp.path().join("a.txt"), "changed\n").unwrap();
+    fs::remove_file(temp.path().join("b.txt")).unwrap();
+
+    repo.stage("a.txt").unwrap();
+    assert_eq!(
+        repo.staged_changes().unwrap(),
+        vec![StatusEntry {
+            path: "a.txt".to_string(),
+            kind: StatusKind::Modified,
+        }]
+    );
+    repo.unstage("a.txt").unwrap();
+    assert!(repo.staged_changes().unwrap().is_empty());
+    assert_eq!(
+        fs::read_to_string(temp.path().join("a.txt")).unwrap(),
+        "changed\n"
+    );
+
+    repo.stage("b.txt").unwrap();
+    assert!(!in_index("b.txt"));
+    assert_eq!(kind("b.txt"), Some(StatusKind::Deleted));
+    repo.unstage("b.txt").unwrap();
+    assert!(in_index("b.txt"));
+
+    repo.stage_all().unwrap();
+    let mut staged: Vec<String> = repo
+        .staged_changes()
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.path)
+        .collect();
+    staged.sort();
+    assert_eq!(staged, vec!["a.txt", "b.txt", "new.txt"]);
+    assert!(repo.unstaged_changes().unwrap().is_empty());
+}
+
+#[test]
+fn test_commit() {
+    let (temp, git_repo) = init_repo();
+    let mut config = git_repo.config().unwrap();
+    config.set_str("user.name", "Configured").unwrap();
+    config
+        .set_str("user.email", "configured@example.com")
+        .unwrap();
+    let repo = Repository::open(temp.path()).unwrap();
+
+    // The first commit has no parent, and falls back to the configured user
+    fs::write(temp.path().join("a.txt"), "one\n").unwrap();
+    repo.stage("a.txt").unwrap();
+    let first = repo.commit("First", None).unwrap();
+    assert!(!repo.is_head_unborn());
+    let commit = repo.get_commit(&first).unwrap();
+    assert_eq!(commit.message, "First");
+    assert_eq!(commit.author_name, "Configured");
+    assert_eq!(commit.committer_email, "configured@example.com");
+    assert!(commit.parent_ids.is_empty());
+
+    fs::write(temp.path().join("a.txt"), "two\n").unwrap();
+    repo.stage("a.txt").unwrap();
+    let signature = git2::Signature::now("Given", "given@example.com").unwrap();
+    let second = repo.commit("Second", Some(signature)).unwrap();
+
+    // The new commit heads the log, on top of the previous one
+    let log = repo.log(None).unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].id, second);
+    assert_eq!(log[0].author_name, "Given");
+    assert_eq!(log[0].parent_ids, vec![first]);
+    assert_eq!(
+        repo.get_content_at_revision(&second, "a.txt").unwrap(),
+        Some("two\n".to_string())
+    );
+    assert!(repo.staged_changes().unwrap().is_empty());
+}
+
+#[test]
+fn test_branches() {
+    let (temp, git_repo) = init_repo();
+    let repo = Repository::open(temp.path()).unwrap();
+    assert!(repo.branches().unwrap().is_empty());
+    let default_branch = repo.current_branch().unwrap().unwrap();
+
+    let id = commit_files(&git_repo, &[("a.txt", "one\n")], "First");
+    let commit = git_repo
+        .find_commit(git2::Oid::from_str(&id).unwrap())
+        .unwrap();
+    git_repo.branch("feature", &commit, false).unwrap();
+    git_repo
+        .remote("origin", "https://example.com/repo.git")
+        .unwrap();
+    git_repo
+        .reference("refs/remotes/origin/main", commit.id(), false, "fetch")
+        .unwrap();
+    git_repo
+        .reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main",
+            false,
+            "",
+        )
+        .unwrap();
+    git_repo
+        .find_branch("feature", git2::BranchType::Local)
+        .unwrap()
+        .set_upstream(Some("origin/main"))
+        .unwrap();
+
+    let branch = |name: &str, is_head, is_remote, upstream: Option<&str>| BranchInfo {
+        name: name.to_string(),
+        is_head,
+        is_remote,
+        upstream: upstream.map(str::to_string),
+    };
+    let mut expected = vec![
+        branch(&default_branch, true, false, None),
+        branch("feature", false, false, Some("origin/main")),
+    ];
+    expected.sort_by(|a, b| a.name.cmp(&b.name));
+    expected.push(branch("origin/main", false, true, None));
+    assert_eq!(repo.branches().unwrap(), expected);
+    assert_eq!(repo.current_branch().unwrap(), Some(default_branch));
+
+    // A detached HEAD isn't on any branch
+    git_repo.set_head_detached(commit.id()).unwrap();
+    assert_eq!(repo.current_branch().unwrap(), None);
+    assert!(repo
+        .branches()
+        .unwrap()
+        .iter()
+        .all(|branch| !branch.is_head));
+}
+
+#[test]
+fn test_log_includes_body_and_committer() {
+    let (temp, git_repo) = init_repo();
+    let tree = git_repo
+        .find_tree(git_repo.index().unwrap().write_tree().unwrap())
+        .unwrap();
+    let author =
+        git2::Signature::new("Alice", "alice@example.com", &git2::Time::new(100, 0)).unwrap();
+    let committer =
+        git2::Signature::new("Bob", "bob@example.com", &git2::Time::new(200, 0)).unwrap();
+    let message = "Fix the thing\n\nLonger explanation\nover two lines.\n";
+    git_repo
+        .commit(Some("HEAD"), &author, &committer, message, &tree, &[])
+        .unwrap();
+
+    let repo = Repository::open(temp.path()).unwrap();
+    let commit = &repo.log(None).unwrap()[0];
+    assert_eq!(commit.message, "Fix the thing");
+    assert_eq!(commit.body, message);
+    assert_eq!(
+        (commit.author_name.as_str(), commit.author_email.as_str()),
+        ("Alice", "alice@example.com")
+    );
+    assert_eq!(commit.committer_name, "Bob");
+    assert_eq!(commit.committer_email, "bob@example.com");
+    // The commit's time is when it was committed; the author time is kept apart
+    assert_eq!(commit.time, 200);
+    assert_eq!(commit.committer_time, 200);
+    assert_eq!(commit.author_time, 100);
+    assert_eq!(&repo.get_commit(&commit.id).unwrap(), commit);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_log_json_round_trips() {
+    let (temp, git_repo) = init_repo();
+    commit_files(&git_repo, &[("a.txt", "one\n")], "First");
+    commit_files(&git_repo, &[("a.txt", "two\n")], "Second");
+
+    let repo = Repository::open(temp.path()).unwrap();
+    let json = repo.log_json(None).unwrap();
+    let commits: Vec<git::Commit> = serde_json::from_str(&json).unwrap();
+    assert_eq!(commits, repo.log(None).unwrap());
+    assert_eq!(commits[0].message, "Second");
+
+    // Timestamps are plain integers
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value[0]["time"].as_i64(), Some(commits[0].time));
+
+    assert_eq!(repo.log_json(Some(1)).unwrap().matches("\"id\"").count(), 1);
+}
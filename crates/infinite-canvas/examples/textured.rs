@@ -7,6 +7,7 @@
 //! - Pan with middle mouse button
 //! - Zoom with scroll wheel (centered on cursor)
 //! - Items rendered as textures (zoomable)
+//! - Click the status bar to save each rendered texture as a PNG
 //!
 //! Run with: RUST_LOG=info cargo run -p infinite-canvas --example textured
 
@@ -16,6 +17,9 @@ use log::info;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Folder in the temp dir that rendered textures are saved to
+const SNAPSHOT_DIR: &str = "textured-example";
+
 /// Background color for the canvas
 const BG_COLOR: u32 = 0x1e1e1e;
 
@@ -73,11 +77,40 @@ impl TexturedCanvasView {
 
         Self { provider }
     }
+
+    /// Save every rendered texture to `SNAPSHOT_DIR`, named after its item
+    fn save_snapshots(&self, cx: &App) {
+        let dir = std::env::temp_dir().join(SNAPSHOT_DIR);
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            log::warn!(
+                "[textured_example] Failed to create {}: {}",
+                dir.display(),
+                e
+            );
+            return;
+        }
+
+        let provider = self.provider.borrow();
+        let mut saved = 0;
+        for item in provider.items() {
+            let path = dir.join(format!("{}.png", item.id));
+            match provider.save_texture_png(&item.id, &path, cx) {
+                Ok(true) => saved += 1,
+                Ok(false) => {}
+                Err(e) => log::warn!("[textured_example] Failed to save {}: {}", item.id, e),
+            }
+        }
+        info!(
+            "[textured_example] Saved {} textures to {}",
+            saved,
+            dir.display()
+        );
+    }
 }
 
 impl Render for TexturedCanvasView {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
-        let item_count = self.provider.borrow().item_count();
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let status = self.provider.borrow().status(cx);
 
         div()
             .size_full()
@@ -102,7 +135,12 @@ impl Render for TexturedCanvasView {
                     .rounded_md()
                     .text_sm()
                     .text_color(white())
-                    .child(format!("Items: {}", item_count)),
+                    .cursor_pointer()
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, _, _, cx| this.save_snapshots(cx)),
+                    )
+                    .child(format!("Items: {} ({} ready)", status.total, status.ready)),
             )
             // Help text overlay
             .child(
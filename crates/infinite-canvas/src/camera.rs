@@ -5,9 +5,15 @@
 //! - Zoom level (scale factor)
 //! - Coordinate conversion between screen space and canvas space
 
-use gpui::{Bounds, Pixels, Point, Size};
+use gpui::{px, Bounds, Pixels, Point, Size};
 use serde::{Deserialize, Serialize};
 
+/// Zoom range accepted by `Camera::from_url_fragment`.
+///
+/// Wider than any sensible `CanvasOptions` limits, but keeps a hand-edited
+/// link from producing a zero or absurd zoom.
+const FRAGMENT_ZOOM_RANGE: (f32, f32) = (0.001, 1000.0);
+
 /// The camera state for an infinite canvas.
 ///
 /// The camera defines the viewport into the infinite canvas space.
@@ -49,6 +55,41 @@ impl Camera {
         Self { offset, zoom }
     }
 
+    /// Encode the camera as a compact `x,y,zoom` string, e.g. for a URL fragment.
+    ///
+    /// Numbers use the shortest form that parses back to the same value, so
+    /// `from_url_fragment` restores the camera exactly.
+    pub fn to_url_fragment(&self) -> String {
+        let x: f32 = self.offset.x.into();
+        let y: f32 = self.offset.y.into();
+        format!("{},{},{}", x, y, self.zoom)
+    }
+
+    /// Decode a camera from a string made by `to_url_fragment`.
+    ///
+    /// A leading `#` is ignored. Returns `None` unless the string holds exactly
+    /// three finite numbers and a positive zoom. The zoom is clamped to a wide
+    /// but sane range; clamp it further to the canvas's `CanvasOptions` limits
+    /// as needed.
+    pub fn from_url_fragment(fragment: &str) -> Option<Self> {
+        let fragment = fragment.strip_prefix('#').unwrap_or(fragment);
+        let mut values = fragment.split(',').map(|value| value.trim().parse::<f32>());
+        let (Some(Ok(x)), Some(Ok(y)), Some(Ok(zoom)), None) =
+            (values.next(), values.next(), values.next(), values.next())
+        else {
+            return None;
+        };
+        if !(x.is_finite() && y.is_finite() && zoom.is_finite()) || zoom <= 0.0 {
+            return None;
+        }
+
+        let (min_zoom, max_zoom) = FRAGMENT_ZOOM_RANGE;
+        Some(Self::with_offset_and_zoom(
+            Point::new(px(x), px(y)),
+            zoom.clamp(min_zoom, max_zoom),
+        ))
+    }
+
     /// Convert a point from screen space to canvas space.
     pub fn screen_to_canvas(&self, screen_point: Point<Pixels>) -> Point<Pixels> {
         Point::new(
@@ -147,6 +188,17 @@ impl Camera {
         self.zoom_around(factor, center, min_zoom, max_zoom);
     }
 
+    /// Compute the zoom at which `world_unit` canvas pixels span `screen_pixels` on screen.
+    ///
+    /// For example, `zoom_for_scale(grid_size, px(32.))` gives the zoom that
+    /// makes each grid cell exactly 32 screen pixels wide. Returns `None` if
+    /// `world_unit` isn't positive.
+    pub fn zoom_for_scale(world_unit: Pixels, screen_pixels: Pixels) -> Option<f32> {
+        let world_unit: f32 = world_unit.into();
+        let screen_pixels: f32 = screen_pixels.into();
+        (world_unit > 0.0).then(|| screen_pixels / world_unit)
+    }
+
     /// Reset the camera to default state (no pan, 100% zoom).
     pub fn reset(&mut self) {
         *self = Self::default();
@@ -293,6 +345,20 @@ mod tests {
         assert_eq!(visible.size.height, px(300.));
     }
 
+    #[test]
+    fn test_zoom_for_scale() {
+        // A 20px grid cell should span 50 screen pixels
+        let zoom = Camera::zoom_for_scale(px(20.), px(50.)).unwrap();
+        assert_eq!(zoom, 2.5);
+
+        let camera = Camera::with_offset_and_zoom(point(px(13.), px(-7.)), zoom);
+        let cell = Bounds::new(point(px(40.), px(40.)), size(px(20.), px(20.)));
+        let on_screen = camera.canvas_to_screen_bounds(cell);
+        assert_eq!(on_screen.size, size(px(50.), px(50.)));
+
+        assert_eq!(Camera::zoom_for_scale(px(0.), px(50.)), None);
+    }
+
     #[test]
     fn test_zoom_steps() {
         let camera = Camera::with_offset_and_zoom(point(px(0.), px(0.)), 1.0);
@@ -301,4 +367,37 @@ mod tests {
         assert_eq!(camera.next_zoom_step(&steps), 2.0);
         assert_eq!(camera.prev_zoom_step(&steps), 0.5);
     }
+
+    #[test]
+    fn test_url_fragment_round_trip() {
+        let cameras = [
+            Camera::default(),
+            Camera::with_offset_and_zoom(point(px(-1250.5), px(-0.125)), 0.75),
+            Camera::with_offset_and_zoom(point(px(333.33), px(-42.)), 1.0 / 3.0),
+        ];
+        for camera in cameras {
+            let fragment = camera.to_url_fragment();
+            assert_eq!(Camera::from_url_fragment(&fragment), Some(camera));
+        }
+
+        assert_eq!(Camera::default().to_url_fragment(), "0,0,1");
+        assert_eq!(
+            Camera::from_url_fragment("#-10,20.5,2.5"),
+            Some(Camera::with_offset_and_zoom(point(px(-10.), px(20.5)), 2.5))
+        );
+    }
+
+    #[test]
+    fn test_url_fragment_validation() {
+        for invalid in [
+            "", "1,2", "1,2,3,4", "a,2,3", "1,NaN,1", "1,2,inf", "1,2,0", "1,2,-1",
+        ] {
+            assert_eq!(Camera::from_url_fragment(invalid), None, "{invalid}");
+        }
+
+        let zoomed_in = Camera::from_url_fragment("0,0,1e9").unwrap();
+        assert_eq!(zoomed_in.zoom, FRAGMENT_ZOOM_RANGE.1);
+        let zoomed_out = Camera::from_url_fragment("0,0,1e-9").unwrap();
+        assert_eq!(zoomed_out.zoom, FRAGMENT_ZOOM_RANGE.0);
+    }
 }
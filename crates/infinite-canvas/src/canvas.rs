@@ -4,20 +4,262 @@
 //! a pannable, zoomable canvas with items from a `CanvasItemsProvider`.
 
 use gpui::{
-    point, px, AnyElement, App, AvailableSpace, Bounds, Element, ElementId, GlobalElementId,
-    Hitbox, HitboxBehavior, InspectorElementId, IntoElement, LayoutId, Length, MouseButton,
-    MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, Point, ScrollWheelEvent, Size, Style,
-    Window,
+    point, px, AnyElement, App, AsyncApp, AvailableSpace, Bounds, Element, ElementId, FocusHandle,
+    GlobalElementId, Hitbox, HitboxBehavior, Hsla, InspectorElementId, IntoElement, KeyDownEvent,
+    LayoutId, Length, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, Point,
+    ScrollWheelEvent, Size, Style, Window,
 };
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::ops::Range;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use crate::camera::Camera;
+use crate::event::CanvasEvent;
+use crate::handle::CanvasHandle;
+use crate::keymap::CanvasAction;
 use crate::options::CanvasOptions;
-use crate::provider::{CanvasItemsProvider, ItemDescriptor};
+use crate::provider::{sort_by_viewport_proximity, CanvasItemsProvider, ItemDescriptor, ItemId};
 
-/// A shared reference to a canvas items provider.
-pub type SharedProvider<P> = Rc<RefCell<P>>;
+/// A shared handle to a canvas items provider.
+///
+/// Cloning the handle shares the underlying provider, so several canvases can
+/// display the same items (and textures). Mutations made through one handle
+/// are visible through all others.
+///
+/// `SharedProvider` implements `CanvasItemsProvider` by delegating to the
+/// shared provider.
+pub struct SharedProvider<P: ?Sized>(Rc<RefCell<P>>);
+
+impl<P> SharedProvider<P> {
+    /// Wrap a provider in a new shared handle.
+    pub fn new(provider: P) -> Self {
+        Self(Rc::new(RefCell::new(provider)))
+    }
+}
+
+impl<P: ?Sized> SharedProvider<P> {
+    /// Immutably borrow the shared provider.
+    pub fn borrow(&self) -> Ref<'_, P> {
+        self.0.borrow()
+    }
+
+    /// Mutably borrow the shared provider.
+    pub fn borrow_mut(&self) -> RefMut<'_, P> {
+        self.0.borrow_mut()
+    }
+
+    /// Check whether two handles share the same provider.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<P: ?Sized> Clone for SharedProvider<P> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<P: ?Sized> From<Rc<RefCell<P>>> for SharedProvider<P> {
+    fn from(provider: Rc<RefCell<P>>) -> Self {
+        Self(provider)
+    }
+}
+
+impl<P: CanvasItemsProvider + ?Sized> CanvasItemsProvider for SharedProvider<P> {
+    fn items(&self) -> Vec<ItemDescriptor> {
+        self.borrow().items()
+    }
+
+    fn items_with_context(&self, cx: &App) -> Vec<ItemDescriptor> {
+        self.borrow().items_with_context(cx)
+    }
+
+    fn render_item(
+        &self,
+        id: &ItemId,
+        screen_bounds: Bounds<Pixels>,
+        cx: &App,
+    ) -> Option<AnyElement> {
+        self.borrow().render_item(id, screen_bounds, cx)
+    }
+
+    fn item_count(&self) -> usize {
+        self.borrow().item_count()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.borrow().is_empty()
+    }
+
+    fn content_bounds(&self) -> Option<Bounds<Pixels>> {
+        self.borrow().content_bounds()
+    }
+
+    fn content_centroid(&self) -> Option<Point<Pixels>> {
+        self.borrow().content_centroid()
+    }
+}
+
+/// Window positions of the canvas axes within `bounds`.
+///
+/// Returns the y of the x axis (canvas y = 0) and the x of the y axis
+/// (canvas x = 0), or `None` for an axis that is outside the bounds.
+fn axis_positions(camera: &Camera, bounds: Bounds<Pixels>) -> (Option<Pixels>, Option<Pixels>) {
+    let origin = camera.canvas_to_screen(point(px(0.), px(0.)));
+    let x_axis = bounds.origin.y + origin.y;
+    let y_axis = bounds.origin.x + origin.x;
+
+    let x_visible = x_axis >= bounds.origin.y && x_axis <= bounds.origin.y + bounds.size.height;
+    let y_visible = y_axis >= bounds.origin.x && y_axis <= bounds.origin.x + bounds.size.width;
+    (x_visible.then_some(x_axis), y_visible.then_some(y_axis))
+}
+
+/// A subscriber to canvas events.
+type EventListener = Rc<dyn Fn(&CanvasEvent) + 'static>;
+
+/// Deliver an event to every listener, in registration order.
+fn emit(listeners: &[EventListener], event: &CanvasEvent) {
+    for listener in listeners {
+        listener(event);
+    }
+}
+
+/// Pan the camera by a screen-space delta, applying the options' constraints.
+///
+/// Returns the events the pan produces.
+fn pan_camera(
+    camera: &mut Camera,
+    delta: Point<Pixels>,
+    options: &CanvasOptions,
+    viewport_size: Size<Pixels>,
+) -> [CanvasEvent; 2] {
+    camera.pan(delta);
+    options.constraints.apply(camera, viewport_size);
+    [
+        CanvasEvent::Panned { delta },
+        CanvasEvent::CameraChanged(*camera),
+    ]
+}
+
+/// Zoom the camera around an anchor, applying the options' limits and constraints.
+///
+/// Returns the events the zoom produces.
+fn zoom_camera(
+    camera: &mut Camera,
+    factor: f32,
+    anchor: Point<Pixels>,
+    options: &CanvasOptions,
+    viewport_size: Size<Pixels>,
+) -> [CanvasEvent; 2] {
+    camera.zoom_around(factor, anchor, options.min_zoom, options.max_zoom);
+    options.constraints.apply(camera, viewport_size);
+    [
+        CanvasEvent::Zoomed {
+            zoom: camera.zoom,
+            anchor,
+        },
+        CanvasEvent::CameraChanged(*camera),
+    ]
+}
+
+/// Screen distance a pan shortcut moves the view, before `pan_speed`.
+const KEY_PAN_STEP: f32 = 50.0;
+
+/// Padding around the content when fitting it to the viewport.
+const FIT_PADDING: f32 = 40.0;
+
+/// Apply a keyboard action to the camera.
+///
+/// `content_bounds` is only used by `CanvasAction::FitAll`. Returns the
+/// events the action produces, which is empty if it had nothing to do.
+fn apply_key_action(
+    camera: &mut Camera,
+    action: CanvasAction,
+    options: &CanvasOptions,
+    viewport_size: Size<Pixels>,
+    content_bounds: Option<Bounds<Pixels>>,
+) -> Vec<CanvasEvent> {
+    let step = px(KEY_PAN_STEP * options.pan_speed);
+    let pan = |camera: &mut Camera, delta: Point<Pixels>| -> Vec<CanvasEvent> {
+        pan_camera(camera, delta, options, viewport_size).into()
+    };
+    let zoom_to = |camera: &mut Camera, zoom: f32| -> Vec<CanvasEvent> {
+        let center = point(viewport_size.width / 2.0, viewport_size.height / 2.0);
+        zoom_camera(camera, zoom / camera.zoom, center, options, viewport_size).into()
+    };
+
+    match action {
+        CanvasAction::ZoomIn => {
+            let zoom = camera.next_zoom_step(&options.zoom_steps);
+            zoom_to(camera, zoom)
+        }
+        CanvasAction::ZoomOut => {
+            let zoom = camera.prev_zoom_step(&options.zoom_steps);
+            zoom_to(camera, zoom)
+        }
+        // Panning the view one way moves the content the other way
+        CanvasAction::PanLeft => pan(camera, point(step, px(0.))),
+        CanvasAction::PanRight => pan(camera, point(-step, px(0.))),
+        CanvasAction::PanUp => pan(camera, point(px(0.), step)),
+        CanvasAction::PanDown => pan(camera, point(px(0.), -step)),
+        CanvasAction::ResetView => {
+            camera.reset();
+            options.constraints.apply(camera, viewport_size);
+            vec![CanvasEvent::CameraChanged(*camera)]
+        }
+        CanvasAction::FitAll => match content_bounds {
+            Some(content_bounds) => {
+                camera.zoom_to_fit(
+                    content_bounds,
+                    viewport_size,
+                    px(FIT_PADDING),
+                    options.min_zoom,
+                    options.max_zoom,
+                );
+                options.constraints.apply(camera, viewport_size);
+                vec![CanvasEvent::CameraChanged(*camera)]
+            }
+            None => Vec::new(),
+        },
+    }
+}
+
+/// Screen bounds of a horizontal band across an item, for `highlight_region`.
+///
+/// `y_range` is measured down from the top of the item, in canvas pixels, and
+/// is clipped to the item. Returns `None` if nothing of the band is left.
+fn highlight_bounds(
+    camera: &Camera,
+    item_bounds: Bounds<Pixels>,
+    y_range: &Range<Pixels>,
+) -> Option<Bounds<Pixels>> {
+    let start = f32::from(y_range.start).max(0.0);
+    let end = f32::from(y_range.end).min(item_bounds.size.height.into());
+    if end <= start {
+        return None;
+    }
+
+    let band = Bounds::new(
+        point(item_bounds.origin.x, item_bounds.origin.y + px(start)),
+        Size::new(item_bounds.size.width, px(end - start)),
+    );
+    Some(camera.canvas_to_screen_bounds(band))
+}
+
+/// Find the topmost item containing a window position.
+///
+/// `item_bounds` holds the painted items' window bounds in paint order, so
+/// later entries are on top.
+fn item_at(item_bounds: &[(ItemId, Bounds<Pixels>)], position: Point<Pixels>) -> Option<&ItemId> {
+    item_bounds
+        .iter()
+        .rev()
+        .find(|(_, bounds)| bounds.contains(&position))
+        .map(|(id, _)| id)
+}
 
 /// Persistent state for the canvas element, stored in GPUI's element state system.
 #[derive(Default)]
@@ -28,6 +270,114 @@ struct CanvasElementState {
     is_panning: Option<Rc<RefCell<bool>>>,
     /// The last mouse position during a pan operation.
     last_pan_position: Option<Rc<RefCell<Point<Pixels>>>>,
+    /// Pending throttled camera change.
+    camera_change_gate: Option<Rc<RefCell<ThrottleGate<Camera>>>>,
+}
+
+/// Throttle bookkeeping with a trailing call.
+///
+/// A value pushed at least `interval` after the last delivery is delivered
+/// right away. Anything pushed sooner is held and delivered by a single
+/// trailing timer at the end of the interval, so continuous pushes yield one
+/// value per interval and the latest value is never dropped.
+struct ThrottleGate<T> {
+    last_fired: Option<Instant>,
+    pending: Option<T>,
+    trailing_scheduled: bool,
+}
+
+/// What the caller should do with a value pushed into a [`ThrottleGate`].
+#[derive(Debug, PartialEq)]
+enum Throttled<T> {
+    /// Deliver the value now.
+    Fire(T),
+    /// Start a timer for the trailing call, firing after this delay.
+    Schedule(Duration),
+    /// The value is held for the already scheduled trailing call.
+    Held,
+}
+
+impl<T> Default for ThrottleGate<T> {
+    fn default() -> Self {
+        Self {
+            last_fired: None,
+            pending: None,
+            trailing_scheduled: false,
+        }
+    }
+}
+
+impl<T> ThrottleGate<T> {
+    /// Record a new value pushed at `now`.
+    fn push(&mut self, value: T, now: Instant, interval: Duration) -> Throttled<T> {
+        if self.trailing_scheduled {
+            self.pending = Some(value);
+            return Throttled::Held;
+        }
+
+        match self.last_fired {
+            Some(last) if now.duration_since(last) < interval => {
+                self.pending = Some(value);
+                self.trailing_scheduled = true;
+                Throttled::Schedule(last + interval - now)
+            }
+            _ => {
+                self.last_fired = Some(now);
+                Throttled::Fire(value)
+            }
+        }
+    }
+
+    /// Take the held value when the trailing timer fires at `now`.
+    fn take_trailing(&mut self, now: Instant) -> Option<T> {
+        self.trailing_scheduled = false;
+        let value = self.pending.take();
+        if value.is_some() {
+            self.last_fired = Some(now);
+        }
+        value
+    }
+}
+
+/// Delivers `CameraChanged` events to the listeners, throttled if configured.
+#[derive(Clone)]
+struct CameraChangeNotifier {
+    listeners: Rc<[EventListener]>,
+    interval: Option<Duration>,
+    gate: Rc<RefCell<ThrottleGate<Camera>>>,
+}
+
+impl CameraChangeNotifier {
+    fn notify(&self, camera: Camera, cx: &mut App) {
+        if self.listeners.is_empty() {
+            return;
+        }
+
+        let Some(interval) = self.interval else {
+            emit(&self.listeners, &CanvasEvent::CameraChanged(camera));
+            return;
+        };
+
+        let throttled = self
+            .gate
+            .borrow_mut()
+            .push(camera, Instant::now(), interval);
+        match throttled {
+            Throttled::Fire(camera) => emit(&self.listeners, &CanvasEvent::CameraChanged(camera)),
+            Throttled::Schedule(delay) => {
+                let notifier = self.clone();
+                cx.spawn(async move |cx: &mut AsyncApp| {
+                    cx.background_executor().timer(delay).await;
+                    let latest = notifier.gate.borrow_mut().take_trailing(Instant::now());
+                    if let Some(camera) = latest {
+                        emit(&notifier.listeners, &CanvasEvent::CameraChanged(camera));
+                    }
+                })
+                .detach();
+            }
+            Throttled::Held => {}
+        }
+    }
 }
 
 /// The infinite canvas component.
@@ -66,19 +416,39 @@ pub struct InfiniteCanvas<P: CanvasItemsProvider + 'static> {
     initial_camera: Camera,
     /// Canvas options.
     options: CanvasOptions,
-    /// Optional callback when camera changes.
-    on_camera_change: Option<Rc<dyn Fn(Camera) + 'static>>,
+    /// Subscribers to canvas events.
+    listeners: Vec<EventListener>,
+    /// Optional handle sharing the camera with the host view.
+    handle: Option<CanvasHandle>,
+    /// Focus handle that routes key presses to the canvas.
+    focus_handle: Option<FocusHandle>,
+    /// Color painted behind the grid and items.
+    background: Hsla,
+    /// Item and vertical range to draw a highlight band over.
+    highlight: Option<(ItemId, Range<Pixels>)>,
 }
 
 impl<P: CanvasItemsProvider + 'static> InfiniteCanvas<P> {
+    /// The background color used unless `background` is set.
+    pub const DEFAULT_BACKGROUND: u32 = 0x1e1e1e;
+
+    /// The translucent color of the `highlight_region` band.
+    pub const HIGHLIGHT_COLOR: u32 = 0xf2cc6050;
+
     /// Create a new infinite canvas with the given ID and provider.
-    pub fn new(id: impl Into<ElementId>, provider: SharedProvider<P>) -> Self {
+    ///
+    /// Accepts a `SharedProvider` or an `Rc<RefCell<P>>`.
+    pub fn new(id: impl Into<ElementId>, provider: impl Into<SharedProvider<P>>) -> Self {
         Self {
             id: id.into(),
-            provider,
+            provider: provider.into(),
             initial_camera: Camera::default(),
             options: CanvasOptions::default(),
-            on_camera_change: None,
+            listeners: Vec::new(),
+            handle: None,
+            focus_handle: None,
+            background: gpui::rgb(Self::DEFAULT_BACKGROUND).into(),
+            highlight: None,
         }
     }
 
@@ -95,9 +465,68 @@ impl<P: CanvasItemsProvider + 'static> InfiniteCanvas<P> {
         self
     }
 
+    /// Subscribe to every canvas event.
+    ///
+    /// Can be called more than once; listeners run in registration order.
+    pub fn on_event(mut self, listener: impl Fn(&CanvasEvent) + 'static) -> Self {
+        self.listeners.push(Rc::new(listener));
+        self
+    }
+
     /// Set the camera change callback.
-    pub fn on_camera_change(mut self, callback: impl Fn(Camera) + 'static) -> Self {
-        self.on_camera_change = Some(Rc::new(callback));
+    pub fn on_camera_change(self, callback: impl Fn(Camera) + 'static) -> Self {
+        self.on_event(move |event| {
+            if let CanvasEvent::CameraChanged(camera) = event {
+                callback(*camera);
+            }
+        })
+    }
+
+    /// Set the item click callback, called with the clicked item's id.
+    pub fn on_item_click(self, callback: impl Fn(&ItemId) + 'static) -> Self {
+        self.on_event(move |event| {
+            if let CanvasEvent::ItemClicked { id, .. } = event {
+                callback(id);
+            }
+        })
+    }
+
+    /// Drive the camera through a `CanvasHandle`.
+    ///
+    /// The canvas uses the handle's camera instead of its own element state,
+    /// so the host can move the camera programmatically. The initial camera
+    /// set via `camera` is ignored when a handle is set.
+    pub fn handle(mut self, handle: CanvasHandle) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+
+    /// Track focus with the given handle, enabling keyboard shortcuts.
+    ///
+    /// The canvas takes focus when clicked, and while focused handles the
+    /// shortcuts in `CanvasOptions::key_map`. Without a focus handle the
+    /// canvas never receives key presses.
+    pub fn track_focus(mut self, focus_handle: &FocusHandle) -> Self {
+        self.focus_handle = Some(focus_handle.clone());
+        self
+    }
+
+    /// Set the color painted behind the grid and items.
+    ///
+    /// Defaults to a dark gray; pass the theme's background to match it.
+    pub fn background(mut self, color: impl Into<Hsla>) -> Self {
+        self.background = color.into();
+        self
+    }
+
+    /// Draw a highlight band across an item, e.g. to point at a line after
+    /// jumping to it.
+    ///
+    /// `y_range` is measured down from the top of the item in canvas pixels,
+    /// so the band follows the item as the camera pans and zooms. It's
+    /// painted over the item's texture and clipped to the item.
+    pub fn highlight_region(mut self, id: impl Into<ItemId>, y_range: Range<Pixels>) -> Self {
+        self.highlight = Some((id.into(), y_range));
         self
     }
 }
@@ -111,7 +540,11 @@ impl<P: CanvasItemsProvider + 'static> IntoElement for InfiniteCanvas<P> {
             provider: self.provider,
             initial_camera: self.initial_camera,
             options: self.options,
-            on_camera_change: self.on_camera_change,
+            listeners: self.listeners,
+            handle: self.handle,
+            focus_handle: self.focus_handle,
+            background: self.background,
+            highlight: self.highlight,
         }
     }
 }
@@ -122,7 +555,11 @@ pub struct CanvasElement<P: CanvasItemsProvider + 'static> {
     provider: SharedProvider<P>,
     initial_camera: Camera,
     options: CanvasOptions,
-    on_camera_change: Option<Rc<dyn Fn(Camera) + 'static>>,
+    listeners: Vec<EventListener>,
+    handle: Option<CanvasHandle>,
+    focus_handle: Option<FocusHandle>,
+    background: Hsla,
+    highlight: Option<(ItemId, Range<Pixels>)>,
 }
 
 impl<P: CanvasItemsProvider + 'static> IntoElement for CanvasElement<P> {
@@ -139,8 +576,13 @@ pub struct CanvasElementPrepaintState {
     camera: Rc<RefCell<Camera>>,
     is_panning: Rc<RefCell<bool>>,
     last_pan_position: Rc<RefCell<Point<Pixels>>>,
+    camera_change_gate: Rc<RefCell<ThrottleGate<Camera>>>,
     /// Elements to paint (prepared during prepaint)
     item_elements: Vec<AnyElement>,
+    /// Window bounds of the painted items, in paint order
+    item_bounds: Vec<(ItemId, Bounds<Pixels>)>,
+    /// Window bounds of the highlight band, if its item is visible
+    highlight_bounds: Option<Bounds<Pixels>>,
 }
 
 impl<P: CanvasItemsProvider + 'static> Element for CanvasElement<P> {
@@ -180,9 +622,13 @@ impl<P: CanvasItemsProvider + 'static> Element for CanvasElement<P> {
         cx: &mut App,
     ) -> Self::PrepaintState {
         let hitbox = window.insert_hitbox(bounds, HitboxBehavior::Normal);
+        if let Some(focus_handle) = &self.focus_handle {
+            window.set_focus_handle(focus_handle, cx);
+        }
 
         let initial_camera = self.initial_camera;
-        let (camera, is_panning, last_pan_position) = window
+        let handle_camera = self.handle.as_ref().map(CanvasHandle::camera_cell);
+        let (camera, is_panning, last_pan_position, camera_change_gate) = window
             .with_optional_element_state::<CanvasElementState, _>(
                 global_id,
                 |element_state, _window| {
@@ -190,10 +636,13 @@ impl<P: CanvasItemsProvider + 'static> Element for CanvasElement<P> {
                         .map(|s| s.unwrap_or_default())
                         .unwrap_or_default();
 
-                    let camera = state
-                        .camera
-                        .get_or_insert_with(|| Rc::new(RefCell::new(initial_camera)))
-                        .clone();
+                    let camera = match handle_camera {
+                        Some(camera) => camera,
+                        None => state
+                            .camera
+                            .get_or_insert_with(|| Rc::new(RefCell::new(initial_camera)))
+                            .clone(),
+                    };
 
                     let is_panning = state
                         .is_panning
@@ -205,20 +654,46 @@ impl<P: CanvasItemsProvider + 'static> Element for CanvasElement<P> {
                         .get_or_insert_with(|| Rc::new(RefCell::new(point(px(0.), px(0.)))))
                         .clone();
 
-                    ((camera, is_panning, last_pan_position), Some(state))
+                    let camera_change_gate = state
+                        .camera_change_gate
+                        .get_or_insert_with(Default::default)
+                        .clone();
+
+                    (
+                        (camera, is_panning, last_pan_position, camera_change_gate),
+                        Some(state),
+                    )
                 },
             );
 
         // Prepare item elements during prepaint phase
         let camera_val = *camera.borrow();
         let viewport_size = bounds.size;
+        if let Some(handle) = &self.handle {
+            handle.set_viewport_size(viewport_size);
+        }
         let visible_canvas_bounds = camera_val.visible_canvas_bounds(viewport_size);
 
         // Use items_with_context to get measured sizes (e.g., for FixedWidth mode)
         let mut items: Vec<ItemDescriptor> = self.provider.borrow().items_with_context(cx);
         items.sort_by_key(|item| item.z_index);
 
-        for item in &items {
+        // Prepare items nearest the viewport center first so their textures are
+        // requested first, but remember the z-order for painting.
+        let mut items: Vec<(usize, ItemDescriptor)> = {
+            let paint_order: HashMap<ItemId, usize> = items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| (item.id.clone(), index))
+                .collect();
+            sort_by_viewport_proximity(&mut items, &camera_val, viewport_size);
+            items
+                .into_iter()
+                .map(|item| (paint_order[&item.id], item))
+                .collect()
+        };
+
+        for (_, item) in &items {
             log::debug!(
                 "[Canvas] Item '{}': canvas_bounds={:?}",
                 item.id,
@@ -226,14 +701,29 @@ impl<P: CanvasItemsProvider + 'static> Element for CanvasElement<P> {
             );
         }
 
-        let mut item_elements: Vec<AnyElement> = Vec::new();
+        let mut item_elements: Vec<(usize, ItemId, Bounds<Pixels>, AnyElement)> = Vec::new();
+        let mut highlight = None;
 
-        for item in items {
+        for (paint_index, item) in items {
             // Check if item intersects visible area
-            if !item.bounds.intersects(&visible_canvas_bounds) {
+            if !item.intersects(&visible_canvas_bounds) {
                 continue;
             }
 
+            if let Some((id, y_range)) = &self.highlight {
+                if *id == item.id {
+                    highlight = highlight_bounds(&camera_val, item.bounds, y_range).map(|band| {
+                        Bounds::new(
+                            point(
+                                bounds.origin.x + band.origin.x,
+                                bounds.origin.y + band.origin.y,
+                            ),
+                            band.size,
+                        )
+                    });
+                }
+            }
+
             // Transform item bounds to screen space
             let screen_bounds = camera_val.canvas_to_screen_bounds(item.bounds);
             log::debug!(
@@ -275,16 +765,25 @@ impl<P: CanvasItemsProvider + 'static> Element for CanvasElement<P> {
                     window,
                     cx,
                 );
-                item_elements.push(element);
+                item_elements.push((paint_index, item.id, adjusted_bounds, element));
             }
         }
 
+        item_elements.sort_by_key(|(paint_index, ..)| *paint_index);
+        let (item_bounds, item_elements) = item_elements
+            .into_iter()
+            .map(|(_, id, bounds, element)| ((id, bounds), element))
+            .unzip();
+
         CanvasElementPrepaintState {
             hitbox,
             camera,
             is_panning,
             last_pan_position,
+            camera_change_gate,
             item_elements,
+            item_bounds,
+            highlight_bounds: highlight,
         }
     }
 
@@ -303,20 +802,31 @@ impl<P: CanvasItemsProvider + 'static> Element for CanvasElement<P> {
         let hitbox = &prepaint.hitbox;
 
         // Draw background
-        window.paint_quad(gpui::fill(bounds, gpui::rgb(0x1e1e1e)));
+        window.paint_quad(gpui::fill(bounds, self.background));
 
         // Draw background grid if enabled
         if options.show_grid {
             self.paint_grid(bounds, &camera, options, window);
         }
 
+        // Draw the origin axes on top of the grid
+        if options.show_axes {
+            self.paint_axes(bounds, &camera, window);
+        }
+
         // Paint all the item elements that were prepared during prepaint
         for element in &mut prepaint.item_elements {
             element.paint(window, cx);
         }
 
+        // Draw the highlight band over its item
+        if let Some(band) = prepaint.highlight_bounds {
+            let color = gpui::rgba(InfiniteCanvas::<P>::HIGHLIGHT_COLOR);
+            window.paint_quad(gpui::fill(band, color));
+        }
+
         // Set up mouse event handlers
-        self.setup_event_handlers(prepaint, hitbox.id, window);
+        self.setup_event_handlers(prepaint, hitbox.id, bounds, window);
     }
 }
 
@@ -372,44 +882,83 @@ impl<P: CanvasItemsProvider + 'static> CanvasElement<P> {
         }
     }
 
-    /// Set up mouse event handlers for pan and zoom.
+    /// Paint the x = 0 and y = 0 canvas lines.
+    fn paint_axes(&self, bounds: Bounds<Pixels>, camera: &Camera, window: &mut Window) {
+        let axis_color = gpui::rgba(0x4fc3f780);
+        let (x_axis, y_axis) = axis_positions(camera, bounds);
+
+        if let Some(y) = x_axis {
+            window.paint_quad(gpui::fill(
+                Bounds::new(
+                    point(bounds.origin.x, y - px(1.)),
+                    Size::new(bounds.size.width, px(2.)),
+                ),
+                axis_color,
+            ));
+        }
+
+        if let Some(x) = y_axis {
+            window.paint_quad(gpui::fill(
+                Bounds::new(
+                    point(x - px(1.), bounds.origin.y),
+                    Size::new(px(2.), bounds.size.height),
+                ),
+                axis_color,
+            ));
+        }
+    }
+
+    /// Set up mouse event handlers for pan, zoom and item clicks.
     fn setup_event_handlers(
         &self,
         prepaint: &CanvasElementPrepaintState,
         hitbox_id: gpui::HitboxId,
+        bounds: Bounds<Pixels>,
         window: &mut Window,
     ) {
         let options = &self.options;
         let view_id = window.current_view();
+        let viewport_size = bounds.size;
+        let listeners: Rc<[EventListener]> = self.listeners.clone().into();
+        let notifier = CameraChangeNotifier {
+            listeners: listeners.clone(),
+            interval: options.camera_change_debounce,
+            gate: prepaint.camera_change_gate.clone(),
+        };
+
+        // Camera changes go through the notifier so they can be throttled;
+        // everything else is delivered immediately.
+        let dispatch = move |events: Vec<CanvasEvent>, cx: &mut App| {
+            for event in events {
+                match event {
+                    CanvasEvent::CameraChanged(camera) => notifier.notify(camera, cx),
+                    event => emit(&notifier.listeners, &event),
+                }
+            }
+        };
 
         // Handle scroll wheel for zooming
         if !options.locked {
             let camera_rc = prepaint.camera.clone();
             let options_clone = options.clone();
-            let on_camera_change = self.on_camera_change.clone();
+            let dispatch = dispatch.clone();
 
             window.on_mouse_event(move |event: &ScrollWheelEvent, phase, window, cx| {
                 if phase.bubble()
                     && hitbox_id.is_hovered(window)
                     && options_clone.wheel_behavior.is_zoom()
                 {
-                    let mut camera = camera_rc.borrow_mut();
                     let delta = event.delta.pixel_delta(px(20.));
                     let zoom_factor = 1.0 - f32::from(delta.y) * options_clone.zoom_speed * 0.001;
 
-                    camera.zoom_around(
+                    let events = zoom_camera(
+                        &mut camera_rc.borrow_mut(),
                         zoom_factor,
                         event.position,
-                        options_clone.min_zoom,
-                        options_clone.max_zoom,
+                        &options_clone,
+                        viewport_size,
                     );
-
-                    let new_camera = *camera;
-                    drop(camera);
-
-                    if let Some(ref callback) = on_camera_change {
-                        callback(new_camera);
-                    }
+                    dispatch(events.into(), cx);
 
                     window.refresh();
                     cx.notify(view_id);
@@ -438,7 +987,8 @@ impl<P: CanvasItemsProvider + 'static> CanvasElement<P> {
             let camera_rc = prepaint.camera.clone();
             let is_panning = prepaint.is_panning.clone();
             let last_pan_position = prepaint.last_pan_position.clone();
-            let on_camera_change = self.on_camera_change.clone();
+            let options_clone = options.clone();
+            let dispatch = dispatch.clone();
 
             window.on_mouse_event(move |event: &MouseMoveEvent, phase, window, cx| {
                 if phase.bubble() {
@@ -448,16 +998,14 @@ impl<P: CanvasItemsProvider + 'static> CanvasElement<P> {
                         let delta =
                             point(event.position.x - last_pos.x, event.position.y - last_pos.y);
 
-                        let mut camera = camera_rc.borrow_mut();
-                        camera.pan(delta);
-                        let new_camera = *camera;
-                        drop(camera);
-
+                        let events = pan_camera(
+                            &mut camera_rc.borrow_mut(),
+                            delta,
+                            &options_clone,
+                            viewport_size,
+                        );
                         *last_pan_position.borrow_mut() = event.position;
-
-                        if let Some(ref callback) = on_camera_change {
-                            callback(new_camera);
-                        }
+                        dispatch(events.into(), cx);
 
                         window.refresh();
                         cx.notify(view_id);
@@ -476,5 +1024,412 @@ impl<P: CanvasItemsProvider + 'static> CanvasElement<P> {
                 }
             });
         }
+
+        // Take focus on click so key presses reach the canvas
+        if let Some(focus_handle) = self.focus_handle.clone() {
+            window.on_mouse_event(move |_event: &MouseDownEvent, phase, window, _cx| {
+                if phase.bubble() && hitbox_id.is_hovered(window) {
+                    focus_handle.focus(window);
+                }
+            });
+        }
+
+        // Handle keyboard shortcuts
+        if !options.locked && self.focus_handle.is_some() {
+            let camera_rc = prepaint.camera.clone();
+            let provider = self.provider.clone();
+            let options_clone = options.clone();
+            let dispatch = dispatch.clone();
+
+            window.on_key_event(move |event: &KeyDownEvent, phase, window, cx| {
+                if !phase.bubble() {
+                    return;
+                }
+                let Some(action) = options_clone.key_map.action_for(&event.keystroke) else {
+                    return;
+                };
+
+                let content_bounds = match action {
+                    CanvasAction::FitAll => provider.content_bounds(),
+                    _ => None,
+                };
+                let events = apply_key_action(
+                    &mut camera_rc.borrow_mut(),
+                    action,
+                    &options_clone,
+                    viewport_size,
+                    content_bounds,
+                );
+                if !events.is_empty() {
+                    dispatch(events, cx);
+                    window.refresh();
+                    cx.notify(view_id);
+                }
+                cx.stop_propagation();
+            });
+        }
+
+        // Handle left click on items
+        if !listeners.is_empty() {
+            let camera_rc = prepaint.camera.clone();
+            let item_bounds = prepaint.item_bounds.clone();
+
+            window.on_mouse_event(move |event: &MouseDownEvent, phase, window, _cx| {
+                if phase.bubble()
+                    && hitbox_id.is_hovered(window)
+                    && event.button == MouseButton::Left
+                {
+                    if let Some(id) = item_at(&item_bounds, event.position) {
+                        let position = camera_rc
+                            .borrow()
+                            .screen_to_canvas(event.position - bounds.origin);
+                        emit(
+                            &listeners,
+                            &CanvasEvent::ItemClicked {
+                                id: id.clone(),
+                                position,
+                            },
+                        );
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{point, px, size};
+
+    #[derive(Default)]
+    struct TestProvider {
+        items: Vec<ItemDescriptor>,
+    }
+
+    impl TestProvider {
+        fn add(&mut self, id: &str, x: f32) {
+            self.items.push(ItemDescriptor::new(
+                id,
+                Bounds::new(point(px(x), px(0.0)), size(px(10.0), px(10.0))),
+            ));
+        }
+    }
+
+    impl CanvasItemsProvider for TestProvider {
+        fn items(&self) -> Vec<ItemDescriptor> {
+            self.items.clone()
+        }
+
+        fn render_item(
+            &self,
+            _id: &ItemId,
+            _screen_bounds: Bounds<Pixels>,
+            _cx: &App,
+        ) -> Option<AnyElement> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_shared_provider_mutations_visible_through_clones() {
+        let first = SharedProvider::new(TestProvider::default());
+        let second = first.clone();
+        assert!(first.ptr_eq(&second));
+
+        first.borrow_mut().add("a", 0.0);
+        second.borrow_mut().add("b", 100.0);
+
+        assert_eq!(first.item_count(), 2);
+        assert_eq!(second.item_count(), 2);
+        let ids: Vec<_> = second.items().into_iter().map(|item| item.id).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(
+            first.content_bounds(),
+            Some(Bounds::new(
+                point(px(0.0), px(0.0)),
+                size(px(110.0), px(10.0))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_item_descriptor_accepts_str_and_string_ids() {
+        let bounds = Bounds::new(point(px(0.0), px(0.0)), size(px(10.0), px(10.0)));
+        let from_str = ItemDescriptor::new("diff-0", bounds);
+        let from_string = ItemDescriptor::with_z_index(format!("diff-{}", 0), bounds, 1);
+
+        assert_eq!(from_str.id, from_string.id);
+        assert_eq!(from_str.id, "diff-0");
+        assert_eq!(from_string.id.as_str(), "diff-0");
+        assert_eq!(from_string.id.to_string(), "diff-0");
+
+        // Maps keyed by `ItemId` can be queried with a plain `&str`
+        let paint_order: HashMap<ItemId, usize> = HashMap::from([(from_str.id, 0)]);
+        assert_eq!(paint_order.get("diff-0"), Some(&0));
+    }
+
+    #[test]
+    fn test_custom_background_reaches_element() {
+        let provider = SharedProvider::new(TestProvider::default());
+
+        let canvas = InfiniteCanvas::new("canvas", provider.clone());
+        let default: Hsla = gpui::rgb(InfiniteCanvas::<TestProvider>::DEFAULT_BACKGROUND).into();
+        assert_eq!(canvas.into_element().background, default);
+
+        let light: Hsla = gpui::rgb(0xffffff).into();
+        let canvas = InfiniteCanvas::new("canvas", provider).background(light);
+        assert_eq!(canvas.background, light);
+        assert_eq!(canvas.into_element().background, light);
+    }
+
+    #[test]
+    fn test_shared_provider_from_rc() {
+        let rc = Rc::new(RefCell::new(TestProvider::default()));
+        let shared = SharedProvider::from(rc.clone());
+
+        rc.borrow_mut().add("a", 0.0);
+        assert_eq!(shared.item_count(), 1);
+        assert!(!shared.is_empty());
+    }
+
+    #[test]
+    fn test_axis_positions_follow_camera() {
+        let bounds = Bounds::new(point(px(100.0), px(50.0)), size(px(800.0), px(600.0)));
+
+        // Canvas origin at (300, 200) in the viewport, regardless of zoom
+        let camera = Camera::with_offset_and_zoom(point(px(300.0), px(200.0)), 2.5);
+        assert_eq!(
+            axis_positions(&camera, bounds),
+            (Some(px(250.0)), Some(px(400.0)))
+        );
+
+        // Zooming out around (400, 300) pulls the origin halfway towards it
+        let mut camera = Camera::new();
+        camera.zoom_around(0.5, point(px(400.0), px(300.0)), 0.1, 8.0);
+        assert_eq!(
+            axis_positions(&camera, bounds),
+            (Some(px(200.0)), Some(px(300.0)))
+        );
+
+        // Panned so the origin is off to the left and below the viewport
+        let camera = Camera::with_offset_and_zoom(point(px(-10.0), px(700.0)), 1.0);
+        assert_eq!(axis_positions(&camera, bounds), (None, None));
+    }
+
+    #[test]
+    fn test_highlight_bounds_follow_camera() {
+        let item = Bounds::new(point(px(100.0), px(50.0)), size(px(200.0), px(300.0)));
+        let camera = Camera::with_offset_and_zoom(point(px(10.0), px(-20.0)), 2.0);
+
+        // 30..60 below the item's top is canvas y 80..110
+        assert_eq!(
+            highlight_bounds(&camera, item, &(px(30.0)..px(60.0))),
+            Some(Bounds::new(
+                point(px(210.0), px(140.0)),
+                size(px(400.0), px(60.0))
+            ))
+        );
+
+        // Clipped to the item
+        assert_eq!(
+            highlight_bounds(&camera, item, &(px(-10.0)..px(400.0))),
+            Some(camera.canvas_to_screen_bounds(item))
+        );
+        assert_eq!(
+            highlight_bounds(&camera, item, &(px(300.0)..px(320.0))),
+            None
+        );
+        assert_eq!(highlight_bounds(&camera, item, &(px(40.0)..px(40.0))), None);
+    }
+
+    #[test]
+    fn test_throttle_gate_fires_once_per_interval_with_trailing_call() {
+        let mut gate = ThrottleGate::default();
+        let interval = Duration::from_millis(100);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // The first change is delivered right away
+        assert_eq!(gate.push(1, at(0), interval), Throttled::Fire(1));
+        // Changes within the interval schedule one trailing call
+        assert_eq!(
+            gate.push(2, at(10), interval),
+            Throttled::Schedule(Duration::from_millis(90))
+        );
+        assert_eq!(gate.push(3, at(50), interval), Throttled::Held);
+        // The trailing call delivers the latest change exactly once
+        assert_eq!(gate.take_trailing(at(100)), Some(3));
+        assert_eq!(gate.take_trailing(at(100)), None);
+
+        // Once the interval has passed, a change is delivered immediately
+        assert_eq!(gate.push(4, at(250), interval), Throttled::Fire(4));
+    }
+
+    #[test]
+    fn test_throttle_gate_fires_during_continuous_changes() {
+        let mut gate = ThrottleGate::default();
+        let interval = Duration::from_millis(100);
+        let start = Instant::now();
+        let mut trailing_at = None;
+        let mut delivered = Vec::new();
+
+        // A change every 10ms for one second, never pausing
+        for ms in (0..1000).step_by(10) {
+            let now = start + Duration::from_millis(ms);
+            if trailing_at.is_some_and(|due| due <= now) {
+                trailing_at = None;
+                delivered.extend(gate.take_trailing(now));
+            }
+            match gate.push(ms, now, interval) {
+                Throttled::Fire(value) => delivered.push(value),
+                Throttled::Schedule(delay) => trailing_at = Some(now + delay),
+                Throttled::Held => {}
+            }
+        }
+        delivered.extend(gate.take_trailing(start + Duration::from_millis(1000)));
+
+        // One delivery per interval, ending with the last change
+        assert_eq!(delivered.len(), 11);
+        assert_eq!(delivered[0], 0);
+        assert_eq!(delivered.last(), Some(&990));
+    }
+
+    #[test]
+    fn test_interactions_emit_canvas_events() {
+        let options = CanvasOptions::default();
+        let viewport_size = size(px(800.0), px(600.0));
+        let mut camera = Camera::default();
+
+        let delta = point(px(30.0), px(-10.0));
+        let events = pan_camera(&mut camera, delta, &options, viewport_size);
+        assert_eq!(events[0], CanvasEvent::Panned { delta });
+        assert_eq!(events[1], CanvasEvent::CameraChanged(camera));
+        assert_eq!(camera.offset, delta);
+
+        let anchor = point(px(400.0), px(300.0));
+        let events = zoom_camera(&mut camera, 2.0, anchor, &options, viewport_size);
+        assert_eq!(events[0], CanvasEvent::Zoomed { zoom: 2.0, anchor });
+        assert_eq!(events[1], CanvasEvent::CameraChanged(camera));
+    }
+
+    #[test]
+    fn test_custom_key_map_drives_camera() {
+        use crate::keymap::KeyMap;
+        use gpui::Keystroke;
+
+        let options = CanvasOptions::default()
+            .key_map(KeyMap::new().bind(Keystroke::parse("k").unwrap(), CanvasAction::ZoomIn));
+        let viewport_size = size(px(800.0), px(600.0));
+        let mut camera = Camera::default();
+
+        // The default zoom-in key is no longer bound
+        assert_eq!(
+            options.key_map.action_for(&Keystroke::parse("=").unwrap()),
+            None
+        );
+        let action = options
+            .key_map
+            .action_for(&Keystroke::parse("k").unwrap())
+            .unwrap();
+        let events = apply_key_action(&mut camera, action, &options, viewport_size, None);
+        assert_eq!(camera.zoom, 1.5);
+        assert_eq!(events.last(), Some(&CanvasEvent::CameraChanged(camera)));
+
+        let events = apply_key_action(
+            &mut camera,
+            CanvasAction::ResetView,
+            &options,
+            viewport_size,
+            None,
+        );
+        assert_eq!(camera, Camera::default());
+        assert_eq!(events, vec![CanvasEvent::CameraChanged(camera)]);
+
+        let events = apply_key_action(
+            &mut camera,
+            CanvasAction::PanLeft,
+            &options,
+            viewport_size,
+            None,
+        );
+        assert_eq!(camera.offset, point(px(KEY_PAN_STEP), px(0.0)));
+        assert_eq!(events.len(), 2);
+
+        // Fitting with no content does nothing
+        let events = apply_key_action(
+            &mut camera,
+            CanvasAction::FitAll,
+            &options,
+            viewport_size,
+            None,
+        );
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_item_at_picks_topmost_item() {
+        let item_bounds = vec![
+            (
+                ItemId::from("below"),
+                Bounds::new(point(px(0.0), px(0.0)), size(px(100.0), px(100.0))),
+            ),
+            (
+                ItemId::from("above"),
+                Bounds::new(point(px(50.0), px(50.0)), size(px(100.0), px(100.0))),
+            ),
+        ];
+
+        let at = |x: f32, y: f32| item_at(&item_bounds, point(px(x), px(y))).map(ItemId::as_str);
+        assert_eq!(at(10.0, 10.0), Some("below"));
+        assert_eq!(at(75.0, 75.0), Some("above"));
+        assert_eq!(at(200.0, 200.0), None);
+    }
+
+    #[test]
+    fn test_content_centroid_is_area_weighted() {
+        let mut provider = TestProvider::default();
+        assert_eq!(provider.content_centroid(), None);
+
+        // A 100x100 item centered at (50, 50) and a 10x10 item centered at (505, 5)
+        provider.items.push(ItemDescriptor::new(
+            "big",
+            Bounds::new(point(px(0.0), px(0.0)), size(px(100.0), px(100.0))),
+        ));
+        provider.items.push(ItemDescriptor::new(
+            "small",
+            Bounds::new(point(px(500.0), px(0.0)), size(px(10.0), px(10.0))),
+        ));
+        // x = (10000 * 50 + 100 * 505) / 10100, y = (10000 * 50 + 100 * 5) / 10100
+        let centroid = provider.content_centroid().unwrap();
+        assert!((f32::from(centroid.x) - 54.5049).abs() < 0.01);
+        assert!((f32::from(centroid.y) - 49.5545).abs() < 0.01);
+
+        // A second big item outweighs the small one, pulling the centroid between the two
+        provider.items.push(ItemDescriptor::new(
+            "big-2",
+            Bounds::new(point(px(200.0), px(200.0)), size(px(100.0), px(100.0))),
+        ));
+        let centroid = provider.content_centroid().unwrap();
+        assert!((f32::from(centroid.x) - 151.7662).abs() < 0.01);
+        assert!((f32::from(centroid.y) - 149.2786).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_handle_center_on_content() {
+        let mut provider = TestProvider::default();
+        provider.add("a", 0.0);
+        provider.add("b", 100.0);
+
+        let handle = CanvasHandle::new();
+        handle.set_viewport_size(size(px(400.0), px(300.0)));
+        assert!(handle.center_on_content(&provider));
+
+        // Both items are 10x10, so the centroid is midway between their centers
+        assert_eq!(
+            handle.camera().canvas_to_screen(point(px(55.0), px(5.0))),
+            point(px(200.0), px(150.0))
+        );
+        assert!(!handle.center_on_content(&TestProvider::default()));
     }
 }
@@ -0,0 +1,38 @@
+//! Typed events emitted by the canvas.
+//!
+//! Hosts can subscribe to every interaction with `InfiniteCanvas::on_event`,
+//! or to a single kind with the narrower `on_camera_change` and
+//! `on_item_click` callbacks, which are filters over the same event stream.
+
+use gpui::{Pixels, Point};
+
+use crate::camera::Camera;
+use crate::provider::ItemId;
+
+/// An interaction on the canvas.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CanvasEvent {
+    /// The camera moved or zoomed. Carries the new camera state.
+    ///
+    /// Throttled when `CanvasOptions::camera_change_debounce` is set.
+    CameraChanged(Camera),
+    /// The canvas was panned by a screen-space delta.
+    Panned {
+        /// Screen-space distance the content moved.
+        delta: Point<Pixels>,
+    },
+    /// The canvas was zoomed.
+    Zoomed {
+        /// The zoom level after the change.
+        zoom: f32,
+        /// The window position that was kept fixed.
+        anchor: Point<Pixels>,
+    },
+    /// An item was clicked with the left mouse button.
+    ItemClicked {
+        /// The topmost item under the cursor.
+        id: ItemId,
+        /// The click position in canvas coordinates.
+        position: Point<Pixels>,
+    },
+}
@@ -0,0 +1,160 @@
+//! Handle for controlling a canvas from outside the canvas element.
+//!
+//! The canvas normally keeps its camera in GPUI element state, which the
+//! host view can't reach. Passing a `CanvasHandle` to `InfiniteCanvas::handle`
+//! makes the canvas use the handle's camera instead, so the host can read and
+//! move the camera (e.g. to reveal a search result).
+
+use gpui::{Pixels, Point, Size};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::camera::Camera;
+use crate::provider::CanvasItemsProvider;
+
+/// A cloneable handle to a canvas' camera.
+///
+/// Changes made through the handle take effect on the canvas' next render, so
+/// callers should notify their view after moving the camera.
+#[derive(Clone, Default)]
+pub struct CanvasHandle {
+    /// The camera shared with the canvas element.
+    camera: Rc<RefCell<Camera>>,
+    /// The viewport size from the canvas' last prepaint.
+    viewport_size: Rc<Cell<Size<Pixels>>>,
+}
+
+impl CanvasHandle {
+    /// Create a handle with a default camera.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a handle with a specific initial camera.
+    pub fn with_camera(camera: Camera) -> Self {
+        Self {
+            camera: Rc::new(RefCell::new(camera)),
+            viewport_size: Rc::default(),
+        }
+    }
+
+    /// Get the current camera state.
+    pub fn camera(&self) -> Camera {
+        *self.camera.borrow()
+    }
+
+    /// Replace the camera state.
+    pub fn set_camera(&self, camera: Camera) {
+        *self.camera.borrow_mut() = camera;
+    }
+
+    /// Get the viewport size the canvas was last laid out with.
+    ///
+    /// Zero until the canvas has been rendered once.
+    pub fn viewport_size(&self) -> Size<Pixels> {
+        self.viewport_size.get()
+    }
+
+    /// Pan the camera so a canvas point is at the center of the viewport.
+    pub fn center_on(&self, canvas_point: Point<Pixels>) {
+        let viewport_size = self.viewport_size();
+        self.camera
+            .borrow_mut()
+            .center_on(canvas_point, viewport_size);
+    }
+
+    /// Zoom so `world_unit` canvas pixels span `screen_pixels`, keeping the viewport center fixed.
+    ///
+    /// The zoom is clamped to `min_zoom..=max_zoom`. Does nothing if
+    /// `world_unit` isn't positive.
+    pub fn set_scale(
+        &self,
+        world_unit: Pixels,
+        screen_pixels: Pixels,
+        min_zoom: f32,
+        max_zoom: f32,
+    ) {
+        if let Some(zoom) = Camera::zoom_for_scale(world_unit, screen_pixels) {
+            let viewport_size = self.viewport_size();
+            self.camera
+                .borrow_mut()
+                .set_zoom(zoom, viewport_size, min_zoom, max_zoom);
+        }
+    }
+
+    /// Pan the camera so the provider's content centroid is centered.
+    ///
+    /// Unlike a bounding-box fit, this frames where most of the content is,
+    /// and leaves the zoom unchanged. Returns `false` if the provider has no
+    /// items, in which case the camera is not moved.
+    pub fn center_on_content<P: CanvasItemsProvider + ?Sized>(&self, provider: &P) -> bool {
+        match provider.content_centroid() {
+            Some(centroid) => {
+                self.center_on(centroid);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The shared camera cell, used by the canvas element.
+    pub(crate) fn camera_cell(&self) -> Rc<RefCell<Camera>> {
+        self.camera.clone()
+    }
+
+    /// Record the viewport size, called by the canvas element on prepaint.
+    pub(crate) fn set_viewport_size(&self, viewport_size: Size<Pixels>) {
+        self.viewport_size.set(viewport_size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{point, px, size};
+
+    #[test]
+    fn test_clones_share_camera() {
+        let handle = CanvasHandle::new();
+        let other = handle.clone();
+
+        other.set_camera(Camera::with_offset_and_zoom(point(px(10.0), px(20.0)), 2.0));
+
+        assert_eq!(handle.camera().zoom, 2.0);
+        assert_eq!(handle.camera().offset, point(px(10.0), px(20.0)));
+    }
+
+    #[test]
+    fn test_set_scale_keeps_center() {
+        let handle = CanvasHandle::new();
+        handle.set_viewport_size(size(px(800.0), px(600.0)));
+        let center = handle
+            .camera()
+            .screen_to_canvas(point(px(400.0), px(300.0)));
+
+        handle.set_scale(px(10.0), px(40.0), 0.1, 8.0);
+
+        let camera = handle.camera();
+        assert_eq!(camera.zoom, 4.0);
+        assert_eq!(camera.canvas_to_screen(center), point(px(400.0), px(300.0)));
+
+        // Clamped to the zoom limits
+        handle.set_scale(px(1.0), px(100.0), 0.1, 8.0);
+        assert_eq!(handle.camera().zoom, 8.0);
+    }
+
+    #[test]
+    fn test_center_on_uses_last_viewport_size() {
+        let handle =
+            CanvasHandle::with_camera(Camera::with_offset_and_zoom(point(px(0.0), px(0.0)), 2.0));
+        handle.set_viewport_size(size(px(800.0), px(600.0)));
+
+        handle.center_on(point(px(100.0), px(50.0)));
+
+        let camera = handle.camera();
+        assert_eq!(
+            camera.canvas_to_screen(point(px(100.0), px(50.0))),
+            point(px(400.0), px(300.0))
+        );
+    }
+}
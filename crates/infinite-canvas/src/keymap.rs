@@ -0,0 +1,174 @@
+//! Keyboard shortcuts for the canvas.
+//!
+//! A `KeyMap` maps keystrokes to named `CanvasAction`s. The canvas consults
+//! the map set in `CanvasOptions::key_map` when a key is pressed while it has
+//! focus, so hosts can replace or extend the default shortcuts.
+
+use gpui::Keystroke;
+use serde::{Deserialize, Serialize};
+
+/// A camera action that can be bound to a keystroke.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CanvasAction {
+    /// Zoom in to the next step in `CanvasOptions::zoom_steps`.
+    ZoomIn,
+    /// Zoom out to the previous step in `CanvasOptions::zoom_steps`.
+    ZoomOut,
+    /// Reset the camera to no pan and 100% zoom.
+    ResetView,
+    /// Zoom and pan so all items fit in the viewport.
+    FitAll,
+    /// Move the view left.
+    PanLeft,
+    /// Move the view right.
+    PanRight,
+    /// Move the view up.
+    PanUp,
+    /// Move the view down.
+    PanDown,
+}
+
+/// Maps keystrokes to canvas actions.
+///
+/// Keystrokes match on their key and modifiers. Binding a keystroke that is
+/// already bound replaces its action.
+///
+/// # Example
+///
+/// ```ignore
+/// let key_map = KeyMap::default()
+///     .bind(Keystroke::parse("ctrl-=").unwrap(), CanvasAction::ZoomIn)
+///     .unbind_action(CanvasAction::FitAll);
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(
+    into = "Vec<(String, CanvasAction)>",
+    try_from = "Vec<(String, CanvasAction)>"
+)]
+pub struct KeyMap {
+    bindings: Vec<(Keystroke, CanvasAction)>,
+}
+
+impl KeyMap {
+    /// Create an empty key map with no shortcuts.
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Bind a keystroke to an action, replacing any existing binding for it.
+    pub fn bind(mut self, keystroke: Keystroke, action: CanvasAction) -> Self {
+        self.bindings
+            .retain(|(bound, _)| !same_keystroke(bound, &keystroke));
+        self.bindings.push((keystroke, action));
+        self
+    }
+
+    /// Remove every keystroke bound to an action.
+    pub fn unbind_action(mut self, action: CanvasAction) -> Self {
+        self.bindings.retain(|(_, bound)| *bound != action);
+        self
+    }
+
+    /// Get the action bound to a keystroke, if any.
+    pub fn action_for(&self, keystroke: &Keystroke) -> Option<CanvasAction> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| same_keystroke(bound, keystroke))
+            .map(|(_, action)| *action)
+    }
+
+    /// Get the keystrokes bound to an action.
+    pub fn keystrokes_for(&self, action: CanvasAction) -> impl Iterator<Item = &Keystroke> {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|(keystroke, _)| keystroke)
+    }
+}
+
+impl Default for KeyMap {
+    /// The default shortcuts: `=`/`-` to zoom, `0` to reset, `f` to fit all
+    /// items, and the arrow keys to pan.
+    fn default() -> Self {
+        [
+            ("=", CanvasAction::ZoomIn),
+            ("-", CanvasAction::ZoomOut),
+            ("0", CanvasAction::ResetView),
+            ("f", CanvasAction::FitAll),
+            ("left", CanvasAction::PanLeft),
+            ("right", CanvasAction::PanRight),
+            ("up", CanvasAction::PanUp),
+            ("down", CanvasAction::PanDown),
+        ]
+        .into_iter()
+        .fold(Self::new(), |map, (keystroke, action)| {
+            map.bind(
+                Keystroke::parse(keystroke).expect("default keystrokes are valid"),
+                action,
+            )
+        })
+    }
+}
+
+impl From<KeyMap> for Vec<(String, CanvasAction)> {
+    fn from(map: KeyMap) -> Self {
+        map.bindings
+            .into_iter()
+            .map(|(keystroke, action)| (keystroke.unparse(), action))
+            .collect()
+    }
+}
+
+impl TryFrom<Vec<(String, CanvasAction)>> for KeyMap {
+    type Error = gpui::InvalidKeystrokeError;
+
+    fn try_from(bindings: Vec<(String, CanvasAction)>) -> Result<Self, Self::Error> {
+        bindings
+            .into_iter()
+            .try_fold(Self::new(), |map, (keystroke, action)| {
+                Ok(map.bind(Keystroke::parse(&keystroke)?, action))
+            })
+    }
+}
+
+/// Whether two keystrokes press the same key with the same modifiers.
+fn same_keystroke(a: &Keystroke, b: &Keystroke) -> bool {
+    a.key == b.key && a.modifiers == b.modifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(source: &str) -> Keystroke {
+        Keystroke::parse(source).unwrap()
+    }
+
+    #[test]
+    fn test_default_key_map() {
+        let map = KeyMap::default();
+        assert_eq!(map.action_for(&key("=")), Some(CanvasAction::ZoomIn));
+        assert_eq!(map.action_for(&key("-")), Some(CanvasAction::ZoomOut));
+        assert_eq!(map.action_for(&key("left")), Some(CanvasAction::PanLeft));
+        // Modifiers must match too
+        assert_eq!(map.action_for(&key("ctrl-=")), None);
+    }
+
+    #[test]
+    fn test_custom_key_map_routes_keystrokes() {
+        let map = KeyMap::new()
+            .bind(key("ctrl-="), CanvasAction::ZoomIn)
+            .bind(key("h"), CanvasAction::PanLeft)
+            .bind(key("h"), CanvasAction::FitAll);
+
+        assert_eq!(map.action_for(&key("ctrl-=")), Some(CanvasAction::ZoomIn));
+        assert_eq!(map.action_for(&key("h")), Some(CanvasAction::FitAll));
+        assert_eq!(map.action_for(&key("=")), None);
+        assert_eq!(map.keystrokes_for(CanvasAction::PanLeft).count(), 0);
+
+        let map = map.unbind_action(CanvasAction::ZoomIn);
+        assert_eq!(map.action_for(&key("ctrl-=")), None);
+    }
+}
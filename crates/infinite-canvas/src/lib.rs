@@ -9,7 +9,10 @@
 //! - **`CanvasItemsProvider`** - Trait for providing items to the canvas
 //! - **`TexturedCanvasItemsProvider`** - Provider that renders items as zoomable textures
 //! - **`Camera`** - Viewport state (offset, zoom) with coordinate transforms
+//! - **`CanvasHandle`** - Shared camera for moving the viewport from the host view
+//! - **`CanvasEvent`** - Typed events for camera changes and item clicks
 //! - **`CanvasOptions`** - Configuration for zoom limits, grid, etc.
+//! - **`KeyMap`** - Keyboard shortcuts mapped to `CanvasAction`s
 //!
 //! # Example
 //!
@@ -30,25 +33,41 @@
 
 mod camera;
 mod canvas;
+mod event;
+mod handle;
+mod keymap;
 mod options;
+mod png;
 mod provider;
 mod textured_provider;
 
 pub use camera::Camera;
 pub use canvas::{CanvasElement, InfiniteCanvas, SharedProvider};
+pub use event::CanvasEvent;
+pub use handle::CanvasHandle;
+pub use keymap::{CanvasAction, KeyMap};
 pub use options::{
     CameraConstraints, CanvasOptions, ConstraintBehavior, ConstraintBounds, WheelBehavior,
 };
-pub use provider::{CanvasItemsProvider, ItemDescriptor, ItemId};
-pub use textured_provider::{ItemSizing, TexturedCanvasItemsProvider};
+pub use png::{save_bgra_png, save_rgba_png};
+pub use provider::{sort_by_viewport_proximity, CanvasItemsProvider, ItemDescriptor, ItemId};
+pub use textured_provider::{
+    pixels_to_render_image, render_image_to_rgba, InitialSizeMode, ItemSizing, ProviderStatus,
+    TexturedCanvasItemsProvider, DEFAULT_MAX_CONCURRENT_RENDERS,
+};
 
 /// Re-export commonly used types.
 pub mod prelude {
     pub use crate::camera::Camera;
     pub use crate::canvas::{InfiniteCanvas, SharedProvider};
+    pub use crate::event::CanvasEvent;
+    pub use crate::handle::CanvasHandle;
+    pub use crate::keymap::{CanvasAction, KeyMap};
     pub use crate::options::CanvasOptions;
     pub use crate::provider::{CanvasItemsProvider, ItemDescriptor, ItemId};
-    pub use crate::textured_provider::{ItemSizing, TexturedCanvasItemsProvider};
+    pub use crate::textured_provider::{
+        InitialSizeMode, ItemSizing, ProviderStatus, TexturedCanvasItemsProvider,
+    };
 }
 
 /// Initialize the infinite canvas component.
@@ -4,8 +4,12 @@
 //! various aspects of canvas behavior including zoom limits, pan/zoom
 //! speeds, grid display, and input handling.
 
-use gpui::{px, Pixels};
+use gpui::{px, Pixels, Size};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::camera::Camera;
+use crate::keymap::KeyMap;
 
 /// Configuration options for an infinite canvas.
 ///
@@ -35,6 +39,10 @@ pub struct CanvasOptions {
     /// Grid cell size in canvas units.
     pub grid_size: Pixels,
 
+    /// Whether to highlight the x = 0 and y = 0 lines so the origin is visible.
+    #[serde(default)]
+    pub show_axes: bool,
+
     /// Whether the camera is locked (prevents pan/zoom).
     pub locked: bool,
 
@@ -46,6 +54,22 @@ pub struct CanvasOptions {
 
     /// Friction coefficient for inertial panning (0.0-1.0, higher = more friction).
     pub inertia_friction: f32,
+
+    /// Constraints applied to the camera after every pan or zoom.
+    #[serde(default)]
+    pub constraints: CameraConstraints,
+
+    /// Minimum interval between `on_camera_change` calls.
+    ///
+    /// When set, the callback fires at most once per interval, with a trailing
+    /// call carrying the latest camera, so it keeps firing during continuous
+    /// input. The camera itself still updates immediately. `None` fires on
+    /// every change.
+    pub camera_change_debounce: Option<Duration>,
+
+    /// Keyboard shortcuts, used while the canvas has focus.
+    #[serde(default)]
+    pub key_map: KeyMap,
 }
 
 impl Default for CanvasOptions {
@@ -58,10 +82,14 @@ impl Default for CanvasOptions {
             zoom_speed: 1.0,
             show_grid: true,
             grid_size: px(20.0),
+            show_axes: false,
             locked: false,
             wheel_behavior: WheelBehavior::default(),
             inertia_enabled: false,
             inertia_friction: 0.92,
+            constraints: CameraConstraints::default(),
+            camera_change_debounce: None,
+            key_map: KeyMap::default(),
         }
     }
 }
@@ -121,6 +149,12 @@ impl CanvasOptions {
         self
     }
 
+    /// Enable or disable the origin axes.
+    pub fn show_axes(mut self, show: bool) -> Self {
+        self.show_axes = show;
+        self
+    }
+
     /// Lock or unlock the camera.
     pub fn locked(mut self, locked: bool) -> Self {
         self.locked = locked;
@@ -144,6 +178,24 @@ impl CanvasOptions {
         self.inertia_friction = friction.clamp(0.0, 1.0);
         self
     }
+
+    /// Set the camera constraints.
+    pub fn constraints(mut self, constraints: CameraConstraints) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
+    /// Throttle the camera change callback to at most once per interval.
+    pub fn camera_change_debounce(mut self, interval: Duration) -> Self {
+        self.camera_change_debounce = Some(interval);
+        self
+    }
+
+    /// Set the keyboard shortcuts.
+    pub fn key_map(mut self, key_map: KeyMap) -> Self {
+        self.key_map = key_map;
+        self
+    }
 }
 
 /// Behavior when using the scroll wheel.
@@ -180,14 +232,29 @@ impl WheelBehavior {
 /// Constraints on camera movement.
 ///
 /// These can be used to limit the camera to specific bounds
-/// or behaviors.
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+/// or behaviors. Fields missing from saved constraints take their defaults.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CameraConstraints {
     /// Optional bounds that constrain camera movement.
     pub bounds: Option<ConstraintBounds>,
 
     /// How the constraints are applied.
     pub behavior: ConstraintBehavior,
+
+    /// Minimum overlap in screen pixels kept between the bounds and the
+    /// viewport on each axis (used by `KeepContentVisible`).
+    pub min_visible: f32,
+}
+
+impl Default for CameraConstraints {
+    fn default() -> Self {
+        Self {
+            bounds: None,
+            behavior: ConstraintBehavior::default(),
+            min_visible: 50.0,
+        }
+    }
 }
 
 impl CameraConstraints {
@@ -201,8 +268,77 @@ impl CameraConstraints {
         Self {
             bounds: Some(bounds),
             behavior,
+            ..Self::default()
+        }
+    }
+
+    /// Create constraints that keep part of the content bounds on screen.
+    pub fn keep_content_visible(content_bounds: ConstraintBounds, min_visible: f32) -> Self {
+        Self {
+            bounds: Some(content_bounds),
+            behavior: ConstraintBehavior::KeepContentVisible,
+            min_visible,
+        }
+    }
+
+    /// Set the minimum visible overlap in screen pixels.
+    pub fn min_visible(mut self, min_visible: f32) -> Self {
+        self.min_visible = min_visible;
+        self
+    }
+
+    /// Clamp the camera so that it satisfies these constraints.
+    ///
+    /// Only `KeepContentVisible` is currently enforced; other behaviors
+    /// leave the camera unchanged.
+    pub fn apply(&self, camera: &mut Camera, viewport_size: Size<Pixels>) {
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+
+        if self.behavior == ConstraintBehavior::KeepContentVisible {
+            let (min_x, max_x) = Self::keep_visible_range(
+                bounds.min_x,
+                bounds.max_x,
+                camera.zoom,
+                viewport_size.width.into(),
+                self.min_visible,
+            );
+            let (min_y, max_y) = Self::keep_visible_range(
+                bounds.min_y,
+                bounds.max_y,
+                camera.zoom,
+                viewport_size.height.into(),
+                self.min_visible,
+            );
+
+            let offset_x: f32 = camera.offset.x.into();
+            let offset_y: f32 = camera.offset.y.into();
+            camera.offset.x = px(offset_x.clamp(min_x, max_x));
+            camera.offset.y = px(offset_y.clamp(min_y, max_y));
         }
     }
+
+    /// Allowed camera offset range on one axis that keeps `min_visible` pixels
+    /// of the content span `[content_min, content_max]` inside the viewport.
+    fn keep_visible_range(
+        content_min: f32,
+        content_max: f32,
+        zoom: f32,
+        viewport: f32,
+        min_visible: f32,
+    ) -> (f32, f32) {
+        // Never require more overlap than the content or viewport can provide
+        let content_extent = (content_max - content_min).max(0.0) * zoom;
+        let overlap = min_visible.min(content_extent).min(viewport).max(0.0);
+
+        // Content's far edge must be at least `overlap` past the viewport's
+        // left/top edge, and its near edge at least `overlap` before the
+        // right/bottom edge.
+        let min_offset = overlap - content_max * zoom;
+        let max_offset = viewport - overlap - content_min * zoom;
+        (min_offset, max_offset)
+    }
 }
 
 /// Bounds for constraining camera movement.
@@ -258,11 +394,16 @@ pub enum ConstraintBehavior {
 
     /// Camera adjusts zoom to contain the bounds.
     Contain,
+
+    /// Camera can move freely, but never so far that the bounds (typically
+    /// the content bounding box) leave the viewport entirely.
+    KeepContentVisible,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use gpui::{point, size};
 
     #[test]
     fn test_default_options() {
@@ -304,4 +445,84 @@ mod tests {
         let options = CanvasOptions::new().inertia_friction(-0.5);
         assert_eq!(options.inertia_friction, 0.0);
     }
+
+    #[test]
+    fn test_keep_content_visible_pulls_camera_back() {
+        let content = ConstraintBounds::from_origin_size(0.0, 0.0, 1000.0, 500.0);
+        let constraints = CameraConstraints::keep_content_visible(content, 50.0);
+        let viewport = size(px(800.), px(600.));
+
+        // Pan far to the right/bottom: content is pushed off the top-left
+        let mut camera = Camera::default();
+        camera.pan(point(px(-10_000.), px(-10_000.)));
+        constraints.apply(&mut camera, viewport);
+        assert_eq!(camera.offset.x, px(50. - 1000.));
+        assert_eq!(camera.offset.y, px(50. - 500.));
+
+        // Pan far to the left/top at 2x zoom: content is pushed off the bottom-right
+        let mut camera = Camera::with_offset_and_zoom(point(px(10_000.), px(10_000.)), 2.0);
+        constraints.apply(&mut camera, viewport);
+        assert_eq!(camera.offset.x, px(800. - 50.));
+        assert_eq!(camera.offset.y, px(600. - 50.));
+
+        let visible = camera.visible_canvas_bounds(viewport);
+        let overlap_x: f32 = (visible.origin.x + visible.size.width).into();
+        assert_eq!(overlap_x * camera.zoom, 50.);
+    }
+
+    #[test]
+    fn test_keep_content_visible_leaves_valid_camera_alone() {
+        let content = ConstraintBounds::from_origin_size(0.0, 0.0, 1000.0, 500.0);
+        let constraints = CameraConstraints::keep_content_visible(content, 50.0);
+
+        let mut camera = Camera::with_offset_and_zoom(point(px(-200.), px(100.)), 1.0);
+        let before = camera;
+        constraints.apply(&mut camera, size(px(800.), px(600.)));
+        assert_eq!(camera, before);
+    }
+
+    #[test]
+    fn test_free_constraints_do_nothing() {
+        let constraints = CameraConstraints::none();
+        let mut camera = Camera::with_offset_and_zoom(point(px(-1e6), px(1e6)), 1.0);
+        let before = camera;
+        constraints.apply(&mut camera, size(px(800.), px(600.)));
+        assert_eq!(camera, before);
+    }
+
+    /// Serialize `value` and drop `key` from it, as saved before `key` existed
+    fn saved_without(value: impl Serialize, key: &str) -> serde_json::Value {
+        let mut saved = serde_json::to_value(value).unwrap();
+        saved.as_object_mut().unwrap().remove(key);
+        saved
+    }
+
+    #[test]
+    fn test_options_saved_without_constraints_still_load() {
+        let saved = saved_without(CanvasOptions::default(), "constraints");
+        let options: CanvasOptions = serde_json::from_value(saved).unwrap();
+        assert_eq!(options.constraints.behavior, ConstraintBehavior::Free);
+        assert_eq!(options.constraints.min_visible, 50.0);
+
+        let bounds = ConstraintBounds::new(0.0, 0.0, 1000.0, 1000.0);
+        let constraints = CameraConstraints::with_bounds(bounds, ConstraintBehavior::Inside);
+        let saved = saved_without(constraints, "min_visible");
+        let constraints: CameraConstraints = serde_json::from_value(saved).unwrap();
+        assert_eq!(constraints.behavior, ConstraintBehavior::Inside);
+        assert_eq!(constraints.min_visible, 50.0);
+    }
+
+    #[test]
+    fn test_options_saved_without_key_map_use_default_shortcuts() {
+        let saved = saved_without(CanvasOptions::default(), "key_map");
+        let options: CanvasOptions = serde_json::from_value(saved).unwrap();
+        assert_eq!(options.key_map, KeyMap::default());
+    }
+
+    #[test]
+    fn test_options_saved_without_show_axes_hide_them() {
+        let saved = saved_without(CanvasOptions::new().show_axes(true), "show_axes");
+        let options: CanvasOptions = serde_json::from_value(saved).unwrap();
+        assert!(!options.show_axes);
+    }
 }
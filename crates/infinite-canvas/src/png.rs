@@ -0,0 +1,98 @@
+//! Saving raw pixel buffers as PNG files.
+//!
+//! Canvas textures are BGRA, as GPUI stores them, while most other pixel data
+//! is RGBA, so both layouts are accepted. Buffers whose length doesn't match
+//! their dimensions are reported as errors rather than panicking in the
+//! encoder.
+
+use std::path::Path;
+
+use image::error::{ImageError, ParameterError, ParameterErrorKind};
+use image::{ImageFormat, RgbaImage};
+
+/// Save tightly packed RGBA pixels as a PNG file.
+///
+/// Fails if `pixels` doesn't hold exactly `width * height` pixels, or if the
+/// file can't be written.
+pub fn save_rgba_png(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    path: impl AsRef<Path>,
+) -> Result<(), ImageError> {
+    let buffer = RgbaImage::from_raw(width, height, pixels.to_vec()).ok_or_else(|| {
+        ImageError::Parameter(ParameterError::from_kind(
+            ParameterErrorKind::DimensionMismatch,
+        ))
+    })?;
+    buffer.save_with_format(path, ImageFormat::Png)
+}
+
+/// Save tightly packed BGRA pixels, such as a canvas texture, as a PNG file.
+///
+/// The red and blue channels are swapped before saving, so the file holds
+/// ordinary RGBA. Fails like `save_rgba_png`.
+pub fn save_bgra_png(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    path: impl AsRef<Path>,
+) -> Result<(), ImageError> {
+    let mut rgba = pixels.to_vec();
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+    save_rgba_png(&rgba, width, height, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2x2 image: red, green / blue, half-transparent white
+    const RGBA: [u8; 16] = [
+        255, 0, 0, 255, 0, 255, 0, 255, //
+        0, 0, 255, 255, 255, 255, 255, 128,
+    ];
+
+    fn reload(path: &Path) -> Vec<u8> {
+        let image = image::open(path).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (2, 2));
+        image.into_raw()
+    }
+
+    #[test]
+    fn test_save_rgba_png_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("rgba.png");
+
+        save_rgba_png(&RGBA, 2, 2, &path).unwrap();
+        assert_eq!(reload(&path), RGBA);
+    }
+
+    #[test]
+    fn test_save_bgra_png_swaps_channels() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("bgra.png");
+
+        let mut bgra = RGBA;
+        for pixel in bgra.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        save_bgra_png(&bgra, 2, 2, &path).unwrap();
+        assert_eq!(reload(&path), RGBA);
+    }
+
+    #[test]
+    fn test_save_png_rejects_mismatched_buffers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("bad.png");
+
+        assert!(matches!(
+            save_rgba_png(&RGBA[..12], 2, 2, &path),
+            Err(ImageError::Parameter(_))
+        ));
+        assert!(save_bgra_png(&RGBA, 3, 2, &path).is_err());
+        assert!(!path.exists());
+    }
+}
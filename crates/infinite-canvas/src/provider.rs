@@ -3,10 +3,98 @@
 //! This module defines the `CanvasItemsProvider` trait which abstracts
 //! over different ways of providing items to an `InfiniteCanvas`.
 
-use gpui::{AnyElement, App, Bounds, Pixels, Point};
+use std::borrow::Borrow;
+use std::fmt;
+
+use gpui::{AnyElement, App, Bounds, Pixels, Point, Size};
+
+use crate::camera::Camera;
 
 /// Unique identifier for a canvas item.
-pub type ItemId = String;
+///
+/// A thin wrapper around `String` that keeps item ids distinct from other
+/// strings. Converts from `&str` and `String`, so APIs taking
+/// `impl Into<ItemId>` accept either. Borrows as `str`, so maps keyed by
+/// `ItemId` can be queried with a plain `&str`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ItemId(String);
+
+impl ItemId {
+    /// Create an item id.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    /// Get the id as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Convert the id into its underlying `String`.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl From<&str> for ItemId {
+    fn from(id: &str) -> Self {
+        Self(id.to_owned())
+    }
+}
+
+impl From<String> for ItemId {
+    fn from(id: String) -> Self {
+        Self(id)
+    }
+}
+
+impl From<&String> for ItemId {
+    fn from(id: &String) -> Self {
+        Self(id.clone())
+    }
+}
+
+impl From<&ItemId> for ItemId {
+    fn from(id: &ItemId) -> Self {
+        id.clone()
+    }
+}
+
+impl From<ItemId> for String {
+    fn from(id: ItemId) -> Self {
+        id.0
+    }
+}
+
+impl AsRef<str> for ItemId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for ItemId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ItemId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for ItemId {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for ItemId {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
 
 /// Describes a canvas item's position and bounds.
 #[derive(Clone, Debug)]
@@ -21,7 +109,7 @@ pub struct ItemDescriptor {
 
 impl ItemDescriptor {
     /// Create a new item descriptor.
-    pub fn new(id: impl Into<String>, bounds: Bounds<Pixels>) -> Self {
+    pub fn new(id: impl Into<ItemId>, bounds: Bounds<Pixels>) -> Self {
         Self {
             id: id.into(),
             bounds,
@@ -30,7 +118,7 @@ impl ItemDescriptor {
     }
 
     /// Create a new item descriptor with z-index.
-    pub fn with_z_index(id: impl Into<String>, bounds: Bounds<Pixels>, z_index: i32) -> Self {
+    pub fn with_z_index(id: impl Into<ItemId>, bounds: Bounds<Pixels>, z_index: i32) -> Self {
         Self {
             id: id.into(),
             bounds,
@@ -42,6 +130,51 @@ impl ItemDescriptor {
     pub fn origin(&self) -> Point<Pixels> {
         self.bounds.origin
     }
+
+    /// Whether this item overlaps `bounds`, as used for culling.
+    pub fn intersects(&self, bounds: &Bounds<Pixels>) -> bool {
+        self.bounds.intersects(bounds)
+    }
+
+    /// Whether `point` (in canvas space) falls on this item, for hit testing.
+    ///
+    /// Points on the item's border count as inside.
+    pub fn contains_point(&self, point: Point<Pixels>) -> bool {
+        let (min, max) = (self.bounds.origin, self.bounds.bottom_right());
+        point.x >= min.x && point.x <= max.x && point.y >= min.y && point.y <= max.y
+    }
+
+    /// Whether this item lies entirely within `bounds`, for marquee selection.
+    ///
+    /// An item touching the edge of `bounds` from the inside counts as
+    /// contained.
+    pub fn contained_by(&self, bounds: &Bounds<Pixels>) -> bool {
+        let (min, max) = (self.bounds.origin, self.bounds.bottom_right());
+        let (outer_min, outer_max) = (bounds.origin, bounds.bottom_right());
+        min.x >= outer_min.x && min.y >= outer_min.y && max.x <= outer_max.x && max.y <= outer_max.y
+    }
+}
+
+/// Order items so those nearest the center of the viewport come first.
+///
+/// Distance is measured from each item's center to the center of the visible
+/// canvas area. Items at equal distance keep their relative order. The canvas
+/// uses this to prepare items (and so dispatch their texture renders) nearest
+/// the viewport center first, which improves perceived load speed.
+pub fn sort_by_viewport_proximity(
+    items: &mut [ItemDescriptor],
+    camera: &Camera,
+    viewport_size: Size<Pixels>,
+) {
+    let center = camera.visible_canvas_bounds(viewport_size).center();
+    let distance_sq = |item: &ItemDescriptor| {
+        let item_center = item.bounds.center();
+        let dx: f32 = (item_center.x - center.x).into();
+        let dy: f32 = (item_center.y - center.y).into();
+        dx * dx + dy * dy
+    };
+
+    items.sort_by(|a, b| distance_sq(a).total_cmp(&distance_sq(b)));
 }
 
 /// Trait for providing items to an `InfiniteCanvas`.
@@ -69,7 +202,7 @@ impl ItemDescriptor {
 ///         self.items.iter().map(|item| ItemDescriptor::new(&item.id, item.bounds)).collect()
 ///     }
 ///
-///     fn render_item(&self, id: &str, screen_bounds: Bounds<Pixels>, cx: &App) -> Option<AnyElement> {
+///     fn render_item(&self, id: &ItemId, screen_bounds: Bounds<Pixels>, cx: &App) -> Option<AnyElement> {
 ///         // Render item at the given screen bounds
 ///     }
 /// }
@@ -110,7 +243,12 @@ pub trait CanvasItemsProvider {
     /// # Returns
     ///
     /// An element to render, or `None` if the item cannot be rendered.
-    fn render_item(&self, id: &str, screen_bounds: Bounds<Pixels>, cx: &App) -> Option<AnyElement>;
+    fn render_item(
+        &self,
+        id: &ItemId,
+        screen_bounds: Bounds<Pixels>,
+        cx: &App,
+    ) -> Option<AnyElement>;
 
     /// Get the number of items.
     fn item_count(&self) -> usize {
@@ -138,6 +276,45 @@ pub trait CanvasItemsProvider {
 
         Some(bounds)
     }
+
+    /// Get the area-weighted center of all items (in canvas space).
+    ///
+    /// Large items pull the centroid towards them more than small ones, so this
+    /// finds where most of the content is rather than the middle of its
+    /// bounding box. If every item has zero area, the plain average of their
+    /// centers is used. Returns `None` if there are no items.
+    fn content_centroid(&self) -> Option<Point<Pixels>> {
+        let items = self.items();
+        if items.is_empty() {
+            return None;
+        }
+
+        let (mut total_weight, mut sum_x, mut sum_y) = (0.0, 0.0, 0.0);
+        for item in &items {
+            let area = f32::from(item.bounds.size.width) * f32::from(item.bounds.size.height);
+            let center = item.bounds.center();
+            total_weight += area;
+            sum_x += area * f32::from(center.x);
+            sum_y += area * f32::from(center.y);
+        }
+
+        if total_weight <= 0.0 {
+            total_weight = items.len() as f32;
+            sum_x = items
+                .iter()
+                .map(|item| f32::from(item.bounds.center().x))
+                .sum();
+            sum_y = items
+                .iter()
+                .map(|item| f32::from(item.bounds.center().y))
+                .sum();
+        }
+
+        Some(Point::new(
+            Pixels::from(sum_x / total_weight),
+            Pixels::from(sum_y / total_weight),
+        ))
+    }
 }
 
 // Implement for references to providers
@@ -150,7 +327,12 @@ impl<T: CanvasItemsProvider + ?Sized> CanvasItemsProvider for &T {
         (*self).items_with_context(cx)
     }
 
-    fn render_item(&self, id: &str, screen_bounds: Bounds<Pixels>, cx: &App) -> Option<AnyElement> {
+    fn render_item(
+        &self,
+        id: &ItemId,
+        screen_bounds: Bounds<Pixels>,
+        cx: &App,
+    ) -> Option<AnyElement> {
         (*self).render_item(id, screen_bounds, cx)
     }
 
@@ -165,4 +347,51 @@ impl<T: CanvasItemsProvider + ?Sized> CanvasItemsProvider for &T {
     fn content_bounds(&self) -> Option<Bounds<Pixels>> {
         (*self).content_bounds()
     }
+
+    fn content_centroid(&self) -> Option<Point<Pixels>> {
+        (*self).content_centroid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{point, px, size};
+
+    fn item() -> ItemDescriptor {
+        ItemDescriptor::new(
+            "card",
+            Bounds::new(point(px(10.0), px(20.0)), size(px(100.0), px(50.0))),
+        )
+    }
+
+    #[test]
+    fn test_contains_point() {
+        let item = item();
+        assert!(item.contains_point(point(px(50.0), px(40.0))));
+        assert!(!item.contains_point(point(px(5.0), px(40.0))));
+        assert!(!item.contains_point(point(px(50.0), px(71.0))));
+
+        // Points exactly on the border are inside
+        assert!(item.contains_point(point(px(10.0), px(20.0))));
+        assert!(item.contains_point(point(px(110.0), px(70.0))));
+        assert!(item.contains_point(point(px(110.0), px(45.0))));
+        assert!(!item.contains_point(point(px(110.5), px(45.0))));
+    }
+
+    #[test]
+    fn test_contained_by() {
+        let item = item();
+        let marquee =
+            |x: f32, y: f32, w: f32, h: f32| Bounds::new(point(px(x), px(y)), size(px(w), px(h)));
+
+        assert!(item.contained_by(&marquee(0.0, 0.0, 200.0, 200.0)));
+        // Overlapping isn't enough
+        assert!(!item.contained_by(&marquee(50.0, 0.0, 200.0, 200.0)));
+        assert!(item.intersects(&marquee(50.0, 0.0, 200.0, 200.0)));
+        // Edges touching from the inside, including a marquee of exactly the item
+        assert!(item.contained_by(&marquee(10.0, 20.0, 100.0, 50.0)));
+        assert!(!item.contained_by(&marquee(10.0, 20.0, 99.0, 50.0)));
+        assert!(!item.contained_by(&marquee(300.0, 300.0, 10.0, 10.0)));
+    }
 }
@@ -9,14 +9,24 @@
 //! On other platforms, items will show placeholder content.
 
 use gpui::{
-    div, img, point, px, size, AnyElement, AnyView, App, AppContext as _, Bounds, Context,
-    IntoElement, ObjectFit, ParentElement, Pixels, Point, RenderImage, Size, Styled, StyledImage,
-    Window,
+    div, img, point, px, size, AnyElement, AnyView, App, AppContext as _, AvailableSpace, Bounds,
+    Context, IntoElement, ObjectFit, ParentElement, Pixels, Point, RenderImage, Size, Styled,
+    StyledImage, Window,
 };
+use std::cell::Cell;
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+use std::cell::RefCell;
 use std::collections::HashMap;
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+use std::collections::HashSet;
+use std::path::Path;
 use std::sync::Arc;
 
-use crate::provider::{CanvasItemsProvider, ItemDescriptor, ItemId};
+use image::ImageError;
+
+use crate::camera::Camera;
+use crate::png::save_rgba_png;
+use crate::provider::{sort_by_viewport_proximity, CanvasItemsProvider, ItemDescriptor, ItemId};
 
 #[cfg(any(target_os = "linux", target_os = "freebsd"))]
 use gpui::TexturedView;
@@ -33,16 +43,262 @@ type TextureGetter = Box<dyn Fn(&App) -> Option<Arc<RenderImage>> + Send + Sync>
 /// Type alias for the size getter closure (to query measured size from TexturedView).
 type SizeGetter = Box<dyn Fn(&App) -> Option<Size<Pixels>> + Send + Sync>;
 
-/// Internal storage for a canvas item.
-struct CanvasItemEntry {
-    /// Position on canvas (canvas space).
-    origin: Point<Pixels>,
-    /// Initial/estimated size of the item.
-    size: Size<Pixels>,
-    /// Z-index for ordering.
-    z_index: i32,
+/// Type alias for the closure that lays out an item's element at a given width.
+type Measurer = Box<dyn Fn(Pixels, &mut Window, &mut App) -> Size<Pixels>>;
+
+/// How the initial (placeholder) size of a new item is determined.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InitialSizeMode {
+    /// Use `ItemSizing::initial_size()`, i.e. the estimated height for `FixedWidth`.
+    Estimated,
+    /// Lay the element out once before its texture is rendered and use the
+    /// measured height, capped at `max_height`.
+    ///
+    /// Only affects `FixedWidth` sizing; other modes already know their size.
+    Measured {
+        /// Upper bound for the measured height.
+        max_height: Pixels,
+    },
+}
+
+/// Resolve the initial size of an item.
+///
+/// `measure` lays the item's element out at the given width and returns its
+/// size. It is only called for `FixedWidth` sizing in `Measured` mode.
+fn resolve_initial_size(
+    sizing: &ItemSizing,
+    mode: InitialSizeMode,
+    measure: impl FnOnce(Pixels) -> Size<Pixels>,
+) -> Size<Pixels> {
+    match (sizing, mode) {
+        (ItemSizing::FixedWidth { width, .. }, InitialSizeMode::Measured { max_height }) => {
+            let measured = measure(*width);
+            size(*width, measured.height.min(max_height))
+        }
+        _ => sizing.initial_size(),
+    }
+}
+
+/// Lay out an element once at a fixed width and return its size.
+fn measure_element<E: IntoElement>(
+    element: E,
+    width: Pixels,
+    window: &mut Window,
+    cx: &mut App,
+) -> Size<Pixels> {
+    element.into_any_element().layout_as_root(
+        size(AvailableSpace::Definite(width), AvailableSpace::MinContent),
+        window,
+        cx,
+    )
+}
+
+/// Size of an item measured at the sizing's width.
+///
+/// The width is always the configured one; only the height comes from the
+/// layout, so a measured item keeps lining up with its neighbours.
+fn measured_size(
+    sizing: &ItemSizing,
+    measure: impl FnOnce(Pixels) -> Size<Pixels>,
+) -> Size<Pixels> {
+    let width = sizing.initial_size().width;
+    size(width, measure(width).height)
+}
+
+/// Build a `Measurer` that lays out the element produced by `render_fn`.
+fn measurer<F, E>(render_fn: F) -> Measurer
+where
+    F: Fn() -> E + 'static,
+    E: IntoElement + 'static,
+{
+    Box::new(move |width, window, cx| measure_element(render_fn(), width, window, cx))
+}
+
+/// One-shot latch for the "all items ready" notification.
+///
+/// Armed whenever new work is queued; `observe` reports readiness only the
+/// first time it is seen after arming, so a load cycle fires exactly once.
+#[derive(Default)]
+struct ReadyLatch {
+    armed: Cell<bool>,
+}
+
+impl ReadyLatch {
+    /// Arm the latch because new renders were queued.
+    fn arm(&self) {
+        self.armed.set(true);
+    }
+
+    /// Returns `true` if `ready` is the first readiness seen since arming.
+    fn observe(&self, ready: bool) -> bool {
+        if ready && self.armed.get() {
+            self.armed.set(false);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// How many textures render at once unless configured otherwise.
+pub const DEFAULT_MAX_CONCURRENT_RENDERS: usize = 4;
+
+/// A render limit suited to this machine: one less than the available
+/// parallelism, leaving a core for the UI thread, but at least 1.
+fn auto_max_concurrent_renders() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get().saturating_sub(1))
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Tracks the items whose textures are rendering, so that no more than a
+/// limited number are requested at once.
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+#[derive(Default)]
+struct RenderSlots {
+    in_flight: RefCell<HashSet<ItemId>>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+impl RenderSlots {
+    /// Claim a slot for `id`, or keep the one it has.
+    ///
+    /// Returns `false` if all `limit` slots are taken by other items.
+    fn acquire(&self, id: &ItemId, limit: usize) -> bool {
+        let mut in_flight = self.in_flight.borrow_mut();
+        if in_flight.contains(id) {
+            return true;
+        }
+        if in_flight.len() >= limit {
+            return false;
+        }
+        in_flight.insert(id.clone());
+        true
+    }
+
+    /// Free the slot held by `id`, if any.
+    fn release(&self, id: &ItemId) {
+        self.in_flight.borrow_mut().remove(id);
+    }
+
+    /// Free the slots of items for which `keep` returns `false`.
+    fn retain(&self, mut keep: impl FnMut(&ItemId) -> bool) {
+        self.in_flight.borrow_mut().retain(|id| keep(id));
+    }
+
+    /// Check whether `id` holds a slot.
+    fn contains(&self, id: &ItemId) -> bool {
+        self.in_flight.borrow().contains(id)
+    }
+}
+
+/// Counts of the provider's items by render state, from
+/// `TexturedCanvasItemsProvider::status`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProviderStatus {
+    /// Number of items, in any state.
+    pub total: usize,
+    /// Items whose texture has rendered.
+    pub ready: usize,
+    /// Items whose texture is being rendered in the background.
+    pub rendering: usize,
+    /// Placeholders waiting for an element factory, and items waiting for a
+    /// free render slot (see `set_max_concurrent_renders`).
+    pub queued: usize,
+    /// Items whose render finished without an image to draw: a texture with
+    /// no frames or an empty one.
+    pub failed: usize,
+}
+
+/// The render state of a single item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RenderState {
+    /// A placeholder without an element factory, or an item waiting for a
+    /// render slot.
+    Queued,
+    /// The texture is rendering in the background.
+    Rendering,
+    /// The texture has rendered.
+    Ready,
+    /// The render finished without an image to draw.
+    #[cfg_attr(not(any(target_os = "linux", target_os = "freebsd")), allow(dead_code))]
+    Failed,
+}
+
+impl FromIterator<RenderState> for ProviderStatus {
+    fn from_iter<I: IntoIterator<Item = RenderState>>(states: I) -> Self {
+        let mut status = ProviderStatus::default();
+        for state in states {
+            status.total += 1;
+            match state {
+                RenderState::Queued => status.queued += 1,
+                RenderState::Rendering => status.rendering += 1,
+                RenderState::Ready => status.ready += 1,
+                RenderState::Failed => status.failed += 1,
+            }
+        }
+        status
+    }
+}
+
+/// Build a `RenderImage` from tightly packed RGBA pixels.
+///
+/// GPUI stores images as BGRA, so the red and blue channels are swapped.
+/// Returns `None` if `rgba` doesn't hold exactly `width * height` pixels.
+pub fn pixels_to_render_image(rgba: &[u8], width: u32, height: u32) -> Option<RenderImage> {
+    let mut bgra = rgba.to_vec();
+    for pixel in bgra.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+    let buffer = image::RgbaImage::from_raw(width, height, bgra)?;
+    Some(RenderImage::new(vec![image::Frame::new(buffer)]))
+}
+
+/// Whether a rendered texture holds an image to draw.
+///
+/// A texture without frames, or with an empty first frame, came from a failed
+/// render. Drawing one would panic on its missing size.
+fn has_image(texture: &RenderImage) -> bool {
+    if texture.frame_count() == 0 {
+        return false;
+    }
+    let size = texture.size(0);
+    size.width.0 > 0 && size.height.0 > 0
+}
+
+/// Copy the first frame of a `RenderImage` out as RGBA pixels.
+///
+/// Returns the pixels with the frame's width and height, or `None` if the
+/// image has no frames or its buffer doesn't match its dimensions. Use this
+/// instead of `RenderImage::as_bytes`, which returns BGRA and whose size
+/// lookup panics on images without frames.
+pub fn render_image_to_rgba(image: &RenderImage) -> Option<(Vec<u8>, u32, u32)> {
+    if image.frame_count() == 0 {
+        return None;
+    }
+    let size = image.size(0);
+    let width = u32::try_from(size.width.0).ok()?;
+    let height = u32::try_from(size.height.0).ok()?;
+
+    let bytes = image.as_bytes(0)?;
+    if bytes.len() != width as usize * height as usize * 4 {
+        return None;
+    }
+
+    let mut rgba = bytes.to_vec();
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+    Some((rgba, width, height))
+}
+
+/// The renderable content of a canvas item, built from its element factory.
+struct ItemContent {
     /// The view (TexturedView or placeholder).
     view: AnyView,
+    /// Lays out the item's element, for `measure_item`.
+    measurer: Measurer,
     /// Closure to get the texture (for zoom-scaled rendering).
     #[cfg(any(target_os = "linux", target_os = "freebsd"))]
     texture_getter: TextureGetter,
@@ -51,6 +307,78 @@ struct CanvasItemEntry {
     size_getter: SizeGetter,
 }
 
+/// Build an item's content, queueing its texture to render in the background.
+///
+/// The item's size is only needed by the unsupported platform stub, whose
+/// placeholder view has no layout of its own.
+#[cfg(any(target_os = "linux", target_os = "freebsd"))]
+fn item_content<V: 'static, F, E>(
+    sizing: ItemSizing,
+    _size: Size<Pixels>,
+    window: &mut Window,
+    cx: &mut Context<V>,
+    render_fn: F,
+) -> ItemContent
+where
+    F: Fn() -> E + Send + Clone + 'static,
+    E: IntoElement + 'static,
+{
+    let measurer = measurer(render_fn.clone());
+
+    // Create TexturedView for this item
+    let entity = cx.new(|cx| {
+        TexturedView::with_options(sizing, gpui::RenderMode::Once, window, cx, render_fn)
+    });
+
+    // Create a closure to get the texture from this entity
+    let entity_for_texture = entity.clone();
+    let texture_getter: TextureGetter =
+        Box::new(move |cx: &App| entity_for_texture.read(cx).texture());
+
+    // Create a closure to get the measured size from the entity
+    let entity_for_size = entity.clone();
+    let size_getter: SizeGetter =
+        Box::new(move |cx: &App| entity_for_size.read(cx).measured_size());
+
+    ItemContent {
+        view: entity.into(),
+        measurer,
+        texture_getter,
+        size_getter,
+    }
+}
+
+/// Build an item's content (unsupported platform stub).
+#[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+fn item_content<V: 'static, F, E>(
+    _sizing: ItemSizing,
+    size: Size<Pixels>,
+    _window: &mut Window,
+    cx: &mut Context<V>,
+    render_fn: F,
+) -> ItemContent
+where
+    F: Fn() -> E + Send + Clone + 'static,
+    E: IntoElement + 'static,
+{
+    ItemContent {
+        view: cx.new(|_| UnsupportedPlatformView { size }).into(),
+        measurer: measurer(render_fn),
+    }
+}
+
+/// Internal storage for a canvas item.
+struct CanvasItemEntry {
+    /// Position on canvas (canvas space).
+    origin: Point<Pixels>,
+    /// Initial/estimated size of the item.
+    size: Size<Pixels>,
+    /// Z-index for ordering.
+    z_index: i32,
+    /// The item's content, or `None` for a placeholder without a factory yet.
+    content: Option<ItemContent>,
+}
+
 // ============================================================================
 // TexturedCanvasItemsProvider
 // ============================================================================
@@ -82,6 +410,17 @@ pub struct TexturedCanvasItemsProvider {
     items: HashMap<ItemId, CanvasItemEntry>,
     /// Default sizing for new items.
     default_sizing: ItemSizing,
+    /// How new items get their initial size.
+    initial_size_mode: InitialSizeMode,
+    /// Tracks whether `on_all_ready` should fire on the next ready check.
+    ready_latch: ReadyLatch,
+    /// Called once each time every queued texture has finished rendering.
+    on_all_ready: Option<Box<dyn Fn()>>,
+    /// Most textures rendering at once.
+    max_concurrent_renders: usize,
+    /// Items currently holding one of the `max_concurrent_renders` slots.
+    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+    render_slots: RenderSlots,
 }
 
 impl TexturedCanvasItemsProvider {
@@ -92,6 +431,12 @@ impl TexturedCanvasItemsProvider {
             default_sizing: ItemSizing::Fixed {
                 size: size(px(300.0), px(200.0)),
             },
+            initial_size_mode: InitialSizeMode::Estimated,
+            ready_latch: ReadyLatch::default(),
+            on_all_ready: None,
+            max_concurrent_renders: DEFAULT_MAX_CONCURRENT_RENDERS,
+            #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+            render_slots: RenderSlots::default(),
         }
     }
 
@@ -100,6 +445,12 @@ impl TexturedCanvasItemsProvider {
         Self {
             items: HashMap::new(),
             default_sizing: sizing,
+            initial_size_mode: InitialSizeMode::Estimated,
+            ready_latch: ReadyLatch::default(),
+            on_all_ready: None,
+            max_concurrent_renders: DEFAULT_MAX_CONCURRENT_RENDERS,
+            #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+            render_slots: RenderSlots::default(),
         }
     }
 
@@ -113,14 +464,123 @@ impl TexturedCanvasItemsProvider {
         &self.default_sizing
     }
 
+    /// Set how new items get their initial size.
+    ///
+    /// With `InitialSizeMode::Measured`, `FixedWidth` items are laid out once
+    /// when added so their placeholder already has the real height, avoiding a
+    /// layout jump when the texture's measured size arrives.
+    pub fn set_initial_size_mode(&mut self, mode: InitialSizeMode) {
+        self.initial_size_mode = mode;
+    }
+
+    /// Get how new items get their initial size.
+    pub fn initial_size_mode(&self) -> InitialSizeMode {
+        self.initial_size_mode
+    }
+
+    /// Set the most textures that may render at once (at least 1).
+    ///
+    /// Visible items beyond the limit show an empty placeholder until an
+    /// earlier render finishes. Defaults to `DEFAULT_MAX_CONCURRENT_RENDERS`.
+    pub fn set_max_concurrent_renders(&mut self, max: usize) {
+        self.max_concurrent_renders = max.max(1);
+    }
+
+    /// Scale the render limit with the machine.
+    ///
+    /// Uses one less than `std::thread::available_parallelism()`, leaving a
+    /// core for the UI thread, but at least 1.
+    pub fn set_max_concurrent_renders_auto(&mut self) {
+        self.set_max_concurrent_renders(auto_max_concurrent_renders());
+    }
+
+    /// Get the most textures that may render at once.
+    pub fn max_concurrent_renders(&self) -> usize {
+        self.max_concurrent_renders
+    }
+
+    /// Check whether every item's texture has been rendered.
+    ///
+    /// Placeholders added with `add_placeholder` have nothing to render until
+    /// their factory is set, so they don't hold this up. Always `true` on
+    /// platforms without `TexturedView`, where items are shown as placeholders
+    /// and nothing is rendered in the background.
+    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+    pub fn all_ready(&self, cx: &App) -> bool {
+        self.items
+            .values()
+            .filter_map(|item| item.content.as_ref())
+            .all(|content| (content.texture_getter)(cx).is_some())
+    }
+
+    /// Check whether every item's texture has been rendered (unsupported platform stub).
+    #[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+    pub fn all_ready(&self, _cx: &App) -> bool {
+        true
+    }
+
+    /// Count the items in each render state, in one pass over the items.
+    ///
+    /// Lets a status bar show loading progress from a single snapshot. On
+    /// platforms without `TexturedView`, items with content count as ready,
+    /// as in `all_ready`.
+    pub fn status(&self, cx: &App) -> ProviderStatus {
+        self.items
+            .iter()
+            .map(|(id, item)| self.render_state(id, item, cx))
+            .collect()
+    }
+
+    /// The render state of an item.
+    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+    fn render_state(&self, id: &ItemId, item: &CanvasItemEntry, cx: &App) -> RenderState {
+        let Some(content) = &item.content else {
+            return RenderState::Queued;
+        };
+        match (content.texture_getter)(cx) {
+            Some(texture) if has_image(&texture) => RenderState::Ready,
+            Some(_) => RenderState::Failed,
+            None if self.render_slots.contains(id) => RenderState::Rendering,
+            None => RenderState::Queued,
+        }
+    }
+
+    /// The render state of an item (unsupported platform stub).
+    #[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
+    fn render_state(&self, _id: &ItemId, item: &CanvasItemEntry, _cx: &App) -> RenderState {
+        match &item.content {
+            None => RenderState::Queued,
+            Some(_) => RenderState::Ready,
+        }
+    }
+
+    /// Set a callback for when all pending texture renders have finished.
+    ///
+    /// The callback fires once, the first time the canvas sees every texture
+    /// ready after items were added or invalidated. Adding or invalidating
+    /// items re-arms it for the next load cycle. Useful for hiding a loading
+    /// indicator.
+    pub fn on_all_ready(&mut self, callback: impl Fn() + 'static) {
+        self.on_all_ready = Some(Box::new(callback));
+    }
+
+    /// Fire `on_all_ready` if this is the first time all textures are ready
+    /// since the last item was added or invalidated.
+    fn check_all_ready(&self, cx: &App) {
+        if let Some(callback) = &self.on_all_ready {
+            if self.ready_latch.observe(self.all_ready(cx)) {
+                callback();
+            }
+        }
+    }
+
     /// Add an item at a specific position.
     ///
     /// The `render_fn` creates the GPUI element to render as a texture.
     /// Rendering happens asynchronously in a background thread.
-    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
     pub fn add_item<V: 'static, F, E>(
         &mut self,
-        id: impl Into<String>,
+        id: impl Into<ItemId>,
         origin: Point<Pixels>,
         window: &mut Window,
         cx: &mut Context<V>,
@@ -131,22 +591,10 @@ impl TexturedCanvasItemsProvider {
     {
         let id = id.into();
         let sizing = self.default_sizing.clone();
-        let initial_size = sizing.initial_size();
-
-        // Create TexturedView for this item
-        let entity = cx.new(|cx| {
-            TexturedView::with_options(sizing, gpui::RenderMode::Once, window, cx, render_fn)
+        let initial_size = resolve_initial_size(&sizing, self.initial_size_mode, |width| {
+            measure_element(render_fn(), width, window, cx)
         });
-
-        // Create a closure to get the texture from this entity
-        let entity_for_texture = entity.clone();
-        let texture_getter: TextureGetter =
-            Box::new(move |cx: &App| entity_for_texture.read(cx).texture());
-
-        // Create a closure to get the measured size from the entity
-        let entity_for_size = entity.clone();
-        let size_getter: SizeGetter =
-            Box::new(move |cx: &App| entity_for_size.read(cx).measured_size());
+        let content = item_content(sizing, initial_size, window, cx, render_fn);
 
         self.items.insert(
             id,
@@ -154,48 +602,68 @@ impl TexturedCanvasItemsProvider {
                 origin,
                 size: initial_size,
                 z_index: 0,
-                view: entity.into(),
-                texture_getter,
-                size_getter,
+                content: Some(content),
             },
         );
+        self.ready_latch.arm();
     }
 
-    /// Add an item at a specific position (unsupported platform stub).
-    #[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
-    pub fn add_item<V: 'static, F, E>(
+    /// Add an item with just its bounds, before its content exists.
+    ///
+    /// The placeholder takes part in `items()` and layout like any other item,
+    /// but renders nothing until `set_factory` gives it an element factory.
+    /// This lets a layout pass run on geometry alone.
+    pub fn add_placeholder(&mut self, id: impl Into<ItemId>, bounds: Bounds<Pixels>) {
+        self.items.insert(
+            id.into(),
+            CanvasItemEntry {
+                origin: bounds.origin,
+                size: bounds.size,
+                z_index: 0,
+                content: None,
+            },
+        );
+    }
+
+    /// Set the element factory of an item, queueing its texture to render.
+    ///
+    /// Turns a placeholder into a renderable item, keeping its bounds, or
+    /// replaces the content of an existing item. Returns `false` if there is
+    /// no such item.
+    pub fn set_factory<V: 'static, F, E>(
         &mut self,
-        id: impl Into<String>,
-        origin: Point<Pixels>,
-        _window: &mut Window,
+        id: impl AsRef<str>,
+        window: &mut Window,
         cx: &mut Context<V>,
-        _render_fn: F,
-    ) where
+        render_fn: F,
+    ) -> bool
+    where
         F: Fn() -> E + Send + Clone + 'static,
         E: IntoElement + 'static,
     {
-        let id = id.into();
-        let initial_size = self.default_sizing.initial_size();
+        let Some(size) = self.items.get(id.as_ref()).map(|item| item.size) else {
+            return false;
+        };
+        let content = item_content(self.default_sizing.clone(), size, window, cx, render_fn);
 
-        let view = cx
-            .new(|_| UnsupportedPlatformView { size: initial_size })
-            .into();
+        if let Some(item) = self.items.get_mut(id.as_ref()) {
+            item.content = Some(content);
+        }
+        self.ready_latch.arm();
+        true
+    }
 
-        self.items.insert(
-            id,
-            CanvasItemEntry {
-                origin,
-                size: initial_size,
-                z_index: 0,
-                view,
-            },
-        );
+    /// Check if an item is a placeholder still waiting for its factory.
+    pub fn is_placeholder(&self, id: impl AsRef<str>) -> bool {
+        self.items
+            .get(id.as_ref())
+            .is_some_and(|item| item.content.is_none())
     }
 
     /// Add an item at the origin (0, 0).
     pub fn add_item_at_origin<V: 'static, F, E>(
         &mut self,
-        id: impl Into<String>,
+        id: impl Into<ItemId>,
         window: &mut Window,
         cx: &mut Context<V>,
         render_fn: F,
@@ -207,34 +675,107 @@ impl TexturedCanvasItemsProvider {
     }
 
     /// Remove an item by ID.
-    pub fn remove_item(&mut self, id: &str) -> bool {
-        self.items.remove(id).is_some()
+    pub fn remove_item(&mut self, id: impl AsRef<str>) -> bool {
+        self.items.remove(id.as_ref()).is_some()
     }
 
     /// Set an item's position.
-    pub fn set_position(&mut self, id: &str, origin: Point<Pixels>) {
-        if let Some(item) = self.items.get_mut(id) {
-            item.origin = origin;
+    ///
+    /// Positions are only used for layout, so this never re-renders the
+    /// item's texture. See `move_item`.
+    pub fn set_position(&mut self, id: impl AsRef<str>, origin: Point<Pixels>) {
+        self.move_item(id, origin);
+    }
+
+    /// Move an item to a new origin without touching its texture.
+    ///
+    /// Textures are rendered in the item's own space, so only the origin
+    /// changes: the texture, its view and the pending-render state are kept
+    /// as they are. This makes it cheap to call for every dragged item on
+    /// every frame. Returns `false` if there is no such item.
+    pub fn move_item(&mut self, id: impl AsRef<str>, origin: Point<Pixels>) -> bool {
+        match self.items.get_mut(id.as_ref()) {
+            Some(item) => {
+                item.origin = origin;
+                true
+            }
+            None => false,
         }
     }
 
     /// Set an item's z-index.
-    pub fn set_z_index(&mut self, id: &str, z_index: i32) {
-        if let Some(item) = self.items.get_mut(id) {
+    pub fn set_z_index(&mut self, id: impl AsRef<str>, z_index: i32) {
+        if let Some(item) = self.items.get_mut(id.as_ref()) {
             item.z_index = z_index;
         }
     }
 
     /// Get an item's bounds.
-    pub fn bounds(&self, id: &str) -> Option<Bounds<Pixels>> {
+    pub fn bounds(&self, id: impl AsRef<str>) -> Option<Bounds<Pixels>> {
         self.items
-            .get(id)
+            .get(id.as_ref())
             .map(|item| Bounds::new(item.origin, item.size))
     }
 
+    /// Measure an item by laying out its element at the configured width.
+    ///
+    /// Updates the item's size to the measured height and returns it, or
+    /// `None` if there is no such item or it's a placeholder without a
+    /// factory. Unlike the texture's measured size, this works before (or
+    /// without) rendering the texture.
+    pub fn measure_item(
+        &mut self,
+        id: impl AsRef<str>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<Size<Pixels>> {
+        let item = self.items.get_mut(id.as_ref())?;
+        let measurer = &item.content.as_ref()?.measurer;
+        let measured = measured_size(&self.default_sizing, |width| measurer(width, window, cx));
+        item.size = measured;
+        Some(measured)
+    }
+
+    /// Save an item's rendered texture as a PNG file.
+    ///
+    /// Returns `Ok(false)` without writing anything if there is no such item
+    /// or its texture hasn't rendered (or failed to).
+    pub fn save_texture_png(
+        &self,
+        id: impl AsRef<str>,
+        path: impl AsRef<Path>,
+        cx: &App,
+    ) -> Result<bool, ImageError> {
+        let texture = self
+            .items
+            .get(id.as_ref())
+            .and_then(|item| item.content.as_ref())
+            .and_then(|content| (content.texture_getter)(cx))
+            .filter(|texture| has_image(texture));
+        let Some((pixels, width, height)) = texture.and_then(|t| render_image_to_rgba(&t)) else {
+            return Ok(false);
+        };
+
+        save_rgba_png(&pixels, width, height, path)?;
+        Ok(true)
+    }
+
+    /// Get item IDs ordered by distance from the viewport center, nearest first.
+    ///
+    /// Useful for deciding which item textures to render first.
+    pub fn items_by_viewport_proximity(
+        &self,
+        camera: &Camera,
+        viewport_size: Size<Pixels>,
+    ) -> Vec<ItemId> {
+        let mut items = self.items();
+        sort_by_viewport_proximity(&mut items, camera, viewport_size);
+        items.into_iter().map(|item| item.id).collect()
+    }
+
     /// Check if an item exists.
-    pub fn contains(&self, id: &str) -> bool {
-        self.items.contains_key(id)
+    pub fn contains(&self, id: impl AsRef<str>) -> bool {
+        self.items.contains_key(id.as_ref())
     }
 
     /// Clear all items.
@@ -243,10 +784,11 @@ impl TexturedCanvasItemsProvider {
     }
 
     /// Invalidate an item's texture (force re-render).
-    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+    ///
+    /// Equivalent to `set_factory`, ignoring missing items.
     pub fn invalidate<V: 'static, F, E>(
         &mut self,
-        id: &str,
+        id: impl AsRef<str>,
         window: &mut Window,
         cx: &mut Context<V>,
         render_fn: F,
@@ -254,35 +796,7 @@ impl TexturedCanvasItemsProvider {
         F: Fn() -> E + Send + Clone + 'static,
         E: IntoElement + 'static,
     {
-        if let Some(item) = self.items.get_mut(id) {
-            let sizing = self.default_sizing.clone();
-
-            let entity = cx.new(|cx| {
-                TexturedView::with_options(sizing, gpui::RenderMode::Once, window, cx, render_fn)
-            });
-
-            // Update view, texture_getter, and size_getter
-            let entity_for_texture = entity.clone();
-            item.texture_getter = Box::new(move |cx: &App| entity_for_texture.read(cx).texture());
-            let entity_for_size = entity.clone();
-            item.size_getter = Box::new(move |cx: &App| entity_for_size.read(cx).measured_size());
-            item.view = entity.into();
-        }
-    }
-
-    /// Invalidate an item's texture (unsupported platform stub).
-    #[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
-    pub fn invalidate<V: 'static, F, E>(
-        &mut self,
-        _id: &str,
-        _window: &mut Window,
-        _cx: &mut Context<V>,
-        _render_fn: F,
-    ) where
-        F: Fn() -> E + Send + Clone + 'static,
-        E: IntoElement + 'static,
-    {
-        // No-op on unsupported platforms
+        self.set_factory(id, window, cx, render_fn);
     }
 }
 
@@ -311,10 +825,23 @@ impl CanvasItemsProvider for TexturedCanvasItemsProvider {
     /// Get items with measured sizes (requires App context).
     #[cfg(any(target_os = "linux", target_os = "freebsd"))]
     fn items_with_context(&self, cx: &App) -> Vec<ItemDescriptor> {
+        self.check_all_ready(cx);
+
+        // Free the slots of finished and removed items before this frame's renders
+        self.render_slots.retain(|id| {
+            self.items
+                .get(id)
+                .and_then(|item| item.content.as_ref())
+                .is_some_and(|content| (content.texture_getter)(cx).is_none())
+        });
+
         self.items
             .iter()
             .map(|(id, item)| {
-                let measured = (item.size_getter)(cx);
+                let measured = item
+                    .content
+                    .as_ref()
+                    .and_then(|content| (content.size_getter)(cx));
                 let size = measured.unwrap_or(item.size);
                 log::debug!(
                     "[TexturedProvider] Item '{}': initial={:?}, measured={:?}, using={:?}",
@@ -333,42 +860,59 @@ impl CanvasItemsProvider for TexturedCanvasItemsProvider {
     }
 
     #[cfg(any(target_os = "linux", target_os = "freebsd"))]
-    fn render_item(&self, id: &str, screen_bounds: Bounds<Pixels>, cx: &App) -> Option<AnyElement> {
-        self.items.get(id).map(|item| {
-            // Try to get the texture for proper scaling
-            if let Some(texture) = (item.texture_getter)(cx) {
-                // Render with proper scaling using object_fit
-                div()
-                    .absolute()
-                    .left(screen_bounds.origin.x)
-                    .top(screen_bounds.origin.y)
-                    .w(screen_bounds.size.width)
-                    .h(screen_bounds.size.height)
-                    .child(img(texture).size_full().object_fit(ObjectFit::Fill))
-                    .into_any_element()
-            } else {
-                // Texture not ready yet, show the view (which has loading placeholder)
-                div()
-                    .absolute()
-                    .left(screen_bounds.origin.x)
-                    .top(screen_bounds.origin.y)
-                    .w(screen_bounds.size.width)
-                    .h(screen_bounds.size.height)
-                    .overflow_hidden()
-                    .child(item.view.clone())
-                    .into_any_element()
+    fn render_item(
+        &self,
+        id: &ItemId,
+        screen_bounds: Bounds<Pixels>,
+        cx: &App,
+    ) -> Option<AnyElement> {
+        // Placeholders have nothing to render yet
+        let content = self.items.get(id)?.content.as_ref()?;
+
+        // Try to get the texture for proper scaling
+        let texture = (content.texture_getter)(cx);
+        if texture.is_some() {
+            self.render_slots.release(id);
+        }
+        let frame = || {
+            div()
+                .absolute()
+                .left(screen_bounds.origin.x)
+                .top(screen_bounds.origin.y)
+                .w(screen_bounds.size.width)
+                .h(screen_bounds.size.height)
+        };
+        let element = match texture {
+            // Render with proper scaling using object_fit
+            Some(texture) if has_image(&texture) => frame()
+                .child(img(texture).size_full().object_fit(ObjectFit::Fill))
+                .into_any_element(),
+            // The render failed, which `status` reports; there's nothing to draw
+            Some(_) => frame().into_any_element(),
+            // Every render slot is taken; mounting the view would start another render
+            None if !self.render_slots.acquire(id, self.max_concurrent_renders) => {
+                frame().into_any_element()
             }
-        })
+            // Texture not ready yet, show the view (which has loading placeholder)
+            None => frame()
+                .overflow_hidden()
+                .child(content.view.clone())
+                .into_any_element(),
+        };
+        Some(element)
     }
 
     #[cfg(not(any(target_os = "linux", target_os = "freebsd")))]
     fn render_item(
         &self,
-        id: &str,
+        id: &ItemId,
         screen_bounds: Bounds<Pixels>,
         _cx: &App,
     ) -> Option<AnyElement> {
-        self.items.get(id).map(|item| {
+        // Placeholders have nothing to render yet
+        let content = self.items.get(id)?.content.as_ref()?;
+
+        Some(
             div()
                 .absolute()
                 .left(screen_bounds.origin.x)
@@ -376,9 +920,9 @@ impl CanvasItemsProvider for TexturedCanvasItemsProvider {
                 .w(screen_bounds.size.width)
                 .h(screen_bounds.size.height)
                 .overflow_hidden()
-                .child(item.view.clone())
-                .into_any_element()
-        })
+                .child(content.view.clone())
+                .into_any_element(),
+        )
     }
 
     fn item_count(&self) -> usize {
@@ -426,6 +970,145 @@ mod tests {
         assert!(provider.is_empty());
     }
 
+    #[test]
+    fn test_max_concurrent_renders_auto() {
+        let mut provider = TexturedCanvasItemsProvider::new();
+        assert_eq!(
+            provider.max_concurrent_renders(),
+            DEFAULT_MAX_CONCURRENT_RENDERS
+        );
+
+        provider.set_max_concurrent_renders_auto();
+        let parallelism = std::thread::available_parallelism().map_or(1, |n| n.get());
+        assert!(provider.max_concurrent_renders() >= 1);
+        assert!(provider.max_concurrent_renders() <= parallelism);
+
+        provider.set_max_concurrent_renders(0);
+        assert_eq!(provider.max_concurrent_renders(), 1);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "freebsd"))]
+    #[test]
+    fn test_render_slots_limit_in_flight_items() {
+        let slots = RenderSlots::default();
+        let (a, b, c) = (ItemId::from("a"), ItemId::from("b"), ItemId::from("c"));
+
+        assert!(slots.acquire(&a, 2));
+        assert!(slots.acquire(&b, 2));
+        assert!(!slots.acquire(&c, 2));
+        // Items keep the slot they already hold
+        assert!(slots.acquire(&a, 2));
+
+        slots.release(&a);
+        assert!(slots.acquire(&c, 2));
+        slots.retain(|id| id != &b);
+        assert!(!slots.contains(&b));
+        assert!(slots.contains(&c));
+    }
+
+    #[test]
+    fn test_ready_latch_fires_once_per_load_cycle() {
+        let latch = ReadyLatch::default();
+
+        // Nothing queued yet, so readiness doesn't fire
+        assert!(!latch.observe(true));
+
+        // First load: fires once when rendering finishes
+        latch.arm();
+        assert!(!latch.observe(false));
+        assert!(latch.observe(true));
+        assert!(!latch.observe(true));
+
+        // Re-adding items re-arms it for the next cycle
+        latch.arm();
+        latch.arm();
+        assert!(!latch.observe(false));
+        assert!(latch.observe(true));
+        assert!(!latch.observe(true));
+    }
+
+    #[gpui::test]
+    fn test_status_counts_placeholders_as_queued(cx: &mut gpui::TestAppContext) {
+        let mut provider = TexturedCanvasItemsProvider::new();
+        let bounds = Bounds::new(point(px(0.0), px(0.0)), size(px(100.0), px(50.0)));
+        provider.add_placeholder("a", bounds);
+        provider.add_placeholder("b", bounds);
+
+        let status = cx.update(|cx| provider.status(cx));
+        assert_eq!(
+            status,
+            ProviderStatus {
+                total: 2,
+                ready: 0,
+                rendering: 0,
+                queued: 2,
+                failed: 0,
+            }
+        );
+
+        provider.remove_item("a");
+        let status = cx.update(|cx| provider.status(cx));
+        assert_eq!((status.total, status.queued), (1, 1));
+    }
+
+    #[test]
+    fn test_has_image_rejects_failed_renders() {
+        let texture = pixels_to_render_image(&[255, 0, 0, 255], 1, 1).unwrap();
+        assert!(has_image(&texture));
+
+        assert!(!has_image(&RenderImage::new(Vec::new())));
+        let empty = RenderImage::new(vec![image::Frame::new(image::RgbaImage::new(0, 0))]);
+        assert!(!has_image(&empty));
+    }
+
+    #[test]
+    fn test_provider_status_counts_states() {
+        let status: ProviderStatus = [
+            RenderState::Ready,
+            RenderState::Rendering,
+            RenderState::Ready,
+            RenderState::Queued,
+            RenderState::Ready,
+            RenderState::Rendering,
+            RenderState::Failed,
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            status,
+            ProviderStatus {
+                total: 7,
+                ready: 3,
+                rendering: 2,
+                queued: 1,
+                failed: 1,
+            }
+        );
+        assert_eq!(
+            std::iter::empty::<RenderState>().collect::<ProviderStatus>(),
+            ProviderStatus::default()
+        );
+    }
+
+    #[test]
+    fn test_render_image_rgba_round_trip() {
+        // 2x1 image: opaque red, half-transparent blue
+        let pixels = vec![255, 0, 0, 255, 0, 0, 255, 128];
+        let image = pixels_to_render_image(&pixels, 2, 1).unwrap();
+
+        // Stored as BGRA internally
+        assert_eq!(
+            image.as_bytes(0).unwrap(),
+            &[0, 0, 255, 255, 255, 0, 0, 128]
+        );
+        assert_eq!(render_image_to_rgba(&image), Some((pixels, 2, 1)));
+
+        // Mismatched buffer sizes and frameless images are rejected
+        assert!(pixels_to_render_image(&[0; 7], 2, 1).is_none());
+        assert_eq!(render_image_to_rgba(&RenderImage::new(Vec::new())), None);
+    }
+
     #[test]
     fn test_provider_with_sizing() {
         let provider = TexturedCanvasItemsProvider::with_sizing(ItemSizing::Fixed {
@@ -440,6 +1123,130 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_initial_size_mode_defaults_to_estimated() {
+        let mut provider = TexturedCanvasItemsProvider::new();
+        assert_eq!(provider.initial_size_mode(), InitialSizeMode::Estimated);
+
+        let mode = InitialSizeMode::Measured {
+            max_height: px(2000.0),
+        };
+        provider.set_initial_size_mode(mode);
+        assert_eq!(provider.initial_size_mode(), mode);
+    }
+
+    #[test]
+    fn test_resolve_initial_size_uses_measurement() {
+        let sizing = ItemSizing::FixedWidth {
+            width: px(280.0),
+            estimated_height: px(150.0),
+        };
+        // Stands in for laying out a known element that is 420px tall at any width
+        let measure = |width: Pixels| size(width, px(420.0));
+
+        let measured = resolve_initial_size(
+            &sizing,
+            InitialSizeMode::Measured {
+                max_height: px(2000.0),
+            },
+            measure,
+        );
+        assert_eq!(measured, size(px(280.0), px(420.0)));
+
+        let capped = resolve_initial_size(
+            &sizing,
+            InitialSizeMode::Measured {
+                max_height: px(300.0),
+            },
+            measure,
+        );
+        assert_eq!(capped, size(px(280.0), px(300.0)));
+
+        let estimated = resolve_initial_size(&sizing, InitialSizeMode::Estimated, |_| {
+            panic!("estimated mode must not measure")
+        });
+        assert_eq!(estimated, sizing.initial_size());
+    }
+
+    #[test]
+    fn test_resolve_initial_size_ignores_fixed_sizing() {
+        let sizing = ItemSizing::Fixed {
+            size: size(px(400.0), px(300.0)),
+        };
+        let resolved = resolve_initial_size(
+            &sizing,
+            InitialSizeMode::Measured {
+                max_height: px(2000.0),
+            },
+            |_| panic!("fixed sizing must not measure"),
+        );
+        assert_eq!(resolved, size(px(400.0), px(300.0)));
+    }
+
+    #[test]
+    fn test_measured_size_keeps_configured_width() {
+        let sizing = ItemSizing::FixedWidth {
+            width: px(280.0),
+            estimated_height: px(150.0),
+        };
+        // Stands in for a layout that is wider than asked and 420px tall
+        let measured = measured_size(&sizing, |width| size(width + px(20.0), px(420.0)));
+        assert_eq!(measured, size(px(280.0), px(420.0)));
+
+        let sizing = ItemSizing::Fixed {
+            size: size(px(400.0), px(300.0)),
+        };
+        let measured = measured_size(&sizing, |width| {
+            assert_eq!(width, px(400.0));
+            size(width, px(90.0))
+        });
+        assert_eq!(measured, size(px(400.0), px(90.0)));
+    }
+
+    /// Holds a provider in a test window, so items can be added and laid out
+    struct TestHost(TexturedCanvasItemsProvider);
+
+    impl gpui::Render for TestHost {
+        fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+            div()
+        }
+    }
+
+    #[gpui::test]
+    fn test_measure_item_updates_size(cx: &mut gpui::TestAppContext) {
+        let window = cx.add_window(|window, cx| {
+            let mut provider = TexturedCanvasItemsProvider::with_sizing(ItemSizing::FixedWidth {
+                width: px(200.0),
+                estimated_height: px(50.0),
+            });
+            provider.add_item("tall", point(px(10.0), px(20.0)), window, cx, || {
+                div().w_full().h(px(300.0))
+            });
+            TestHost(provider)
+        });
+
+        window
+            .update(cx, |host, window, cx| {
+                let provider = &mut host.0;
+                assert_eq!(
+                    provider.bounds("tall").map(|bounds| bounds.size),
+                    Some(size(px(200.0), px(50.0)))
+                );
+
+                let measured = provider.measure_item("tall", window, cx);
+                assert_eq!(measured, Some(size(px(200.0), px(300.0))));
+                assert_eq!(
+                    provider.bounds("tall"),
+                    Some(Bounds::new(
+                        point(px(10.0), px(20.0)),
+                        size(px(200.0), px(300.0))
+                    ))
+                );
+                assert_eq!(provider.measure_item("missing", window, cx), None);
+            })
+            .unwrap();
+    }
+
     #[test]
     fn test_set_position_nonexistent() {
         let mut provider = TexturedCanvasItemsProvider::new();
@@ -447,6 +1254,110 @@ mod tests {
         assert!(!provider.contains("nonexistent"));
     }
 
+    #[test]
+    fn test_move_item_keeps_texture_state() {
+        let mut provider = TexturedCanvasItemsProvider::new();
+        provider.ready_latch.arm();
+
+        assert!(!provider.move_item("nonexistent", point(px(10.0), px(20.0))));
+
+        let bounds = Bounds::new(point(px(0.0), px(0.0)), size(px(300.0), px(150.0)));
+        provider.add_placeholder("card", bounds);
+        assert!(provider.move_item("card", point(px(10.0), px(20.0))));
+        assert_eq!(
+            provider.bounds("card"),
+            Some(Bounds::new(point(px(10.0), px(20.0)), bounds.size))
+        );
+
+        // Moving doesn't queue a re-render, so the pending load cycle is untouched
+        assert!(provider.ready_latch.armed.get());
+        assert!(provider.ready_latch.observe(true));
+        provider.set_position("nonexistent", point(px(30.0), px(40.0)));
+        assert!(!provider.ready_latch.armed.get());
+    }
+
+    #[test]
+    fn test_placeholder_takes_part_in_layout() {
+        let mut provider = TexturedCanvasItemsProvider::new();
+        let bounds = Bounds::new(point(px(10.0), px(20.0)), size(px(300.0), px(150.0)));
+        provider.add_placeholder("later", bounds);
+
+        assert!(provider.contains("later"));
+        assert!(provider.is_placeholder("later"));
+        assert!(!provider.is_placeholder("missing"));
+        assert_eq!(provider.bounds("later"), Some(bounds));
+
+        let items = provider.items();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, "later");
+        assert_eq!(items[0].bounds, bounds);
+
+        // Layout can move it before it has any content
+        provider.move_item("later", point(px(0.0), px(0.0)));
+        assert_eq!(
+            provider.bounds("later"),
+            Some(Bounds::new(point(px(0.0), px(0.0)), bounds.size))
+        );
+
+        // Placeholders don't queue any rendering
+        assert!(!provider.ready_latch.armed.get());
+    }
+
+    #[test]
+    fn test_sort_by_viewport_proximity() {
+        let item = |id: &str, x: f32, y: f32| {
+            ItemDescriptor::new(
+                id,
+                Bounds::new(point(px(x), px(y)), size(px(100.0), px(100.0))),
+            )
+        };
+        // Viewport is 800x600 with no offset, so its center is (400, 300)
+        let mut items = vec![
+            item("far", 2000.0, 2000.0),
+            item("near", 400.0, 300.0),
+            item("center", 350.0, 250.0),
+            item("left", -100.0, 250.0),
+            item("right", 800.0, 250.0),
+        ];
+
+        sort_by_viewport_proximity(&mut items, &Camera::new(), size(px(800.0), px(600.0)));
+
+        let order: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        // "left" and "right" are equidistant, so they keep their original order
+        assert_eq!(order, vec!["center", "near", "left", "right", "far"]);
+    }
+
+    #[test]
+    fn test_sort_by_viewport_proximity_follows_camera() {
+        let mut items = vec![
+            ItemDescriptor::new(
+                "a",
+                Bounds::new(point(px(0.0), px(0.0)), size(px(10.0), px(10.0))),
+            ),
+            ItemDescriptor::new(
+                "b",
+                Bounds::new(point(px(1000.0), px(0.0)), size(px(10.0), px(10.0))),
+            ),
+        ];
+
+        let mut camera = Camera::new();
+        camera.center_on(point(px(1005.0), px(5.0)), size(px(200.0), px(200.0)));
+        sort_by_viewport_proximity(&mut items, &camera, size(px(200.0), px(200.0)));
+
+        assert_eq!(items[0].id, "b");
+    }
+
+    #[test]
+    fn test_lookups_accept_str_and_string_ids() {
+        let mut provider = TexturedCanvasItemsProvider::new();
+        let id = String::from("missing");
+        assert!(!provider.contains("missing"));
+        assert!(!provider.contains(&id));
+        assert!(!provider.contains(ItemId::from(id.clone())));
+        assert_eq!(provider.bounds(id.clone()), None);
+        assert!(!provider.remove_item(id));
+    }
+
     #[test]
     fn test_default() {
         let provider = TexturedCanvasItemsProvider::default();
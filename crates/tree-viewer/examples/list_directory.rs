@@ -35,7 +35,7 @@ fn main() {
     println!();
     println!("Tree loaded successfully!");
     println!("Total nodes: {}", tree.node_count());
-    println!("Files: {}", tree.leaves().len());
+    println!("Files: {}", tree.leaves(TraversalOrder::PreOrder).count());
     println!("Directories: {}", tree.containers().len());
     println!();
     println!("Directory Structure:");
@@ -70,13 +70,12 @@ fn main() {
     println!("Summary:");
     println!("  Total items: {}", tree.node_count());
     println!("  Directories: {}", tree.containers().len());
-    println!("  Files: {}", tree.leaves().len());
+    println!("  Files: {}", tree.leaves(TraversalOrder::PreOrder).count());
 
     // Calculate total size
     let total_size: u64 = tree
-        .leaves()
-        .iter()
-        .filter_map(|&id| tree.get(id))
+        .leaves(TraversalOrder::PreOrder)
+        .filter_map(|id| tree.get(id))
         .map(|n| n.data.size)
         .sum();
     println!("  Total size: {}", format_size(total_size));
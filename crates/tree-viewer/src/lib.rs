@@ -8,6 +8,8 @@
 //! - **Tree**: Generic trait for hierarchical data structures
 //! - **Node**: Individual elements in the tree (containers or leaves)
 //! - **FilesystemTree**: Lazy-loading filesystem implementation
+//! - **GitTreeSnapshot**: The full file tree of a git commit
+//! - **GitTreeDiff**: The merged file trees of two commits, with change statuses
 //!
 //! # Example
 //!
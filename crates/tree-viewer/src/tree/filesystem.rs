@@ -1,7 +1,11 @@
 //! Filesystem tree implementation with lazy loading support
 
-use crate::tree::{Node, NodeId, NodeKind, Tree};
-use std::collections::HashMap;
+use crate::tree::{Node, NodeId, NodeKind, TraversalOrder, Tree, TreeTraversal};
+use globset::Glob;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::cmp::Reverse;
+use std::collections::{HashMap, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
@@ -154,7 +158,7 @@ impl FilesystemTree {
         self.nodes[id.get()].children = ChildrenState::Loading;
 
         // Load children from filesystem
-        match self.load_children(&path) {
+        match self.load_children(&path, &HashMap::new()) {
             Ok(child_ids) => {
                 // Update parent's children list
                 for child_id in &child_ids {
@@ -172,7 +176,14 @@ impl FilesystemTree {
     }
 
     /// Load children from the filesystem
-    fn load_children(&mut self, path: &Path) -> std::io::Result<Vec<NodeId>> {
+    ///
+    /// Entries whose path is in `existing` with the same kind keep that node,
+    /// with its data refreshed; every other entry gets a new node.
+    fn load_children(
+        &mut self,
+        path: &Path,
+        existing: &HashMap<PathBuf, NodeId>,
+    ) -> std::io::Result<Vec<NodeId>> {
         let mut child_ids = Vec::new();
 
         let entries = fs::read_dir(path)?;
@@ -205,6 +216,15 @@ impl FilesystemTree {
                 extension,
             };
 
+            if let Some(&node_id) = existing.get(&entry_path) {
+                let node = &mut self.nodes[node_id.get()].node;
+                if node.kind == kind {
+                    node.data = file_data;
+                    child_ids.push(node_id);
+                    continue;
+                }
+            }
+
             let node = FsNode {
                 node: Node::new(name, kind, file_data),
                 full_path: entry_path.clone(),
@@ -243,6 +263,74 @@ impl FilesystemTree {
         self.ensure_loaded(id)
     }
 
+    /// Re-read a directory's entries, keeping the nodes that are still there
+    ///
+    /// Unlike `reload`, entries that still exist keep their `NodeId` and any
+    /// loaded descendants, so expansion and selection elsewhere in the tree
+    /// survive. Their data is refreshed from disk. New entries get new nodes,
+    /// and removed entries are detached. Unloaded directories are simply loaded.
+    pub fn reload_children(&mut self, id: NodeId) -> Result<(), String> {
+        let Some(node) = self.nodes.get(id.get()) else {
+            return Err("Invalid node ID".to_string());
+        };
+        let ChildrenState::Loaded(ref old_children) = node.children else {
+            return self.ensure_loaded(id);
+        };
+        let existing: HashMap<_, _> = old_children
+            .iter()
+            .map(|&child| (self.nodes[child.get()].full_path.clone(), child))
+            .collect();
+        let path = node.full_path.clone();
+
+        let child_ids = match self.load_children(&path, &existing) {
+            Ok(child_ids) => child_ids,
+            Err(e) => {
+                let error_msg = e.to_string();
+                self.nodes[id.get()].children = ChildrenState::Error(error_msg.clone());
+                return Err(error_msg);
+            }
+        };
+
+        for (_, old_child) in existing {
+            if !child_ids.contains(&old_child) {
+                self.detach(old_child);
+            }
+        }
+        for child_id in &child_ids {
+            self.nodes[child_id.get()].parent = Some(id);
+        }
+        self.nodes[id.get()].children = ChildrenState::Loaded(child_ids);
+        Ok(())
+    }
+
+    /// Replace a node's data, leaving its name, kind and children alone
+    ///
+    /// Returns `false` if the node doesn't exist.
+    pub fn update_node(&mut self, id: NodeId, data: FileData) -> bool {
+        match self.nodes.get_mut(id.get()) {
+            Some(node) => {
+                node.node.data = data;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Unlink a removed node and its loaded descendants from path lookups
+    fn detach(&mut self, id: NodeId) {
+        let node = &mut self.nodes[id.get()];
+        node.parent = None;
+        let path = node.full_path.clone();
+        if self.path_cache.get(&path) == Some(&id) {
+            self.path_cache.remove(&path);
+        }
+        if let ChildrenState::Loaded(children) = self.nodes[id.get()].children.clone() {
+            for child in children {
+                self.detach(child);
+            }
+        }
+    }
+
     /// Check if a node's children have been loaded
     pub fn is_loaded(&self, id: NodeId) -> bool {
         self.nodes
@@ -251,6 +339,44 @@ impl FilesystemTree {
             .unwrap_or(false)
     }
 
+    /// Count a node and its descendants among the nodes loaded so far
+    ///
+    /// This never reads from disk: unloaded directories count as a single node.
+    /// Call `load_recursive` first for a full count.
+    pub fn subtree_count_loaded(&self, id: NodeId) -> usize {
+        self.subtree_count(id)
+    }
+
+    /// Get the loaded nodes at exactly `depth`, in display order
+    ///
+    /// The root is at depth 0. Only directories above `depth` are visited, so
+    /// this is cheaper than filtering a full walk. Nodes inside unloaded
+    /// directories aren't included; call `load_recursive` first for all of them.
+    pub fn nodes_at_depth(&self, depth: usize) -> Vec<NodeId> {
+        let mut level = vec![self.root()];
+        for _ in 0..depth {
+            level = level.iter().flat_map(|&id| self.children(id)).collect();
+        }
+        level
+    }
+
+    /// Format the loaded part of the tree as an indented listing
+    ///
+    /// See `TreeTraversal::to_indented_string`. Unloaded directories are listed
+    /// without their contents; call `load_recursive` first for a full listing.
+    pub fn to_indented_string(&self, order: TraversalOrder) -> String {
+        TreeTraversal::to_indented_string(self, order)
+    }
+
+    /// Iterate over the loaded files in the given order, skipping directories
+    ///
+    /// Shadows `TreeTraversal::leaves`, which is always pre-order. Files in
+    /// unloaded directories aren't included; call `load_recursive` first for
+    /// all of them.
+    pub fn leaves(&self, order: TraversalOrder) -> impl Iterator<Item = NodeId> + '_ {
+        self.walk(order).filter(move |&id| self.is_leaf(id))
+    }
+
     /// Recursively load all children (use with caution on large trees!)
     pub fn load_recursive(&mut self, id: NodeId) -> Result<(), String> {
         self.ensure_loaded(id)?;
@@ -274,6 +400,166 @@ impl FilesystemTree {
         Ok(())
     }
 
+    /// Find the `n` largest files in the tree, largest first
+    ///
+    /// Loads every directory that hasn't been loaded yet, so the whole tree is
+    /// read from disk on the first call. Directories that fail to load are
+    /// treated as empty. Files of equal size are returned in pre-order.
+    pub fn largest_files(&mut self, n: usize) -> Vec<(NodeId, u64)> {
+        let mut files = Vec::new();
+        let mut stack = vec![self.root()];
+
+        while let Some(current) = stack.pop() {
+            if !self.is_container(current) {
+                if let Some(node) = self.get(current) {
+                    files.push((current, node.data.size));
+                }
+                continue;
+            }
+
+            let _ = self.ensure_loaded(current);
+            let children: Vec<_> = self.children(current).collect();
+            stack.extend(children.into_iter().rev());
+        }
+
+        files.sort_by_key(|&(_, size)| Reverse(size));
+        files.truncate(n);
+        files
+    }
+
+    /// Find the first node matching a predicate, loading directories as the walk reaches them
+    ///
+    /// Unlike `TreeTraversal::find_first`, which only sees already-loaded nodes, this
+    /// loads children on demand. The walk stops at the first match, so directories
+    /// that come after it in traversal order are never read from disk. Directories
+    /// that fail to load are treated as empty.
+    pub fn find_first_loading<F>(&mut self, order: TraversalOrder, predicate: F) -> Option<NodeId>
+    where
+        F: Fn(&Node<FileData>) -> bool,
+    {
+        let matches = |tree: &Self, id: NodeId| tree.get(id).map(&predicate).unwrap_or(false);
+
+        match order {
+            TraversalOrder::PreOrder => {
+                let mut stack = vec![self.root()];
+                while let Some(current) = stack.pop() {
+                    if matches(self, current) {
+                        return Some(current);
+                    }
+                    let _ = self.ensure_loaded(current);
+                    let children: Vec<_> = self.children(current).collect();
+                    stack.extend(children.into_iter().rev());
+                }
+                None
+            }
+            TraversalOrder::BreadthFirst => {
+                let mut queue = VecDeque::from([self.root()]);
+                while let Some(current) = queue.pop_front() {
+                    if matches(self, current) {
+                        return Some(current);
+                    }
+                    let _ = self.ensure_loaded(current);
+                    let children: Vec<_> = self.children(current).collect();
+                    queue.extend(children);
+                }
+                None
+            }
+            TraversalOrder::PostOrder => {
+                // (node, children already pushed)
+                let mut stack = vec![(self.root(), false)];
+                while let Some((current, expanded)) = stack.pop() {
+                    if expanded {
+                        if matches(self, current) {
+                            return Some(current);
+                        }
+                        continue;
+                    }
+                    stack.push((current, true));
+                    let _ = self.ensure_loaded(current);
+                    let children: Vec<_> = self.children(current).collect();
+                    stack.extend(children.into_iter().rev().map(|child| (child, false)));
+                }
+                None
+            }
+        }
+    }
+
+    /// Find all nodes whose path relative to the root matches a glob, skipping ignored entries
+    ///
+    /// `.gitignore` files are honored at every level, and ignored directories are pruned
+    /// before they are loaded, so e.g. `**/*.rs` never descends into an ignored `target/`.
+    /// `.git` directories are always skipped. Directories that fail to load are treated
+    /// as empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the glob pattern is invalid.
+    pub fn find_respecting_ignore(&mut self, pattern: &str) -> Result<Vec<NodeId>, String> {
+        let glob = Glob::new(pattern)
+            .map_err(|e| e.to_string())?
+            .compile_matcher();
+
+        let mut results = Vec::new();
+        // Walk in pre-order, carrying the ignore rules of every ancestor directory
+        let mut stack = vec![(self.root(), Vec::new())];
+
+        while let Some((current, mut ignores)) = stack.pop() {
+            if current != self.root()
+                && self
+                    .relative_path(current)
+                    .is_some_and(|path| glob.is_match(path))
+            {
+                results.push(current);
+            }
+
+            if !self.is_container(current) {
+                continue;
+            }
+
+            if let Some(gitignore) = Self::load_gitignore(&self.nodes[current.get()].full_path) {
+                ignores.push(gitignore);
+            }
+
+            let _ = self.ensure_loaded(current);
+            let children: Vec<_> = self.children(current).collect();
+
+            for child in children.into_iter().rev() {
+                let is_dir = self.is_container(child);
+                let is_git_dir = is_dir && self.name(child) == Some(".git");
+                if !is_git_dir
+                    && !Self::is_ignored(&ignores, &self.nodes[child.get()].full_path, is_dir)
+                {
+                    stack.push((child, ignores.clone()));
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Build the ignore rules from a directory's `.gitignore`, if it has one
+    fn load_gitignore(dir: &Path) -> Option<Gitignore> {
+        let path = dir.join(".gitignore");
+        if !path.is_file() {
+            return None;
+        }
+        let mut builder = GitignoreBuilder::new(dir);
+        builder.add(path);
+        builder.build().ok()
+    }
+
+    /// Check a path against ignore rules, letting deeper `.gitignore` files take precedence
+    fn is_ignored(ignores: &[Gitignore], path: &Path, is_dir: bool) -> bool {
+        for gitignore in ignores.iter().rev() {
+            match gitignore.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+
     /// Get the relative path from the tree root
     pub fn relative_path(&self, id: NodeId) -> Option<PathBuf> {
         let full_path = self.full_path(id)?;
@@ -321,6 +607,7 @@ impl Tree for FilesystemTree {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tree::TreeTraversal;
     use std::fs;
     use tempfile::TempDir;
 
@@ -382,6 +669,97 @@ mod tests {
         assert_eq!(tree.node_count(), 6);
     }
 
+    #[test]
+    fn test_subtree_count_loaded() {
+        let (_temp, mut tree) = create_test_tree();
+        let root = tree.root();
+        assert_eq!(tree.subtree_count_loaded(root), 1);
+
+        tree.ensure_loaded(root).unwrap();
+        let dir1 = tree.find_by_name("dir1").unwrap();
+        // root, file1.txt and the still-unloaded dir1
+        assert_eq!(tree.subtree_count_loaded(root), 3);
+        assert_eq!(tree.subtree_count_loaded(dir1), 1);
+        assert_eq!(tree.node_count(), 3);
+
+        tree.load_recursive(root).unwrap();
+        assert_eq!(tree.subtree_count_loaded(root), 6);
+        assert_eq!(tree.subtree_count_loaded(dir1), 4);
+    }
+
+    #[test]
+    fn test_nodes_at_depth() {
+        let (_temp, mut tree) = create_test_tree();
+        let root = tree.root();
+        assert_eq!(tree.nodes_at_depth(0), vec![root]);
+
+        tree.ensure_loaded(root).unwrap();
+        let dir1 = tree.find_by_name("dir1").unwrap();
+        let file1 = tree.find_by_name("file1.txt").unwrap();
+        assert_eq!(tree.nodes_at_depth(1), vec![dir1, file1]);
+        // dir1 isn't loaded yet
+        assert!(tree.nodes_at_depth(2).is_empty());
+
+        tree.load_recursive(root).unwrap();
+        let dir2 = tree.find_by_name("dir2").unwrap();
+        let file2 = tree.find_by_name("file2.txt").unwrap();
+        assert_eq!(tree.nodes_at_depth(2), vec![dir2, file2]);
+        assert!(tree.nodes_at_depth(2).iter().all(|&id| tree.depth(id) == 2));
+        assert!(tree.nodes_at_depth(4).is_empty());
+    }
+
+    #[test]
+    fn test_largest_files() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(root.join("small.txt"), "a").unwrap();
+        fs::write(root.join("medium.txt"), "a".repeat(50)).unwrap();
+        fs::create_dir_all(root.join("nested/deeper")).unwrap();
+        fs::write(root.join("nested/large.bin"), "a".repeat(200)).unwrap();
+        fs::write(root.join("nested/deeper/huge.bin"), "a".repeat(1000)).unwrap();
+        fs::write(root.join("nested/deeper/tiny.txt"), "").unwrap();
+
+        let mut tree = FilesystemTree::new(root).unwrap();
+        let top = tree.largest_files(3);
+
+        let names: Vec<_> = top
+            .iter()
+            .map(|&(id, size)| (tree.name(id).unwrap().to_string(), size))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("huge.bin".to_string(), 1000),
+                ("large.bin".to_string(), 200),
+                ("medium.txt".to_string(), 50),
+            ]
+        );
+
+        assert_eq!(tree.largest_files(10).len(), 5);
+        assert!(tree.largest_files(0).is_empty());
+    }
+
+    #[test]
+    fn test_to_indented_string() {
+        let (_temp, mut tree) = create_test_tree();
+        let root_name = tree.name(tree.root()).unwrap().to_string();
+
+        tree.ensure_loaded(tree.root()).unwrap();
+        assert_eq!(
+            tree.to_indented_string(TraversalOrder::PreOrder),
+            format!("{}\n  dir1\n  file1.txt\n", root_name)
+        );
+
+        tree.load_recursive(tree.root()).unwrap();
+        assert_eq!(
+            tree.to_indented_string(TraversalOrder::PreOrder),
+            format!(
+                "{}\n  dir1\n    dir2\n      file3.txt\n    file2.txt\n  file1.txt\n",
+                root_name
+            )
+        );
+    }
+
     #[test]
     fn test_path_operations() {
         let (_temp, mut tree) = create_test_tree();
@@ -401,4 +779,155 @@ mod tests {
         let dir1_path = tree.relative_path(dir1).unwrap();
         assert_eq!(dir1_path.to_str().unwrap(), "dir1");
     }
+
+    #[test]
+    fn test_update_node_leaves_siblings_alone() {
+        let (_temp, mut tree) = create_test_tree();
+        tree.load_recursive(tree.root()).unwrap();
+        let file1 = tree.find_by_name("file1.txt").unwrap();
+        let dir1 = tree.find_by_name("dir1").unwrap();
+        let dir1_size = tree.get(dir1).unwrap().data.size;
+        let dir1_children: Vec<_> = tree.children(dir1).collect();
+
+        let data = FileData {
+            size: 42,
+            ..FileData::default()
+        };
+        assert!(tree.update_node(file1, data));
+        assert!(!tree.update_node(NodeId::new(100), FileData::default()));
+
+        assert_eq!(tree.get(file1).unwrap().data.size, 42);
+        assert_eq!(tree.get(dir1).unwrap().data.size, dir1_size);
+        assert_eq!(tree.children(dir1).collect::<Vec<_>>(), dir1_children);
+        assert_eq!(tree.node_count(), 6);
+    }
+
+    #[test]
+    fn test_reload_children_keeps_unchanged_ids() {
+        let (temp, mut tree) = create_test_tree();
+        let root = tree.root();
+        tree.load_recursive(root).unwrap();
+        let file1 = tree.find_by_name("file1.txt").unwrap();
+        let file2 = tree.find_by_name("file2.txt").unwrap();
+        let dir1 = tree.find_by_name("dir1").unwrap();
+        let dir2 = tree.find_by_name("dir2").unwrap();
+
+        fs::write(temp.path().join("dir1/file2.txt"), "longer content").unwrap();
+        fs::write(temp.path().join("dir1/new.txt"), "new").unwrap();
+        fs::remove_dir_all(temp.path().join("dir1/dir2")).unwrap();
+        tree.reload_children(dir1).unwrap();
+
+        let names: Vec<_> = tree
+            .children(dir1)
+            .map(|id| tree.name(id).unwrap())
+            .collect();
+        assert_eq!(names, ["file2.txt", "new.txt"]);
+        assert_eq!(tree.children(dir1).next(), Some(file2));
+        assert_eq!(tree.get(file2).unwrap().data.size, 14);
+        assert_eq!(tree.parent(dir2), None);
+
+        // Siblings outside the reloaded directory are untouched
+        assert_eq!(tree.children(root).collect::<Vec<_>>(), [dir1, file1]);
+        assert!(tree.is_loaded(dir1));
+    }
+
+    #[test]
+    fn test_leaves_in_traversal_order() {
+        let (_temp, mut tree) = create_test_tree();
+        tree.load_recursive(tree.root()).unwrap();
+        let names = |order| -> Vec<_> {
+            tree.leaves(order)
+                .map(|id| tree.name(id).unwrap())
+                .collect()
+        };
+
+        assert_eq!(
+            names(TraversalOrder::PreOrder),
+            ["file3.txt", "file2.txt", "file1.txt"]
+        );
+        assert_eq!(
+            names(TraversalOrder::BreadthFirst),
+            ["file1.txt", "file2.txt", "file3.txt"]
+        );
+        assert!(tree
+            .leaves(TraversalOrder::PostOrder)
+            .all(|id| tree.is_leaf(id)));
+    }
+
+    #[test]
+    fn test_find_first_loading_stops_at_match() {
+        let (_temp, mut tree) = create_test_tree();
+
+        // Pre-order visits root, dir1, dir2, file3.txt, file2.txt, file1.txt
+        let dir2 = tree
+            .find_first_loading(TraversalOrder::PreOrder, |n| n.name == "dir2")
+            .unwrap();
+        assert_eq!(tree.name(dir2), Some("dir2"));
+
+        // Only root and dir1 were read: root + file1 + dir1 + dir2 + file2
+        assert!(tree.is_loaded(tree.root()));
+        assert!(!tree.is_loaded(dir2));
+        assert_eq!(tree.node_count(), 5);
+    }
+
+    #[test]
+    fn test_find_first_loading_matches_walk_order() {
+        let (_temp, mut tree) = create_test_tree();
+        let found = tree
+            .find_first_loading(TraversalOrder::PreOrder, |n| n.is_leaf())
+            .unwrap();
+        assert_eq!(tree.name(found), Some("file3.txt"));
+
+        let (_temp, mut tree) = create_test_tree();
+        let found = tree
+            .find_first_loading(TraversalOrder::BreadthFirst, |n| n.is_leaf())
+            .unwrap();
+        assert_eq!(tree.name(found), Some("file1.txt"));
+
+        let (_temp, mut tree) = create_test_tree();
+        assert!(tree
+            .find_first_loading(TraversalOrder::PostOrder, |n| n.name == "missing")
+            .is_none());
+        assert_eq!(tree.node_count(), 6);
+    }
+
+    #[test]
+    fn test_find_respecting_ignore_prunes_ignored_dirs() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("src/nested/lib.rs"), "").unwrap();
+        fs::write(root.join("src/notes.txt"), "").unwrap();
+        fs::create_dir_all(root.join("target/debug/build")).unwrap();
+        fs::write(root.join("target/debug/build/out.rs"), "").unwrap();
+
+        let mut tree = FilesystemTree::new(root).unwrap();
+        let found: Vec<_> = tree
+            .find_respecting_ignore("**/*.rs")
+            .unwrap()
+            .into_iter()
+            .map(|id| tree.relative_path(id).unwrap())
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                PathBuf::from("src/nested/lib.rs"),
+                PathBuf::from("src/main.rs")
+            ]
+        );
+
+        // The ignored directory was never read from disk
+        let target = tree.find_by_path("target").unwrap();
+        assert!(!tree.is_loaded(target));
+    }
+
+    #[test]
+    fn test_find_respecting_ignore_invalid_glob() {
+        let (_temp, mut tree) = create_test_tree();
+        assert!(tree.find_respecting_ignore("a[").is_err());
+    }
 }
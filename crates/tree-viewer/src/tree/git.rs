@@ -0,0 +1,257 @@
+//! Tree implementation backed by the git tree object of a commit
+
+use crate::tree::{GitTreeDiff, Node, NodeId, Tree};
+use git::{Repository, TreeEntry, TreeEntryKind};
+use std::collections::HashMap;
+
+/// Metadata for nodes of a git tree
+#[derive(Debug, Clone, Default)]
+pub struct GitEntryData {
+    /// The SHA-1 hash of the node's object (empty for the root)
+    pub id: String,
+    /// The node's path relative to the repository root (empty for the root)
+    pub path: String,
+}
+
+impl std::fmt::Display for GitEntryData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.7}", self.id)
+    }
+}
+
+/// Internal node storage
+#[derive(Debug, Clone)]
+struct GitNode {
+    /// The node data
+    node: Node<GitEntryData>,
+    /// Parent node ID
+    parent: Option<NodeId>,
+    /// Child node IDs, in git's order
+    children: Vec<NodeId>,
+}
+
+/// The full file tree of a commit
+///
+/// Unlike `FilesystemTree`, the whole tree is read up front: a commit's tree
+/// never changes, so there's nothing to load lazily. Every file present at
+/// the commit is included, not just the ones it changed.
+///
+/// # Example
+///
+/// ```ignore
+/// let repo = Repository::open(".")?;
+/// let tree = repo.tree_at_commit("HEAD")?;
+///
+/// for id in tree.leaves() {
+///     println!("{}", tree.get(id).unwrap().data.path);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GitTreeSnapshot {
+    /// Arena storage for nodes
+    nodes: Vec<GitNode>,
+}
+
+impl GitTreeSnapshot {
+    /// Build a tree from entries listed in pre-order, as returned by
+    /// `Repository::tree_entries`
+    ///
+    /// The root is named `root_name`. Entries whose parent directory wasn't
+    /// listed before them are skipped.
+    pub fn from_entries(root_name: impl Into<String>, entries: Vec<TreeEntry>) -> Self {
+        let mut nodes = vec![GitNode {
+            node: Node::container(root_name, GitEntryData::default()),
+            parent: None,
+            children: Vec::new(),
+        }];
+        let mut dirs: HashMap<String, NodeId> = HashMap::new();
+
+        for entry in entries {
+            let parent = match entry.path.rsplit_once('/') {
+                Some((dir, _)) => match dirs.get(dir) {
+                    Some(&id) => id,
+                    None => continue,
+                },
+                None => NodeId::ROOT,
+            };
+
+            let id = NodeId::new(nodes.len());
+            let name = entry.name().to_string();
+            let data = GitEntryData {
+                id: entry.id,
+                path: entry.path,
+            };
+            let node = match entry.kind {
+                TreeEntryKind::Directory => {
+                    dirs.insert(data.path.clone(), id);
+                    Node::container(name, data)
+                }
+                TreeEntryKind::File => Node::leaf(name, data),
+            };
+
+            nodes.push(GitNode {
+                node,
+                parent: Some(parent),
+                children: Vec::new(),
+            });
+            nodes[parent.get()].children.push(id);
+        }
+
+        Self { nodes }
+    }
+}
+
+impl Tree for GitTreeSnapshot {
+    type NodeData = GitEntryData;
+
+    fn root(&self) -> NodeId {
+        NodeId::ROOT
+    }
+
+    fn get(&self, id: NodeId) -> Option<&Node<GitEntryData>> {
+        self.nodes.get(id.get()).map(|n| &n.node)
+    }
+
+    fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes.get(id.get()).and_then(|n| n.parent)
+    }
+
+    fn children(&self, id: NodeId) -> Box<dyn Iterator<Item = NodeId> + '_> {
+        Box::new(
+            self.nodes
+                .get(id.get())
+                .map(|n| n.children.iter().copied())
+                .into_iter()
+                .flatten(),
+        )
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+/// Extension trait for browsing a repository's commits as trees
+pub trait RepositoryTreeExt {
+    /// Get the full file tree of a commit (can be short or full hash)
+    ///
+    /// The root is named after the repository's working directory.
+    fn tree_at_commit(&self, id: &str) -> anyhow::Result<GitTreeSnapshot>;
+
+    /// Get the merged file trees of two commits, with each node's change
+    /// status between them
+    ///
+    /// The root is named after the repository's working directory.
+    fn tree_diff(&self, old_id: &str, new_id: &str) -> anyhow::Result<GitTreeDiff>;
+}
+
+impl RepositoryTreeExt for Repository {
+    fn tree_at_commit(&self, id: &str) -> anyhow::Result<GitTreeSnapshot> {
+        Ok(GitTreeSnapshot::from_entries(
+            root_name(self),
+            self.tree_entries(id)?,
+        ))
+    }
+
+    fn tree_diff(&self, old_id: &str, new_id: &str) -> anyhow::Result<GitTreeDiff> {
+        Ok(GitTreeDiff::from_entries(
+            root_name(self),
+            self.tree_entries(old_id)?,
+            self.tree_entries(new_id)?,
+            &self.diff_revisions(old_id, new_id)?,
+        ))
+    }
+}
+
+/// Name of a tree's root: the repository's working directory name
+fn root_name(repo: &Repository) -> String {
+    repo.work_dir()
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("root")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{TraversalOrder, TreeTraversal};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    /// Commit files into a new repository, returning the commit id
+    fn create_fixture_commit(temp: &TempDir) -> String {
+        let repo = git2::Repository::init(temp.path()).unwrap();
+        let mut index = repo.index().unwrap();
+
+        // Create test structure:
+        //   README.md
+        //   src/
+        //     lib.rs
+        //     nested/
+        //       mod.rs
+        for (path, content) in [
+            ("README.md", "readme"),
+            ("src/lib.rs", "lib"),
+            ("src/nested/mod.rs", "mod"),
+        ] {
+            let full_path = temp.path().join(path);
+            fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+            fs::write(&full_path, content).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_tree_at_commit_lists_all_files() {
+        let temp = TempDir::new().unwrap();
+        let id = create_fixture_commit(&temp);
+
+        // Later working directory changes don't affect the commit's tree
+        fs::write(temp.path().join("untracked.txt"), "new").unwrap();
+
+        let repo = Repository::open(temp.path()).unwrap();
+        let tree = repo.tree_at_commit(&id).unwrap();
+
+        let files: Vec<_> = tree
+            .leaves()
+            .into_iter()
+            .map(|id| tree.get(id).unwrap().data.path.clone())
+            .collect();
+        assert_eq!(files, vec!["README.md", "src/lib.rs", "src/nested/mod.rs"]);
+
+        assert_eq!(tree.node_count(), 6);
+        let nested = tree.find_by_path("src/nested").unwrap();
+        assert!(tree.is_container(nested));
+        assert_eq!(tree.depth(nested), 2);
+        assert_eq!(
+            tree.to_indented_string(TraversalOrder::PreOrder)
+                .lines()
+                .skip(1)
+                .collect::<Vec<_>>(),
+            vec![
+                "  README.md",
+                "  src",
+                "    lib.rs",
+                "    nested",
+                "      mod.rs"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tree_at_invalid_commit() {
+        let temp = TempDir::new().unwrap();
+        create_fixture_commit(&temp);
+
+        let repo = Repository::open(temp.path()).unwrap();
+        assert!(repo.tree_at_commit("does-not-exist").is_err());
+    }
+}
@@ -0,0 +1,332 @@
+//! Tree of the files in two commits, annotated with how each changed
+
+use crate::tree::{Node, NodeId, Tree};
+use git::{StatusEntry, StatusKind, TreeEntry, TreeEntryKind};
+use std::collections::{BTreeMap, HashMap};
+
+/// How a node changed between the two commits of a `GitTreeDiff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TreeDiffStatus {
+    /// Only in the new commit
+    Added,
+    /// Only in the old commit
+    Removed,
+    /// In both commits, with different content (for directories, something
+    /// inside them changed)
+    Modified,
+    /// In both commits, with the same content
+    #[default]
+    Unchanged,
+}
+
+impl TreeDiffStatus {
+    /// The sRGB color nodes with this status are drawn in
+    pub const fn color(self) -> [f32; 3] {
+        match self {
+            TreeDiffStatus::Added => [0.3, 0.8, 0.3],
+            TreeDiffStatus::Removed => [0.8, 0.2, 0.2],
+            TreeDiffStatus::Modified => [0.9, 0.7, 0.2],
+            TreeDiffStatus::Unchanged => [0.6, 0.6, 0.6],
+        }
+    }
+}
+
+/// Metadata for nodes of a tree diff
+#[derive(Debug, Clone, Default)]
+pub struct TreeDiffData {
+    /// The node's path relative to the repository root (empty for the root)
+    pub path: String,
+    /// How the node changed between the two commits
+    pub status: TreeDiffStatus,
+}
+
+impl std::fmt::Display for TreeDiffData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.status)
+    }
+}
+
+/// Internal node storage
+#[derive(Debug, Clone)]
+struct DiffNode {
+    /// The node data
+    node: Node<TreeDiffData>,
+    /// Parent node ID
+    parent: Option<NodeId>,
+    /// Child node IDs, sorted by path
+    children: Vec<NodeId>,
+}
+
+/// The merged file trees of two commits, with each node's change status
+///
+/// Every file and directory in either commit is included. A file's status
+/// comes from the changes between the commits; a directory is added or
+/// removed if it exists in only one commit, and modified if anything inside
+/// it changed. Selecting a file's path gives what's needed to load its diff.
+///
+/// # Example
+///
+/// ```ignore
+/// let repo = Repository::open(".")?;
+/// let tree = repo.tree_diff("HEAD~1", "HEAD")?;
+///
+/// for id in tree.leaves() {
+///     let data = &tree.get(id).unwrap().data;
+///     println!("{:?} {}", data.status, data.path);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GitTreeDiff {
+    /// Arena storage for nodes
+    nodes: Vec<DiffNode>,
+}
+
+impl GitTreeDiff {
+    /// Build a tree diff from the entries of the old and new commits, as
+    /// returned by `Repository::tree_entries`, and the changes between them,
+    /// as returned by `Repository::diff_revisions`
+    ///
+    /// The root is named `root_name`. A path that's a file in one commit and
+    /// a directory in the other takes its kind from the new commit.
+    pub fn from_entries(
+        root_name: impl Into<String>,
+        old_entries: Vec<TreeEntry>,
+        new_entries: Vec<TreeEntry>,
+        changes: &[StatusEntry],
+    ) -> Self {
+        // Sorting by path puts every directory before its contents
+        let mut entries: BTreeMap<String, (TreeEntryKind, bool, bool)> = BTreeMap::new();
+        for entry in old_entries {
+            entries.insert(entry.path, (entry.kind, true, false));
+        }
+        for entry in new_entries {
+            let in_old = entries
+                .get(&entry.path)
+                .is_some_and(|(_, in_old, _)| *in_old);
+            entries.insert(entry.path, (entry.kind, in_old, true));
+        }
+        let changes: HashMap<&str, StatusKind> = changes
+            .iter()
+            .map(|change| (change.path.as_str(), change.kind))
+            .collect();
+
+        let mut nodes = vec![DiffNode {
+            node: Node::container(root_name, TreeDiffData::default()),
+            parent: None,
+            children: Vec::new(),
+        }];
+        let mut dirs: HashMap<String, NodeId> = HashMap::new();
+
+        for (path, (kind, in_old, in_new)) in entries {
+            let parent = match path.rsplit_once('/') {
+                Some((dir, _)) => match dirs.get(dir) {
+                    Some(&id) => id,
+                    None => continue,
+                },
+                None => NodeId::ROOT,
+            };
+
+            let status = match (in_old, in_new) {
+                (false, _) => TreeDiffStatus::Added,
+                (_, false) => TreeDiffStatus::Removed,
+                _ => match changes.get(path.as_str()) {
+                    Some(StatusKind::Added) => TreeDiffStatus::Added,
+                    Some(StatusKind::Deleted) => TreeDiffStatus::Removed,
+                    Some(_) => TreeDiffStatus::Modified,
+                    None => TreeDiffStatus::Unchanged,
+                },
+            };
+
+            let id = NodeId::new(nodes.len());
+            let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+            let data = TreeDiffData {
+                path: path.clone(),
+                status,
+            };
+            let node = match kind {
+                TreeEntryKind::Directory => {
+                    dirs.insert(path, id);
+                    Node::container(name, data)
+                }
+                TreeEntryKind::File => Node::leaf(name, data),
+            };
+
+            nodes.push(DiffNode {
+                node,
+                parent: Some(parent),
+                children: Vec::new(),
+            });
+            nodes[parent.get()].children.push(id);
+        }
+
+        // Children come after their parents, so walking backwards sees every
+        // change inside a directory before the directory itself
+        for index in (1..nodes.len()).rev() {
+            let changed = nodes[index].node.data.status != TreeDiffStatus::Unchanged;
+            let parent = nodes[index].parent.unwrap_or(NodeId::ROOT).get();
+            let parent_data = &mut nodes[parent].node.data;
+            if changed && parent_data.status == TreeDiffStatus::Unchanged {
+                parent_data.status = TreeDiffStatus::Modified;
+            }
+        }
+
+        Self { nodes }
+    }
+}
+
+impl Tree for GitTreeDiff {
+    type NodeData = TreeDiffData;
+
+    fn root(&self) -> NodeId {
+        NodeId::ROOT
+    }
+
+    fn get(&self, id: NodeId) -> Option<&Node<TreeDiffData>> {
+        self.nodes.get(id.get()).map(|n| &n.node)
+    }
+
+    fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes.get(id.get()).and_then(|n| n.parent)
+    }
+
+    fn children(&self, id: NodeId) -> Box<dyn Iterator<Item = NodeId> + '_> {
+        Box::new(
+            self.nodes
+                .get(id.get())
+                .map(|n| n.children.iter().copied())
+                .into_iter()
+                .flatten(),
+        )
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{RepositoryTreeExt, TraversalOrder, TreeTraversal};
+    use git::Repository;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    /// Commit the index of `repo` after writing `files` and removing `removed`
+    fn commit(
+        repo: &git2::Repository,
+        files: &[(&str, &str)],
+        removed: &[&str],
+        message: &str,
+    ) -> String {
+        let work_dir = repo.workdir().unwrap().to_path_buf();
+        let mut index = repo.index().unwrap();
+        for (path, content) in files {
+            let full_path = work_dir.join(path);
+            fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+            fs::write(&full_path, content).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        for path in removed {
+            fs::remove_file(work_dir.join(path)).unwrap();
+            index.remove_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+        .to_string()
+    }
+
+    #[test]
+    fn test_tree_diff_statuses() {
+        let temp = TempDir::new().unwrap();
+        let git_repo = git2::Repository::init(temp.path()).unwrap();
+
+        // Old:                  New:
+        //   README.md             README.md        (unchanged)
+        //   docs/guide.md         src/lib.rs       (modified)
+        //   src/lib.rs            src/new.rs       (added)
+        //   src/old.rs
+        let old = commit(
+            &git_repo,
+            &[
+                ("README.md", "readme"),
+                ("docs/guide.md", "guide"),
+                ("src/lib.rs", "lib"),
+                ("src/old.rs", "old"),
+            ],
+            &[],
+            "Initial",
+        );
+        let new = commit(
+            &git_repo,
+            &[("src/lib.rs", "lib v2"), ("src/new.rs", "new")],
+            &["docs/guide.md", "src/old.rs"],
+            "Rework",
+        );
+
+        let repo = Repository::open(temp.path()).unwrap();
+        let tree = repo.tree_diff(&old, &new).unwrap();
+        let status = |path: &str| {
+            tree.get(tree.find_by_path(path).unwrap())
+                .unwrap()
+                .data
+                .status
+        };
+
+        assert_eq!(status("README.md"), TreeDiffStatus::Unchanged);
+        assert_eq!(status("docs"), TreeDiffStatus::Removed);
+        assert_eq!(status("docs/guide.md"), TreeDiffStatus::Removed);
+        assert_eq!(status("src"), TreeDiffStatus::Modified);
+        assert_eq!(status("src/lib.rs"), TreeDiffStatus::Modified);
+        assert_eq!(status("src/new.rs"), TreeDiffStatus::Added);
+        assert_eq!(status("src/old.rs"), TreeDiffStatus::Removed);
+        assert_eq!(
+            tree.get(tree.root()).unwrap().data.status,
+            TreeDiffStatus::Modified
+        );
+
+        // Files from both commits are merged in path order
+        let files: Vec<_> = tree
+            .leaves()
+            .into_iter()
+            .map(|id| tree.get(id).unwrap().data.path.clone())
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                "README.md",
+                "docs/guide.md",
+                "src/lib.rs",
+                "src/new.rs",
+                "src/old.rs"
+            ]
+        );
+
+        let color = |path: &str| status(path).color();
+        assert_eq!(color("src/new.rs"), [0.3, 0.8, 0.3]);
+        assert_eq!(color("docs"), [0.8, 0.2, 0.2]);
+        assert_eq!(color("src/lib.rs"), [0.9, 0.7, 0.2]);
+        assert_eq!(color("README.md"), [0.6, 0.6, 0.6]);
+
+        // Nothing changes between a commit and itself
+        let same = repo.tree_diff(&new, &new).unwrap();
+        assert!(same
+            .walk(TraversalOrder::PreOrder)
+            .into_iter()
+            .all(|id| same.get(id).unwrap().data.status == TreeDiffStatus::Unchanged));
+    }
+}
@@ -4,16 +4,22 @@
 //! data structure, with specific focus on filesystem-like trees.
 
 pub mod filesystem;
+pub mod git;
+pub mod git_diff;
 mod node;
 mod traits;
 
 pub use filesystem::{FileData, FilesystemTree};
+pub use git::{GitEntryData, GitTreeSnapshot, RepositoryTreeExt};
+pub use git_diff::{GitTreeDiff, TreeDiffData, TreeDiffStatus};
 pub use node::{Node, NodeId, NodeKind};
 pub use traits::{TraversalOrder, Tree, TreeTraversal};
 
 /// Re-export common types for convenience
 pub mod prelude {
     pub use super::{
-        FileData, FilesystemTree, Node, NodeId, NodeKind, TraversalOrder, Tree, TreeTraversal,
+        FileData, FilesystemTree, GitEntryData, GitTreeDiff, GitTreeSnapshot, Node, NodeId,
+        NodeKind, RepositoryTreeExt, TraversalOrder, Tree, TreeDiffData, TreeDiffStatus,
+        TreeTraversal,
     };
 }
@@ -114,6 +114,24 @@ pub trait Tree {
         self.children(id).count()
     }
 
+    /// Count a node and all of its descendants
+    ///
+    /// Only counts children the tree can currently report, so lazily loaded
+    /// trees only count what has been loaded. Returns 0 for invalid IDs.
+    fn subtree_count(&self, id: NodeId) -> usize {
+        if self.get(id).is_none() {
+            return 0;
+        }
+
+        let mut count = 0;
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            count += 1;
+            stack.extend(self.children(current));
+        }
+        count
+    }
+
     /// Get all ancestors of a node, from parent to root
     ///
     /// Returns an empty vector for the root or invalid IDs.
@@ -202,6 +220,40 @@ pub trait TreeTraversal: Tree {
             .collect()
     }
 
+    /// Find the first node matching a predicate in the given traversal order
+    ///
+    /// Unlike `find`, this stops walking as soon as a match is found.
+    fn find_first<F>(&self, order: TraversalOrder, predicate: F) -> Option<NodeId>
+    where
+        F: Fn(&Node<Self::NodeData>) -> bool,
+        Self: Sized,
+    {
+        self.walk(order)
+            .find(|&id| self.get(id).map(&predicate).unwrap_or(false))
+    }
+
+    /// Format the tree as an indented listing, one node name per line
+    ///
+    /// Nodes are listed in the given traversal order and indented by
+    /// `depth * 2` spaces. Each line, including the last, ends with a newline.
+    fn to_indented_string(&self, order: TraversalOrder) -> String
+    where
+        Self: Sized,
+    {
+        let mut output = String::new();
+        for id in self.walk(order) {
+            if let Some(name) = self.name(id) {
+                output.push_str(&format!(
+                    "{:indent$}{}\n",
+                    "",
+                    name,
+                    indent = self.depth(id) * 2
+                ));
+            }
+        }
+        output
+    }
+
     /// Find a node by path
     ///
     /// Returns `None` if the path doesn't exist.
@@ -432,6 +484,21 @@ mod tests {
         assert_eq!(tree.depth(file1), 3);
     }
 
+    #[test]
+    fn test_subtree_count() {
+        let mut tree = TestTree::new();
+        let dir1 = tree.add_child(NodeId::ROOT, Node::container("dir1", 0));
+        let dir2 = tree.add_child(dir1, Node::container("dir2", 0));
+        tree.add_child(dir2, Node::leaf("file3.txt", 0));
+        tree.add_child(dir1, Node::leaf("file2.txt", 0));
+        let file1 = tree.add_child(NodeId::ROOT, Node::leaf("file1.txt", 0));
+
+        assert_eq!(tree.subtree_count(NodeId::ROOT), 6);
+        assert_eq!(tree.subtree_count(dir1), 4);
+        assert_eq!(tree.subtree_count(file1), 1);
+        assert_eq!(tree.subtree_count(NodeId::new(100)), 0);
+    }
+
     #[test]
     fn test_tree_traversal_preorder() {
         let mut tree = TestTree::new();
@@ -442,4 +509,43 @@ mod tests {
         let nodes: Vec<_> = tree.walk(TraversalOrder::PreOrder).collect();
         assert_eq!(nodes, vec![NodeId::ROOT, dir1, file2, file1]);
     }
+
+    #[test]
+    fn test_to_indented_string() {
+        let mut tree = TestTree::new();
+        let dir1 = tree.add_child(NodeId::ROOT, Node::container("dir1", 0));
+        tree.add_child(dir1, Node::leaf("file2.txt", 0));
+        tree.add_child(NodeId::ROOT, Node::leaf("file1.txt", 0));
+
+        assert_eq!(
+            tree.to_indented_string(TraversalOrder::PreOrder),
+            "root\n  dir1\n    file2.txt\n  file1.txt\n"
+        );
+        assert_eq!(
+            tree.to_indented_string(TraversalOrder::BreadthFirst),
+            "root\n  dir1\n  file1.txt\n    file2.txt\n"
+        );
+    }
+
+    #[test]
+    fn test_find_first_respects_order() {
+        let mut tree = TestTree::new();
+        let dir1 = tree.add_child(NodeId::ROOT, Node::container("dir1", 0));
+        let nested = tree.add_child(dir1, Node::leaf("match", 1));
+        let shallow = tree.add_child(NodeId::ROOT, Node::leaf("match", 2));
+
+        let is_match = |n: &Node<i32>| n.name == "match";
+        assert_eq!(
+            tree.find_first(TraversalOrder::PreOrder, is_match),
+            Some(nested)
+        );
+        assert_eq!(
+            tree.find_first(TraversalOrder::BreadthFirst, is_match),
+            Some(shallow)
+        );
+        assert_eq!(
+            tree.find_first(TraversalOrder::PreOrder, |n| n.name == "missing"),
+            None
+        );
+    }
 }